@@ -0,0 +1,248 @@
+//! Decides whether a tool the model wants to call runs right away, waits for the user or is refused.
+//!
+//! Rules are checked in order and the first one that matches decides. A rule matches by server,
+//! by a glob on the tool name and by the `readOnlyHint` and `destructiveHint` annotations a server
+//! gives its tools. Tools no rule matches are asked about.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use crate::components::tool_registry::ToolRegistry;
+
+/// What happens to a tool call a rule matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalAction {
+    /// Run without asking
+    Allow,
+    /// Show the call and wait for the user
+    #[default]
+    Ask,
+    /// Refuse to run it
+    Deny,
+}
+
+impl ApprovalAction {
+    pub const ALL: [ApprovalAction; 3] = [ApprovalAction::Allow, ApprovalAction::Ask, ApprovalAction::Deny];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ApprovalAction::Allow => "allow",
+            ApprovalAction::Ask => "ask",
+            ApprovalAction::Deny => "deny",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.label() == label)
+    }
+}
+
+/// One approval rule, every condition that is set has to hold for it to match
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ApprovalRule {
+    pub action: ApprovalAction,
+    /// Id of the server providing the tool
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Glob on the tool name, `*` matches any run of characters and `?` a single one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// Whether the tool only reads, per its `readOnlyHint`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    /// Whether the tool may destroy data, per its `destructiveHint`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destructive: Option<bool>,
+}
+
+impl ApprovalRule {
+    pub fn matches(&self, request: &ApprovalRequest) -> bool {
+        if let Some(server) = &self.server {
+            if request.server_id.as_deref() != Some(server.as_str()) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.tool {
+            if !glob_matches(pattern, &request.tool_name) {
+                return false;
+            }
+        }
+        if let Some(read_only) = self.read_only {
+            if request.annotations.is_read_only() != read_only {
+                return false;
+            }
+        }
+        if let Some(destructive) = self.destructive {
+            if request.annotations.is_destructive() != destructive {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl fmt::Display for ApprovalRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut conditions = Vec::new();
+        if let Some(server) = &self.server {
+            conditions.push(format!("server {}", server));
+        }
+        if let Some(tool) = &self.tool {
+            conditions.push(format!("tool {}", tool));
+        }
+        match self.read_only {
+            Some(true) => conditions.push("read-only".to_string()),
+            Some(false) => conditions.push("not read-only".to_string()),
+            None => {}
+        }
+        match self.destructive {
+            Some(true) => conditions.push("destructive".to_string()),
+            Some(false) => conditions.push("not destructive".to_string()),
+            None => {}
+        }
+        if conditions.is_empty() {
+            write!(f, "{} every tool", self.action.label())
+        } else {
+            write!(f, "{} {}", self.action.label(), conditions.join(", "))
+        }
+    }
+}
+
+/// Hints a server gives about a tool in its `annotations`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ToolAnnotations {
+    pub read_only: Option<bool>,
+    pub destructive: Option<bool>,
+}
+
+impl ToolAnnotations {
+    /// Read the hints from a tool as listed by `tools/list`
+    pub fn from_tool(tool: &Value) -> Self {
+        let annotations = &tool["annotations"];
+        Self {
+            read_only: annotations["readOnlyHint"].as_bool(),
+            destructive: annotations["destructiveHint"].as_bool(),
+        }
+    }
+
+    /// Tools are taken to modify their environment unless they say otherwise
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.unwrap_or(false)
+    }
+
+    /// Tools that modify their environment are taken to be destructive unless they say otherwise,
+    /// as the MCP specification prescribes
+    pub fn is_destructive(&self) -> bool {
+        !self.is_read_only() && self.destructive.unwrap_or(true)
+    }
+}
+
+/// Annotations of the tools of every server, as they last listed them
+#[derive(Debug, Clone, Default)]
+pub struct ToolAnnotationStore {
+    servers: Arc<RwLock<HashMap<String, HashMap<String, ToolAnnotations>>>>,
+}
+
+impl ToolAnnotationStore {
+    /// Take the annotations from the result of a `tools/list` request
+    pub fn record_tools_list(&self, server_id: &str, result: &Value) {
+        let Some(tools) = result["tools"].as_array() else {
+            return;
+        };
+        let mut servers = self.servers.write().unwrap();
+        let annotations = servers.entry(server_id.to_string()).or_default();
+        for tool in tools {
+            if let Some(name) = tool["name"].as_str() {
+                annotations.insert(name.to_string(), ToolAnnotations::from_tool(tool));
+            }
+        }
+    }
+
+    /// Annotations of a tool, empty for tools that weren't listed
+    pub fn get(&self, server_id: &str, tool_name: &str) -> ToolAnnotations {
+        self.servers
+            .read()
+            .unwrap()
+            .get(server_id)
+            .and_then(|tools| tools.get(tool_name))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// A tool call to decide on
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ApprovalRequest {
+    /// Server providing the tool, `None` when it can't be told
+    pub server_id: Option<String>,
+    /// Name of the tool as the server calls it
+    pub tool_name: String,
+    pub annotations: ToolAnnotations,
+}
+
+impl ApprovalRequest {
+    /// The call of a tool by the name the chat knows it by, bare or qualified with its server
+    pub fn for_tool(name: &str, registry: &ToolRegistry, annotations: &ToolAnnotationStore) -> Self {
+        match registry.resolve(name) {
+            Ok((server_id, tool_name)) => Self {
+                annotations: annotations.get(&server_id, &tool_name),
+                server_id: Some(server_id),
+                tool_name,
+            },
+            Err(_) => Self {
+                tool_name: name.to_string(),
+                ..Self::default()
+            },
+        }
+    }
+
+    /// How the tool is remembered when it is allowed for a conversation
+    pub fn key(&self) -> String {
+        match &self.server_id {
+            Some(server_id) => ToolRegistry::qualified_name(server_id, &self.tool_name),
+            None => self.tool_name.clone(),
+        }
+    }
+}
+
+/// Outcome of checking a tool call against the rules
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalDecision {
+    /// Run the tool, saying why
+    Allow(String),
+    Ask,
+    /// Don't run the tool, saying why
+    Deny(String),
+}
+
+/// Decide on a tool call
+///
+/// `allowed_in_conversation` holds the tools the user allowed for the rest of the conversation,
+/// they run without asking unless a rule denies them.
+pub fn decide(rules: &[ApprovalRule], request: &ApprovalRequest, allowed_in_conversation: &[String]) -> ApprovalDecision {
+    match rules.iter().find(|rule| rule.matches(request)) {
+        Some(rule) if rule.action == ApprovalAction::Deny => ApprovalDecision::Deny(format!("rule \"{}\"", rule)),
+        Some(rule) if rule.action == ApprovalAction::Allow => ApprovalDecision::Allow(format!("rule \"{}\"", rule)),
+        _ if allowed_in_conversation.contains(&request.key()) => {
+            ApprovalDecision::Allow("allowed for this conversation".to_string())
+        }
+        _ => ApprovalDecision::Ask,
+    }
+}
+
+/// Whether `name` matches the glob `pattern`
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+    let regex = pattern
+        .split('*')
+        .map(|part| part.split('?').map(regex::escape).collect::<Vec<_>>().join("."))
+        .collect::<Vec<_>>()
+        .join(".*");
+    Regex::new(&format!("^{}$", regex))
+        .map(|regex| regex.is_match(name))
+        .unwrap_or(false)
+}
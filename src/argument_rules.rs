@@ -0,0 +1,349 @@
+//! Declarative rules on the arguments of tool calls, checked before a call is sent to its server.
+//!
+//! A rule applies to the tools whose name matches its glob, optionally only on one server. Each
+//! constraint picks an argument by JSON pointer and requires it to match a glob or regex, not to
+//! match a regex, or to be a path under one of a list of prefixes. `allowed_roots` requires every
+//! argument that is a path to lie under one of the roots: any string that looks like one, and any
+//! string under a path-like key (`path`, `file`, `dir`, ...) or with a `uri` format in the schema,
+//! relative paths included. A call breaking any rule is not run, whoever approved it.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::approvals::glob_matches;
+use crate::variables::VariableContext;
+
+/// Rules on the arguments of the tools matching `tool`
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ArgumentRule {
+    /// Glob on the tool name, `*` matches any run of characters and `?` a single one
+    pub tool: String,
+    /// Id of the server providing the tool, any server without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Directories every path argument has to be in, variables like `${HOME}` are expanded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_roots: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<ArgumentConstraint>,
+}
+
+/// Conditions on one argument, every one that is set has to hold
+///
+/// An argument that is an array has to meet them with each of its items. Missing arguments are
+/// left to the schema of the tool.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ArgumentConstraint {
+    /// JSON pointer to the argument, e.g. `/path`
+    pub pointer: String,
+    /// Glob the value has to match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glob: Option<String>,
+    /// Regex the value has to contain a match of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    /// Regex the value must not contain a match of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deny_regex: Option<String>,
+    /// Directories the value has to be a path in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_prefix: Vec<String>,
+    /// Reason shown when the argument breaks the constraint, instead of the generated one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// An argument of a tool call that breaks a rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgumentViolation {
+    pub tool: String,
+    /// JSON pointer to the argument
+    pub pointer: String,
+    pub reason: String,
+}
+
+impl fmt::Display for ArgumentViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Argument {} of {} is not allowed: {}", self.pointer, self.tool, self.reason)
+    }
+}
+
+impl ArgumentRule {
+    pub fn applies_to(&self, server_id: Option<&str>, tool_name: &str) -> bool {
+        let server_matches = match &self.server {
+            Some(server) => server_id == Some(server.as_str()),
+            None => true,
+        };
+        server_matches && glob_matches(&self.tool, tool_name)
+    }
+
+    /// Everything in `arguments` that breaks this rule
+    pub fn check(&self, tool_name: &str, arguments: &Value, context: &VariableContext) -> Vec<ArgumentViolation> {
+        self.check_with_schema(tool_name, arguments, &Value::Null, context)
+    }
+
+    /// Everything in `arguments` that breaks this rule, `schema` being the input schema of the tool
+    ///
+    /// The schema tells which arguments are paths beyond their keys, e.g. a `uri` format.
+    pub fn check_with_schema(
+        &self,
+        tool_name: &str,
+        arguments: &Value,
+        schema: &Value,
+        context: &VariableContext,
+    ) -> Vec<ArgumentViolation> {
+        let mut violations = Vec::new();
+        let mut violation = |pointer: &str, reason: String| {
+            violations.push(ArgumentViolation {
+                tool: tool_name.to_string(),
+                pointer: pointer.to_string(),
+                reason,
+            });
+        };
+
+        if !self.allowed_roots.is_empty() {
+            let mut strings = Vec::new();
+            collect_strings(arguments, String::new(), None, Some(schema), &mut strings);
+            for argument in strings.into_iter().filter(StringArgument::is_path) {
+                let path = argument.value.strip_prefix("file://").unwrap_or(argument.value);
+                if let Err(reason) = check_path(path, &self.allowed_roots, context) {
+                    violation(&argument.pointer, reason);
+                }
+            }
+        }
+
+        for constraint in &self.constraints {
+            let Some(argument) = arguments.pointer(&constraint.pointer) else {
+                continue;
+            };
+            let values: Vec<(String, &Value)> = match argument {
+                Value::Array(items) => items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| (format!("{}/{}", constraint.pointer, i), item))
+                    .collect(),
+                value => vec![(constraint.pointer.clone(), value)],
+            };
+            for (pointer, value) in values {
+                if let Err(reason) = constraint.check(value, context) {
+                    violation(&pointer, constraint.message.clone().unwrap_or(reason));
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// What keeps the rule from being used, e.g. a regex that doesn't compile
+    pub fn problems(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        if self.tool.trim().is_empty() {
+            problems.push(("tool".to_string(), "must not be empty, use * for every tool".to_string()));
+        }
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            let field = |name: &str| format!("constraints[{}].{}", i, name);
+            if !constraint.pointer.is_empty() && !constraint.pointer.starts_with('/') {
+                problems.push((field("pointer"), format!("\"{}\" is not a JSON pointer, e.g. /path", constraint.pointer)));
+            }
+            for (name, pattern) in [("regex", &constraint.regex), ("deny_regex", &constraint.deny_regex)] {
+                if let Some(Err(e)) = pattern.as_deref().map(Regex::new) {
+                    problems.push((field(name), e.to_string()));
+                }
+            }
+        }
+        problems
+    }
+}
+
+impl ArgumentConstraint {
+    fn check(&self, value: &Value, context: &VariableContext) -> Result<(), String> {
+        let text = match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+
+        if let Some(glob) = &self.glob {
+            if !glob_matches(glob, &text) {
+                return Err(format!("\"{}\" doesn't match {}", text, glob));
+            }
+        }
+        if let Some(pattern) = &self.regex {
+            let regex = Regex::new(pattern).map_err(|e| format!("invalid regex {}: {}", pattern, e))?;
+            if !regex.is_match(&text) {
+                return Err(format!("\"{}\" doesn't match /{}/", text, pattern));
+            }
+        }
+        if let Some(pattern) = &self.deny_regex {
+            let regex = Regex::new(pattern).map_err(|e| format!("invalid regex {}: {}", pattern, e))?;
+            if let Some(found) = regex.find(&text) {
+                return Err(format!("\"{}\" contains \"{}\"", text, found.as_str()));
+            }
+        }
+        if !self.path_prefix.is_empty() {
+            check_path(&text, &self.path_prefix, context)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check `arguments` against every rule for the tool, `tool_name` being the name the server gives it
+/// and `schema` its input schema
+pub fn check_arguments(
+    rules: &[ArgumentRule],
+    server_id: Option<&str>,
+    tool_name: &str,
+    arguments: &Value,
+    schema: &Value,
+) -> Result<(), Vec<ArgumentViolation>> {
+    let context = VariableContext::current();
+    let violations: Vec<ArgumentViolation> = rules
+        .iter()
+        .filter(|rule| rule.applies_to(server_id, tool_name))
+        .flat_map(|rule| rule.check_with_schema(tool_name, arguments, schema, &context))
+        .collect();
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+/// Whether `path` is in one of `roots`, after resolving `..` and `~`
+///
+/// Symlinks are not followed, a link inside a root pointing out of it is not noticed.
+fn check_path(path: &str, roots: &[String], context: &VariableContext) -> Result<(), String> {
+    let resolved = resolve_path(path, context);
+    if !resolved.is_absolute() {
+        return Err(format!("\"{}\" is not an absolute path", path));
+    }
+    for root in roots {
+        let root = context
+            .expand(root)
+            .map_err(|e| format!("the allowed root {} can't be expanded: {}", root, e))?;
+        if resolved.starts_with(resolve_path(&root, context)) {
+            return Ok(());
+        }
+    }
+    Err(format!("\"{}\" is outside {}", path, roots.join(", ")))
+}
+
+/// `path` with a leading `~` replaced by the home directory and `.` and `..` resolved
+fn resolve_path(path: &str, context: &VariableContext) -> PathBuf {
+    let path = match (path.strip_prefix('~'), &context.home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') => {
+            home.join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(path),
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Whether an argument is taken for a path: absolute, in the home directory or climbing up with `..`
+fn looks_like_path(value: &str) -> bool {
+    let path = Path::new(value);
+    path.is_absolute()
+        || value.starts_with('/')
+        || value == "~"
+        || value.starts_with("~/")
+        || path.components().any(|component| component == Component::ParentDir)
+}
+
+/// Key words naming an argument that holds a path, e.g. `path`, `source_file` or `workingDir`
+const PATH_KEY_WORDS: &[&str] = &[
+    "path", "paths", "file", "files", "filename", "filepath", "dir", "dirs", "directory", "directories", "folder",
+    "cwd", "root", "source", "src", "destination", "dest", "dst",
+];
+
+/// Formats of the schema taking a string for a path or a location
+const PATH_FORMATS: &[&str] = &["uri", "uri-reference", "iri", "iri-reference", "path"];
+
+/// A string in the arguments of a call, with what tells whether it is a path
+struct StringArgument<'a> {
+    pointer: String,
+    value: &'a str,
+    /// Key of the object field holding the string, or the array it is in
+    key: Option<&'a str>,
+    schema: Option<&'a Value>,
+}
+
+impl StringArgument<'_> {
+    /// Whether the string is taken for a path: it looks like one, or its key or schema says it is one
+    ///
+    /// URLs other than `file://` ones are locations, not paths, whatever their key.
+    fn is_path(&self) -> bool {
+        if looks_like_path(self.value) || self.value.starts_with("file://") {
+            return true;
+        }
+        if self.value.contains("://") {
+            return false;
+        }
+        let format = self.schema.and_then(|schema| schema.get("format")).and_then(Value::as_str);
+        format.is_some_and(|format| PATH_FORMATS.contains(&format)) || self.key.is_some_and(is_path_key)
+    }
+}
+
+/// Whether a key names a path, split into words at `_`, `-` and camel case
+fn is_path_key(key: &str) -> bool {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for c in key.chars() {
+        if c == '_' || c == '-' || c == '.' || c.is_uppercase() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        if c.is_alphanumeric() {
+            word.extend(c.to_lowercase());
+        }
+    }
+    words.push(word);
+    words.iter().any(|word| PATH_KEY_WORDS.contains(&word.as_str()))
+}
+
+/// Every string in `value` with its JSON pointer, key and schema, `schema` being the schema of `value`
+fn collect_strings<'a>(
+    value: &'a Value,
+    pointer: String,
+    key: Option<&'a str>,
+    schema: Option<&'a Value>,
+    strings: &mut Vec<StringArgument<'a>>,
+) {
+    match value {
+        Value::String(text) => strings.push(StringArgument {
+            pointer,
+            value: text,
+            key,
+            schema,
+        }),
+        Value::Array(items) => {
+            let items_schema = schema.and_then(|schema| schema.get("items"));
+            for (i, item) in items.iter().enumerate() {
+                collect_strings(item, format!("{}/{}", pointer, i), key, items_schema, strings);
+            }
+        }
+        Value::Object(fields) => {
+            for (name, item) in fields {
+                let field_schema = schema
+                    .and_then(|schema| schema.get("properties"))
+                    .and_then(|properties| properties.get(name));
+                let escaped = name.replace('~', "~0").replace('/', "~1");
+                collect_strings(item, format!("{}/{}", pointer, escaped), Some(name), field_schema, strings);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,296 @@
+//! The `chat` command: a conversation with the model on the terminal
+//!
+//! Tool calls go through the same approval rules as in the app. Calls no rule decides on are
+//! shown with their arguments and run once the user agrees. Models that can't call tools get
+//! tools picked for them by the `LLMToolSelector` instead.
+
+use std::io::{self, BufRead, Write};
+
+use m_desk_new::approvals::{self, ApprovalDecision, ApprovalRequest};
+use m_desk_new::components::agent::{AgentDecision, AgentRun, DEFAULT_MAX_AGENT_STEPS};
+use m_desk_new::components::tool_selection::LLMToolSelector;
+use m_desk_new::components::ToolManager;
+use m_desk_new::openrouter::{ChatMessage, FunctionNames, OpenRouterClient, OpenRouterError, ToolCall, ToolChoice, ToolDefinition};
+use m_desk_new::McpState;
+use mcp_core::Tool;
+use serde_json::{json, Value};
+
+use crate::{run_tool, selected_servers, start_servers, CliError, Options};
+
+const DEFAULT_MODEL: &str = "anthropic/claude-3-opus";
+
+/// Confidence the tool selector needs before a tool is offered to a model without function calling
+const CONFIDENCE_THRESHOLD: f64 = 0.7;
+
+const SYSTEM_PROMPT: &str = "You are a helpful AI assistant with access to tools from MCP servers. \
+Call a tool when it directly helps with the user's request. The user approves every tool call before it runs. \
+Tool results are sent back to you, so you can call further tools until you can give a final answer.";
+
+const HELP: &str = "\
+/tools   list the tools the model can call
+/allowed list the tools allowed for this conversation
+/exit    end the chat";
+
+/// What the user said to a tool call
+enum Answer {
+    Yes,
+    No,
+    Always,
+}
+
+struct Chat<'a> {
+    state: &'a McpState,
+    client: OpenRouterClient,
+    selector: LLMToolSelector,
+    model: String,
+    tools: Vec<Tool>,
+    /// Names the tools are offered to the model under
+    function_names: FunctionNames,
+    history: Vec<ChatMessage>,
+    /// Tools the user allowed for the rest of the conversation
+    allowed_tools: Vec<String>,
+    /// Whether the model rejected the tools, then the selector picks them instead
+    native_tools: bool,
+}
+
+pub async fn run(state: &mut McpState, options: &Options) -> Result<(), CliError> {
+    let api_key = state
+        .secrets
+        .openrouter_api_key()
+        .filter(|key| !key.trim().is_empty())
+        .ok_or_else(|| "No OpenRouter API key, set OPENROUTER_API_KEY".to_string())?;
+    let model = options.model.clone().unwrap_or_else(|| DEFAULT_MODEL.to_string());
+
+    let servers = selected_servers(state, options)?;
+    start_servers(state, &servers).await;
+    let state: &McpState = state;
+
+    let tools = state.tool_registry.tools();
+    let mut chat = Chat {
+        state,
+        client: OpenRouterClient::new(api_key.clone()),
+        selector: LLMToolSelector::new(api_key, model.clone()),
+        model,
+        function_names: FunctionNames::new(&tools),
+        tools,
+        history: vec![ChatMessage::new("system", SYSTEM_PROMPT.to_string())],
+        allowed_tools: Vec::new(),
+        native_tools: true,
+    };
+    println!("Chatting with {} and {} tools. Type /help for commands.", chat.model, chat.tools.len());
+
+    while let Some(line) = read_line("> ").await.map_err(|e| e.to_string())? {
+        let line = line.trim();
+        match line {
+            "" => continue,
+            "/exit" | "/quit" => break,
+            "/help" => println!("{}", HELP),
+            "/tools" => {
+                for tool in &chat.tools {
+                    println!("{:<32} {}", tool.name, tool.description.lines().next().unwrap_or_default());
+                }
+            }
+            "/allowed" => println!("{}", chat.allowed_tools.join("\n")),
+            message => {
+                if let Err(e) = chat.send(message).await {
+                    eprintln!("mdesk: {}", e);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Chat<'_> {
+    /// Answer a user message, running the tools the model asks for until it gives a final answer
+    async fn send(&mut self, message: &str) -> Result<(), String> {
+        self.history.push(ChatMessage::new("user", message.to_string()));
+        if !self.native_tools {
+            return self.send_with_selector(message).await;
+        }
+
+        let mut run = AgentRun::new(DEFAULT_MAX_AGENT_STEPS);
+        let mut tool_choice = ToolChoice::Auto;
+        loop {
+            // At the step limit the tools stay defined, the history holds calls of them
+            let tools = Some(ToolDefinition::from_tools(&self.tools, &self.function_names));
+            let reply = match self
+                .client
+                .chat_completion_with_tools(&self.model, self.history.clone(), tools, tool_choice, Some(0.7), Some(1000))
+                .await
+            {
+                Ok(response) => response.choices.into_iter().next().map(|choice| choice.message),
+                Err(e) if e.is_tools_unsupported() => {
+                    println!("({} can't call tools, picking tools for it instead)", self.model);
+                    self.native_tools = false;
+                    return self.send_with_selector(message).await;
+                }
+                Err(e) => return Err(self.forget_message(e)),
+            };
+            let Some(reply) = reply else {
+                return Err("The model sent no answer".to_string());
+            };
+
+            let tool_calls = reply.tool_calls.clone().unwrap_or_default();
+            if !reply.content.trim().is_empty() {
+                println!("{}", reply.content.trim());
+            }
+            self.history.push(reply);
+            if tool_calls.is_empty() {
+                return Ok(());
+            }
+
+            let mut stopped = false;
+            for call in &tool_calls {
+                // Every call needs an answer, also the ones after a refusal
+                let result = if stopped {
+                    "Not run, an earlier tool call of this turn was refused".to_string()
+                } else {
+                    match self.handle_call(call, &mut run).await {
+                        Ok(result) => result,
+                        Err(refusal) => {
+                            stopped = true;
+                            refusal
+                        }
+                    }
+                };
+                self.history.push(ChatMessage::tool_result(call.id.clone(), result));
+            }
+            if stopped {
+                return Ok(());
+            }
+            if run.decision() == AgentDecision::FinalAnswer {
+                tool_choice = ToolChoice::None;
+            }
+        }
+    }
+
+    /// Decide on a tool call and run it, returning the result for the model or why it didn't run
+    async fn handle_call(&mut self, call: &ToolCall, run: &mut AgentRun) -> Result<String, String> {
+        let Some(tool) = ToolManager::find_tool_by_function_name(&call.function.name, &self.function_names, &self.tools).cloned() else {
+            return Ok(format!("There is no tool named {}", call.function.name));
+        };
+        let arguments = call.parsed_arguments();
+
+        // Nothing to approve once the step limit is reached
+        if run.begin_step().is_none() {
+            return Err("Not run, the step limit of this answer is reached".to_string());
+        }
+        self.approve(&tool, &arguments).await?;
+        match run_tool(self.state, &tool, arguments).await {
+            Ok((text, _)) => {
+                println!("{}", indent(&text));
+                Ok(text)
+            }
+            Err(e) => {
+                println!("{}", indent(&e));
+                Ok(e)
+            }
+        }
+    }
+
+    /// Apply the approval rules, asking the user when none decides
+    async fn approve(&mut self, tool: &Tool, arguments: &Value) -> Result<(), String> {
+        let state = self.state;
+        let request = ApprovalRequest::for_tool(&tool.name, &state.tool_registry, state.inspector.annotations());
+        match approvals::decide(&state.server_configs.approval_rules, &request, &self.allowed_tools) {
+            ApprovalDecision::Allow(reason) => {
+                println!("Running {} ({}) with {}", tool.name, reason, arguments);
+                Ok(())
+            }
+            ApprovalDecision::Deny(reason) => {
+                println!("Tool '{}' blocked by approval {}", tool.name, reason);
+                Err(format!("The tool was blocked by approval {}", reason))
+            }
+            ApprovalDecision::Ask => match ask(&tool.name, arguments).await.map_err(|e| e.to_string())? {
+                Answer::Yes => Ok(()),
+                Answer::Always => {
+                    self.allowed_tools.push(request.key());
+                    Ok(())
+                }
+                Answer::No => Err("The user declined to run the tool".to_string()),
+            },
+        }
+    }
+
+    /// Let the tool selector pick a tool for the message, run it if approved and answer with its result
+    async fn send_with_selector(&mut self, message: &str) -> Result<(), String> {
+        let selected = match self.selector.select_tools(message, self.tools.clone()).await {
+            Ok(selection) => selection
+                .best_match()
+                .filter(|found| found.confidence >= CONFIDENCE_THRESHOLD)
+                .map(|found| (found.tool.clone(), found.suggested_parameters.clone().unwrap_or_else(|| json!({})))),
+            Err(e) => {
+                eprintln!("mdesk: no tool picked: {}", e);
+                None
+            }
+        };
+
+        if let Some((tool, arguments)) = selected {
+            let result = match self.approve(&tool, &arguments).await {
+                Ok(()) => run_tool(self.state, &tool, arguments).await.map(|(text, _)| text).unwrap_or_else(|e| e),
+                Err(refusal) => refusal,
+            };
+            println!("{}", indent(&result));
+            self.history.push(ChatMessage::new(
+                "system",
+                format!("Tool '{}' returned result:\n\n{}", tool.name, result),
+            ));
+        }
+
+        let response = self
+            .client
+            .chat_completion(&self.model, self.history.clone(), Some(0.7), Some(1000))
+            .await
+            .map_err(|e| self.forget_message(e))?;
+        if let Some(choice) = response.choices.into_iter().next() {
+            println!("{}", choice.message.content.trim());
+            self.history.push(ChatMessage::new("assistant", choice.message.content));
+        }
+        Ok(())
+    }
+
+    /// Drop the unanswered user message after a failed request, so it can be sent again
+    fn forget_message(&mut self, error: OpenRouterError) -> String {
+        if self.history.last().is_some_and(|message| message.role == "user") {
+            self.history.pop();
+        }
+        format!("Request failed: {}", error)
+    }
+}
+
+/// Show a tool call and ask whether to run it
+async fn ask(tool_name: &str, arguments: &Value) -> io::Result<Answer> {
+    let arguments = serde_json::to_string_pretty(arguments).unwrap_or_else(|_| arguments.to_string());
+    println!("The model wants to run {} with\n{}", tool_name, indent(&arguments));
+    loop {
+        let Some(answer) = read_line("Run it? [y]es, [n]o, [a]lways in this chat: ").await? else {
+            return Ok(Answer::No);
+        };
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(Answer::Yes),
+            "n" | "no" => return Ok(Answer::No),
+            "a" | "always" => return Ok(Answer::Always),
+            _ => {}
+        }
+    }
+}
+
+/// Read a line from stdin without blocking the servers' tasks, `None` at the end of the input
+async fn read_line(prompt: &str) -> io::Result<Option<String>> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    tokio::task::spawn_blocking(|| {
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line)? {
+            0 => Ok(None),
+            _ => Ok(Some(line)),
+        }
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+fn indent(text: &str) -> String {
+    text.trim_end().lines().map(|line| format!("  {}", line)).collect::<Vec<_>>().join("\n")
+}
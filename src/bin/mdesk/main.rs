@@ -0,0 +1,492 @@
+//! `mdesk`, the headless command line interface to mDesk
+//!
+//! Uses the same server configuration, profiles, secrets, validation and approval rules as the
+//! app, so scripts and CI can start servers, list and call tools, or chat with the model.
+
+mod chat;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Instant;
+
+use m_desk_new::approvals::{self, ApprovalDecision, ApprovalRequest};
+use m_desk_new::components::tool_registry::QUALIFIED_NAME_SEPARATOR;
+use m_desk_new::components::{ToolManager, ToolRegistry, ToolRouter, ValidationPipeline, ValidationState};
+use m_desk_new::connection_manager;
+use m_desk_new::secrets;
+use m_desk_new::server_config::{self, ServerConfig, ServerConfigs};
+use m_desk_new::{logging, McpState, ServerStatus};
+use mcp_core::Tool;
+use serde_json::{json, Value};
+
+/// Environment variable holding the passphrase of the secret store, for machines without a keyring
+const PASSPHRASE_ENV: &str = "MDESK_SECRETS_PASSPHRASE";
+
+const USAGE: &str = "\
+Usage: mdesk [options] <command>
+
+Commands:
+  servers list              List the configured servers
+  servers start [id...]     Start servers and keep them running until Enter is pressed
+  servers test [id...]      Start servers, list their tools, ping them and stop them again
+  tools list                List the tools of the started servers
+  call <tool> --args <json> Call a tool, by its bare or server/tool name
+  chat                      Chat with the model, asking before tools run
+
+Servers default to the ones enabled in the active profile.
+
+Options:
+  --config <path>   Server configuration to use instead of the app's
+  --profile <name>  Profile to use instead of the one chosen in the app
+  --server <id>     Only start this server, may be repeated
+  --model <id>      Model to chat with, anthropic/claude-3-opus by default
+  --json            Print machine-readable JSON
+  -h, --help        Show this help
+
+Set OPENROUTER_API_KEY or the secret of that name to chat, and MDESK_SECRETS_PASSPHRASE to
+unlock the secrets where the OS keyring isn't available.";
+
+/// A failed command, printed before exiting with status 1, or 2 for usage errors
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Failed(message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    ServersList,
+    ServersStart,
+    ServersTest,
+    ToolsList,
+    Call { tool: String, arguments: Value },
+    Chat,
+    Help,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Options {
+    config: Option<PathBuf>,
+    profile: Option<String>,
+    /// Servers named on the command line, all enabled ones without any
+    servers: Vec<String>,
+    model: Option<String>,
+    json: bool,
+}
+
+fn main() -> ExitCode {
+    let (options, command) = match parse_args(std::env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("mdesk: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    if command == Command::Help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    if let Err(e) = logging::init_stderr() {
+        eprintln!("mdesk: failed to initialize logging: {}", e);
+    }
+    let _ = dotenv::dotenv();
+
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("mdesk: can't start the async runtime: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = runtime.block_on(run(options, command));
+    // Don't wait for a prompt that is still reading from stdin
+    runtime.shutdown_background();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError::Usage(message)) => {
+            eprintln!("mdesk: {}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("mdesk: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<(Options, Command), String> {
+    let mut options = Options::default();
+    let mut positional = Vec::new();
+    let mut arguments = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok((options, Command::Help)),
+            "--config" => options.config = Some(PathBuf::from(value("--config")?)),
+            "--profile" => options.profile = Some(value("--profile")?),
+            "--server" => options.servers.push(value("--server")?),
+            "--model" => options.model = Some(value("--model")?),
+            "--args" => {
+                let text = value("--args")?;
+                let parsed: Value =
+                    serde_json::from_str(&text).map_err(|e| format!("--args is not valid JSON: {}", e))?;
+                if !parsed.is_object() {
+                    return Err("--args has to be a JSON object".to_string());
+                }
+                arguments = Some(parsed);
+            }
+            "--json" => options.json = true,
+            flag if flag.starts_with('-') => return Err(format!("unknown option {}", flag)),
+            _ => positional.push(arg),
+        }
+    }
+
+    let words: Vec<&str> = positional.iter().map(String::as_str).collect();
+    let command = match words.as_slice() {
+        [] => Command::Help,
+        ["servers", "list"] => Command::ServersList,
+        ["servers", "start", ids @ ..] | ["servers", "test", ids @ ..] => {
+            options.servers.extend(ids.iter().map(ToString::to_string));
+            if words[1] == "start" {
+                Command::ServersStart
+            } else {
+                Command::ServersTest
+            }
+        }
+        ["tools", "list"] => Command::ToolsList,
+        ["call", tool] => Command::Call {
+            tool: tool.to_string(),
+            arguments: arguments.take().unwrap_or_else(|| json!({})),
+        },
+        ["chat"] => Command::Chat,
+        ["help"] => Command::Help,
+        _ => return Err(format!("unknown command \"{}\"", words.join(" "))),
+    };
+    if arguments.is_some() {
+        return Err("--args is only used by call".to_string());
+    }
+    Ok((options, command))
+}
+
+async fn run(options: Options, command: Command) -> Result<(), CliError> {
+    let mut state = load_state(&options)?;
+    match command {
+        Command::ServersList => {
+            servers_list(&state, &options);
+            Ok(())
+        }
+        Command::ServersStart => servers_start(&mut state, &options).await,
+        Command::ServersTest => servers_test(&mut state, &options).await,
+        Command::ToolsList => {
+            let servers = selected_servers(&state, &options)?;
+            start_servers(&mut state, &servers).await;
+            tools_list(&state, &options);
+            stop_servers(&state).await;
+            Ok(())
+        }
+        Command::Call { tool, arguments } => {
+            let result = call(&mut state, &options, &tool, arguments).await;
+            stop_servers(&state).await;
+            result
+        }
+        Command::Chat => {
+            let result = chat::run(&mut state, &options).await;
+            stop_servers(&state).await;
+            result
+        }
+        Command::Help => Ok(()),
+    }
+}
+
+/// The state the app starts with: configuration, profile and unlocked secrets
+fn load_state(options: &Options) -> Result<McpState, CliError> {
+    let configs = match &options.config {
+        Some(path) => ServerConfigs::load_from_file(path),
+        None => ServerConfigs::load_or_init(&server_config::config_path()),
+    }
+    .map_err(|e| e.to_string())?;
+
+    let profile = options.profile.clone().or_else(server_config::load_active_profile);
+    if let Some(name) = &profile {
+        if configs.get_profile(name).is_none() {
+            return Err(CliError::Usage(format!("there is no profile named \"{}\"", name)));
+        }
+    }
+
+    let state = McpState {
+        server_configs: configs,
+        active_profile: profile,
+        ..McpState::default()
+    };
+    match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) if state.secrets.exists() => state
+            .secrets
+            .unlock(&passphrase)
+            .map_err(|e| format!("Can't unlock the secrets with {}: {}", PASSPHRASE_ENV, e))?,
+        _ => secrets::try_unlock_at_startup(&state.secrets),
+    }
+    Ok(state)
+}
+
+/// The servers a command works on: the ones named, or the ones the profile enables
+fn selected_servers(state: &McpState, options: &Options) -> Result<Vec<ServerConfig>, CliError> {
+    let configs = &state.server_configs;
+    if options.servers.is_empty() {
+        return Ok(configs.for_profile(state.active_profile.as_deref()).servers);
+    }
+    options
+        .servers
+        .iter()
+        .map(|id| {
+            configs
+                .get_by_id(id)
+                .cloned()
+                .ok_or_else(|| CliError::Usage(format!("there is no server with the id \"{}\"", id)))
+        })
+        .collect()
+}
+
+/// Start servers and publish them to the state, reporting the ones that fail on stderr
+async fn start_servers(state: &mut McpState, servers: &[ServerConfig]) -> Vec<(String, Result<(), String>)> {
+    let results = state.connections.start_all(servers).await;
+    connection_manager::sync_state(state);
+    results
+        .into_iter()
+        .map(|(config, result)| {
+            if let Err(e) = &result {
+                eprintln!("mdesk: server {} failed to start: {}", config.id, e);
+            }
+            (config.id, result.map(|_| ()).map_err(|e| e.to_string()))
+        })
+        .collect()
+}
+
+async fn stop_servers(state: &McpState) {
+    state.connections.stop_all().await;
+}
+
+fn servers_list(state: &McpState, options: &Options) {
+    let configs = &state.server_configs;
+    let profile = state.active_profile.as_deref();
+    if options.json {
+        let servers: Vec<Value> = configs
+            .servers
+            .iter()
+            .map(|server| {
+                json!({
+                    "id": server.id,
+                    "name": server.name,
+                    "enabled": configs.is_enabled(&server.id, profile),
+                    "default": server.is_default,
+                })
+            })
+            .collect();
+        println!("{}", json!({ "profile": profile, "servers": servers }));
+        return;
+    }
+
+    if let Some(profile) = profile {
+        println!("Profile: {}", profile);
+    }
+    for server in &configs.servers {
+        let enabled = if configs.is_enabled(&server.id, profile) { "" } else { "  (not in profile)" };
+        println!("{:<20} {}{}", server.id, server.name, enabled);
+    }
+}
+
+async fn servers_start(state: &mut McpState, options: &Options) -> Result<(), CliError> {
+    let servers = selected_servers(state, options)?;
+    let results = start_servers(state, &servers).await;
+    if results.iter().all(|(_, result)| result.is_err()) {
+        return Err("No server is running".to_string().into());
+    }
+    print_status(state);
+    println!("Press Enter to stop the servers.");
+
+    // Keep the servers healthy and restart them per their policies while waiting
+    let connections = state.connections.clone();
+    let mut changes = connections.subscribe();
+    let supervisor = tokio::spawn(async move { connections.supervise().await });
+    let mut enter = tokio::task::spawn_blocking(|| std::io::stdin().read_line(&mut String::new()));
+    loop {
+        tokio::select! {
+            _ = &mut enter => break,
+            changed = changes.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                connection_manager::sync_state(state);
+                print_status(state);
+            }
+        }
+    }
+    supervisor.abort();
+    stop_servers(state).await;
+    Ok(())
+}
+
+fn print_status(state: &McpState) {
+    let mut ids: Vec<&String> = state.server_status.keys().collect();
+    ids.sort();
+    for id in ids {
+        let tools = state.tool_registry.server_tools(id).len();
+        match &state.server_status[id] {
+            ServerStatus::Running => println!("{:<20} running, {} tools", id, tools),
+            status => println!("{:<20} {:?}", id, status),
+        }
+    }
+}
+
+async fn servers_test(state: &mut McpState, options: &Options) -> Result<(), CliError> {
+    let servers = selected_servers(state, options)?;
+    let started = Instant::now();
+    let results = start_servers(state, &servers).await;
+    let startup = started.elapsed();
+
+    let mut reports = Vec::new();
+    for (id, result) in results {
+        let report = match result {
+            Ok(()) => match state.connections.ping(&id).await {
+                Ok(latency) => json!({
+                    "id": id,
+                    "ok": true,
+                    "tools": state.tool_registry.server_tools(&id).len(),
+                    "ping_ms": latency.as_millis() as u64,
+                }),
+                Err(e) => json!({ "id": id, "ok": false, "error": format!("no answer to a ping: {}", e) }),
+            },
+            Err(e) => json!({ "id": id, "ok": false, "error": e }),
+        };
+        reports.push(report);
+    }
+    stop_servers(state).await;
+
+    if options.json {
+        println!("{}", json!({ "servers": reports, "startup_ms": startup.as_millis() as u64 }));
+    } else {
+        for report in &reports {
+            let id = report["id"].as_str().unwrap_or_default();
+            if report["ok"] == true {
+                println!("ok     {:<20} {} tools, ping {} ms", id, report["tools"], report["ping_ms"]);
+            } else {
+                println!("FAILED {:<20} {}", id, report["error"].as_str().unwrap_or_default());
+            }
+        }
+    }
+
+    let failed = reports.iter().filter(|report| report["ok"] != true).count();
+    if failed > 0 {
+        return Err(format!("{} of {} servers failed", failed, reports.len()).into());
+    }
+    Ok(())
+}
+
+fn tools_list(state: &McpState, options: &Options) {
+    let mut tools = state.tool_registry.tools();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    if options.json {
+        let tools: Vec<Value> = tools
+            .iter()
+            .map(|tool| {
+                let server = state.tool_registry.resolve(&tool.name).ok().map(|(server_id, _)| server_id);
+                json!({
+                    "name": tool.name,
+                    "server": server,
+                    "description": tool.description,
+                    "input_schema": tool.input_schema,
+                })
+            })
+            .collect();
+        println!("{}", Value::Array(tools));
+        return;
+    }
+
+    for tool in &tools {
+        let description = tool.description.lines().next().unwrap_or_default();
+        println!("{:<32} {}", tool.name, description);
+    }
+}
+
+async fn call(state: &mut McpState, options: &Options, tool_name: &str, arguments: Value) -> Result<(), CliError> {
+    // A qualified name says which server to start
+    let named_server = tool_name
+        .split_once(QUALIFIED_NAME_SEPARATOR)
+        .filter(|_| options.servers.is_empty())
+        .and_then(|(server_id, _)| state.server_configs.get_by_id(server_id).cloned());
+    let servers = match named_server {
+        Some(config) => vec![config],
+        None => selected_servers(state, options)?,
+    };
+    start_servers(state, &servers).await;
+
+    let tool = find_tool(state, tool_name)?;
+    let request = ApprovalRequest::for_tool(&tool.name, &state.tool_registry, state.inspector.annotations());
+    // Naming the tool on the command line approves the call, unless a rule refuses it
+    if let ApprovalDecision::Deny(reason) = approvals::decide(&state.server_configs.approval_rules, &request, &[]) {
+        return Err(format!("Tool {} blocked by approval {}", tool.name, reason).into());
+    }
+
+    let (text, is_error) = run_tool(state, &tool, arguments).await?;
+    if options.json {
+        println!("{}", json!({ "tool": tool.name, "is_error": is_error, "result": text }));
+    } else {
+        print!("{}", text);
+    }
+    if is_error {
+        return Err(format!("Tool {} reported an error", tool.name).into());
+    }
+    Ok(())
+}
+
+/// A tool of the started servers by its bare or qualified name
+fn find_tool(state: &McpState, name: &str) -> Result<Tool, CliError> {
+    let (server_id, bare_name) = state.tool_registry.resolve(name).map_err(|e| e.to_string())?;
+    let mut tool = state
+        .tool_registry
+        .get(name)
+        .cloned()
+        .ok_or_else(|| format!("Unknown tool: {}", name))?;
+    tool.name = if state.tool_registry.servers_providing(&bare_name).len() > 1 {
+        ToolRegistry::qualified_name(&server_id, &bare_name)
+    } else {
+        bare_name
+    };
+    Ok(tool)
+}
+
+/// Validate the arguments like the chat does, including the sanitization and argument rules, then call the tool
+///
+/// Returns the text of the result and whether the tool reported an error.
+async fn run_tool(state: &McpState, tool: &Tool, arguments: Value) -> Result<(String, bool), String> {
+    let request = ApprovalRequest::for_tool(&tool.name, &state.tool_registry, state.inspector.annotations());
+    let pipeline = ValidationPipeline::new()
+        .with_auto_fix(true)
+        .with_suggest_alternatives(false)
+        .with_sanitization(&state.server_configs.sanitization)
+        .with_argument_rules(state.server_configs.argument_rules.clone())
+        .with_server(request.server_id);
+
+    let validated = match pipeline.validate_input(tool, arguments) {
+        ValidationState::Invalid { errors, .. } => {
+            return Err(format!("Invalid arguments for {}:\n{}", tool.name, errors.join("\n")));
+        }
+        validated => validated,
+    };
+    for change in validated.get_changes() {
+        eprintln!("mdesk: {}", change);
+    }
+    let arguments = validated.get_value().cloned().unwrap_or_else(|| json!({}));
+
+    let result = ToolManager::execute_tool(tool.name.clone(), arguments, &ToolRouter::from(state))
+        .await
+        .map_err(|e| format!("Tool {} failed: {}", tool.name, e))?;
+    Ok((ToolManager::format_tool_result(&result), result.is_error.unwrap_or(false)))
+}
@@ -0,0 +1,327 @@
+//! A scriptable MCP server speaking newline delimited JSON-RPC over stdio, for tests
+//!
+//! ```text
+//! mock-mcp-server <fixture.json>
+//! ```
+//!
+//! The fixture lists the tools, resources and prompts the server offers, in the shape MCP
+//! sends them in, and how the server behaves when they are used: how long it takes to
+//! answer, the error it answers with or the exit code it crashes with. The same can be set
+//! for a whole method under `methods`, e.g. a slow `ping` or an `initialize` that fails.
+//!
+//! ```json
+//! {
+//!   "stderr": ["mock server starting"],
+//!   "tools": [
+//!     { "name": "echo", "description": "Echo the arguments", "inputSchema": { "type": "object" } },
+//!     { "name": "slow", "inputSchema": { "type": "object" }, "delay_ms": 2000 },
+//!     { "name": "boom", "inputSchema": { "type": "object" }, "crash": 3 },
+//!     {
+//!       "name": "fail",
+//!       "inputSchema": { "type": "object" },
+//!       "result": { "content": [{ "type": "text", "text": "no such file" }], "isError": true }
+//!     }
+//!   ],
+//!   "resources": [{ "uri": "file:///notes.txt", "name": "Notes", "mimeType": "text/plain", "text": "hello" }],
+//!   "prompts": [{ "name": "greet", "messages": [{ "role": "user", "content": { "type": "text", "text": "Hi" } }] }],
+//!   "methods": { "ping": { "delay_ms": 100 }, "prompts/get": { "error": { "code": -32603, "message": "broken" } } }
+//! }
+//! ```
+//!
+//! Tools without a `result` answer with their arguments as text. Lists left out of the
+//! fixture aren't announced as capabilities and their methods are unknown. Every request
+//! is noted on stderr, and the server exits when its input closes.
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const USAGE: &str = "usage: mock-mcp-server <fixture.json>";
+
+/// Protocol version answered to clients that don't ask for one
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// How the server handles a request, for a method or a single tool, resource or prompt
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Behavior {
+    /// Time to wait before answering, other requests are answered in the meantime
+    #[serde(default)]
+    delay_ms: u64,
+    /// JSON-RPC error to answer with instead of the result
+    #[serde(default)]
+    error: Option<RpcError>,
+    /// Exit with this code instead of answering
+    #[serde(default)]
+    crash: Option<i32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RpcError {
+    #[serde(default = "default_error_code")]
+    code: i64,
+    message: String,
+    #[serde(default)]
+    data: Option<Value>,
+}
+
+fn default_error_code() -> i64 {
+    -32000
+}
+
+#[derive(Debug, Deserialize)]
+struct FixtureTool {
+    #[serde(flatten)]
+    behavior: Behavior,
+    /// `CallToolResult` to answer with, the arguments as text without one
+    #[serde(default)]
+    result: Option<Value>,
+    /// Sent in `tools/list`: name, description, inputSchema, annotations
+    #[serde(flatten)]
+    definition: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixtureResource {
+    #[serde(flatten)]
+    behavior: Behavior,
+    #[serde(default)]
+    text: Option<String>,
+    /// Base64 encoded binary contents
+    #[serde(default)]
+    blob: Option<String>,
+    /// Sent in `resources/list`: uri, name, description, mimeType, annotations
+    #[serde(flatten)]
+    definition: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixturePrompt {
+    #[serde(flatten)]
+    behavior: Behavior,
+    #[serde(default)]
+    messages: Vec<Value>,
+    /// Sent in `prompts/list`: name, description, arguments
+    #[serde(flatten)]
+    definition: Map<String, Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Fixture {
+    server_info: Option<Value>,
+    /// Lines written to stderr on startup
+    stderr: Vec<String>,
+    tools: Option<Vec<FixtureTool>>,
+    resources: Option<Vec<FixtureResource>>,
+    prompts: Option<Vec<FixturePrompt>>,
+    /// Behavior of every request of a method, applied before the one of a tool, resource or prompt
+    methods: HashMap<String, Behavior>,
+}
+
+/// What to do with a request once its behaviors played out
+enum Reply {
+    Result(Value),
+    Error(RpcError),
+}
+
+fn error(code: i64, message: String) -> Reply {
+    Reply::Error(RpcError { code, message, data: None })
+}
+
+fn name_of(definition: &Map<String, Value>, key: &str) -> String {
+    definition.get(key).and_then(Value::as_str).unwrap_or_default().to_string()
+}
+
+impl Fixture {
+    fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+        serde_json::from_str(&text).map_err(|e| format!("invalid fixture {}: {}", path, e))
+    }
+
+    fn capabilities(&self) -> Value {
+        let mut capabilities = Map::new();
+        if self.tools.is_some() {
+            capabilities.insert("tools".to_string(), json!({}));
+        }
+        if self.resources.is_some() {
+            capabilities.insert("resources".to_string(), json!({}));
+        }
+        if self.prompts.is_some() {
+            capabilities.insert("prompts".to_string(), json!({}));
+        }
+        Value::Object(capabilities)
+    }
+
+    /// Answer a request, along with the behavior of the tool, resource or prompt it is about
+    fn handle(&self, method: &str, params: &Value) -> (Option<&Behavior>, Reply) {
+        let name = params.get("name").and_then(Value::as_str).unwrap_or_default();
+        match method {
+            "initialize" => {
+                let version = params.get("protocolVersion").cloned().unwrap_or_else(|| json!(PROTOCOL_VERSION));
+                let server_info = self
+                    .server_info
+                    .clone()
+                    .unwrap_or_else(|| json!({"name": "mock-mcp-server", "version": env!("CARGO_PKG_VERSION")}));
+                let result = json!({
+                    "protocolVersion": version,
+                    "capabilities": self.capabilities(),
+                    "serverInfo": server_info,
+                });
+                (None, Reply::Result(result))
+            }
+            "ping" => (None, Reply::Result(json!({}))),
+            "tools/list" if self.tools.is_some() => {
+                let tools: Vec<&Map<String, Value>> = self.tools.iter().flatten().map(|tool| &tool.definition).collect();
+                (None, Reply::Result(json!({ "tools": tools })))
+            }
+            "tools/call" if self.tools.is_some() => {
+                let Some(tool) = self.tools.iter().flatten().find(|tool| name_of(&tool.definition, "name") == name) else {
+                    return (None, error(INVALID_PARAMS, format!("Unknown tool: {}", name)));
+                };
+                let result = tool.result.clone().unwrap_or_else(|| {
+                    let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+                    json!({ "content": [{ "type": "text", "text": arguments.to_string() }], "isError": false })
+                });
+                (Some(&tool.behavior), Reply::Result(result))
+            }
+            "resources/list" if self.resources.is_some() => {
+                let resources: Vec<&Map<String, Value>> =
+                    self.resources.iter().flatten().map(|resource| &resource.definition).collect();
+                (None, Reply::Result(json!({ "resources": resources })))
+            }
+            "resources/read" if self.resources.is_some() => {
+                let uri = params.get("uri").and_then(Value::as_str).unwrap_or_default();
+                let Some(resource) = self.resources.iter().flatten().find(|r| name_of(&r.definition, "uri") == uri) else {
+                    return (None, error(INVALID_PARAMS, format!("Unknown resource: {}", uri)));
+                };
+                let mut contents = Map::new();
+                contents.insert("uri".to_string(), json!(uri));
+                if let Some(mime_type) = resource.definition.get("mimeType") {
+                    contents.insert("mimeType".to_string(), mime_type.clone());
+                }
+                match &resource.blob {
+                    Some(blob) => contents.insert("blob".to_string(), json!(blob)),
+                    None => contents.insert("text".to_string(), json!(resource.text.clone().unwrap_or_default())),
+                };
+                (Some(&resource.behavior), Reply::Result(json!({ "contents": [contents] })))
+            }
+            "prompts/list" if self.prompts.is_some() => {
+                let prompts: Vec<&Map<String, Value>> = self.prompts.iter().flatten().map(|prompt| &prompt.definition).collect();
+                (None, Reply::Result(json!({ "prompts": prompts })))
+            }
+            "prompts/get" if self.prompts.is_some() => {
+                let Some(prompt) = self.prompts.iter().flatten().find(|p| name_of(&p.definition, "name") == name) else {
+                    return (None, error(INVALID_PARAMS, format!("Unknown prompt: {}", name)));
+                };
+                let mut result = Map::new();
+                if let Some(description) = prompt.definition.get("description") {
+                    result.insert("description".to_string(), description.clone());
+                }
+                result.insert("messages".to_string(), json!(prompt.messages));
+                (Some(&prompt.behavior), Reply::Result(Value::Object(result)))
+            }
+            _ => (None, error(METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        }
+    }
+}
+
+/// Write one message to stdout, whole lines at a time since requests are answered from several threads
+fn send(stdout: &Mutex<io::Stdout>, message: &Value) {
+    let mut stdout = stdout.lock().unwrap();
+    let _ = writeln!(stdout, "{}", message);
+    let _ = stdout.flush();
+}
+
+/// Play out the behaviors in order, then answer unless one of them crashed or failed the request
+fn respond(stdout: &Mutex<io::Stdout>, id: Value, behaviors: &[Behavior], mut reply: Reply) {
+    for behavior in behaviors {
+        if behavior.delay_ms > 0 {
+            thread::sleep(Duration::from_millis(behavior.delay_ms));
+        }
+        if let Some(code) = behavior.crash {
+            eprintln!("crashing with exit code {}", code);
+            std::process::exit(code);
+        }
+        if let Some(error) = &behavior.error {
+            reply = Reply::Error(error.clone());
+            break;
+        }
+    }
+
+    let message = match reply {
+        Reply::Result(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Reply::Error(error) => {
+            let mut body = json!({ "code": error.code, "message": error.message });
+            if let Some(data) = error.data {
+                body["data"] = data;
+            }
+            json!({ "jsonrpc": "2.0", "id": id, "error": body })
+        }
+    };
+    send(stdout, &message);
+}
+
+fn serve(fixture: Fixture) {
+    let stdout = Arc::new(Mutex::new(io::stdout()));
+    for line in &fixture.stderr {
+        eprintln!("{}", line);
+    }
+
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                eprintln!("ignoring malformed message: {}", e);
+                continue;
+            }
+        };
+        let method = message["method"].as_str().unwrap_or_default().to_string();
+        // Notifications such as `notifications/initialized` get no answer
+        let Some(id) = message.get("id").filter(|id| !id.is_null()).cloned() else {
+            eprintln!("received {}", method);
+            continue;
+        };
+        eprintln!("received {} ({})", method, id);
+
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let (item, reply) = fixture.handle(&method, &params);
+        let behaviors: Vec<Behavior> = fixture.methods.get(&method).into_iter().chain(item).cloned().collect();
+        // Slow requests are answered on their own thread, so pings still get through
+        if behaviors.iter().any(|behavior| behavior.delay_ms > 0) {
+            let stdout = stdout.clone();
+            thread::spawn(move || respond(&stdout, id, &behaviors, reply));
+        } else {
+            respond(&stdout, id, &behaviors, reply);
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let (Some(path), None) = (args.next(), args.next()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    match Fixture::load(&path) {
+        Ok(fixture) => {
+            serve(fixture);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("mock-mcp-server: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+/// Default number of tool steps a single agent run may take
+pub const DEFAULT_MAX_AGENT_STEPS: usize = 5;
+
+/// Position of a tool execution within an agent run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentStep {
+    pub run_id: String,
+    /// 1-based step number
+    pub index: usize,
+    pub max_steps: usize,
+}
+
+impl AgentStep {
+    pub fn label(&self) -> String {
+        format!("Step {} of {}", self.index, self.max_steps)
+    }
+}
+
+/// What the agent loop does once a tool step has finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgentDecision {
+    /// Send the tool result back and let the model request the next tool
+    Continue,
+    /// Send the tool result back and ask for a final answer without tools
+    FinalAnswer,
+}
+
+/// A multi-step agent run, started by a user message
+///
+/// The model may chain tool calls, each approved tool call is one step. The run
+/// ends when the model replies without requesting a tool, or when the step limit
+/// is reached and the model is asked for a final answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentRun {
+    pub id: String,
+    pub steps_taken: usize,
+    pub max_steps: usize,
+}
+
+impl AgentRun {
+    pub fn new(max_steps: usize) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            steps_taken: 0,
+            max_steps: max_steps.max(1),
+        }
+    }
+
+    /// Start the next tool step, or `None` if the step limit is already reached
+    pub fn begin_step(&mut self) -> Option<AgentStep> {
+        if self.limit_reached() {
+            return None;
+        }
+
+        self.steps_taken += 1;
+        Some(AgentStep {
+            run_id: self.id.clone(),
+            index: self.steps_taken,
+            max_steps: self.max_steps,
+        })
+    }
+
+    pub fn limit_reached(&self) -> bool {
+        self.steps_taken >= self.max_steps
+    }
+
+    /// Decide how to continue after a step has finished
+    pub fn decision(&self) -> AgentDecision {
+        if self.limit_reached() {
+            AgentDecision::FinalAnswer
+        } else {
+            AgentDecision::Continue
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use dioxus::prelude::*;
+use tracing::error;
+
+use crate::approvals::{ApprovalAction, ApprovalRule};
+use crate::server_config;
+use crate::McpState;
+
+/// Approval settings props
+#[derive(Props, Clone, PartialEq)]
+pub struct ApprovalSettingsProps {
+    pub mcp_state: Signal<McpState>,
+}
+
+/// Editor for the rules deciding which tool calls run without asking
+#[component]
+pub fn ApprovalSettings(props: ApprovalSettingsProps) -> Element {
+    let mut mcp_state = props.mcp_state;
+    let mut rules = use_signal(|| mcp_state.peek().server_configs.approval_rules.clone());
+    let mut notice = use_signal(|| None::<String>);
+    let mut error = use_signal(|| None::<String>);
+
+    // Pick up rules changed in the configuration file, unless there are unsaved edits
+    let mut saved_rules = use_signal(|| rules.peek().clone());
+    use_effect(move || {
+        let applied = mcp_state.read().server_configs.approval_rules.clone();
+        if *saved_rules.peek() != applied {
+            if *rules.peek() == *saved_rules.peek() {
+                rules.set(applied.clone());
+            }
+            saved_rules.set(applied);
+        }
+    });
+
+    let save = move |_| {
+        let mut configs = mcp_state.peek().server_configs.clone();
+        configs.approval_rules = rules.read().clone();
+
+        let problems = configs.validate();
+        if !problems.is_empty() {
+            let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+            error.set(Some(format!("Invalid rules: {}", problems.join("; "))));
+            notice.set(None);
+            return;
+        }
+
+        if let Err(e) = configs.save_to_file(server_config::config_path()) {
+            error!("Failed to save approval rules: {}", e);
+            error.set(Some(format!("Error saving the rules: {}", e)));
+            notice.set(None);
+            return;
+        }
+        saved_rules.set(configs.approval_rules.clone());
+        mcp_state.write().server_configs = configs;
+        error.set(None);
+        notice.set(Some("Saved the approval rules".to_string()));
+    };
+
+    let server_ids: Vec<String> = mcp_state
+        .read()
+        .server_configs
+        .servers
+        .iter()
+        .map(|server| server.id.clone())
+        .collect();
+    let is_changed = *rules.read() != *saved_rules.read();
+    let count = rules.read().len();
+
+    rsx! {
+        div { class: "approval-settings",
+            h2 { class: "server-manager-title", "Tool Approval" }
+            p { class: "section-description",
+                "Decide which tool calls run without asking. The first rule that matches a call applies, calls no rule matches are asked about. Read-only and destructive refer to the hints servers give their tools; tools without hints count as destructive."
+            }
+
+            if let Some(message) = notice.read().clone() {
+                div { class: "conversation-notice", "{message}" }
+            }
+            if let Some(message) = error.read().clone() {
+                div { class: "error-alert", "{message}" }
+            }
+
+            div { class: "approval-rules",
+                if count == 0 {
+                    div { class: "empty-servers", "No rules yet, every tool call is asked about." }
+                }
+                for (idx, rule) in rules.read().iter().cloned().enumerate() {
+                    div {
+                        key: "{idx}",
+                        class: "approval-rule",
+                        select {
+                            class: "form-control approval-action",
+                            value: rule.action.label(),
+                            onchange: move |e| {
+                                if let Some(action) = ApprovalAction::from_label(&e.value()) {
+                                    rules.write()[idx].action = action;
+                                }
+                            },
+                            for action in ApprovalAction::ALL {
+                                option { value: action.label(), "{action.label()}" }
+                            }
+                        }
+                        select {
+                            class: "form-control",
+                            value: rule.server.clone().unwrap_or_default(),
+                            onchange: move |e| {
+                                let server = e.value();
+                                rules.write()[idx].server = (!server.is_empty()).then_some(server);
+                            },
+                            option { value: "", "Any server" }
+                            for id in server_ids.iter() {
+                                option { key: "{id}", value: "{id}", "{id}" }
+                            }
+                        }
+                        input {
+                            class: "form-control input-field",
+                            placeholder: "Any tool, or a glob like git_*",
+                            value: rule.tool.clone().unwrap_or_default(),
+                            oninput: move |e| {
+                                let tool = e.value();
+                                rules.write()[idx].tool = (!tool.trim().is_empty()).then_some(tool);
+                            }
+                        }
+                        select {
+                            class: "form-control",
+                            value: hint_value(rule.read_only),
+                            onchange: move |e| rules.write()[idx].read_only = parse_hint(&e.value()),
+                            option { value: "", "Read-only or not" }
+                            option { value: "yes", "Read-only" }
+                            option { value: "no", "Not read-only" }
+                        }
+                        select {
+                            class: "form-control",
+                            value: hint_value(rule.destructive),
+                            onchange: move |e| rules.write()[idx].destructive = parse_hint(&e.value()),
+                            option { value: "", "Destructive or not" }
+                            option { value: "yes", "Destructive" }
+                            option { value: "no", "Not destructive" }
+                        }
+                        button {
+                            class: "server-action",
+                            title: "Check this rule earlier",
+                            disabled: idx == 0,
+                            onclick: move |_| rules.write().swap(idx - 1, idx),
+                            "↑"
+                        }
+                        button {
+                            class: "server-action",
+                            title: "Check this rule later",
+                            disabled: idx + 1 == count,
+                            onclick: move |_| rules.write().swap(idx, idx + 1),
+                            "↓"
+                        }
+                        button {
+                            class: "server-action delete",
+                            onclick: move |_| {
+                                rules.write().remove(idx);
+                            },
+                            "×"
+                        }
+                    }
+                }
+            }
+
+            div { class: "approval-settings-actions",
+                button {
+                    class: "server-action",
+                    onclick: move |_| rules.write().push(ApprovalRule::default()),
+                    "Add Rule"
+                }
+                button {
+                    class: "server-action",
+                    disabled: !is_changed,
+                    onclick: move |_| rules.set(saved_rules.read().clone()),
+                    "Discard"
+                }
+                button {
+                    class: "btn-submit",
+                    disabled: !is_changed,
+                    onclick: save,
+                    "Save Rules"
+                }
+            }
+        }
+    }
+}
+
+/// Value of a hint condition in its select
+fn hint_value(hint: Option<bool>) -> &'static str {
+    match hint {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "",
+    }
+}
+
+fn parse_hint(value: &str) -> Option<bool> {
+    match value {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
@@ -1,18 +1,26 @@
 use dioxus::prelude::*;
-use crate::openrouter::{OpenRouterClient, ChatMessage, ModelInfo};
+use crate::openrouter::{OpenRouterClient, OpenRouterError, ChatMessage, ModelInfo, FunctionNames, StreamAccumulator, StreamEvent, ToolCall, ToolChoice, ToolDefinition};
 use crate::components::message::{Message, MessageRole, MessageView};
+use crate::components::agent::{AgentDecision, AgentRun, DEFAULT_MAX_AGENT_STEPS};
+use crate::components::conversation_sidebar::ConversationSidebar;
+use crate::resources::ResourceAttachment;
+use crate::approvals::{self, ApprovalDecision, ApprovalRequest};
+use crate::conversation_export::{self, ExportFormat};
+use crate::conversation_store::{Conversation, ConversationStore};
 use std::env;
 use mcp_core::Tool;
 use mcp_client::McpClientTrait;
-use crate::components::tool_manager::{ToolManager, ToolInteraction};
+use crate::components::tool_manager::{ToolManager, ToolInteraction, ToolRouter};
 use crate::components::tool_suggestion::ToolExecutionStatus;
 use crate::McpState;
 use serde_json::{Value, json};
 use crate::components::validation_pipeline::{ValidationPipeline, ValidationState, RecoveryStrategy};
 use crate::components::tool_selection_cache::ToolSelectionCache;
 use crate::components::tool_selection::{LLMToolSelector, RankedToolSelection, ToolMatch, ValidationStatus};
+use std::collections::HashSet;
 use std::sync::Arc;
 use anyhow::Result;
+use futures::StreamExt;
 use tracing::{debug, info, warn, error};
 
 // Define a struct to hold OpenRouter models for the dropdown
@@ -33,142 +41,81 @@ impl ModelSelection {
             error: None,
         }
     }
+    
+    /// Whether the selected model can be offered native tool definitions
+    pub fn selected_supports_tools(&self) -> bool {
+        self.models
+            .iter()
+            .find(|m| m.id == self.selected_model)
+            .map(|m| m.supports_tools())
+            .unwrap_or(true)
+    }
 }
 
 #[component]
 pub fn ChatTab(
-    mcp_tools: Vec<Tool>,
     api_key: Option<String>,
     mcp_state: Signal<McpState>,
+    /// Messages of a filled in MCP prompt, waiting to be inserted into the conversation
+    prompt_messages: Signal<Vec<Message>>,
+    /// Resources attached to the next user message
+    attachments: Signal<Vec<ResourceAttachment>>,
 ) -> Element {
     // Clone api_key to avoid ownership issues
     let api_key_ref = api_key.clone();
     
+    // Conversations are saved to disk, the most recent one is resumed
+    let store = use_signal(ConversationStore::default_location);
+    let resumed = use_hook(|| store.peek().most_recent());
+    
     // Chat state
-    let mut messages = use_signal(Vec::<Message>::new);
+    let mut messages = use_signal(|| resumed.as_ref().map(|c| c.messages.clone()).unwrap_or_default());
     let mut input = use_signal(String::new);
     let mut is_sending = use_signal(|| false);
-    let mut model_selection = use_signal(ModelSelection::new);
-    let mut confidence_threshold = use_signal(|| 0.7); // New signal for confidence threshold
-    
-    // Store mcp_tools in a signal so it can be accessed from multiple closures
-    let tools = use_signal(|| {
-        // Fetch initial tools from props
-        let mut all_tools = mcp_tools.clone();
-        
-        // Log the available tools for debugging
-        debug!("Available tools for ChatTab component: {}", all_tools.len());
-        for tool in &all_tools {
-            debug!("  - Tool: {} ({})", tool.name, tool.description);
+    let mut stream_task = use_signal(|| None::<Task>);
+    let mut model_selection = use_signal(|| {
+        let mut selection = ModelSelection::new();
+        if let Some(conversation) = &resumed {
+            selection.selected_model = conversation.model.clone();
         }
-        
-        all_tools
+        selection
     });
+    // Metadata of the current conversation, its messages live in `messages`
+    let mut conversation = use_signal(|| match &resumed {
+        Some(c) => Conversation { messages: Vec::new(), ..c.clone() },
+        None => Conversation::new(ModelSelection::new().selected_model),
+    });
+    // Messages as last written to disk, to skip saving unchanged conversations
+    let mut saved_messages = use_signal(|| resumed.as_ref().map(|c| c.messages.clone()).unwrap_or_default());
+    // Messages whose tool suggestion the approval rules were applied to, loaded ones are left for the user
+    let mut policy_applied = use_signal(|| {
+        resumed
+            .as_ref()
+            .map(|c| c.messages.iter().map(|m| m.id.clone()).collect::<HashSet<String>>())
+            .unwrap_or_default()
+    });
+    let mut conversation_revision = use_signal(|| 0u64);
+    let mut transfer_notice = use_signal(|| None::<String>);
+    let mut confidence_threshold = use_signal(|| 0.7); // New signal for confidence threshold
+    let mut agent_run = use_signal(|| None::<AgentRun>);
+    let mut max_agent_steps = use_signal(|| DEFAULT_MAX_AGENT_STEPS);
     
-    // Function to fetch tools from all connected servers
-    let mut fetch_all_servers_tools = {
-        to_owned![tools, mcp_state];
-        move || {
-            // Use a static variable to track the last fetch time
-            static mut LAST_FETCH_TIME: Option<std::time::Instant> = None;
-            
-            // Only fetch if it's been at least 5 seconds since the last fetch
-            let should_fetch = unsafe {
-                match LAST_FETCH_TIME {
-                    Some(last_time) => {
-                        if last_time.elapsed() >= std::time::Duration::from_secs(5) {
-                            LAST_FETCH_TIME = Some(std::time::Instant::now());
-                            true
-                        } else {
-                            false
-                        }
-                    },
-                    None => {
-                        // First time fetching
-                        LAST_FETCH_TIME = Some(std::time::Instant::now());
-                        true
-                    }
-                }
-            };
-            
-            // Skip if we recently fetched tools
-            if !should_fetch && !tools.read().is_empty() {
-                debug!("Skipping tool fetch - last fetch was too recent");
-                return;
-            }
-            
-            info!("Initiating tool fetch from all servers");
-            spawn({
-                to_owned![tools, mcp_state];
-                async move {
-                    // Get all active clients
-                    let active_clients = mcp_state.read().active_clients.clone();
-                    
-                    // Skip if no clients
-                    if active_clients.is_empty() {
-                        return;
-                    }
-                    
-                    // Collect tools from all clients
-                    let mut all_tools = Vec::new();
-                    
-                    for (server_id, client_arc) in active_clients {
-                        // Get a lock on the client
-                        let client = client_arc.lock().await;
-                        
-                        // Try to fetch tools
-                        match client.list_tools(None).await {
-                            Ok(result) => {
-                                info!("Fetched {} tools from server {}", result.tools.len(), server_id);
-                                
-                                // Add these tools to our collection
-                                all_tools.extend(result.tools);
-                            }
-                            Err(e) => {
-                                error!("Error fetching tools from server {}: {}", server_id, e);
-                            }
-                        }
-                    }
-                    
-                    // Now update the tools signal with all tools from all servers
-                    if !all_tools.is_empty() {
-                        info!("Updating tools with {} total tools from all servers", all_tools.len());
-                        tools.set(all_tools);
-                    }
-                }
-            });
-        }
-    };
-    
-    // Call the function to fetch tools during initialization
-    // Only call this if we haven't preloaded tools yet
-    if !unsafe { PRELOAD_CALLED } {
-        fetch_all_servers_tools();
-    }
-    
-    // Periodically refresh tools (every 2 minutes instead of 30 seconds)
-    use_coroutine(move |_rx: dioxus::prelude::UnboundedReceiver<()>| {
-        to_owned![fetch_all_servers_tools];
-        async move {
-            loop {
-                // Wait 2 minutes (120 seconds)
-                tokio::time::sleep(std::time::Duration::from_secs(120)).await;
-                
-                // Fetch tools
-                fetch_all_servers_tools();
-            }
-        }
+    // Tools of the running servers, from the registry the connection manager keeps in sync
+    let tools = use_memo(move || {
+        let tools = mcp_state.read().tool_registry.tools();
+        debug!("Available tools for ChatTab component: {}", tools.len());
+        tools
     });
     
     // Tool validation pipeline - We don't use use_memo since we need to get the read value each time
     let validation_pipeline = ValidationPipeline::new()
         .with_max_depth(10)
-        .with_max_string_length(1000)
         .with_auto_fix(true)
         .with_suggest_alternatives(true)
         .with_max_alternatives(3)
         .with_fallback("count", json!(5))
         .with_fallback("limit", json!(100))
+        .with_sanitization(&mcp_state.peek().server_configs.sanitization)
         .with_available_tools(tools.read().clone());
     
     // Tool selection cache
@@ -213,48 +160,30 @@ pub fn ChatTab(
         debug!("ChatTab received MCP client state: {}", if mcp_state.read().client.is_some() { "Client available" } else { "No client available" });
     }
     
-    // Only preload tools if we don't have any and fetch_all_servers_tools hasn't been called yet
-    static mut PRELOAD_CALLED: bool = false;
-    let should_preload = unsafe {
-        if PRELOAD_CALLED {
-            false
-        } else {
-            PRELOAD_CALLED = true;
-            tools.read().is_empty() && mcp_state.read().client.is_some()
-        }
-    };
-    
-    if should_preload {
-        info!("Preloading tools during ChatTab initialization");
-        let tools_clone = tools.clone();
-        let mcp_clone = mcp_state.clone();
-        
-        spawn({
-            to_owned![tools_clone, mcp_clone];
-            async move {
-                if let Some(client_arc) = &mcp_clone.read().client {
-                    let client = client_arc.lock().await;
-                    match client.list_tools(None).await {
-                        Ok(result) => {
-                            info!("Successfully preloaded {} tools during initialization", result.tools.len());
-                            tools_clone.set(result.tools);
-                        }
-                        Err(e) => {
-                            error!("Error preloading tools: {}", e);
-                        }
-                    }
-                }
-            }
-        });
-    }
-    
     // OpenRouter client setup
     let openrouter_api_key = match &api_key_ref {
         Some(key) => key.clone(),
         None => env::var("OPENROUTER_API_KEY").unwrap_or_default(),
     };
     
-    let client = use_signal(|| OpenRouterClient::new(openrouter_api_key.clone()));
+    let mut client = use_signal(|| OpenRouterClient::new(openrouter_api_key.clone()));
+    
+    // A key unlocked from the secret store after the chat was opened replaces the one the clients were created with
+    let mut current_key = use_signal(|| openrouter_api_key.clone());
+    use_effect(use_reactive((&openrouter_api_key,), move |(api_key,)| {
+        if *current_key.peek() == api_key {
+            return;
+        }
+        let model = model_selection.peek().selected_model.clone();
+        tool_selector.set(
+            LLMToolSelector::new(api_key.clone(), model)
+                .with_cache(cache.peek().clone())
+                .with_max_prompt_tools(25),
+        );
+        client.set(OpenRouterClient::new(api_key.clone()));
+        current_key.set(api_key);
+        debug!("Recreated the OpenRouter clients with a new API key");
+    }));
     
     // Use a static flag to ensure model loading only happens once
     static mut MODELS_LOADED: bool = false;
@@ -312,6 +241,7 @@ pub fn ChatTab(
             description: Some("Anthropic's most capable model for highly complex tasks".to_string()),
             context_length: Some(200000),
             pricing: None,
+            supported_parameters: None,
         },
         ModelInfo {
             id: "anthropic/claude-3-sonnet".to_string(),
@@ -319,6 +249,7 @@ pub fn ChatTab(
             description: Some("Anthropic's balanced model for most tasks".to_string()),
             context_length: Some(180000),
             pricing: None,
+            supported_parameters: None,
         },
         ModelInfo {
             id: "openai/gpt-4o".to_string(),
@@ -326,6 +257,7 @@ pub fn ChatTab(
             description: Some("OpenAI's latest multimodal model".to_string()),
             context_length: Some(128000),
             pricing: None,
+            supported_parameters: None,
         },
     ];
     
@@ -388,48 +320,6 @@ pub fn ChatTab(
     // Add tool-related state
     let mut active_tool: Option<(String, Value)> = None;
     
-    // Function to fetch MCP tools synchronously
-    let fetch_tools_sync = move || {
-        if mcp_state.read().client.is_some() {
-            debug!("Fetching MCP tools synchronously for ChatTab");
-            
-            // We need to run this in a blocking context
-            let mut tools_clone = tools.clone();
-            let mcp_clone = mcp_state.clone();
-            
-            // Create a oneshot channel to get the result back
-            let (tx, rx) = tokio::sync::oneshot::channel();
-            
-            spawn(async move {
-                if let Some(client_arc) = &mcp_clone.read().client {
-                    let client = client_arc.lock().await;
-                    match client.list_tools(None).await {
-                        Ok(result) => {
-                            info!("Successfully fetched {} tools", result.tools.len());
-                            tools_clone.set(result.tools.clone());
-                            let _ = tx.send(result.tools);
-                        }
-                        Err(e) => {
-                            error!("Error fetching tools: {}", e);
-                            let _ = tx.send(Vec::new());
-                        }
-                    }
-                } else {
-                    let _ = tx.send(Vec::new());
-                }
-            });
-            
-            // Wait a short time for tools to load
-            std::thread::sleep(std::time::Duration::from_millis(100));
-            
-            // Return whatever we got
-            return tools.read().clone();
-        }
-        
-        // Return empty list if no client
-        return Vec::new();
-    };
-    
     // Modify send_message function to add tool suggestion detection
     let mut send_message = move |_| {
         let user_input = input.read().trim().to_string();
@@ -437,33 +327,31 @@ pub fn ChatTab(
             return;
         }
         
-        // Add user message
-        messages.write().push(Message::new(MessageRole::User, user_input.clone()));
+        // Add user message, taking along the attached resources as context
+        let mut pending_attachments = attachments;
+        let attached = std::mem::take(&mut *pending_attachments.write());
+        if !attached.is_empty() {
+            info!("Sending {} attached resources as context", attached.len());
+        }
+        messages.write().push(Message::new(MessageRole::User, user_input.clone()).with_attachments(attached));
         input.set("".to_string());
         is_sending.set(true);
         
+        // Every user message starts a new agent run
+        agent_run.set(Some(AgentRun::new(*max_agent_steps.read())));
+        
         // Add thinking message
         let thinking_id = messages.write().len();
         messages.write().push(Message::new(MessageRole::Thinking, "".to_string()));
         
         // Prepare chat history for API
-        let chat_history: Vec<ChatMessage> = messages.read()
-            .iter()
-            .filter(|msg| msg.role != MessageRole::Thinking && msg.role != MessageRole::Tool)
-            .map(|msg| msg.to_openrouter_format())
-            .collect();
+        let chat_history: Vec<ChatMessage> = Message::history_for_model(&messages.read());
         
         // Get selected model
         let selected_model = model_selection.read().selected_model.clone();
         let client_instance = client.read().clone();
         
-        // Check if we have tools available, if not try to fetch them
-        let tools_clone = if tools.read().is_empty() && mcp_state.read().client.is_some() {
-            warn!("No tools available, fetching them before processing message");
-            fetch_tools_sync() // This will ensure we have tools before proceeding
-        } else {
-            tools.read().clone()
-        };
+        let tools_clone = tools.read().clone();
         
         // Log the tools we have
         debug!("Processing message with {} tools available", tools_clone.len());
@@ -471,11 +359,14 @@ pub fn ChatTab(
         // Get the current confidence threshold
         let conf_threshold = *confidence_threshold.read();
         
+        // Prefer streaming with native function calling, prompt based tool selection is the fallback
+        let use_native_tools = tools_clone.is_empty() || model_selection.read().selected_supports_tools();
+        
         // Get a reference to the tool selection cache
         let cache_ref = cache.read().clone();
         
-        spawn({
-            to_owned![messages, is_sending, user_input, mcp_state, conf_threshold];
+        let task = spawn({
+            to_owned![messages, is_sending, user_input, mcp_state, conf_threshold, agent_run];
             async move {
                 // First, check the tool selection cache
                 let cached_tool_suggestion = cache_ref.get(&user_input);
@@ -490,12 +381,12 @@ pub fn ChatTab(
                             // Validate the cached arguments against the tool schema
                             let pipeline = ValidationPipeline::new()
                                 .with_max_depth(10)
-                                .with_max_string_length(1000)
                                 .with_auto_fix(true)
                                 .with_suggest_alternatives(true)
                                 .with_max_alternatives(3)
                                 .with_fallback("count", json!(5))
                                 .with_fallback("limit", json!(100))
+                                .with_sanitization(&mcp_state.peek().server_configs.sanitization)
                                 .with_available_tools(tools_clone.clone());
                                 
                             let validation_result = pipeline.validate_input(tool, cached_args.clone());
@@ -638,6 +529,49 @@ pub fn ChatTab(
                 }
                 
                 // If no cached suggestion or it didn't meet the threshold, continue with normal flow
+                if use_native_tools {
+                    let mut native_messages = vec![ChatMessage::new("system", native_system_prompt(&tools_clone, conf_threshold))];
+                    native_messages.extend(chat_history.clone());
+                    
+                    match stream_native_reply(
+                        &client_instance,
+                        &selected_model,
+                        native_messages,
+                        &tools_clone,
+                        ToolChoice::Auto,
+                        messages,
+                        thinking_id,
+                    ).await {
+                        Ok(requested_tool) => {
+                            // Without a tool call the reply is the final answer of the run
+                            if !requested_tool {
+                                agent_run.set(None);
+                            }
+                            
+                            is_sending.set(false);
+                            return;
+                        }
+                        Err(e) if e.is_tools_unsupported() => {
+                            warn!("Model {} does not support tools, falling back to prompt based tool selection: {}", selected_model, e);
+                        }
+                        Err(e) => {
+                            error!("Error calling OpenRouter API: {}", e);
+                            
+                            // Replace thinking message with error
+                            if thinking_id < messages.read().len() {
+                                messages.write()[thinking_id] = Message::new(
+                                    MessageRole::System,
+                                    format!("Error: {}", e),
+                                );
+                            }
+                            
+                            agent_run.set(None);
+                            is_sending.set(false);
+                            return;
+                        }
+                    }
+                }
+                
                 // Create system message with context about available tools
                 let mut system_message = String::from("You are a helpful AI assistant with access to special tools. ");
                 
@@ -688,12 +622,7 @@ pub fn ChatTab(
                 }
                 
                 // Add system message to beginning of chat history
-                let mut final_messages = vec![
-                    ChatMessage {
-                        role: "system".to_string(),
-                        content: system_message,
-                    }
-                ];
+                let mut final_messages = vec![ChatMessage::new("system", system_message)];
                 final_messages.extend(chat_history);
                 
                 // Call OpenRouter API
@@ -933,26 +862,77 @@ pub fn ChatTab(
                 is_sending.set(false);
             }
         });
+        stream_task.set(Some(task));
+    };
+    
+    // Stop the in-flight request, keeping whatever text has already arrived
+    let stop_generation = move |_| {
+        if let Some(task) = stream_task.write().take() {
+            task.cancel();
+        }
+        
+        messages.write().retain(|msg| {
+            msg.role != MessageRole::Thinking && !(msg.streaming && msg.content.trim().is_empty())
+        });
+        for msg in messages.write().iter_mut() {
+            msg.streaming = false;
+            if let Some(ToolInteraction::Execution { status, .. }) = &mut msg.tool_interaction {
+                if *status == ToolExecutionStatus::Running {
+                    *status = ToolExecutionStatus::Failed("Stopped by user".to_string());
+                }
+            }
+        }
+        
+        agent_run.set(None);
+        is_sending.set(false);
+    };
+    
+    let agent_loop = AgentLoop {
+        client,
+        model_selection,
+        tools,
+        confidence_threshold,
+        agent_run,
+        messages,
+        is_sending,
     };
     
     // Modify execute_tool function to use validation pipeline
-    let execute_tool = move |(tool_name, arguments): (String, Value)| {
+    let execute_tool = move |(tool_name, arguments, tool_call_id): (String, Value, Option<String>)| {
         let message_id = messages.read().len();
         
         // Find the tool definition
         let tool_opt = tools.read().iter().find(|t| t.name == tool_name).cloned();
         
         if let Some(tool) = tool_opt {
-            // Create a fresh ValidationPipeline with current tools
+            // Native tool calls are steps of the agent run, the result goes back to the model
+            let step = if tool_call_id.is_some() {
+                let max_steps = *max_agent_steps.read();
+                agent_run.write()
+                    .get_or_insert_with(|| AgentRun::new(max_steps))
+                    .begin_step()
+            } else {
+                None
+            };
+            
+            // Create a fresh ValidationPipeline with current tools, the configured sanitization and
+            // the argument rules, which hold for every call however it was approved
+            let (argument_rules, sanitization, server_id) = {
+                let state = mcp_state.peek();
+                let request = ApprovalRequest::for_tool(&tool_name, &state.tool_registry, state.inspector.annotations());
+                (state.server_configs.argument_rules.clone(), state.server_configs.sanitization.clone(), request.server_id)
+            };
             let pipeline = ValidationPipeline::new()
                 .with_max_depth(10)
-                .with_max_string_length(1000)
                 .with_auto_fix(true)
                 .with_suggest_alternatives(true)
                 .with_max_alternatives(3)
                 .with_fallback("count", json!(5))
                 .with_fallback("limit", json!(100))
-                .with_available_tools(tools.read().clone());
+                .with_available_tools(tools.read().clone())
+                .with_sanitization(&sanitization)
+                .with_argument_rules(argument_rules)
+                .with_server(server_id);
                 
             // Validate input with our pipeline
             let validation_result = pipeline.validate_input(&tool, arguments.clone());
@@ -964,23 +944,29 @@ pub fn ChatTab(
                         Message::new(
                             MessageRole::Tool,
                             format!("Executing tool: {}", tool_name)
-                        ).with_tool_interaction(
+                        ).with_tool_call_id(tool_call_id.clone()).with_tool_interaction(
                             ToolInteraction::Execution {
                                 tool_name: tool_name.clone(),
                                 arguments: validated_args.clone(),
                                 status: ToolExecutionStatus::Running,
                                 result: None,
                                 message_idx: message_id,
+                                step: step.clone(),
                             }
                         )
                     );
                     
-                    let mcp_state_clone = mcp_state.clone();
-                    spawn({
-                        to_owned![messages, message_id, tool_name, validated_args];
+                    if tool_call_id.is_some() {
+                        is_sending.set(true);
+                    }
+                    
+                    // Taken out of the state, so health checks can update it while the tool runs
+                    let router = ToolRouter::from(&*mcp_state.peek());
+                    let task = spawn({
+                        to_owned![messages, message_id, tool_name, validated_args, tool_call_id, step];
                         async move {
                             // Execute the tool
-                            match ToolManager::execute_tool(tool_name.clone(), validated_args.clone(), &mcp_state_clone.read()).await {
+                            match ToolManager::execute_tool(tool_name.clone(), validated_args.clone(), &router).await {
                                 Ok(result) => {
                                     // Format the result
                                     let result_text = ToolManager::format_tool_result(&result);
@@ -990,23 +976,27 @@ pub fn ChatTab(
                                         messages.write()[message_id] = Message::new(
                                             MessageRole::Tool,
                                             format!("Tool execution completed: {}", tool_name.clone())
-                                        ).with_tool_interaction(
+                                        ).with_tool_call_id(tool_call_id.clone()).with_tool_interaction(
                                             ToolInteraction::Execution {
                                                 tool_name: tool_name.clone(),
                                                 arguments: validated_args,
                                                 status: ToolExecutionStatus::Completed,
                                                 result: Some(result_text.clone()),
                                                 message_idx: message_id,
+                                                step: step.clone(),
                                             }
                                         );
                                         
-                                        // Also add the result to the chat history for the AI
-                                        messages.write().push(
-                                            Message::new(
-                                                MessageRole::System,
-                                                format!("Tool '{}' returned result:\n\n{}", tool_name, result_text)
-                                            )
-                                        );
+                                        // Also add the result to the chat history for the AI,
+                                        // native tool calls get it through the tool message instead
+                                        if tool_call_id.is_none() {
+                                            messages.write().push(
+                                                Message::new(
+                                                    MessageRole::System,
+                                                    format!("Tool '{}' returned result:\n\n{}", tool_name, result_text)
+                                                )
+                                            );
+                                        }
                                     }
                                 },
                                 Err(e) => {
@@ -1015,20 +1005,29 @@ pub fn ChatTab(
                                         messages.write()[message_id] = Message::new(
                                             MessageRole::Tool,
                                             format!("Tool execution failed: {}", tool_name)
-                                        ).with_tool_interaction(
+                                        ).with_tool_call_id(tool_call_id.clone()).with_tool_interaction(
                                             ToolInteraction::Execution {
                                                 tool_name,
                                                 arguments: validated_args,
                                                 status: ToolExecutionStatus::Failed(format!("{}", e)),
                                                 result: None,
                                                 message_idx: message_id,
+                                                step: step.clone(),
                                             }
                                         );
                                     }
                                 }
                             }
+                            
+                            // Let the model continue the agent run with the result
+                            if tool_call_id.is_some() {
+                                agent_loop.continue_run().await;
+                            }
                         }
                     });
+                    if tool_call_id.is_some() {
+                        stream_task.set(Some(task));
+                    }
                 },
                 ValidationState::Recovered { recovered, strategies, errors, .. } => {
                     // Add message about recovery
@@ -1049,24 +1048,29 @@ pub fn ChatTab(
                         Message::new(
                             MessageRole::Tool,
                             format!("Executing tool: {}", tool_name)
-                        ).with_tool_interaction(
+                        ).with_tool_call_id(tool_call_id.clone()).with_tool_interaction(
                             ToolInteraction::Execution {
                                 tool_name: tool_name.clone(),
                                 arguments: recovered.clone(),
                                 status: ToolExecutionStatus::Running,
                                 result: None,
                                 message_idx: message_id + 1, // +1 because we added a message
+                                step: step.clone(),
                             }
                         )
                     );
                     
                     let message_idx = message_id + 1;
-                    let mcp_state_clone = mcp_state.clone();
-                    spawn({
-                        to_owned![messages, message_idx, tool_name, recovered];
+                    if tool_call_id.is_some() {
+                        is_sending.set(true);
+                    }
+                    
+                    let router = ToolRouter::from(&*mcp_state.peek());
+                    let task = spawn({
+                        to_owned![messages, message_idx, tool_name, recovered, tool_call_id, step];
                         async move {
                             // Execute the tool
-                            match ToolManager::execute_tool(tool_name.clone(), recovered.clone(), &mcp_state_clone.read()).await {
+                            match ToolManager::execute_tool(tool_name.clone(), recovered.clone(), &router).await {
                                 Ok(result) => {
                                     // Format the result
                                     let result_text = ToolManager::format_tool_result(&result);
@@ -1076,23 +1080,27 @@ pub fn ChatTab(
                                         messages.write()[message_idx] = Message::new(
                                             MessageRole::Tool,
                                             format!("Tool execution completed: {}", tool_name.clone())
-                                        ).with_tool_interaction(
+                                        ).with_tool_call_id(tool_call_id.clone()).with_tool_interaction(
                                             ToolInteraction::Execution {
                                                 tool_name: tool_name.clone(),
                                                 arguments: recovered,
                                                 status: ToolExecutionStatus::Completed,
                                                 result: Some(result_text.clone()),
                                                 message_idx,
+                                                step: step.clone(),
                                             }
                                         );
                                         
-                                        // Also add the result to the chat history for the AI
-                                        messages.write().push(
-                                            Message::new(
-                                                MessageRole::System,
-                                                format!("Tool '{}' returned result:\n\n{}", tool_name, result_text)
-                                            )
-                                        );
+                                        // Also add the result to the chat history for the AI,
+                                        // native tool calls get it through the tool message instead
+                                        if tool_call_id.is_none() {
+                                            messages.write().push(
+                                                Message::new(
+                                                    MessageRole::System,
+                                                    format!("Tool '{}' returned result:\n\n{}", tool_name, result_text)
+                                                )
+                                            );
+                                        }
                                     }
                                 },
                                 Err(e) => {
@@ -1101,20 +1109,29 @@ pub fn ChatTab(
                                         messages.write()[message_idx] = Message::new(
                                             MessageRole::Tool,
                                             format!("Tool execution failed: {}", tool_name)
-                                        ).with_tool_interaction(
+                                        ).with_tool_call_id(tool_call_id.clone()).with_tool_interaction(
                                             ToolInteraction::Execution {
                                                 tool_name,
                                                 arguments: recovered,
                                                 status: ToolExecutionStatus::Failed(format!("{}", e)),
                                                 result: None,
                                                 message_idx,
+                                                step: step.clone(),
                                             }
                                         );
                                     }
                                 }
                             }
+                            
+                            // Let the model continue the agent run with the result
+                            if tool_call_id.is_some() {
+                                agent_loop.continue_run().await;
+                            }
                         }
                     });
+                    if tool_call_id.is_some() {
+                        stream_task.set(Some(task));
+                    }
                 },
                 ValidationState::Invalid { errors, alternative_tools, .. } => {
                     // Add message about validation failure
@@ -1210,8 +1227,243 @@ pub fn ChatTab(
                 if let Some(msg) = messages.write().get_mut(message_idx) {
                     msg.tool_interaction = None;
                 }
+                
+                // Rejecting a tool ends the agent run
+                agent_run.set(None);
+            }
+        }
+    };
+    
+    // Refuse a tool call the approval rules deny, like the user rejecting it
+    let deny_tool = move |message_idx: usize, tool_name: String, reason: String| {
+        messages.write().push(
+            Message::new(
+                MessageRole::System,
+                format!("Tool '{}' blocked by approval {}", tool_name, reason)
+            )
+        );
+        if let Some(msg) = messages.write().get_mut(message_idx) {
+            msg.tool_interaction = None;
+        }
+        agent_run.set(None);
+    };
+    
+    // Run a tool and let it run without asking for the rest of the conversation
+    let always_allow_tool = move |(tool_name, arguments, tool_call_id): (String, Value, Option<String>)| {
+        let key = {
+            let state = mcp_state.read();
+            ApprovalRequest::for_tool(&tool_name, &state.tool_registry, state.inspector.annotations()).key()
+        };
+        if !conversation.read().allowed_tools.contains(&key) {
+            info!("Allowing {} for the rest of the conversation", key);
+            conversation.write().allowed_tools.push(key);
+        }
+        execute_tool((tool_name, arguments, tool_call_id));
+    };
+    
+    // Apply the approval rules to every new tool suggestion: run it, leave it to the user or refuse it
+    use_effect(move || {
+        let pending: Vec<(usize, String, MessageRole, String, Value, Option<String>)> = messages
+            .read()
+            .iter()
+            .enumerate()
+            .filter(|(_, msg)| !msg.streaming && !policy_applied.peek().contains(&msg.id))
+            .filter_map(|(idx, msg)| match &msg.tool_interaction {
+                Some(ToolInteraction::Suggestion { tool_name, suggested_args, .. }) => Some((
+                    idx,
+                    msg.id.clone(),
+                    msg.role.clone(),
+                    tool_name.clone(),
+                    suggested_args.clone(),
+                    msg.tool_calls.first().map(|call| call.id.clone()),
+                )),
+                _ => None,
+            })
+            .collect();
+        
+        for (message_idx, message_id, role, tool_name, arguments, tool_call_id) in pending {
+            policy_applied.write().insert(message_id);
+            let decision = {
+                let state = mcp_state.peek();
+                let request = ApprovalRequest::for_tool(&tool_name, &state.tool_registry, state.inspector.annotations());
+                approvals::decide(&state.server_configs.approval_rules, &request, &conversation.peek().allowed_tools)
+            };
+            
+            match decision {
+                // Alternatives offered after a failed call wait for the user, whatever the rules say
+                ApprovalDecision::Allow(reason) if role == MessageRole::Assistant => {
+                    info!("Running {} without asking, {}", tool_name, reason);
+                    execute_tool((tool_name, arguments, tool_call_id));
+                }
+                ApprovalDecision::Deny(reason) => {
+                    info!("Not running {}, denied by {}", tool_name, reason);
+                    deny_tool(message_idx, tool_name, reason);
+                }
+                _ => {}
+            }
+        }
+    });
+    
+    // Save the conversation whenever a reply has settled
+    use_effect(move || {
+        if *is_sending.read() {
+            return;
+        }
+        
+        let current_messages = messages.read().clone();
+        if current_messages.is_empty() || *saved_messages.peek() == current_messages {
+            return;
+        }
+        
+        let mut servers: Vec<String> = mcp_state.peek().active_clients.keys().cloned().collect();
+        servers.sort();
+        
+        let mut saved = conversation.peek().clone();
+        saved.messages = current_messages.clone();
+        saved.model = model_selection.peek().selected_model.clone();
+        saved.servers = servers;
+        saved.updated_at = chrono::Utc::now();
+        saved.derive_title();
+        
+        match store.peek().save(&saved) {
+            Ok(()) => {
+                saved.messages.clear();
+                conversation.set(saved);
+                saved_messages.set(current_messages);
+                *conversation_revision.write() += 1;
+            }
+            Err(e) => error!("Failed to save conversation: {}", e),
+        }
+    });
+    
+    let new_conversation = move |_| {
+        if *is_sending.read() {
+            return;
+        }
+        
+        agent_run.set(None);
+        messages.set(Vec::new());
+        saved_messages.set(Vec::new());
+        conversation.set(Conversation::new(model_selection.read().selected_model.clone()));
+    };
+    
+    let select_conversation = move |id: String| {
+        if *is_sending.read() || conversation.read().id == id {
+            return;
+        }
+        
+        let loaded = store.read().load(&id);
+        match loaded {
+            Ok(loaded) => {
+                info!("Resuming conversation {} with {} messages", loaded.id, loaded.messages.len());
+                agent_run.set(None);
+                policy_applied.write().extend(loaded.messages.iter().map(|m| m.id.clone()));
+                model_selection.write().selected_model = loaded.model.clone();
+                saved_messages.set(loaded.messages.clone());
+                messages.set(loaded.messages.clone());
+                conversation.set(Conversation { messages: Vec::new(), ..loaded });
+            }
+            Err(e) => error!("Failed to load conversation {}: {}", id, e),
+        }
+    };
+    
+    let rename_conversation = move |(id, title): (String, String)| {
+        let renamed = store.read().rename(&id, &title);
+        match renamed {
+            Ok(()) => {
+                if conversation.read().id == id {
+                    conversation.write().title = title;
+                }
+                *conversation_revision.write() += 1;
+            }
+            Err(e) => error!("Failed to rename conversation {}: {}", id, e),
+        }
+    };
+    
+    let delete_conversation = move |id: String| {
+        let deleted = store.read().delete(&id);
+        if let Err(e) = deleted {
+            error!("Failed to delete conversation {}: {}", id, e);
+            return;
+        }
+        
+        // Deleting the open conversation starts a fresh one
+        if conversation.read().id == id {
+            agent_run.set(None);
+            messages.set(Vec::new());
+            saved_messages.set(Vec::new());
+            conversation.set(Conversation::new(model_selection.read().selected_model.clone()));
+        }
+        *conversation_revision.write() += 1;
+    };
+    
+    // Insert filled in prompts, the final user message goes into the input for review
+    use_effect(move || {
+        if prompt_messages.read().is_empty() || *is_sending.read() {
+            return;
+        }
+        
+        let mut inbox = prompt_messages;
+        let mut inserted = std::mem::take(&mut *inbox.write());
+        info!("Inserting {} prompt messages into the conversation", inserted.len());
+        
+        if inserted.last().map(|m| m.role == MessageRole::User).unwrap_or(false) {
+            if let Some(last) = inserted.pop() {
+                input.set(last.content);
+            }
+        }
+        messages.write().extend(inserted);
+    });
+    
+    let export_conversation = move |format: ExportFormat| {
+        let mut exported = conversation.read().clone();
+        exported.messages = messages.read().clone();
+        exported.model = model_selection.read().selected_model.clone();
+        
+        if exported.messages.is_empty() {
+            transfer_notice.set(Some("Nothing to export yet".to_string()));
+            return;
+        }
+        
+        match conversation_export::write_export(&conversation_export::default_export_dir(), &exported, format) {
+            Ok(path) => transfer_notice.set(Some(format!("Exported to {}", path.display()))),
+            Err(e) => {
+                error!("Failed to export conversation: {}", e);
+                transfer_notice.set(Some(format!("Export failed: {}", e)));
+            }
+        }
+    };
+    
+    let import_conversation = move |json: String| {
+        if *is_sending.read() {
+            return;
+        }
+        
+        let imported = match conversation_export::from_json(&json) {
+            Ok(imported) => imported,
+            Err(e) => {
+                warn!("Rejected conversation import: {}", e);
+                transfer_notice.set(Some(format!("Import failed: {}", e)));
+                return;
             }
+        };
+        
+        let saved = store.read().save(&imported);
+        if let Err(e) = saved {
+            error!("Failed to save imported conversation: {}", e);
+            transfer_notice.set(Some(format!("Import failed: {}", e)));
+            return;
         }
+        
+        info!("Imported conversation {} with {} messages", imported.id, imported.messages.len());
+        transfer_notice.set(Some(format!("Imported \"{}\"", imported.title)));
+        agent_run.set(None);
+        policy_applied.write().extend(imported.messages.iter().map(|m| m.id.clone()));
+        model_selection.write().selected_model = imported.model.clone();
+        saved_messages.set(imported.messages.clone());
+        messages.set(imported.messages.clone());
+        conversation.set(Conversation { messages: Vec::new(), ..imported });
+        *conversation_revision.write() += 1;
     };
     
     // Handle Enter key
@@ -1223,193 +1475,468 @@ pub fn ChatTab(
         }
     };
     
-    // Clone mcp_tools again for the UI to avoid move errors
+    // Clone the tools again for the UI to avoid move errors
     let tools_for_ui = tools.read().clone();
 
     // UI Rendering
     rsx! {
-        div { class: "chat-container",
-            // Model selector section
-            div { class: "model-selector",
-                div { class: "model-selector-header",
-                    h3 { class: "model-title", "Select AI Model" }
-                    button {
-                        class: "refresh-models-button",
-                        disabled: model_selection.read().loading,
-                        onclick: move |_| retry_load_models(()),
-                        svg {
-                            class: "refresh-icon",
-                            xmlns: "http://www.w3.org/2000/svg",
-                            width: "16",
-                            height: "16",
-                            view_box: "0 0 24 24",
-                            fill: "none",
-                            stroke: "currentColor",
-                            stroke_width: "2",
-                            stroke_linecap: "round",
-                            stroke_linejoin: "round",
-                            path { d: "M23 4v6h-6" }
-                            path { d: "M1 20v-6h6" }
-                            path { d: "M3.51 9a9 9 0 0 1 14.85-3.36L23 10M1 14l4.64 4.36A9 9 0 0 0 20.49 15" }
+        div { class: "chat-layout",
+            ConversationSidebar {
+                store: store.read().clone(),
+                current_id: conversation.read().id.clone(),
+                revision: *conversation_revision.read(),
+                disabled: *is_sending.read(),
+                on_select: select_conversation,
+                on_new: new_conversation,
+                on_rename: rename_conversation,
+                on_delete: delete_conversation,
+                on_export: export_conversation,
+                on_import: import_conversation,
+                notice: transfer_notice.read().clone(),
+            }
+            div { class: "chat-container",
+                // Model selector section
+                div { class: "model-selector",
+                    div { class: "model-selector-header",
+                        h3 { class: "model-title", "Select AI Model" }
+                        button {
+                            class: "refresh-models-button",
+                            disabled: model_selection.read().loading,
+                            onclick: move |_| retry_load_models(()),
+                            svg {
+                                class: "refresh-icon",
+                                xmlns: "http://www.w3.org/2000/svg",
+                                width: "16",
+                                height: "16",
+                                view_box: "0 0 24 24",
+                                fill: "none",
+                                stroke: "currentColor",
+                                stroke_width: "2",
+                                stroke_linecap: "round",
+                                stroke_linejoin: "round",
+                                path { d: "M23 4v6h-6" }
+                                path { d: "M1 20v-6h6" }
+                                path { d: "M3.51 9a9 9 0 0 1 14.85-3.36L23 10M1 14l4.64 4.36A9 9 0 0 0 20.49 15" }
+                            }
+                            "Retry"
                         }
-                        "Retry"
                     }
-                }
                 
-                // Add confidence threshold slider
-                div { class: "confidence-controls",
-                    label { for: "confidence-threshold", "Tool Confidence Threshold:" }
-                    input {
-                        id: "confidence-threshold",
-                        class: "confidence-slider",
-                        r#type: "range",
-                        min: "0.1",
-                        max: "1.0",
-                        step: "0.1",
-                        value: "{confidence_threshold}",
-                        oninput: move |evt| {
-                            if let Ok(val) = evt.value().parse::<f64>() {
-                                confidence_threshold.set(val);
+                    // Add confidence threshold slider
+                    div { class: "confidence-controls",
+                        label { for: "confidence-threshold", "Tool Confidence Threshold:" }
+                        input {
+                            id: "confidence-threshold",
+                            class: "confidence-slider",
+                            r#type: "range",
+                            min: "0.1",
+                            max: "1.0",
+                            step: "0.1",
+                            value: "{confidence_threshold}",
+                            oninput: move |evt| {
+                                if let Ok(val) = evt.value().parse::<f64>() {
+                                    confidence_threshold.set(val);
+                                }
+                            }
+                        }
+                        span { class: "confidence-value", "{confidence_threshold}" }
+                    }
+                
+                    // Step limit for chained tool calls
+                    div { class: "confidence-controls",
+                        label { for: "agent-max-steps", "Max Tool Steps per Request:" }
+                        input {
+                            id: "agent-max-steps",
+                            class: "agent-steps-input",
+                            r#type: "number",
+                            min: "1",
+                            max: "20",
+                            value: "{max_agent_steps}",
+                            oninput: move |evt| {
+                                if let Ok(val) = evt.value().parse::<usize>() {
+                                    max_agent_steps.set(val.clamp(1, 20));
+                                }
                             }
                         }
                     }
-                    span { class: "confidence-value", "{confidence_threshold}" }
-                }
                 
-                if let Some(error) = &model_selection.read().error {
-                    div { class: "model-error", 
-                        // Show more user-friendly error message
-                        if error.contains("Using fallback models") {
-                            div {
-                                span { 
-                                    class: "warning-icon",
-                                    svg {
-                                        xmlns: "http://www.w3.org/2000/svg",
-                                        width: "16", 
-                                        height: "16",
-                                        view_box: "0 0 24 24",
-                                        fill: "none",
-                                        stroke: "currentColor",
-                                        stroke_width: "2",
-                                        stroke_linecap: "round",
-                                        stroke_linejoin: "round",
-                                        path { d: "M10.29 3.86L1.82 18a2 2 0 0 0 1.71 3h16.94a2 2 0 0 0 1.71-3L13.71 3.86a2 2 0 0 0-3.42 0z" },
-                                        line { x1: "12", y1: "9", x2: "12", y2: "13" },
-                                        line { x1: "12", y1: "17", x2: "12.01", y2: "17" }
+                    if let Some(error) = &model_selection.read().error {
+                        div { class: "model-error", 
+                            // Show more user-friendly error message
+                            if error.contains("Using fallback models") {
+                                div {
+                                    span { 
+                                        class: "warning-icon",
+                                        svg {
+                                            xmlns: "http://www.w3.org/2000/svg",
+                                            width: "16", 
+                                            height: "16",
+                                            view_box: "0 0 24 24",
+                                            fill: "none",
+                                            stroke: "currentColor",
+                                            stroke_width: "2",
+                                            stroke_linecap: "round",
+                                            stroke_linejoin: "round",
+                                            path { d: "M10.29 3.86L1.82 18a2 2 0 0 0 1.71 3h16.94a2 2 0 0 0 1.71-3L13.71 3.86a2 2 0 0 0-3.42 0z" },
+                                            line { x1: "12", y1: "9", x2: "12", y2: "13" },
+                                            line { x1: "12", y1: "17", x2: "12.01", y2: "17" }
+                                        }
                                     }
+                                    "API connection error. Using local model data."
                                 }
-                                "API connection error. Using local model data."
+                            } else {
+                                "{error}"
                             }
-                        } else {
-                            "{error}"
                         }
                     }
-                }
-                select {
-                    class: "model-dropdown",
-                    disabled: model_selection.read().loading || model_selection.read().models.is_empty(),
-                    value: "{model_selection.read().selected_model}",
-                    onchange: move |evt| {
-                        let new_model = evt.value().clone();
-                        model_selection.write().selected_model = new_model.clone();
+                    select {
+                        class: "model-dropdown",
+                        disabled: model_selection.read().loading || model_selection.read().models.is_empty(),
+                        value: "{model_selection.read().selected_model}",
+                        onchange: move |evt| {
+                            let new_model = evt.value().clone();
+                            model_selection.write().selected_model = new_model.clone();
                         
-                        // Update the tool_selector with the new model
-                        let api_key = match &api_key_ref {
-                            Some(key) => key.clone(),
-                            None => env::var("OPENROUTER_API_KEY").unwrap_or_default(),
-                        };
+                            // Update the tool_selector with the new model
+                            let api_key = match &api_key_ref {
+                                Some(key) => key.clone(),
+                                None => env::var("OPENROUTER_API_KEY").unwrap_or_default(),
+                            };
                         
-                        // Create a new selector with the updated model
-                        let new_selector = LLMToolSelector::new(api_key, new_model.clone())
-                            .with_cache(cache.read().clone())
-                            .with_max_prompt_tools(25); // Limit to 25 tools per prompt
+                            // Create a new selector with the updated model
+                            let new_selector = LLMToolSelector::new(api_key, new_model.clone())
+                                .with_cache(cache.read().clone())
+                                .with_max_prompt_tools(25); // Limit to 25 tools per prompt
                             
-                        debug!("Updated LLMToolSelector to use model: {}", new_model);
-                        tool_selector.set(new_selector);
-                    },
-                    if model_selection.read().models.is_empty() {
-                        option { value: "", disabled: true,
-                            if model_selection.read().loading {
-                                "Loading models..."
-                            } else {
-                                "No models available"
+                            debug!("Updated LLMToolSelector to use model: {}", new_model);
+                            tool_selector.set(new_selector);
+                        },
+                        if model_selection.read().models.is_empty() {
+                            option { value: "", disabled: true,
+                                if model_selection.read().loading {
+                                    "Loading models..."
+                                } else {
+                                    "No models available"
+                                }
+                            }
+                        } else {
+                            for model in &model_selection.read().models {
+                                option { value: "{model.id}", "{model.name}" }
+                            }
+                        }
+                    }
+                }
+                // Messages area
+                div { class: "chat-messages",
+                    if messages.read().is_empty() {
+                        div { class: "empty-chat",
+                            div { class: "empty-chat-icon",
+                                svg {
+                                    xmlns: "http://www.w3.org/2000/svg",
+                                    width: "48",
+                                    height: "48",
+                                    view_box: "0 0 24 24",
+                                    fill: "none",
+                                    stroke: "currentColor",
+                                    stroke_width: "1",
+                                    stroke_linecap: "round",
+                                    stroke_linejoin: "round",
+                                    path { d: "M21 15a2 2 0 0 1-2 2H7l-4 4V5a2 2 0 0 1 2-2h14a2 2 0 0 1 2 2z" }
+                                }
+                            }
+                            div { class: "empty-chat-title", "No messages yet" }
+                            div { class: "empty-chat-subtitle",
+                                "Start a conversation with any of the available AI models"
                             }
                         }
                     } else {
-                        for model in &model_selection.read().models {
-                            option { value: "{model.id}", "{model.name}" }
+                        for message in messages.read().iter() {
+                            MessageView { 
+                                message: message.clone(),
+                                tools: tools_for_ui.clone(),
+                                on_tool_execute: execute_tool,
+                                on_tool_cancel: cancel_tool,
+                                on_tool_always_allow: always_allow_tool,
+                            }
                         }
                     }
                 }
-            }
-            // Messages area
-            div { class: "chat-messages",
-                if messages.read().is_empty() {
-                    div { class: "empty-chat",
-                        div { class: "empty-chat-icon",
+                // Resources attached to the next message
+                if !attachments.read().is_empty() {
+                    div { class: "chat-attachments",
+                        for (idx, attachment) in attachments.read().iter().enumerate() {
+                            span {
+                                key: "{attachment.uri}",
+                                class: "attachment-chip",
+                                title: "{attachment.uri}",
+                                "📎 {attachment.name} · ≈ {attachment.estimated_tokens()} tokens"
+                                button {
+                                    class: "attachment-remove",
+                                    title: "Remove attachment",
+                                    disabled: *is_sending.read(),
+                                    onclick: move |_| {
+                                        let mut attachments = attachments;
+                                        attachments.write().remove(idx);
+                                    },
+                                    "×"
+                                }
+                            }
+                        }
+                        span { class: "attachment-estimate",
+                            {format!("≈ {} tokens of context", attachments.read().iter().map(|a| a.estimated_tokens()).sum::<usize>())}
+                        }
+                    }
+                }
+                // Input area
+                div { class: "chat-input-container",
+                    textarea {
+                        class: "chat-input",
+                        placeholder: "Type your message...",
+                        value: "{input}",
+                        disabled: *is_sending.read(),
+                        oninput: move |evt| input.set(evt.value().clone()),
+                        onkeydown: handle_keydown,
+                    }
+                    if *is_sending.read() {
+                        button {
+                            class: "chat-send-button chat-stop-button",
+                            title: "Stop generating",
+                            onclick: stop_generation,
                             svg {
                                 xmlns: "http://www.w3.org/2000/svg",
-                                width: "48",
-                                height: "48",
+                                width: "20",
+                                height: "20",
+                                view_box: "0 0 24 24",
+                                fill: "currentColor",
+                                stroke: "none",
+                                rect {
+                                    x: "6",
+                                    y: "6",
+                                    width: "12",
+                                    height: "12",
+                                    rx: "2",
+                                }
+                            }
+                        }
+                    } else {
+                        button {
+                            class: "chat-send-button",
+                            disabled: input.read().trim().is_empty(),
+                            onclick: move |_| send_message(()),
+                            svg {
+                                xmlns: "http://www.w3.org/2000/svg",
+                                width: "20",
+                                height: "20",
                                 view_box: "0 0 24 24",
                                 fill: "none",
                                 stroke: "currentColor",
-                                stroke_width: "1",
+                                stroke_width: "2",
                                 stroke_linecap: "round",
                                 stroke_linejoin: "round",
-                                path { d: "M21 15a2 2 0 0 1-2 2H7l-4 4V5a2 2 0 0 1 2-2h14a2 2 0 0 1 2 2z" }
+                                line {
+                                    x1: "22",
+                                    y1: "2",
+                                    x2: "11",
+                                    y2: "13",
+                                }
+                                polygon { points: "22 2 15 22 11 13 2 9 22 2" }
                             }
                         }
-                        div { class: "empty-chat-title", "No messages yet" }
-                        div { class: "empty-chat-subtitle",
-                            "Start a conversation with any of the available AI models"
-                        }
-                    }
-                } else {
-                    for message in messages.read().iter() {
-                        MessageView { 
-                            message: message.clone(),
-                            tools: tools_for_ui.clone(),
-                            on_tool_execute: execute_tool,
-                            on_tool_cancel: cancel_tool,
-                        }
                     }
                 }
             }
-            // Input area
-            div { class: "chat-input-container",
-                textarea {
-                    class: "chat-input",
-                    placeholder: "Type your message...",
-                    value: "{input}",
-                    disabled: *is_sending.read(),
-                    oninput: move |evt| input.set(evt.value().clone()),
-                    onkeydown: handle_keydown,
-                }
-                button {
-                    class: "chat-send-button",
-                    disabled: *is_sending.read() || input.read().trim().is_empty(),
-                    onclick: move |_| send_message(()),
-                    svg {
-                        xmlns: "http://www.w3.org/2000/svg",
-                        width: "20",
-                        height: "20",
-                        view_box: "0 0 24 24",
-                        fill: "none",
-                        stroke: "currentColor",
-                        stroke_width: "2",
-                        stroke_linecap: "round",
-                        stroke_linejoin: "round",
-                        line {
-                            x1: "22",
-                            y1: "2",
-                            x2: "11",
-                            y2: "13",
-                        }
-                        polygon { points: "22 2 15 22 11 13 2 9 22 2" }
+        }
+    }
+}
+
+/// Finalize a streamed assistant message and attach the tool call it requested
+///
+/// Returns whether a tool call was attached.
+fn finish_streamed_message(messages: &mut Vec<Message>, message_idx: usize, tool_calls: &[ToolCall], tools: &[Tool], names: &FunctionNames) -> bool {
+    let Some(message) = messages.get_mut(message_idx) else {
+        return false;
+    };
+    message.streaming = false;
+    
+    // The request asks for a single call per turn, so only the first one is used
+    let Some(tool_call) = tool_calls.first() else {
+        // Nothing arrived before the stream ended, drop the empty placeholder
+        if message.content.trim().is_empty() {
+            messages.remove(message_idx);
+        }
+        return false;
+    };
+    
+    match ToolManager::find_tool_by_function_name(&tool_call.function.name, names, tools) {
+        Some(tool) => {
+            info!("Model requested tool call {} for '{}'", tool_call.id, tool.name);
+            
+            if message.content.trim().is_empty() {
+                message.content = format!("I'll help you with that using the `{}` tool.\n\nWould you like me to proceed?", tool.name);
+            }
+            message.tool_calls = vec![tool_call.clone()];
+            message.tool_interaction = Some(ToolInteraction::Suggestion {
+                tool_name: tool.name.clone(),
+                suggested_args: tool_call.parsed_arguments(),
+                message_idx,
+            });
+            true
+        }
+        None => {
+            warn!("Model requested unknown function '{}'", tool_call.function.name);
+            
+            if message.content.trim().is_empty() {
+                messages.remove(message_idx);
+            }
+            messages.push(Message::new(
+                MessageRole::System,
+                format!("The model requested the '{}' tool, but it's not available on any running server.", tool_call.function.name)
+            ));
+            false
+        }
+    }
+}
+
+/// System prompt for requests that offer tools through native function calling
+fn native_system_prompt(tools: &[Tool], conf_threshold: f64) -> String {
+    if tools.is_empty() {
+        return "You are a helpful AI assistant. No MCP tools are currently available, so do not offer to use any.".to_string();
+    }
+    
+    format!(
+        "You are a helpful AI assistant with access to tools from MCP servers. Call a tool when it directly helps with the user's request. The user approves every tool call before it runs. Tool results are sent back to you, so you can call further tools until you can give a final answer.\nIMPORTANT: The user's confidence threshold is set to {}. Only call tools when you are confident they will help address the user's query directly.",
+        conf_threshold
+    )
+}
+
+/// Stream a native function calling reply into the message at `message_idx`
+///
+/// The placeholder at `message_idx` becomes the assistant message tokens are appended
+/// to. Returns whether the reply requested a tool call. An error is only returned when
+/// the request could not be started, errors during the stream are shown in the chat.
+async fn stream_native_reply(
+    client: &OpenRouterClient,
+    model: &str,
+    request_messages: Vec<ChatMessage>,
+    tools: &[Tool],
+    tool_choice: ToolChoice,
+    mut messages: Signal<Vec<Message>>,
+    message_idx: usize,
+) -> Result<bool, OpenRouterError> {
+    // The reply calls tools by the names they were offered under
+    let names = FunctionNames::new(tools);
+    let mut stream = client.chat_completion_stream(
+        model,
+        request_messages,
+        Some(ToolDefinition::from_tools(tools, &names)),
+        tool_choice,
+        Some(0.7), // temperature
+        Some(1000), // max tokens
+    ).await?;
+    
+    if message_idx < messages.read().len() {
+        messages.write()[message_idx] = Message::new(MessageRole::Assistant, String::new()).streaming();
+    }
+    
+    let mut reply = StreamAccumulator::new();
+    while let Some(event) = stream.next().await {
+        match event {
+            Ok(event) => {
+                if let StreamEvent::Content(text) = &event {
+                    if let Some(msg) = messages.write().get_mut(message_idx) {
+                        msg.content.push_str(text);
                     }
                 }
+                reply.apply(&event);
+            }
+            Err(e) => {
+                error!("Error while streaming chat completion: {}", e);
+                messages.write().push(Message::new(
+                    MessageRole::System,
+                    format!("Error: {}", e),
+                ));
+                break;
             }
         }
     }
-}
\ No newline at end of file
+    
+    debug!("Streaming finished with reason {:?}", reply.finish_reason);
+    
+    // Tool calls are ignored when the model was asked for a final answer
+    let tool_calls = match tool_choice {
+        ToolChoice::Auto => reply.complete_tool_calls(),
+        ToolChoice::None => Vec::new(),
+    };
+    Ok(finish_streamed_message(&mut messages.write(), message_idx, &tool_calls, tools, &names))
+}
+
+/// Calls the model again after a native tool call ran, so it can chain steps
+#[derive(Clone, Copy)]
+struct AgentLoop {
+    client: Signal<OpenRouterClient>,
+    model_selection: Signal<ModelSelection>,
+    tools: Memo<Vec<Tool>>,
+    confidence_threshold: Signal<f64>,
+    agent_run: Signal<Option<AgentRun>>,
+    messages: Signal<Vec<Message>>,
+    is_sending: Signal<bool>,
+}
+
+impl AgentLoop {
+    /// Send the tool results back to the model and handle its next reply
+    async fn continue_run(mut self) {
+        let Some(run) = self.agent_run.read().clone() else {
+            debug!("No agent run in progress, not calling the model again");
+            self.is_sending.set(false);
+            return;
+        };
+        
+        let decision = run.decision();
+        if decision == AgentDecision::FinalAnswer {
+            info!("Agent run {} reached its limit of {} steps", run.id, run.max_steps);
+            self.messages.write().push(Message::new(
+                MessageRole::System,
+                format!("Reached the limit of {} tool steps, asking the model for a final answer.", run.max_steps)
+            ));
+        }
+        
+        self.is_sending.set(true);
+        let placeholder_idx = self.messages.read().len();
+        self.messages.write().push(Message::new(MessageRole::Thinking, String::new()));
+        
+        let client = self.client.read().clone();
+        let model = self.model_selection.read().selected_model.clone();
+        let tools = self.tools.read().clone();
+        let tool_choice = match decision {
+            AgentDecision::Continue => ToolChoice::Auto,
+            AgentDecision::FinalAnswer => ToolChoice::None,
+        };
+        
+        let mut request_messages = vec![ChatMessage::new("system", native_system_prompt(&tools, *self.confidence_threshold.read()))];
+        request_messages.extend(Message::history_for_model(&self.messages.read()));
+        
+        match stream_native_reply(&client, &model, request_messages, &tools, tool_choice, self.messages, placeholder_idx).await {
+            Ok(true) => {
+                debug!("Agent run {} continues after step {}", run.id, run.steps_taken);
+            }
+            Ok(false) => {
+                info!("Agent run {} finished after {} steps", run.id, run.steps_taken);
+                self.agent_run.set(None);
+            }
+            Err(e) => {
+                error!("Error continuing agent run {}: {}", run.id, e);
+                
+                // Replace thinking message with error
+                if placeholder_idx < self.messages.read().len() {
+                    self.messages.write()[placeholder_idx] = Message::new(
+                        MessageRole::System,
+                        format!("Error: {}", e),
+                    );
+                }
+                self.agent_run.set(None);
+            }
+        }
+        
+        self.is_sending.set(false);
+    }
+}
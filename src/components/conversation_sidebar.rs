@@ -0,0 +1,203 @@
+use dioxus::prelude::*;
+use crate::conversation_export::ExportFormat;
+use crate::conversation_store::{ConversationStore, ConversationSummary};
+use tracing::error;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ConversationSidebarProps {
+    pub store: ConversationStore,
+    /// Id of the conversation shown in the chat
+    pub current_id: String,
+    /// Bumped by the chat whenever a conversation was saved, so the list reloads
+    pub revision: u64,
+    /// Disables switching while a reply is still being generated
+    #[props(default)]
+    pub disabled: bool,
+    pub on_select: EventHandler<String>,
+    pub on_new: EventHandler<()>,
+    pub on_rename: EventHandler<(String, String)>,
+    pub on_delete: EventHandler<String>,
+    pub on_export: EventHandler<ExportFormat>,
+    /// Receives the contents of an imported JSON export
+    pub on_import: EventHandler<String>,
+    /// Outcome of the last export or import
+    #[props(default)]
+    pub notice: Option<String>,
+}
+
+/// Sidebar listing saved conversations with search, rename, delete, export and import
+#[component]
+pub fn ConversationSidebar(props: ConversationSidebarProps) -> Element {
+    let mut query = use_signal(String::new);
+    // Conversation being renamed and the title typed so far
+    let mut editing = use_signal(|| None::<(String, String)>);
+
+    // Searching reads every saved conversation, so it runs off the UI thread and only
+    // again once the query changed or a conversation was saved
+    let search = use_resource(use_reactive((&props.store, &props.revision), move |(store, _revision)| async move {
+        let query = query();
+        let result = match tokio::task::spawn_blocking(move || store.search(&query)).await {
+            Ok(result) => result.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        result.unwrap_or_else(|e| {
+            error!("Failed to list conversations: {}", e);
+            Vec::new()
+        })
+    }));
+    let summaries: Vec<ConversationSummary> = search.read().clone().unwrap_or_default();
+
+    let mut commit_rename = move |on_rename: EventHandler<(String, String)>| {
+        if let Some((id, title)) = editing.write().take() {
+            if !title.trim().is_empty() {
+                on_rename.call((id, title.trim().to_string()));
+            }
+        }
+    };
+
+    rsx! {
+        div { class: "conversation-sidebar",
+            div { class: "conversation-sidebar-header",
+                h3 { "Conversations" }
+                button {
+                    class: "new-conversation-button",
+                    disabled: props.disabled,
+                    onclick: move |_| props.on_new.call(()),
+                    "+ New"
+                }
+            }
+            input {
+                class: "conversation-search",
+                r#type: "search",
+                placeholder: "Search conversations...",
+                value: "{query}",
+                oninput: move |evt| query.set(evt.value()),
+            }
+            div { class: "conversation-list",
+                if summaries.is_empty() {
+                    div { class: "conversation-empty",
+                        if query.read().is_empty() { "No saved conversations" } else { "No matches" }
+                    }
+                }
+                for summary in summaries {
+                    {
+                        let id = summary.id.clone();
+                        let is_current = summary.id == props.current_id;
+                        let is_editing = editing.read().as_ref().map(|(edit_id, _)| edit_id == &summary.id).unwrap_or(false);
+                        let servers = if summary.servers.is_empty() {
+                            "no servers".to_string()
+                        } else {
+                            summary.servers.join(", ")
+                        };
+                        let updated = summary.updated_at.format("%Y-%m-%d %H:%M").to_string();
+
+                        rsx! {
+                            div {
+                                key: "{summary.id}",
+                                class: if is_current { "conversation-item current" } else { "conversation-item" },
+                                if is_editing {
+                                    input {
+                                        class: "conversation-rename-input",
+                                        value: editing.read().as_ref().map(|(_, title)| title.clone()).unwrap_or_default(),
+                                        autofocus: true,
+                                        oninput: move |evt| {
+                                            if let Some((_, title)) = editing.write().as_mut() {
+                                                *title = evt.value();
+                                            }
+                                        },
+                                        onkeydown: move |evt: KeyboardEvent| {
+                                            match evt.key().to_string().as_str() {
+                                                "Enter" => commit_rename(props.on_rename),
+                                                "Escape" => editing.set(None),
+                                                _ => {}
+                                            }
+                                        },
+                                        onblur: move |_| commit_rename(props.on_rename),
+                                    }
+                                } else {
+                                    div {
+                                        class: "conversation-item-main",
+                                        onclick: {
+                                            let id = id.clone();
+                                            move |_| {
+                                                if !props.disabled {
+                                                    props.on_select.call(id.clone());
+                                                }
+                                            }
+                                        },
+                                        div { class: "conversation-title", "{summary.title}" }
+                                        div { class: "conversation-meta",
+                                            "{summary.model} · {servers}"
+                                        }
+                                        div { class: "conversation-meta",
+                                            "{updated} · {summary.message_count} messages"
+                                        }
+                                    }
+                                    div { class: "conversation-actions",
+                                        button {
+                                            class: "conversation-action",
+                                            title: "Rename",
+                                            onclick: {
+                                                let id = id.clone();
+                                                let title = summary.title.clone();
+                                                move |_| editing.set(Some((id.clone(), title.clone())))
+                                            },
+                                            "✎"
+                                        }
+                                        button {
+                                            class: "conversation-action delete",
+                                            title: "Delete",
+                                            disabled: props.disabled && is_current,
+                                            onclick: {
+                                                let id = id.clone();
+                                                move |_| props.on_delete.call(id.clone())
+                                            },
+                                            "×"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            div { class: "conversation-transfer",
+                button {
+                    class: "conversation-transfer-button",
+                    title: "Export the open conversation as a Markdown transcript",
+                    onclick: move |_| props.on_export.call(ExportFormat::Markdown),
+                    "Export .md"
+                }
+                button {
+                    class: "conversation-transfer-button",
+                    title: "Export the open conversation as JSON that can be imported again",
+                    onclick: move |_| props.on_export.call(ExportFormat::Json),
+                    "Export .json"
+                }
+                label {
+                    class: if props.disabled { "conversation-transfer-button disabled" } else { "conversation-transfer-button" },
+                    title: "Import a conversation from a JSON export",
+                    "Import"
+                    input {
+                        r#type: "file",
+                        accept: ".json,application/json",
+                        disabled: props.disabled,
+                        style: "display: none;",
+                        onchange: move |evt| async move {
+                            let Some(engine) = evt.files() else { return };
+                            for name in engine.files() {
+                                match engine.read_file_to_string(&name).await {
+                                    Some(json) => props.on_import.call(json),
+                                    None => error!("Failed to read imported file {}", name),
+                                }
+                            }
+                        },
+                    }
+                }
+            }
+            if let Some(notice) = &props.notice {
+                div { class: "conversation-notice", "{notice}" }
+            }
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use dioxus::prelude::*;
 use crate::components::tool_suggestion::{ToolSuggestion, ToolSuggestionProps, ToolExecution, ToolExecutionProps, ToolExecutionStatus};
 use crate::components::tool_manager::{ToolManager, ToolInteraction};
+use crate::resources::ResourceAttachment;
+use crate::openrouter::{ChatMessage, ToolCall};
 use mcp_core::Tool;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MessageRole {
     User,
     Assistant,
@@ -13,7 +16,7 @@ pub enum MessageRole {
     Tool,  // New role for tool messages
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Message {
     pub id: String,
     pub role: MessageRole,
@@ -21,6 +24,18 @@ pub struct Message {
     pub timestamp: chrono::DateTime<chrono::Utc>,
     // Optional tool interaction associated with this message
     pub tool_interaction: Option<ToolInteraction>,
+    // Native tool calls requested by the model in an assistant message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    // Id of the native tool call a tool message answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    // Resources attached to a user message, sent to the model as context
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<ResourceAttachment>,
+    // Whether tokens are still being appended to this message
+    #[serde(skip)]
+    pub streaming: bool,
 }
 
 impl Message {
@@ -31,6 +46,10 @@ impl Message {
             content,
             timestamp: chrono::Utc::now(),
             tool_interaction: None,
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+            attachments: Vec::new(),
+            streaming: false,
         }
     }
     
@@ -39,7 +58,48 @@ impl Message {
         self
     }
     
-    pub fn to_openrouter_format(&self) -> crate::openrouter::ChatMessage {
+    /// Mark the message as receiving streamed tokens
+    pub fn streaming(mut self) -> Self {
+        self.streaming = true;
+        self
+    }
+    
+    pub fn with_tool_calls(mut self, tool_calls: Vec<ToolCall>) -> Self {
+        self.tool_calls = tool_calls;
+        self
+    }
+    
+    /// Link a tool message to the native tool call it answers, if any
+    pub fn with_tool_call_id(mut self, tool_call_id: Option<String>) -> Self {
+        self.tool_call_id = tool_call_id;
+        self
+    }
+    
+    pub fn with_attachments(mut self, attachments: Vec<ResourceAttachment>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+    
+    /// The message content followed by the contents of its attached resources
+    pub fn content_with_attachments(&self) -> String {
+        if self.attachments.is_empty() {
+            return self.content.clone();
+        }
+        
+        let context: Vec<String> = self.attachments.iter().map(|a| a.context_text()).collect();
+        format!("{}\n\nAttached resources:\n{}", self.content, context.join("\n"))
+    }
+    
+    /// The text sent back to the model for a tool message
+    pub fn tool_result_text(&self) -> String {
+        match &self.tool_interaction {
+            Some(ToolInteraction::Execution { result: Some(result), .. }) => result.clone(),
+            Some(ToolInteraction::Execution { status: ToolExecutionStatus::Failed(e), .. }) => format!("Error: {}", e),
+            _ => self.content.clone(),
+        }
+    }
+    
+    pub fn to_openrouter_format(&self) -> ChatMessage {
         let role = match self.role {
             MessageRole::User => "user",
             MessageRole::Assistant => "assistant",
@@ -48,10 +108,50 @@ impl Message {
             MessageRole::Tool => "system", // Tool messages will be injected as system messages
         };
         
-        crate::openrouter::ChatMessage {
-            role: role.to_string(),
-            content: self.content.clone(),
+        match (&self.role, &self.tool_call_id) {
+            (MessageRole::Tool, Some(tool_call_id)) => {
+                ChatMessage::tool_result(tool_call_id.clone(), self.tool_result_text())
+            }
+            (MessageRole::Assistant, _) if !self.tool_calls.is_empty() => {
+                ChatMessage::assistant_tool_calls(self.content.clone(), self.tool_calls.clone())
+            }
+            _ => ChatMessage::new(role, self.content_with_attachments()),
+        }
+    }
+    
+    /// Build the chat history sent to the model
+    ///
+    /// Thinking messages and legacy tool messages are left out. The API requires
+    /// every native tool call to be answered by a tool message right after the
+    /// assistant message, so answered calls are paired with their results and
+    /// unanswered calls (rejected or still pending) are dropped from the request.
+    pub fn history_for_model(messages: &[Message]) -> Vec<ChatMessage> {
+        let mut history = Vec::new();
+        
+        for (idx, msg) in messages.iter().enumerate() {
+            match msg.role {
+                MessageRole::Thinking | MessageRole::Tool => continue,
+                MessageRole::Assistant if !msg.tool_calls.is_empty() => {
+                    let results: Vec<&Message> = msg.tool_calls.iter()
+                        .filter_map(|call| {
+                            messages[idx + 1..].iter().find(|m| {
+                                m.role == MessageRole::Tool && m.tool_call_id.as_deref() == Some(call.id.as_str())
+                            })
+                        })
+                        .collect();
+                    
+                    if results.len() == msg.tool_calls.len() {
+                        history.push(msg.to_openrouter_format());
+                        history.extend(results.iter().map(|result| result.to_openrouter_format()));
+                    } else if !msg.content.trim().is_empty() {
+                        history.push(ChatMessage::new("assistant", msg.content.clone()));
+                    }
+                }
+                _ => history.push(msg.to_openrouter_format()),
+            }
         }
+        
+        history
     }
 }
 
@@ -59,10 +159,18 @@ impl Message {
 pub struct MessageViewProps {
     pub message: Message,
     pub tools: Vec<Tool>,
-    pub on_tool_execute: EventHandler<(String, Value)>,
+    /// Called with the tool name, arguments and the id of the native tool call, if any
+    pub on_tool_execute: EventHandler<(String, Value, Option<String>)>,
     pub on_tool_cancel: EventHandler<usize>,
+    /// Called like `on_tool_execute` when the tool may run without asking for the rest of the conversation
+    pub on_tool_always_allow: EventHandler<(String, Value, Option<String>)>,
 }
 
+/// Renders one chat message
+///
+/// Message content, tool arguments and tool results are rendered as text nodes, which escapes them.
+/// Tool arguments are no longer escaped when they are validated, so none of them may be rendered as
+/// markup with `dangerous_inner_html`.
 #[component]
 pub fn MessageView(props: MessageViewProps) -> Element {
     // Clone the message to own it fully
@@ -401,6 +509,23 @@ pub fn MessageView(props: MessageViewProps) -> Element {
                                 rsx! {}
                             }
                         })}
+                        
+                        // Caret shown while tokens are still arriving
+                        if message.streaming {
+                            span { class: "streaming-caret" }
+                        }
+                    }
+                    
+                    if !message.attachments.is_empty() {
+                        div { class: "message-attachments",
+                            for attachment in message.attachments.iter() {
+                                span {
+                                    class: "attachment-chip",
+                                    title: "{attachment.uri}",
+                                    "📎 {attachment.name}"
+                                }
+                            }
+                        }
                     }
                     
                     // Render tool interactions if present
@@ -412,13 +537,21 @@ pub fn MessageView(props: MessageViewProps) -> Element {
                                     let tool_name_clone = tool_name.clone();
                                     let args_clone = suggested_args.clone();
                                     let msg_idx = *message_idx;
+                                    let tool_call_id = message.tool_calls.first().map(|call| call.id.clone());
+                                    let always_allow = {
+                                        let tool_call_id = tool_call_id.clone();
+                                        EventHandler::new(move |(name, args): (String, Value)| {
+                                            props.on_tool_always_allow.call((name, args, tool_call_id.clone()))
+                                        })
+                                    };
                                     
                                     rsx! {
                                         ToolSuggestion {
                                             tool: tool.clone(),
                                             suggested_args: args_clone.clone(),
+                                            on_always_allow: Some(always_allow),
                                             on_execute: move |(name, args)| {
-                                                props.on_tool_execute.call((name, args))
+                                                props.on_tool_execute.call((name, args, tool_call_id.clone()))
                                             },
                                             on_cancel: move |_| {
                                                 props.on_tool_cancel.call(msg_idx)
@@ -429,7 +562,7 @@ pub fn MessageView(props: MessageViewProps) -> Element {
                                     rsx! {}
                                 }
                             },
-                            ToolInteraction::Execution { tool_name, arguments: _, status, result, message_idx: _ } => {
+                            ToolInteraction::Execution { tool_name, arguments: _, status, result, message_idx: _, step } => {
                                 // Clone values for the ToolExecution component
                                 let tool_name_clone = tool_name.clone();
                                 let status_clone = status.clone();
@@ -440,6 +573,7 @@ pub fn MessageView(props: MessageViewProps) -> Element {
                                         tool_name: tool_name_clone,
                                         status: status_clone,
                                         result: result_clone,
+                                        step: step.clone(),
                                     }
                                 }
                             }
@@ -1,8 +1,18 @@
 pub mod message;
+pub mod agent;
 pub mod chat;
 pub mod tool_suggestion;
 pub mod tool_manager;
+pub mod tool_registry;
+pub mod prompt_manager;
+pub mod prompt_browser;
+pub mod resource_viewer;
+pub mod conversation_sidebar;
 pub mod server_manager;
+pub mod server_log_viewer;
+pub mod protocol_inspector;
+pub mod secrets_settings;
+pub mod approval_settings;
 pub mod tool_test;
 pub mod tool_selection;
 pub mod parameter_validation;
@@ -10,11 +20,21 @@ pub mod validation_pipeline;
 pub mod tool_selection_cache;
 
 pub use message::{Message, MessageRole, MessageView};
+pub use agent::{AgentRun, AgentStep};
 pub use chat::ChatTab;
 pub use tool_suggestion::{ToolSuggestion, ToolExecution, ToolExecutionStatus};
-pub use tool_manager::{ToolManager, ToolInteraction};
+pub use tool_manager::{ToolManager, ToolInteraction, ToolExecutionError, ToolRouter};
+pub use tool_registry::{ToolRegistry, ToolResolveError};
+pub use prompt_manager::{PromptManager, PromptError, ServerPrompt};
+pub use prompt_browser::PromptBrowser;
+pub use resource_viewer::ResourceViewer;
+pub use conversation_sidebar::ConversationSidebar;
 pub use server_manager::ServerManager;
+pub use server_log_viewer::ServerLogViewer;
+pub use protocol_inspector::ProtocolInspector;
+pub use secrets_settings::SecretsSettings;
+pub use approval_settings::ApprovalSettings;
 pub use tool_test::ToolTestModal;
 pub use tool_selection::{RankedToolSelection, ToolMatch};
-pub use validation_pipeline::{ValidationPipeline, ValidationState};
+pub use validation_pipeline::{SanitizationConfig, SanitizationProfile, ToolSanitization, ValidationPipeline, ValidationState};
 pub use tool_selection_cache::ToolSelectionCache;
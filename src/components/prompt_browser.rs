@@ -0,0 +1,195 @@
+use dioxus::prelude::*;
+use std::collections::HashMap;
+use tracing::{debug, error};
+use crate::McpState;
+use crate::components::message::Message;
+use crate::components::prompt_manager::{PromptListing, PromptManager, ServerPrompt};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct PromptBrowserProps {
+    pub mcp_state: Signal<McpState>,
+    /// Receives the messages of a filled in prompt
+    pub on_insert: EventHandler<Vec<Message>>,
+}
+
+/// Lists the prompts of all running servers and fills them in from their arguments
+#[component]
+pub fn PromptBrowser(props: PromptBrowserProps) -> Element {
+    let mcp_state = props.mcp_state;
+    let mut listing = use_signal(PromptListing::default);
+    let mut loading = use_signal(|| false);
+    // Prompt whose form is open, with the values entered so far
+    let mut selected = use_signal(|| None::<ServerPrompt>);
+    let mut values = use_signal(HashMap::<String, String>::new);
+    let mut form_error = use_signal(|| None::<String>);
+    let mut fetching = use_signal(|| false);
+
+    // Only reload when the set of running servers changes
+    let server_ids = use_memo(move || {
+        let mut ids: Vec<String> = mcp_state.read().active_clients.keys().cloned().collect();
+        ids.sort();
+        ids
+    });
+
+    let mut reload = move || {
+        loading.set(true);
+        spawn(async move {
+            let clients = mcp_state.peek().active_clients.clone();
+            let result = PromptManager::list_all(&clients).await;
+            debug!("Loaded {} prompts from {} servers", result.prompts.len(), clients.len());
+            listing.set(result);
+            loading.set(false);
+        });
+    };
+
+    use_effect(move || {
+        let _ = server_ids.read();
+        reload();
+    });
+
+    let submit = move |_| {
+        let Some(entry) = selected.read().clone() else { return };
+
+        let arguments = match PromptManager::build_arguments(&entry.prompt, &values.read()) {
+            Ok(arguments) => arguments,
+            Err(e) => {
+                form_error.set(Some(e.to_string()));
+                return;
+            }
+        };
+
+        form_error.set(None);
+        fetching.set(true);
+        spawn(async move {
+            let clients = mcp_state.peek().active_clients.clone();
+            match PromptManager::get_prompt(&clients, &entry.server_id, &entry.prompt.name, arguments).await {
+                Ok(prompt_messages) => {
+                    props.on_insert.call(PromptManager::to_chat_messages(&prompt_messages));
+                    selected.set(None);
+                    values.set(HashMap::new());
+                }
+                Err(e) => {
+                    error!("Failed to get prompt {}: {}", entry.prompt.name, e);
+                    form_error.set(Some(format!("Failed to get prompt: {}", e)));
+                }
+            }
+            fetching.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "prompts-container",
+            div { class: "prompts-toolbar",
+                button {
+                    class: "reload-button",
+                    disabled: *loading.read(),
+                    onclick: move |_| reload(),
+                    if *loading.read() { "Loading..." } else { "Reload Prompts" }
+                }
+            }
+
+            for (server_id, error) in listing.read().errors.iter() {
+                div {
+                    key: "prompt-error-{server_id}",
+                    class: "prompt-server-error",
+                    "{server_id}: {error}"
+                }
+            }
+
+            if listing.read().prompts.is_empty() && !*loading.read() {
+                div { class: "empty-state",
+                    div { class: "empty-title", "No Prompts Found" }
+                    div { class: "empty-message", "None of the running servers provide prompts." }
+                }
+            }
+
+            div { class: "prompt-grid",
+                for entry in listing.read().prompts.iter().cloned() {
+                    {
+                        let is_selected = selected.read().as_ref() == Some(&entry);
+                        let arguments = entry.prompt.arguments.clone().unwrap_or_default();
+                        let entry_to_select = entry.clone();
+
+                        rsx! {
+                            div {
+                                key: "prompt-{entry.server_id}-{entry.prompt.name}",
+                                class: if is_selected { "prompt-card selected" } else { "prompt-card" },
+                                div { class: "prompt-header",
+                                    h3 { class: "prompt-name", "{entry.prompt.name}" }
+                                    span { class: "prompt-server", "{entry.server_id}" }
+                                }
+                                if let Some(description) = &entry.prompt.description {
+                                    p { class: "prompt-description", "{description}" }
+                                }
+
+                                if is_selected {
+                                    div { class: "prompt-form",
+                                        for argument in arguments {
+                                            {
+                                                let name = argument.name.clone();
+                                                let required = argument.required.unwrap_or(false);
+
+                                                rsx! {
+                                                    div {
+                                                        key: "{argument.name}",
+                                                        class: "form-group",
+                                                        label {
+                                                            "{argument.name}"
+                                                            if required {
+                                                                span { class: "param-required", "required" }
+                                                            }
+                                                        }
+                                                        if let Some(description) = &argument.description {
+                                                            div { class: "param-description", "{description}" }
+                                                        }
+                                                        input {
+                                                            class: "form-control",
+                                                            value: values.read().get(&argument.name).cloned().unwrap_or_default(),
+                                                            disabled: *fetching.read(),
+                                                            oninput: move |evt| {
+                                                                values.write().insert(name.clone(), evt.value());
+                                                            },
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        if let Some(error) = &*form_error.read() {
+                                            div { class: "prompt-form-error", "{error}" }
+                                        }
+                                        div { class: "prompt-form-actions",
+                                            button {
+                                                class: "btn-cancel",
+                                                onclick: move |_| {
+                                                    selected.set(None);
+                                                    form_error.set(None);
+                                                },
+                                                "Cancel"
+                                            }
+                                            button {
+                                                class: "btn-submit",
+                                                disabled: *fetching.read(),
+                                                onclick: submit,
+                                                if *fetching.read() { "Fetching..." } else { "Insert into Chat" }
+                                            }
+                                        }
+                                    }
+                                } else {
+                                    button {
+                                        class: "tool-test-button",
+                                        onclick: move |_| {
+                                            selected.set(Some(entry_to_select.clone()));
+                                            values.set(HashMap::new());
+                                            form_error.set(None);
+                                        },
+                                        "Use Prompt"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use mcp_client::{McpClientTrait, Error as McpError};
+use mcp_core::prompt::{Prompt, PromptMessage, PromptMessageContent, PromptMessageRole};
+use mcp_core::resource::ResourceContents;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{error, info, instrument};
+use crate::components::message::{Message, MessageRole};
+
+/// Errors that can occur when filling in or fetching a prompt
+#[derive(Debug, thiserror::Error)]
+pub enum PromptError {
+    #[error("Missing required argument: {0}")]
+    MissingArgument(String),
+
+    #[error("Server {0} is not running")]
+    ServerNotRunning(String),
+
+    #[error(transparent)]
+    Mcp(#[from] McpError),
+}
+
+/// A prompt together with the server that provides it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerPrompt {
+    pub server_id: String,
+    pub prompt: Prompt,
+}
+
+/// Prompts of all running servers, and the servers that failed to list theirs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PromptListing {
+    pub prompts: Vec<ServerPrompt>,
+    /// Server id and error, for servers without prompt support or that failed
+    pub errors: Vec<(String, String)>,
+}
+
+/// Lists, fills in and fetches MCP prompts
+pub struct PromptManager;
+
+impl PromptManager {
+    /// Call `list_prompts` on every client
+    #[instrument(level = "debug", skip(clients), fields(client_count = clients.len()))]
+    pub async fn list_all<C>(clients: &HashMap<String, Arc<Mutex<C>>>) -> PromptListing
+    where
+        C: McpClientTrait + ?Sized,
+    {
+        let mut listing = PromptListing::default();
+
+        let mut server_ids: Vec<&String> = clients.keys().collect();
+        server_ids.sort();
+
+        for server_id in server_ids {
+            let client = clients[server_id].lock().await;
+            match client.list_prompts(None).await {
+                Ok(result) => {
+                    info!("Listed {} prompts from server {}", result.prompts.len(), server_id);
+                    listing.prompts.extend(result.prompts.into_iter().map(|prompt| ServerPrompt {
+                        server_id: server_id.clone(),
+                        prompt,
+                    }));
+                }
+                Err(e) => {
+                    error!("Error listing prompts from server {}: {}", server_id, e);
+                    listing.errors.push((server_id.clone(), e.to_string()));
+                }
+            }
+        }
+
+        listing
+    }
+
+    /// Build the `get_prompt` arguments from the values entered in the form
+    ///
+    /// Empty optional arguments are left out, empty required ones are an error.
+    pub fn build_arguments(prompt: &Prompt, values: &HashMap<String, String>) -> Result<Value, PromptError> {
+        let mut arguments = Map::new();
+
+        for argument in prompt.arguments.iter().flatten() {
+            let value = values.get(&argument.name).map(|v| v.trim()).unwrap_or_default();
+            if value.is_empty() {
+                if argument.required.unwrap_or(false) {
+                    return Err(PromptError::MissingArgument(argument.name.clone()));
+                }
+                continue;
+            }
+            arguments.insert(argument.name.clone(), Value::String(value.to_string()));
+        }
+
+        Ok(Value::Object(arguments))
+    }
+
+    /// Call `get_prompt` on the server that provides the prompt
+    #[instrument(level = "debug", skip(clients, arguments))]
+    pub async fn get_prompt<C>(
+        clients: &HashMap<String, Arc<Mutex<C>>>,
+        server_id: &str,
+        name: &str,
+        arguments: Value,
+    ) -> Result<Vec<PromptMessage>, PromptError>
+    where
+        C: McpClientTrait + ?Sized,
+    {
+        let client = clients
+            .get(server_id)
+            .ok_or_else(|| PromptError::ServerNotRunning(server_id.to_string()))?;
+
+        let client = client.lock().await;
+        let result = client.get_prompt(name, arguments).await?;
+        info!("Fetched prompt {} from {} with {} messages", name, server_id, result.messages.len());
+        Ok(result.messages)
+    }
+
+    /// Convert prompt messages into chat messages
+    pub fn to_chat_messages(messages: &[PromptMessage]) -> Vec<Message> {
+        messages
+            .iter()
+            .map(|message| {
+                let role = match message.role {
+                    PromptMessageRole::User => MessageRole::User,
+                    PromptMessageRole::Assistant => MessageRole::Assistant,
+                };
+                Message::new(role, Self::content_text(&message.content))
+            })
+            .collect()
+    }
+
+    fn content_text(content: &PromptMessageContent) -> String {
+        match content {
+            PromptMessageContent::Text { text } => text.clone(),
+            PromptMessageContent::Image { image } => format!("[Image: {}]", image.mime_type),
+            PromptMessageContent::Resource { resource } => match &resource.resource {
+                ResourceContents::TextResourceContents { uri, text, .. } => {
+                    format!("Resource {}:\n{}", uri, text)
+                }
+                ResourceContents::BlobResourceContents { uri, mime_type, .. } => {
+                    format!("[Binary resource {} ({})]", uri, mime_type.as_deref().unwrap_or("unknown type"))
+                }
+            },
+        }
+    }
+}
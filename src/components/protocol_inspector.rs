@@ -0,0 +1,206 @@
+use dioxus::prelude::*;
+use tracing::error;
+use crate::McpState;
+use crate::conversation_export;
+use crate::inspector::{self, InspectorFilter, MessageKind};
+
+/// Keeps the newest message in view while following
+const SCROLL_TO_END: &str = r#"
+    const rows = document.getElementById("inspector-rows");
+    if (rows) { rows.scrollTop = rows.scrollHeight; }
+"#;
+
+/// Props for ProtocolInspector component
+#[derive(Props, Clone, PartialEq)]
+pub struct ProtocolInspectorProps {
+    /// MCP state holding the recorded traffic
+    pub mcp_state: Signal<McpState>,
+}
+
+/// Pane listing the JSON-RPC messages exchanged with the servers, with filters,
+/// expandable message bodies, copying and JSONL export
+#[component]
+pub fn ProtocolInspector(props: ProtocolInspectorProps) -> Element {
+    let recording = props.mcp_state.peek().inspector.clone();
+    let mut server_filter = use_signal(String::new);
+    let mut kind_filter = use_signal(String::new);
+    let mut query = use_signal(String::new);
+    let mut follow = use_signal(|| true);
+    let mut expanded = use_signal(|| None::<u64>);
+    let mut notice = use_signal(|| None::<String>);
+    let mut revision = use_signal(|| 0u64);
+
+    // Pick up new messages while following
+    use_future({
+        let recording = recording.clone();
+        move || {
+            let recording = recording.clone();
+            async move {
+                let mut changes = recording.subscribe();
+                while changes.changed().await.is_ok() {
+                    if *follow.peek() {
+                        revision.with_mut(|r| *r += 1);
+                    }
+                }
+            }
+        }
+    });
+
+    use_effect(move || {
+        let _ = revision.read();
+        if *follow.read() {
+            let _ = document::eval(SCROLL_TO_END);
+        }
+    });
+
+    let _ = revision.read();
+    let filter = InspectorFilter {
+        server_id: Some(server_filter.read().clone()).filter(|id| !id.is_empty()),
+        kind: MessageKind::from_label(&kind_filter.read()),
+        query: query.read().clone(),
+    };
+    let total = recording.entries().len();
+    let shown = recording.filtered(&filter);
+    let server_ids = recording.server_ids();
+    let summary = if shown.len() == total {
+        format!("{} messages", total)
+    } else {
+        format!("{} of {} messages", shown.len(), total)
+    };
+    let expanded_seq = *expanded.read();
+
+    rsx! {
+        div { class: "inspector",
+            div { class: "inspector-toolbar",
+                select {
+                    class: "form-control inspector-filter",
+                    value: "{server_filter}",
+                    onchange: move |e| server_filter.set(e.value()),
+                    option { value: "", "All servers" }
+                    for id in server_ids.iter() {
+                        option { key: "{id}", value: "{id}", "{id}" }
+                    }
+                }
+                select {
+                    class: "form-control inspector-filter",
+                    value: "{kind_filter}",
+                    onchange: move |e| kind_filter.set(e.value()),
+                    option { value: "", "All kinds" }
+                    option { value: "request", "Requests" }
+                    option { value: "response", "Responses" }
+                    option { value: "notification", "Notifications" }
+                    option { value: "error", "Errors" }
+                }
+                input {
+                    class: "form-control input-field inspector-search",
+                    r#type: "search",
+                    placeholder: "Filter by method or content...",
+                    value: "{query}",
+                    oninput: move |e| query.set(e.value())
+                }
+                label { class: "inspector-follow",
+                    input {
+                        r#type: "checkbox",
+                        checked: *follow.read(),
+                        onchange: move |e| {
+                            follow.set(e.checked());
+                            revision.with_mut(|r| *r += 1);
+                        }
+                    }
+                    "Follow"
+                }
+                button {
+                    class: "server-action",
+                    disabled: shown.is_empty(),
+                    onclick: {
+                        let shown = shown.clone();
+                        move |_| match inspector::write_jsonl(&conversation_export::default_export_dir(), &shown) {
+                            Ok(path) => notice.set(Some(format!("Exported to {}", path.display()))),
+                            Err(e) => {
+                                error!("Failed to export inspector messages: {}", e);
+                                notice.set(Some(format!("Export failed: {}", e)));
+                            }
+                        }
+                    },
+                    "Export JSONL"
+                }
+                button {
+                    class: "server-action",
+                    onclick: {
+                        let recording = recording.clone();
+                        move |_| {
+                            recording.clear();
+                            expanded.set(None);
+                            revision.with_mut(|r| *r += 1);
+                        }
+                    },
+                    "Clear"
+                }
+            }
+
+            div { class: "inspector-meta",
+                span { "{summary}" }
+                if let Some(message) = notice.read().as_ref() {
+                    span { class: "inspector-notice", "{message}" }
+                }
+            }
+
+            div { id: "inspector-rows", class: "inspector-rows",
+                if total == 0 {
+                    div { class: "inspector-empty", "No traffic recorded yet. Start a server to see its messages." }
+                } else if shown.is_empty() {
+                    div { class: "inspector-empty", "No messages match the filters" }
+                }
+                for entry in shown.into_iter() {
+                    {
+                        let seq = entry.seq;
+                        let is_expanded = expanded_seq == Some(seq);
+                        let time = entry.timestamp.with_timezone(&chrono::Local).format("%H:%M:%S%.3f").to_string();
+                        let arrow = entry.direction.arrow();
+                        let kind = entry.kind.label();
+                        let method = entry.method.clone().unwrap_or_default();
+                        let id = entry.request_id.map(|id| format!("#{}", id)).unwrap_or_default();
+                        let duration = entry.duration_ms.map(|ms| format!("{} ms", ms)).unwrap_or_default();
+                        let body = entry.pretty_message();
+                        rsx! {
+                            div {
+                                key: "{seq}",
+                                class: if is_expanded { "inspector-row expanded" } else { "inspector-row" },
+                                div {
+                                    class: "inspector-summary kind-{kind}",
+                                    onclick: move |_| {
+                                        expanded.set(if is_expanded { None } else { Some(seq) });
+                                    },
+                                    span { class: "inspector-time", "{time}" }
+                                    span { class: "inspector-server", "{entry.server_id}" }
+                                    span { class: "inspector-direction", "{arrow}" }
+                                    span { class: "inspector-kind", "{kind}" }
+                                    span { class: "inspector-method", "{method}" }
+                                    span { class: "inspector-id", "{id}" }
+                                    span { class: "inspector-duration", "{duration}" }
+                                }
+                                if is_expanded {
+                                    div { class: "inspector-body",
+                                        button {
+                                            class: "server-action inspector-copy",
+                                            onclick: {
+                                                let body = body.clone();
+                                                move |_| {
+                                                    let text = serde_json::to_string(&body).unwrap_or_default();
+                                                    let _ = document::eval(&format!("navigator.clipboard.writeText({});", text));
+                                                    notice.set(Some(format!("Copied message {}", seq)));
+                                                }
+                                            },
+                                            "Copy"
+                                        }
+                                        pre { class: "inspector-json", "{body}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
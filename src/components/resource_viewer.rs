@@ -0,0 +1,125 @@
+use base64::Engine;
+use dioxus::prelude::*;
+use crate::McpState;
+use crate::resources::{Resource, ResourceAttachment, ResourceContent, ResourceService};
+
+/// Props for ResourceViewer component
+#[derive(Props, Clone, PartialEq)]
+pub struct ResourceViewerProps {
+    /// The resource to show
+    pub resource: Resource,
+    /// MCP state for accessing the clients of MCP resources
+    pub mcp_state: Signal<McpState>,
+    /// Event handler for when the dialog is closed
+    pub on_close: EventHandler<()>,
+    /// Event handler for attaching the resource to the next chat message
+    pub on_attach: EventHandler<ResourceAttachment>,
+}
+
+/// Modal component that reads a resource from its provider and shows its text or binary contents
+#[component]
+pub fn ResourceViewer(props: ResourceViewerProps) -> Element {
+    let mut content = use_signal(|| None::<Result<ResourceContent, String>>);
+    let resource = props.resource.clone();
+
+    // Read the resource when the viewer opens
+    use_effect(move || {
+        let uri = props.resource.uri.clone();
+        let mcp_state = props.mcp_state;
+        content.set(None);
+
+        spawn(async move {
+            let service = ResourceService::with_defaults(&mcp_state.peek().active_clients);
+            let result = service.read_resource(&uri).await;
+            content.set(Some(result.map_err(|e| e.to_string())));
+        });
+    });
+
+    let attachment = match &*content.read() {
+        Some(Ok(read)) => Some(ResourceAttachment::new(&resource, read)),
+        _ => None,
+    };
+    let attachment_tokens = attachment.as_ref().map(|a| a.estimated_tokens());
+
+    rsx! {
+        div { class: "dialog-overlay",
+            div { class: "tool-test-dialog resource-viewer-dialog",
+                div { class: "dialog-header",
+                    h2 { class: "dialog-title", "{resource.name}" }
+                    button {
+                        class: "dialog-close",
+                        onclick: move |_| props.on_close.call(()),
+                        "×"
+                    }
+                }
+
+                div { class: "dialog-content",
+                    div { class: "resource-uri", "{resource.uri} · {resource.provider}" }
+
+                    match &*content.read() {
+                        None => rsx! {
+                            div { class: "resource-loading", "Reading resource..." }
+                        },
+                        Some(Err(error)) => rsx! {
+                            div { class: "tool-result error",
+                                h3 { "Error:" }
+                                pre { "{error}" }
+                            }
+                        },
+                        Some(Ok(ResourceContent::Text(text))) => rsx! {
+                            div { class: "resource-content",
+                                div { class: "resource-content-type", "{resource.mime_type}" }
+                                pre { class: "resource-text", "{text}" }
+                            }
+                        },
+                        Some(Ok(ResourceContent::Binary(data))) => {
+                            let size = data.len();
+                            let is_image = resource.mime_type.starts_with("image/");
+                            let src = if is_image {
+                                format!(
+                                    "data:{};base64,{}",
+                                    resource.mime_type,
+                                    base64::engine::general_purpose::STANDARD.encode(data)
+                                )
+                            } else {
+                                String::new()
+                            };
+
+                            rsx! {
+                                div { class: "resource-content",
+                                    div { class: "resource-content-type", "{resource.mime_type} · {size} bytes" }
+                                    if is_image {
+                                        img { class: "resource-image", src: "{src}" }
+                                    } else {
+                                        div { class: "resource-blob", "Binary content cannot be previewed." }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "dialog-footer",
+                    if let Some(tokens) = attachment_tokens {
+                        span { class: "attachment-estimate", "≈ {tokens} tokens as chat context" }
+                    }
+                    button {
+                        class: "btn-cancel",
+                        onclick: move |_| props.on_close.call(()),
+                        "Close"
+                    }
+                    button {
+                        class: "btn-submit",
+                        disabled: attachment.is_none(),
+                        onclick: move |_| {
+                            if let Some(attachment) = attachment.clone() {
+                                props.on_attach.call(attachment);
+                            }
+                        },
+                        "Attach to chat"
+                    }
+                }
+            }
+        }
+    }
+}
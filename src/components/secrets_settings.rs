@@ -0,0 +1,260 @@
+use dioxus::prelude::*;
+use std::collections::HashSet;
+use tracing::warn;
+
+use crate::secrets::{self, SecretError, MASK, OPENROUTER_API_KEY};
+use crate::McpState;
+
+/// Settings props
+#[derive(Props, Clone, PartialEq)]
+pub struct SecretsSettingsProps {
+    pub mcp_state: Signal<McpState>,
+}
+
+/// Settings for unlocking the secret store and adding, revealing and removing secrets
+#[component]
+pub fn SecretsSettings(props: SecretsSettingsProps) -> Element {
+    let store = props.mcp_state.peek().secrets.clone();
+    let mut revision = use_signal(|| 0u64);
+    let mut passphrase = use_signal(String::new);
+    let mut new_passphrase = use_signal(String::new);
+    let mut new_name = use_signal(String::new);
+    let mut new_value = use_signal(String::new);
+    let mut revealed = use_signal(HashSet::<String>::new);
+    let mut notice = use_signal(|| None::<String>);
+    let mut error = use_signal(|| None::<String>);
+
+    // Pick up changes made elsewhere, e.g. a secret stored from the server dialog
+    use_future({
+        let store = store.clone();
+        move || {
+            let store = store.clone();
+            async move {
+                let mut changes = store.subscribe();
+                while changes.changed().await.is_ok() {
+                    revision.with_mut(|r| *r += 1);
+                }
+            }
+        }
+    });
+
+    // Show the outcome of an action on the store
+    let mut report = move |result: Result<String, SecretError>| match result {
+        Ok(message) => {
+            notice.set(Some(message));
+            error.set(None);
+        }
+        Err(e) => {
+            warn!("Secret store: {}", e);
+            error.set(Some(e.to_string()));
+            notice.set(None);
+        }
+    };
+
+    let _ = revision.read();
+    let is_unlocked = store.is_unlocked();
+    let exists = store.exists();
+    let path = store.path().display().to_string();
+    let names = store.names();
+    let has_openrouter_key = names.iter().any(|name| name == OPENROUTER_API_KEY);
+
+    rsx! {
+        div { class: "secrets-settings",
+            div { class: "secrets-header",
+                h2 { class: "server-manager-title", "Secrets" }
+                if is_unlocked {
+                    button {
+                        class: "server-action",
+                        onclick: {
+                            let store = store.clone();
+                            move |_| {
+                                store.lock();
+                                revealed.write().clear();
+                                report(Ok("Locked".to_string()));
+                            }
+                        },
+                        "Lock"
+                    }
+                }
+            }
+            p { class: "section-description",
+                "API keys and tokens are kept encrypted in {path}. Refer to one in a server's environment variables or headers as "
+                code { "${{secret:NAME}}" }
+                ", it is filled in when the server starts."
+            }
+
+            if let Some(message) = notice.read().clone() {
+                div { class: "conversation-notice", "{message}" }
+            }
+            if let Some(message) = error.read().clone() {
+                div { class: "error-alert", "{message}" }
+            }
+
+            if !is_unlocked {
+                div { class: "form-group",
+                    label { for: "secrets-passphrase",
+                        if exists { "Passphrase" } else { "Choose a passphrase for the new secret store" }
+                    }
+                    div { class: "secret-row",
+                        input {
+                            id: "secrets-passphrase",
+                            class: "form-control input-field",
+                            r#type: "password",
+                            value: "{passphrase}",
+                            oninput: move |e| passphrase.set(e.value())
+                        }
+                        button {
+                            class: "btn-submit",
+                            disabled: passphrase.read().is_empty(),
+                            onclick: {
+                                let store = store.clone();
+                                move |_| {
+                                    let result = store.unlock(&passphrase.read());
+                                    passphrase.set(String::new());
+                                    report(result.map(|()| "Unlocked".to_string()));
+                                }
+                            },
+                            if exists { "Unlock" } else { "Create" }
+                        }
+                    }
+                }
+            } else {
+                if !has_openrouter_key {
+                    p { class: "secret-hint",
+                        "Add a secret named {OPENROUTER_API_KEY} to use it as the OpenRouter key instead of the one in .env."
+                    }
+                }
+
+                div { class: "secret-list",
+                    if names.is_empty() {
+                        div { class: "empty-servers", "No secrets yet." }
+                    }
+                    for name in names.into_iter() {
+                        div {
+                            key: "{name}",
+                            class: "secret-item",
+                            span { class: "secret-name", "{name}" }
+                            span { class: "secret-value",
+                                if revealed.read().contains(&name) {
+                                    {store.get(&name).unwrap_or_default()}
+                                } else {
+                                    "{MASK}"
+                                }
+                            }
+                            span { class: "secret-ref", {secrets::secret_ref(&name)} }
+                            button {
+                                class: "server-action",
+                                onclick: {
+                                    let name = name.clone();
+                                    move |_| {
+                                        let mut shown = revealed.write();
+                                        if !shown.remove(&name) {
+                                            shown.insert(name.clone());
+                                        }
+                                    }
+                                },
+                                if revealed.read().contains(&name) { "Hide" } else { "Show" }
+                            }
+                            button {
+                                class: "server-action delete",
+                                onclick: {
+                                    let store = store.clone();
+                                    let name = name.clone();
+                                    move |_| {
+                                        let result = store.remove(&name);
+                                        revealed.write().remove(&name);
+                                        report(result.map(|_| format!("Removed {}", name)));
+                                    }
+                                },
+                                "Remove"
+                            }
+                        }
+                    }
+                }
+
+                div { class: "form-group",
+                    label { "Add or replace a secret" }
+                    div { class: "secret-row",
+                        input {
+                            class: "form-control input-field",
+                            placeholder: "Name, e.g. GITHUB_TOKEN",
+                            value: "{new_name}",
+                            oninput: move |e| new_name.set(e.value())
+                        }
+                        input {
+                            class: "form-control input-field",
+                            r#type: "password",
+                            placeholder: "Value",
+                            value: "{new_value}",
+                            oninput: move |e| new_value.set(e.value())
+                        }
+                        button {
+                            class: "btn-submit",
+                            disabled: new_name.read().trim().is_empty(),
+                            onclick: {
+                                let store = store.clone();
+                                move |_| {
+                                    let name = new_name.read().trim().to_string();
+                                    let result = store.set(&name, &new_value.read());
+                                    if result.is_ok() {
+                                        new_name.set(String::new());
+                                        new_value.set(String::new());
+                                    }
+                                    report(result.map(|()| format!("Saved {}", name)));
+                                }
+                            },
+                            "Save"
+                        }
+                    }
+                }
+
+                div { class: "form-group",
+                    label { "Passphrase" }
+                    div { class: "secret-row",
+                        input {
+                            class: "form-control input-field",
+                            r#type: "password",
+                            placeholder: "New passphrase",
+                            value: "{new_passphrase}",
+                            oninput: move |e| new_passphrase.set(e.value())
+                        }
+                        button {
+                            class: "server-action",
+                            disabled: new_passphrase.read().is_empty(),
+                            onclick: {
+                                let store = store.clone();
+                                move |_| {
+                                    let result = store.change_passphrase(&new_passphrase.read());
+                                    new_passphrase.set(String::new());
+                                    report(result.map(|()| "Changed the passphrase".to_string()));
+                                }
+                            },
+                            "Change"
+                        }
+                    }
+                    if secrets::keyring_available() {
+                        div { class: "secret-row",
+                            button {
+                                class: "server-action",
+                                title: "Unlock the secrets automatically when mDesk starts",
+                                onclick: {
+                                    let store = store.clone();
+                                    move |_| report(store.remember_passphrase().map(|()| "Passphrase saved in the OS keyring".to_string()))
+                                },
+                                "Remember in OS keyring"
+                            }
+                            button {
+                                class: "server-action",
+                                onclick: {
+                                    let store = store.clone();
+                                    move |_| report(store.forget_passphrase().map(|()| "Passphrase removed from the OS keyring".to_string()))
+                                },
+                                "Forget"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
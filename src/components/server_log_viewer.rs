@@ -0,0 +1,152 @@
+use dioxus::prelude::*;
+use crate::McpState;
+use crate::server_config::ServerConfig;
+
+/// Keeps the newest line in view while following
+const SCROLL_TO_END: &str = r#"
+    const lines = document.getElementById("server-log-lines");
+    if (lines) { lines.scrollTop = lines.scrollHeight; }
+"#;
+
+/// Props for ServerLogViewer component
+#[derive(Props, Clone, PartialEq)]
+pub struct ServerLogViewerProps {
+    /// MCP state holding the captured server output
+    pub mcp_state: Signal<McpState>,
+    /// Servers whose logs can be picked
+    pub servers: Vec<ServerConfig>,
+    /// Server whose log is shown
+    pub server_id: String,
+    /// Event handler for picking another server
+    pub on_select: EventHandler<String>,
+    /// Event handler for when the viewer is closed
+    pub on_close: EventHandler<()>,
+}
+
+/// Panel showing what a server wrote to stderr, following new output and filtering by a search
+#[component]
+pub fn ServerLogViewer(props: ServerLogViewerProps) -> Element {
+    let logs = props.mcp_state.peek().server_logs.clone();
+    let mut query = use_signal(String::new);
+    let mut follow = use_signal(|| true);
+    let mut revision = use_signal(|| 0u64);
+
+    // Pick up new output while following
+    use_future({
+        let logs = logs.clone();
+        move || {
+            let logs = logs.clone();
+            async move {
+                let mut changes = logs.subscribe();
+                while changes.changed().await.is_ok() {
+                    if *follow.peek() {
+                        revision.with_mut(|r| *r += 1);
+                    }
+                }
+            }
+        }
+    });
+
+    use_effect(move || {
+        let _ = revision.read();
+        if *follow.read() {
+            let _ = document::eval(SCROLL_TO_END);
+        }
+    });
+
+    let _ = revision.read();
+    let server_id = props.server_id.clone();
+    let all_lines = logs.lines(&server_id);
+    let search = query.read().trim().to_string();
+    let shown: Vec<(String, String, bool)> = all_lines
+        .iter()
+        .filter(|line| line.matches(&search))
+        .map(|line| (line.timestamp.format("%H:%M:%S").to_string(), line.text.clone(), line.is_start))
+        .collect();
+    let dropped = logs.dropped(&server_id);
+    let log_path = logs.log_path(&server_id).map(|path| path.display().to_string());
+    let summary = if search.is_empty() {
+        format!("{} lines", all_lines.len())
+    } else {
+        format!("{} of {} lines", shown.len(), all_lines.len())
+    };
+
+    rsx! {
+        div { class: "server-log-viewer",
+            div { class: "server-log-header",
+                h3 { class: "server-log-title", "Server Log" }
+                select {
+                    class: "form-control server-log-server",
+                    value: "{server_id}",
+                    onchange: move |e| props.on_select.call(e.value()),
+                    for server in props.servers.iter() {
+                        option { key: "{server.id}", value: "{server.id}", "{server.name}" }
+                    }
+                }
+                input {
+                    class: "form-control input-field server-log-search",
+                    r#type: "search",
+                    placeholder: "Search output...",
+                    value: "{query}",
+                    oninput: move |e| query.set(e.value())
+                }
+                label { class: "server-log-follow",
+                    input {
+                        r#type: "checkbox",
+                        checked: *follow.read(),
+                        onchange: move |e| {
+                            follow.set(e.checked());
+                            revision.with_mut(|r| *r += 1);
+                        }
+                    }
+                    "Follow"
+                }
+                button {
+                    class: "server-action",
+                    onclick: {
+                        let logs = logs.clone();
+                        let server_id = server_id.clone();
+                        move |_| {
+                            logs.clear(&server_id);
+                            revision.with_mut(|r| *r += 1);
+                        }
+                    },
+                    "Clear"
+                }
+                button {
+                    class: "dialog-close",
+                    onclick: move |_| props.on_close.call(()),
+                    "×"
+                }
+            }
+
+            div { class: "server-log-meta",
+                span { "{summary}" }
+                if let Some(path) = log_path {
+                    span { class: "server-log-path", title: "{path}", "{path}" }
+                }
+            }
+
+            div { id: "server-log-lines", class: "server-log-lines",
+                if dropped > 0 {
+                    div { class: "server-log-dropped",
+                        "{dropped} earlier lines are only in the log file"
+                    }
+                }
+                if all_lines.is_empty() {
+                    div { class: "server-log-empty", "No output captured yet" }
+                } else if shown.is_empty() {
+                    div { class: "server-log-empty", "No lines match \"{search}\"" }
+                }
+                for (i, (time, text, is_start)) in shown.into_iter().enumerate() {
+                    div {
+                        key: "{i}",
+                        class: if is_start { "server-log-line start" } else { "server-log-line" },
+                        span { class: "server-log-time", "{time}" }
+                        span { class: "server-log-text", "{text}" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,12 @@
 use dioxus::prelude::*;
-use crate::server_config::{ServerConfig, ServerConfigs};
-use std::path::Path;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::{debug, info, warn, error};
-use mcp_client::{transport::{StdioTransport, Error as TransportError}, 
-    ClientInfo, ClientCapabilities, McpClient, McpService, Transport, McpClientTrait
+use crate::components::ServerLogViewer;
+use crate::conversation_export;
+use crate::secrets::{self, SecretStore};
+use crate::server_config::{
+    self, ConflictResolution, HealthCheckConfig, ImportItem, Profile, RestartMode, RestartPolicy, ServerConfig,
+    ServerConfigs, TransportConfig,
 };
+use tracing::{error, warn};
 
 /// Server list component
 #[derive(Props, Clone, PartialEq)]
@@ -26,33 +26,52 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
     let mut is_editing = use_signal(|| false);
     let mut edit_server = use_signal(|| None::<ServerConfig>);
     let mut error_message = use_signal(|| None::<String>);
+    // Server whose stderr output is shown in the log viewer
+    let mut log_server = use_signal(|| None::<String>);
+    let mut show_import_dialog = use_signal(|| false);
+    // Outcome of the last import or export
+    let mut notice = use_signal(|| None::<String>);
+    let mut new_profile = use_signal(String::new);
     
-    // Load servers from the configuration file
+    // Mirror the configuration in effect, which the config watcher reloads when its file changes
     use_effect(move || {
-        // Try to load from servers.json
-        let config_path = Path::new("servers.json");
-        
-        match ServerConfigs::load_from_file(config_path) {
-            Ok(loaded_configs) => {
-                info!("Loaded {} server configurations", loaded_configs.servers.len());
-                configs.set(loaded_configs);
-            }
-            Err(e) => {
-                warn!("Error loading server configurations: {}", e);
-                error_message.set(Some(format!("Error loading configurations: {}", e)));
-                
-                // If the file doesn't exist or has errors, create a default configuration
-                let default_configs = ServerConfigs::initialize_default();
-                configs.set(default_configs.clone());
-                
-                // Try to save the default configuration
-                if let Err(save_err) = default_configs.save_to_file(config_path) {
-                    error!("Error saving default configurations: {}", save_err);
-                }
-            }
+        let applied = props.mcp_state.read().server_configs.clone();
+        if *configs.peek() != applied {
+            configs.set(applied);
+        }
+    });
+    
+    // Ping latencies, refreshed after every health check without writing the shared state
+    let mut health = use_signal(|| props.mcp_state.peek().connections.health_all());
+    use_future(move || async move {
+        let connections = props.mcp_state.peek().connections.clone();
+        let mut pings = connections.subscribe_health();
+        while pings.changed().await.is_ok() {
+            health.set(connections.health_all());
         }
     });
     
+    // Save a changed configuration, refusing one that couldn't be loaded again
+    let mut mcp_state = props.mcp_state;
+    let mut save_configs = move |updated: ServerConfigs| -> bool {
+        let problems = updated.validate();
+        if !problems.is_empty() {
+            let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+            error_message.set(Some(format!("Invalid configuration: {}", problems.join("; "))));
+            return false;
+        }
+        
+        // Shown right away, the config watcher applies it to the servers once it notices the file changed
+        configs.set(updated.clone());
+        mcp_state.write().server_configs = updated.clone();
+        error_message.set(None);
+        
+        if let Err(e) = updated.save_to_file(server_config::config_path()) {
+            error_message.set(Some(format!("Error saving configurations: {}", e)));
+        }
+        true
+    };
+    
     // Function to select a server
     let select_server = move |id: String| {
         if let Some(server) = configs.read().get_by_id(&id) {
@@ -74,126 +93,30 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
         fn call(&self, server_config: ServerConfig) {
             use dioxus::prelude::spawn;
             use crate::ServerStatus;
-            use crate::server_config;
-            use mcp_client::transport::stdio::StdioTransport;
-            use mcp_client::{ClientInfo, ClientCapabilities, McpClient, McpService};
-            use std::time::Duration;
             
             let server_id = server_config.id.clone();
-            let mut props = self.props.clone();
             
             // Check current server status
-            let status = props.mcp_state.read().server_status.get(&server_id).cloned();
+            let status = self.props.mcp_state.read().server_status.get(&server_id).cloned();
+            let connections = self.props.mcp_state.read().connections.clone();
             
             match status {
-                Some(ServerStatus::Running) => {
-                    // Stop the server
-                    let client_opt = props.mcp_state.read().active_clients.get(&server_id).cloned();
-                    if let Some(_client) = client_opt {
-                        // Update status to show stopping
-                        props.mcp_state.write().server_status.insert(server_id.clone(), ServerStatus::Stopped);
-                        
-                        // Remove from active clients
-                        props.mcp_state.write().active_clients.remove(&server_id);
-                        
-                        // If it's the selected server, clear the client
-                        let selected_server = props.mcp_state.read().selected_server.clone();
-                        if let Some(selected) = selected_server {
-                            if selected.id == server_id {
-                                props.mcp_state.write().client = None;
-                                props.mcp_state.write().selected_server = None;
-                            }
-                        }
-                        
-                        // Trigger a tools refresh to remove tools from this server
-                        // We don't have direct access to the tools signal here, so we'll let
-                        // the health check coroutine handle the tool update on its next run
-                        // We just need to make sure the status is updated correctly
-                        spawn({
-                            to_owned![props];
-                            async move {
-                                // Manually call a client health check for remaining servers
-                                let active_clients = props.mcp_state.read().active_clients.clone();
-                                for (id, client) in active_clients {
-                                    if let Ok(client_lock) = client.try_lock() {
-                                        if let Ok(Ok(_)) = tokio::time::timeout(
-                                            std::time::Duration::from_millis(500),
-                                            client_lock.list_resources(None)
-                                        ).await {
-                                            // Server is still running
-                                            props.mcp_state.write().server_status.insert(
-                                                id.clone(), 
-                                                crate::ServerStatus::Running
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-                        });
-                    }
+                Some(ServerStatus::Running) | Some(ServerStatus::Degraded(_)) => {
+                    // Stop the server, the manager drops its client and tools and shuts it down
+                    spawn(async move {
+                        connections.stop(&server_id).await;
+                    });
                 },
-                Some(ServerStatus::Stopped) | None => {
-                    // Start the server
-                    // Mark as starting
-                    props.mcp_state.write().server_status.insert(server_id.clone(), ServerStatus::Starting);
-                    
-                    // Clone what we need for the spawn
-                    let server_config_clone = server_config.clone();
-                    let mut mcp_state_clone = props.mcp_state.clone();
-                    
-                    spawn({
-                        async move {
-                            let server_id = server_config_clone.id.clone();
-                            
-                            // Create transport with the server's configuration
-                            let env_vars = server_config_clone.env.clone();
-                            
-                            let transport = StdioTransport::new(
-                                &server_config_clone.command,
-                                server_config_clone.args.clone(),
-                                env_vars
-                            );
-                            
-                            match transport.start().await {
-                                Ok(handle) => {
-                                    let service = McpService::with_timeout(handle, Duration::from_secs(30));
-                                    let mut client = McpClient::new(service);
-                                    
-                                    match client.initialize(
-                                        ClientInfo {
-                                            name: "mDesk".to_string(),
-                                            version: "0.1.0".to_string(),
-                                        },
-                                        ClientCapabilities::default()
-                                    ).await {
-                                        Ok(_) => {
-                                            // Successfully started the server
-                                            let client_arc = Arc::new(Mutex::new(client));
-                                            
-                                            // Store in active clients
-                                            mcp_state_clone.write().active_clients.insert(server_id.clone(), client_arc.clone());
-                                            
-                                            // Update status to Running
-                                            mcp_state_clone.write().server_status.insert(server_id, ServerStatus::Running);
-                                        },
-                                        Err(e) => {
-                                            // Failed to initialize
-                                            let error_msg = format!("Failed to initialize: {}", e);
-                                            mcp_state_clone.write().server_status.insert(server_id, ServerStatus::Failed(error_msg));
-                                        }
-                                    }
-                                },
-                                Err(e) => {
-                                    // Failed to start transport
-                                    let error_msg = format!("Failed to start: {}", e);
-                                    mcp_state_clone.write().server_status.insert(server_id, ServerStatus::Failed(error_msg));
-                                }
-                            }
+                Some(ServerStatus::Stopped) | Some(ServerStatus::Terminated(_)) | Some(ServerStatus::Failed(_)) | None => {
+                    // Start or retry the server, status changes are published by the manager
+                    spawn(async move {
+                        if let Err(e) = connections.start(&server_config).await {
+                            warn!("Failed to start server {}: {}", server_config.id, e);
                         }
                     });
                 },
-                _ => {
-                    // Do nothing for Failed or Starting states
+                Some(ServerStatus::Starting) | Some(ServerStatus::Stopping) => {
+                    // Already on its way
                 }
             }
         }
@@ -225,14 +148,7 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
             // Ensure at least one server is marked as default
             configs_clone.ensure_default_exists();
             
-            // Update state
-            configs.set(configs_clone.clone());
-            
-            // Save the updated configuration
-            let config_path = Path::new("servers.json");
-            if let Err(e) = configs_clone.save_to_file(config_path) {
-                error_message.set(Some(format!("Error saving configurations: {}", e)));
-            }
+            save_configs(configs_clone);
         }
     };
     
@@ -246,18 +162,11 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
             configs_clone.add_server(server);
         }
         
-        // Update state
-        configs.set(configs_clone.clone());
-        
-        // Close the dialog
-        show_add_dialog.set(false);
-        is_editing.set(false);
-        edit_server.set(None);
-        
-        // Save the updated configuration
-        let config_path = Path::new("servers.json");
-        if let Err(e) = configs_clone.save_to_file(config_path) {
-            error_message.set(Some(format!("Error saving configurations: {}", e)));
+        // Keep the dialog open when the server can't be saved, e.g. because its id is taken
+        if save_configs(configs_clone) {
+            show_add_dialog.set(false);
+            is_editing.set(false);
+            edit_server.set(None);
         }
     };
     
@@ -266,6 +175,99 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
         show_add_dialog.set(false);
         is_editing.set(false);
         edit_server.set(None);
+        error_message.set(None);
+    };
+    
+    // Add the servers chosen in the import preview
+    let submit_import = move |items: Vec<ImportItem>| {
+        let mut configs_clone = configs.read().clone();
+        let summary = configs_clone.apply_import(&items);
+        
+        if save_configs(configs_clone) {
+            notice.set(Some(summary.to_string()));
+            show_import_dialog.set(false);
+        }
+    };
+    
+    // Use the servers of another profile, stopping the ones it leaves out and starting the ones it adds
+    let switch_profile = move |profile: Option<String>| {
+        let previous = mcp_state.peek().active_profile.clone();
+        if previous == profile {
+            return;
+        }
+        if let Err(e) = server_config::save_active_profile(profile.as_deref()) {
+            error!("Failed to remember the active profile: {}", e);
+            error_message.set(Some(format!("Error saving the active profile: {}", e)));
+        }
+        
+        let current = configs.read().clone();
+        let diff = current.for_profile(previous.as_deref()).diff(&current.for_profile(profile.as_deref()));
+        mcp_state.write().active_profile = profile;
+        if !diff.is_empty() {
+            let connections = mcp_state.peek().connections.clone();
+            spawn(async move {
+                connections.apply_config_diff(&diff).await;
+            });
+        }
+    };
+    
+    // Add a profile with every server in it and switch to it
+    let add_profile = move |_| {
+        let name = new_profile.read().trim().to_string();
+        let mut configs_clone = configs.read().clone();
+        if configs_clone.get_profile(&name).is_some() {
+            error_message.set(Some(format!("A profile named \"{}\" already exists", name)));
+            return;
+        }
+        configs_clone.profiles.push(Profile {
+            name: name.clone(),
+            description: None,
+            servers: configs_clone.servers.iter().map(|server| server.id.clone()).collect(),
+        });
+        
+        if save_configs(configs_clone) {
+            new_profile.set(String::new());
+            switch_profile(Some(name));
+        }
+    };
+    
+    // Delete the active profile and go back to all servers
+    let delete_profile = move |_| {
+        let Some(name) = mcp_state.peek().active_profile.clone() else { return };
+        let mut configs_clone = configs.read().clone();
+        configs_clone.profiles.retain(|profile| profile.name != name);
+        
+        if save_configs(configs_clone) {
+            switch_profile(None);
+        }
+    };
+    
+    // Add a server to the active profile or take it out, the config watcher starts or stops it
+    let set_in_profile = move |server_id: String, enabled: bool| {
+        let Some(name) = mcp_state.peek().active_profile.clone() else { return };
+        let mut configs_clone = configs.read().clone();
+        configs_clone.set_enabled(&name, &server_id, enabled);
+        save_configs(configs_clone);
+    };
+    
+    // The profile in use, only when it exists
+    let active_profile = props
+        .mcp_state
+        .read()
+        .active_profile
+        .clone()
+        .filter(|name| configs.read().get_profile(name).is_some());
+    let profile_names: Vec<String> = configs.read().profiles.iter().map(|profile| profile.name.clone()).collect();
+    
+    // Write the servers in the format other MCP clients read
+    let export_servers = move |_| {
+        match configs.read().write_mcp_servers(&conversation_export::default_export_dir()) {
+            Ok(path) => notice.set(Some(format!("Exported to {}", path.display()))),
+            Err(e) => {
+                error!("Failed to export server configurations: {}", e);
+                notice.set(Some(format!("Export failed: {}", e)));
+            }
+        }
     };
     
     rsx! {
@@ -273,32 +275,97 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
             div { class: "server-manager-header",
                 h2 { class: "server-manager-title", "MCP Servers" }
                 
-                button {
-                    class: "add-server-button",
-                    onclick: move |_| add_server(()),
-                    svg {
-                        xmlns: "http://www.w3.org/2000/svg",
-                        width: "16",
-                        height: "16",
-                        view_box: "0 0 24 24",
-                        fill: "none",
-                        stroke: "currentColor",
-                        stroke_width: "2",
-                        stroke_linecap: "round",
-                        stroke_linejoin: "round",
-                        line { x1: "12", y1: "5", x2: "12", y2: "19" }
-                        line { x1: "5", y1: "12", x2: "19", y2: "12" }
+                div { class: "server-manager-actions",
+                    button {
+                        class: "server-action",
+                        title: "Import servers from an mcpServers file, e.g. from Claude Desktop or Cursor",
+                        onclick: move |_| {
+                            error_message.set(None);
+                            show_import_dialog.set(true);
+                        },
+                        "Import"
+                    }
+                    button {
+                        class: "server-action",
+                        title: "Export the servers as an mcpServers file other MCP clients can read",
+                        disabled: configs.read().servers.is_empty(),
+                        onclick: export_servers,
+                        "Export"
+                    }
+                    button {
+                        class: "add-server-button",
+                        onclick: move |_| add_server(()),
+                        svg {
+                            xmlns: "http://www.w3.org/2000/svg",
+                            width: "16",
+                            height: "16",
+                            view_box: "0 0 24 24",
+                            fill: "none",
+                            stroke: "currentColor",
+                            stroke_width: "2",
+                            stroke_linecap: "round",
+                            stroke_linejoin: "round",
+                            line { x1: "12", y1: "5", x2: "12", y2: "19" }
+                            line { x1: "5", y1: "12", x2: "19", y2: "12" }
+                        }
+                        "Add Server"
+                    }
+                }
+            }
+            
+            div { class: "profile-switcher",
+                label { for: "server-profile", "Profile" }
+                select {
+                    id: "server-profile",
+                    class: "form-control profile-select",
+                    value: active_profile.clone().unwrap_or_default(),
+                    onchange: move |e| {
+                        let value = e.value();
+                        switch_profile(if value.is_empty() { None } else { Some(value) });
+                    },
+                    option { value: "", "All servers" }
+                    for name in profile_names.iter() {
+                        option { key: "{name}", value: "{name}", "{name}" }
                     }
-                    "Add Server"
+                }
+                if active_profile.is_some() {
+                    button {
+                        class: "server-action delete",
+                        title: "Delete this profile, its servers stay configured",
+                        onclick: delete_profile,
+                        "Delete"
+                    }
+                }
+                input {
+                    class: "form-control input-field profile-name",
+                    placeholder: "New profile, e.g. work",
+                    value: "{new_profile}",
+                    oninput: move |e| new_profile.set(e.value())
+                }
+                button {
+                    class: "server-action",
+                    disabled: new_profile.read().trim().is_empty(),
+                    onclick: add_profile,
+                    "Add Profile"
                 }
             }
             
+            if let Some(notice) = notice.read().clone() {
+                div { class: "conversation-notice", "{notice}" }
+            }
+            
             if let Some(ref error) = *error_message.read() {
                 div { class: "error-alert",
                     "{error}"
                 }
             }
             
+            if let Some(error) = props.mcp_state.read().config_error.clone() {
+                div { class: "error-alert",
+                    "The configuration file has errors, the servers keep their previous settings: {error}"
+                }
+            }
+            
             if configs.read().servers.is_empty() {
                 div { class: "empty-servers",
                     "No server configurations available. Click 'Add Server' to create one."
@@ -309,12 +376,22 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
                         // Create a vector of RSX nodes, one for each server
                         let props_clone = props.clone();
                         let on_toggle_server_clone = on_toggle_server.clone();
+                        let active_profile = active_profile.clone();
                         configs.read().servers.iter().map(move |server| {
                             let server_id = server.id.clone();
                             let is_selected = props_clone.selected_id.as_ref().map_or(false, |id| id == &server_id);
                             let server_name = server.name.clone();
                             let server_desc = server.description.clone();
+                            let server_transport = server.transport.label();
+                            let server_endpoint = server.endpoint();
                             let is_default = server.is_default;
+                            let has_log = server.transport == TransportConfig::Stdio;
+                            let in_profile = configs.read().is_enabled(&server.id, active_profile.as_deref());
+                            let item_class = match (is_selected, in_profile) {
+                                (true, _) => "server-item selected",
+                                (false, true) => "server-item",
+                                (false, false) => "server-item not-in-profile",
+                            };
                             
                             // Clone server_id for each closure
                             let select_id = server_id.clone();
@@ -326,7 +403,7 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
                             rsx! {
                                 div {
                                     key: "{server_id}",
-                                    class: if is_selected { "server-item selected" } else { "server-item" },
+                                    class: item_class,
                                     div { class: "server-item-content",
                                         div { class: "server-icon",
                                             if is_default {
@@ -377,6 +454,10 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
                                             if let Some(ref desc) = server_desc {
                                                 div { class: "server-description", "{desc}" }
                                             }
+                                            div { class: "server-endpoint",
+                                                span { class: "server-transport", "{server_transport}" }
+                                                "{server_endpoint}"
+                                            }
                                             // Add server status indicator
                                             {
                                                 let server_id_for_status = server_id.clone();
@@ -384,26 +465,62 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
                                                 
                                                 let status_class = match status {
                                                     Some(crate::ServerStatus::Running) => "server-status running",
+                                                    Some(crate::ServerStatus::Degraded(_)) => "server-status degraded",
                                                     Some(crate::ServerStatus::Failed(_)) => "server-status failed",
                                                     Some(crate::ServerStatus::Stopped) => "server-status stopped",
                                                     Some(crate::ServerStatus::Starting) => "server-status starting",
+                                                    Some(crate::ServerStatus::Stopping) => "server-status stopping",
+                                                    Some(crate::ServerStatus::Terminated(_)) => "server-status terminated",
                                                     None => "server-status stopped",
                                                 };
                                                 
                                                 let status_text = match status {
                                                     Some(crate::ServerStatus::Running) => "Running",
+                                                    Some(crate::ServerStatus::Degraded(_)) => "Degraded",
                                                     Some(crate::ServerStatus::Failed(_)) => "Failed",
                                                     Some(crate::ServerStatus::Stopped) => "Stopped",
                                                     Some(crate::ServerStatus::Starting) => "Starting",
+                                                    Some(crate::ServerStatus::Stopping) => "Stopping",
+                                                    Some(crate::ServerStatus::Terminated(_)) => "Stopped",
                                                     None => "Stopped",
                                                 };
                                                 
-                                                let error_msg = if let Some(crate::ServerStatus::Failed(error)) = status {
-                                                    Some(error)
-                                                } else {
-                                                    None
+                                                let is_up = matches!(status, Some(crate::ServerStatus::Running) | Some(crate::ServerStatus::Degraded(_)));
+                                                
+                                                // Errors, and how a server went away when it had to be terminated
+                                                let error_msg = match status {
+                                                    Some(crate::ServerStatus::Failed(error)) => {
+                                                        // What the server wrote last usually explains the failure
+                                                        let tail = local_props.mcp_state.read().server_logs.tail(&server_id_for_status, 5);
+                                                        if tail.is_empty() {
+                                                            Some(error)
+                                                        } else {
+                                                            let output: Vec<String> = tail.into_iter().map(|line| line.text).collect();
+                                                            Some(format!("{}\n\n{}", error, output.join("\n")))
+                                                        }
+                                                    }
+                                                    Some(crate::ServerStatus::Degraded(reason)) => Some(reason),
+                                                    Some(crate::ServerStatus::Terminated(outcome)) => Some(outcome),
+                                                    _ => None,
                                                 };
                                                 
+                                                // Latency of the last ping, with the recent history on hover
+                                                let latency = health.read().get(&server_id_for_status).and_then(|health| {
+                                                    let last = health.last_latency()?;
+                                                    let average = health.average_latency().unwrap_or(last);
+                                                    let max = health.max_latency().unwrap_or(last);
+                                                    Some((
+                                                        format!("{} ms", last.as_millis()),
+                                                        format!(
+                                                            "Ping latency of the last {} checks: average {} ms, max {} ms, {} missed",
+                                                            health.history.len(),
+                                                            average.as_millis(),
+                                                            max.as_millis(),
+                                                            health.missed_total()
+                                                        ),
+                                                    ))
+                                                }).filter(|_| is_up);
+                                                
                                                 rsx! {
                                                     div { class: status_class, 
                                                         "{status_text}"
@@ -414,12 +531,31 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
                                                                 "!"
                                                             }
                                                         }
+                                                        if let Some((last, history)) = latency {
+                                                            span { class: "server-latency", title: "{history}", "{last}" }
+                                                        }
                                                     }
                                                 }
                                             }
                                         }
                                     }
                                     div { class: "server-actions",
+                                        if active_profile.is_some() {
+                                            label {
+                                                class: "profile-member",
+                                                title: "Start this server with the profile",
+                                                input {
+                                                    r#type: "checkbox",
+                                                    checked: in_profile,
+                                                    onchange: {
+                                                        let profile_id = server_id.clone();
+                                                        move |e: FormEvent| set_in_profile(profile_id.clone(), e.checked())
+                                                    }
+                                                }
+                                                "In profile"
+                                            }
+                                        }
+                                        
                                         // Get server status to show appropriate button
                                         {
                                             let server_id_for_status = server_id.clone();
@@ -433,7 +569,7 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
                                                 let mut is_disabled = false;
                                                 
                                                 match status {
-                                                    Some(crate::ServerStatus::Running) => {
+                                                    Some(crate::ServerStatus::Running) | Some(crate::ServerStatus::Degraded(_)) => {
                                                         button_text = "Stop";
                                                         button_class = "server-action stop";
                                                         is_disabled = false;
@@ -448,7 +584,12 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
                                                         button_class = "server-action starting";
                                                         is_disabled = true;
                                                     },
-                                                    Some(crate::ServerStatus::Stopped) | None => {
+                                                    Some(crate::ServerStatus::Stopping) => {
+                                                        button_text = "Stopping...";
+                                                        button_class = "server-action stopping";
+                                                        is_disabled = true;
+                                                    },
+                                                    Some(crate::ServerStatus::Stopped) | Some(crate::ServerStatus::Terminated(_)) | None => {
                                                         button_text = "Start";
                                                         button_class = "server-action start";
                                                         is_disabled = false;
@@ -467,7 +608,7 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
                                                         disabled: is_disabled,
                                                         onclick: move |_| {
                                                             // If server is running, also select it when clicked
-                                                            if matches!(status_for_check, Some(crate::ServerStatus::Running)) {
+                                                            if matches!(status_for_check, Some(crate::ServerStatus::Running) | Some(crate::ServerStatus::Degraded(_))) {
                                                                 select_handler(select_server_id.clone());
                                                             }
                                                             toggle_arc.call(server_for_toggle.clone())
@@ -480,6 +621,30 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
                                             }
                                         }
                                         
+                                        if has_log {
+                                            button {
+                                                class: "server-action logs",
+                                                title: "Show server log",
+                                                onclick: {
+                                                    let log_id = server_id.clone();
+                                                    move |_| log_server.set(Some(log_id.clone()))
+                                                },
+                                                svg {
+                                                    xmlns: "http://www.w3.org/2000/svg",
+                                                    width: "16",
+                                                    height: "16",
+                                                    view_box: "0 0 24 24",
+                                                    fill: "none",
+                                                    stroke: "currentColor",
+                                                    stroke_width: "2",
+                                                    stroke_linecap: "round",
+                                                    stroke_linejoin: "round",
+                                                    polyline { points: "4 17 10 11 4 5" }
+                                                    line { x1: "12", y1: "19", x2: "20", y2: "19" }
+                                                }
+                                            }
+                                        }
+                                        
                                         button {
                                             class: "server-action edit",
                                             onclick: move |_| edit_server_fn(edit_id.clone()),
@@ -523,13 +688,45 @@ pub fn ServerManager(mut props: ServerManagerProps) -> Element {
                 }
             }
             
+            // Stderr output of a local server
+            if let Some(log_id) = log_server.read().clone() {
+                ServerLogViewer {
+                    mcp_state: props.mcp_state,
+                    servers: configs
+                        .read()
+                        .servers
+                        .iter()
+                        .filter(|s| s.transport == TransportConfig::Stdio)
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                    server_id: log_id,
+                    on_select: move |id| log_server.set(Some(id)),
+                    on_close: move |_| log_server.set(None),
+                }
+            }
+            
             // Server add/edit dialog
             if *show_add_dialog.read() {
                 ServerDialog {
                     server: edit_server.read().clone(),
                     is_editing: *is_editing.read(),
+                    error: error_message.read().clone(),
                     on_submit: submit_server,
                     on_cancel: close_dialog,
+                    secrets: props.mcp_state.peek().secrets.clone(),
+                }
+            }
+            
+            // Import preview
+            if *show_import_dialog.read() {
+                ServerImportDialog {
+                    configs: configs.read().clone(),
+                    error: error_message.read().clone(),
+                    on_submit: submit_import,
+                    on_cancel: move |_| {
+                        show_import_dialog.set(false);
+                        error_message.set(None);
+                    },
                 }
             }
         }
@@ -543,8 +740,13 @@ pub struct ServerDialogProps {
     pub server: Option<ServerConfig>,
     #[props(default)]
     pub is_editing: bool,
+    /// Why the server couldn't be saved
+    #[props(default)]
+    pub error: Option<String>,
     pub on_submit: EventHandler<ServerConfig>,
     pub on_cancel: EventHandler<()>,
+    /// Where plaintext values are moved when they are stored as secrets
+    pub secrets: SecretStore,
 }
 
 #[component]
@@ -552,15 +754,31 @@ fn ServerDialog(props: ServerDialogProps) -> Element {
     // Form state
     let mut id = use_signal(|| props.server.as_ref().map_or("".to_string(), |s| s.id.clone()));
     let mut name = use_signal(|| props.server.as_ref().map_or("".to_string(), |s| s.name.clone()));
+    let mut transport_kind = use_signal(|| props.server.as_ref().map_or("stdio", |s| s.transport.label()).to_string());
+    let mut url = use_signal(|| props.server.as_ref().and_then(|s| s.transport.url()).unwrap_or_default().to_string());
     let mut command = use_signal(|| props.server.as_ref().map_or("docker".to_string(), |s| s.command.clone()));
     let mut args = use_signal(|| props.server.as_ref().map_or("".to_string(), |s| s.args.join(" ")));
     let mut description = use_signal(|| props.server.as_ref().map_or("".to_string(), |s| s.description.clone().unwrap_or_default()));
     let mut is_default = use_signal(|| props.server.as_ref().map_or(false, |s| s.is_default));
+    let restart_policy = props.server.as_ref().map(|s| s.restart.clone()).unwrap_or_default();
+    let mut restart_mode = use_signal(|| restart_policy.mode);
+    let mut max_retries = use_signal(|| restart_policy.max_retries.to_string());
+    let mut initial_backoff = use_signal(|| (restart_policy.initial_backoff_ms as f64 / 1000.0).to_string());
+    let health_check = props.server.as_ref().map(|s| s.health.clone()).unwrap_or_default();
+    let mut health_enabled = use_signal(|| health_check.enabled);
+    let mut health_interval = use_signal(|| (health_check.interval_ms as f64 / 1000.0).to_string());
+    let mut health_timeout = use_signal(|| (health_check.timeout_ms as f64 / 1000.0).to_string());
     
     let mut env_keys = use_signal(Vec::<String>::new);
     let mut env_values = use_signal(Vec::<String>::new);
+    let mut header_keys = use_signal(Vec::<String>::new);
+    let mut header_values = use_signal(Vec::<String>::new);
+    // Rows whose values are shown instead of masked
+    let mut revealed_env = use_signal(std::collections::HashSet::<usize>::new);
+    let mut revealed_headers = use_signal(std::collections::HashSet::<usize>::new);
+    let mut secret_error = use_signal(|| None::<String>);
     
-    // Initialize environment variables
+    // Initialize environment variables and headers
     use_effect(move || {
         if let Some(ref server) = props.server {
             let keys: Vec<String> = server.env.keys().cloned().collect();
@@ -568,6 +786,14 @@ fn ServerDialog(props: ServerDialogProps) -> Element {
             
             env_keys.set(keys);
             env_values.set(values);
+            
+            if let Some(headers) = server.transport.headers() {
+                let keys: Vec<String> = headers.keys().cloned().collect();
+                let values: Vec<String> = keys.iter().map(|k| headers.get(k).cloned().unwrap_or_default()).collect();
+                
+                header_keys.set(keys);
+                header_values.set(values);
+            }
         }
     });
     
@@ -582,9 +808,46 @@ fn ServerDialog(props: ServerDialogProps) -> Element {
         if index < env_keys.read().len() {
             env_keys.write().remove(index);
             env_values.write().remove(index);
+            revealed_env.write().clear();
         }
     };
     
+    // Add new header
+    let mut add_header = move |_| {
+        header_keys.write().push("".to_string());
+        header_values.write().push("".to_string());
+    };
+    
+    // Remove header
+    let mut remove_header = move |index: usize| {
+        if index < header_keys.read().len() {
+            header_keys.write().remove(index);
+            header_values.write().remove(index);
+            revealed_headers.write().clear();
+        }
+    };
+    
+    // Move a plaintext value into the secret store and put a reference to it in its place
+    let mut store_as_secret = move |store: &SecretStore, key: String, mut values: Signal<Vec<String>>, idx: usize| {
+        let Some(value) = values.read().get(idx).cloned() else { return };
+        let name = secrets::suggested_name(&id.read(), &key);
+        match store.set(&name, &value) {
+            Ok(()) => {
+                values.write()[idx] = secrets::secret_ref(&name);
+                secret_error.set(None);
+            }
+            Err(e) => secret_error.set(Some(format!("Can't store {} as a secret: {}", key, e))),
+        }
+    };
+    // Values are masked unless revealed, references to secrets aren't secret themselves
+    let is_masked = |value: &str, revealed: bool| !revealed && !value.is_empty() && secrets::secret_refs(value).is_empty();
+    let can_store = props.secrets.is_unlocked();
+    
+    let is_stdio = transport_kind.read().as_str() == "stdio";
+    let can_submit = !id.read().is_empty()
+        && !name.read().is_empty()
+        && if is_stdio { !command.read().is_empty() } else { !url.read().trim().is_empty() };
+    
     // Submit form
     let submit = move |_| {
         // Create environment variables map
@@ -601,15 +864,59 @@ fn ServerDialog(props: ServerDialogProps) -> Element {
             .map(|s| s.to_string())
             .collect::<Vec<String>>();
         
+        // Create headers map
+        let mut headers = std::collections::HashMap::new();
+        for (i, key) in header_keys.read().iter().enumerate() {
+            if !key.trim().is_empty() && i < header_values.read().len() {
+                headers.insert(key.trim().to_string(), header_values.read()[i].clone());
+            }
+        }
+        
+        let url = url.read().trim().to_string();
+        let transport = match transport_kind.read().as_str() {
+            "sse" => TransportConfig::Sse { url, headers },
+            "http" => TransportConfig::Http { url, headers },
+            _ => TransportConfig::Stdio,
+        };
+        
+        // Restart policy, invalid numbers fall back to the defaults
+        let defaults = RestartPolicy::default();
+        let restart = RestartPolicy {
+            mode: *restart_mode.read(),
+            max_retries: max_retries.read().trim().parse().unwrap_or(defaults.max_retries),
+            initial_backoff_ms: initial_backoff
+                .read()
+                .trim()
+                .parse::<f64>()
+                .map(|secs| (secs.max(0.0) * 1000.0) as u64)
+                .unwrap_or(defaults.initial_backoff_ms),
+            max_backoff_ms: restart_policy.max_backoff_ms,
+        };
+        
+        // Health checks, likewise
+        let health_defaults = HealthCheckConfig::default();
+        let seconds_to_ms = |value: &str, default: u64| {
+            value.trim().parse::<f64>().map(|secs| (secs.max(0.0) * 1000.0) as u64).unwrap_or(default)
+        };
+        let health = HealthCheckConfig {
+            enabled: *health_enabled.read(),
+            interval_ms: seconds_to_ms(&health_interval.read(), health_defaults.interval_ms),
+            timeout_ms: seconds_to_ms(&health_timeout.read(), health_defaults.timeout_ms),
+            max_missed: health_check.max_missed,
+        };
+        
         // Create server config
         let server = ServerConfig {
             id: id.read().clone(),
             name: name.read().clone(),
+            transport,
             command: command.read().clone(),
             args: args_vec,
             env,
             description: if description.read().is_empty() { None } else { Some(description.read().clone()) },
             is_default: *is_default.read(),
+            restart,
+            health,
         };
         
         props.on_submit.call(server);
@@ -654,24 +961,212 @@ fn ServerDialog(props: ServerDialogProps) -> Element {
                     }
                     
                     div { class: "form-group",
-                        label { for: "server-command", "Command" }
-                        input { 
-                            id: "server-command",
-                            class: "form-control input-field",
-                            value: "{command}",
-                            placeholder: "e.g., docker",
-                            oninput: move |e| command.set(e.value().clone())
+                        label { for: "server-transport", "Transport" }
+                        select {
+                            id: "server-transport",
+                            class: "form-control",
+                            value: "{transport_kind}",
+                            onchange: move |e| transport_kind.set(e.value()),
+                            option { value: "stdio", "stdio (local command)" }
+                            option { value: "sse", "HTTP + SSE" }
+                            option { value: "http", "Streamable HTTP" }
                         }
                     }
                     
-                    div { class: "form-group",
-                        label { for: "server-args", "Arguments (space separated)" }
-                        textarea { 
-                            id: "server-args",
-                            class: "form-control",
-                            value: "{args}",
-                            placeholder: "e.g., run -i --rm mcp/filesystem",
-                            oninput: move |e| args.set(e.value().clone())
+                    if is_stdio {
+                        div { class: "form-group",
+                            label { for: "server-command", "Command" }
+                            input { 
+                                id: "server-command",
+                                class: "form-control input-field",
+                                value: "{command}",
+                                placeholder: "e.g., docker",
+                                oninput: move |e| command.set(e.value().clone())
+                            }
+                        }
+                    
+                        div { class: "form-group",
+                            label { for: "server-args", "Arguments (space separated)" }
+                            textarea { 
+                                id: "server-args",
+                                class: "form-control",
+                                value: "{args}",
+                                placeholder: "e.g., run -i --rm mcp/filesystem",
+                                oninput: move |e| args.set(e.value().clone())
+                            }
+                        }
+                        
+                        // Environment variables
+                        div { class: "form-group",
+                            div { class: "form-group-header",
+                                label { "Environment Variables" }
+                                button {
+                                    class: "btn-add-env",
+                                    onclick: move |_| add_env_var(()),
+                                    "+"
+                                }
+                            }
+                        
+                            div { class: "env-vars-list",
+                                for (idx, key) in env_keys.read().clone().iter().enumerate() {
+                                    div {
+                                        key: "{idx}",
+                                        class: "env-var-item",
+                                        input {
+                                            class: "form-control env-key",
+                                            placeholder: "Key",
+                                            value: "{key}",
+                                            oninput: move |e| {
+                                                let mut keys = env_keys.read().clone();
+                                                if idx < keys.len() {
+                                                    keys[idx] = e.value().clone();
+                                                    env_keys.set(keys);
+                                                }
+                                            }
+                                        }
+                                        {
+                                            let value = env_values.read().get(idx).cloned().unwrap_or_default();
+                                            let masked = is_masked(&value, revealed_env.read().contains(&idx));
+                                            let storable = can_store && !key.is_empty() && is_masked(&value, false);
+                                            let key = key.clone();
+                                            let store = props.secrets.clone();
+                                            rsx! {
+                                                input {
+                                                    class: "form-control env-value",
+                                                    placeholder: "Value or ${{secret:NAME}}",
+                                                    r#type: if masked { "password" } else { "text" },
+                                                    value: "{value}",
+                                                    oninput: move |e| {
+                                                        let mut values = env_values.read().clone();
+                                                        if idx < values.len() {
+                                                            values[idx] = e.value().clone();
+                                                            env_values.set(values);
+                                                        }
+                                                    }
+                                                }
+                                                div { class: "env-var-actions",
+                                                    button {
+                                                        class: "btn-reveal-env",
+                                                        title: if masked { "Show the value" } else { "Hide the value" },
+                                                        onclick: move |_| {
+                                                            let mut shown = revealed_env.write();
+                                                            if !shown.remove(&idx) {
+                                                                shown.insert(idx);
+                                                            }
+                                                        },
+                                                        if masked { "Show" } else { "Hide" }
+                                                    }
+                                                    if storable {
+                                                        button {
+                                                            class: "btn-reveal-env",
+                                                            title: "Move the value into the secret store and refer to it",
+                                                            onclick: move |_| store_as_secret(&store, key.clone(), env_values, idx),
+                                                            "Secret"
+                                                        }
+                                                    }
+                                                    button {
+                                                        class: "btn-remove-env",
+                                                        onclick: move |_| remove_env_var(idx),
+                                                        "×"
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    } else {
+                        div { class: "form-group",
+                            label { for: "server-url", "URL" }
+                            input {
+                                id: "server-url",
+                                class: "form-control input-field",
+                                value: "{url}",
+                                placeholder: if transport_kind.read().as_str() == "sse" { "e.g., http://localhost:8000/sse" } else { "e.g., http://localhost:8000/mcp" },
+                                oninput: move |e| url.set(e.value().clone())
+                            }
+                        }
+                        
+                        // HTTP headers, e.g. for authorization
+                        div { class: "form-group",
+                            div { class: "form-group-header",
+                                label { "Headers" }
+                                button {
+                                    class: "btn-add-env",
+                                    onclick: move |_| add_header(()),
+                                    "+"
+                                }
+                            }
+                            
+                            div { class: "env-vars-list",
+                                for (idx, key) in header_keys.read().clone().iter().enumerate() {
+                                    div {
+                                        key: "{idx}",
+                                        class: "env-var-item",
+                                        input {
+                                            class: "form-control env-key",
+                                            placeholder: "Header",
+                                            value: "{key}",
+                                            oninput: move |e| {
+                                                let mut keys = header_keys.read().clone();
+                                                if idx < keys.len() {
+                                                    keys[idx] = e.value().clone();
+                                                    header_keys.set(keys);
+                                                }
+                                            }
+                                        }
+                                        {
+                                            let value = header_values.read().get(idx).cloned().unwrap_or_default();
+                                            let masked = is_masked(&value, revealed_headers.read().contains(&idx));
+                                            let storable = can_store && !key.trim().is_empty() && is_masked(&value, false);
+                                            let key = key.trim().to_string();
+                                            let store = props.secrets.clone();
+                                            rsx! {
+                                                input {
+                                                    class: "form-control env-value",
+                                                    placeholder: "Value or ${{secret:NAME}}",
+                                                    r#type: if masked { "password" } else { "text" },
+                                                    value: "{value}",
+                                                    oninput: move |e| {
+                                                        let mut values = header_values.read().clone();
+                                                        if idx < values.len() {
+                                                            values[idx] = e.value().clone();
+                                                            header_values.set(values);
+                                                        }
+                                                    }
+                                                }
+                                                div { class: "env-var-actions",
+                                                    button {
+                                                        class: "btn-reveal-env",
+                                                        title: if masked { "Show the value" } else { "Hide the value" },
+                                                        onclick: move |_| {
+                                                            let mut shown = revealed_headers.write();
+                                                            if !shown.remove(&idx) {
+                                                                shown.insert(idx);
+                                                            }
+                                                        },
+                                                        if masked { "Show" } else { "Hide" }
+                                                    }
+                                                    if storable {
+                                                        button {
+                                                            class: "btn-reveal-env",
+                                                            title: "Move the value into the secret store and refer to it",
+                                                            onclick: move |_| store_as_secret(&store, key.clone(), header_values, idx),
+                                                            "Secret"
+                                                        }
+                                                    }
+                                                    button {
+                                                        class: "btn-remove-env",
+                                                        onclick: move |_| remove_header(idx),
+                                                        "×"
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
                         }
                     }
                     
@@ -686,6 +1181,90 @@ fn ServerDialog(props: ServerDialogProps) -> Element {
                         }
                     }
                     
+                    div { class: "form-group",
+                        label { for: "server-restart", "Restart Policy" }
+                        div { class: "restart-policy",
+                            select {
+                                id: "server-restart",
+                                class: "form-control",
+                                value: match *restart_mode.read() {
+                                    RestartMode::Never => "never",
+                                    RestartMode::OnFailure => "on-failure",
+                                    RestartMode::Always => "always",
+                                },
+                                onchange: move |e| restart_mode.set(match e.value().as_str() {
+                                    "never" => RestartMode::Never,
+                                    "always" => RestartMode::Always,
+                                    _ => RestartMode::OnFailure,
+                                }),
+                                option { value: "never", "Never" }
+                                option { value: "on-failure", "On failure" }
+                                option { value: "always", "Always" }
+                            }
+                            if *restart_mode.read() != RestartMode::Never {
+                                label { class: "restart-field",
+                                    "Max retries"
+                                    input {
+                                        class: "form-control input-field",
+                                        r#type: "number",
+                                        min: "0",
+                                        value: "{max_retries}",
+                                        oninput: move |e| max_retries.set(e.value())
+                                    }
+                                }
+                                label { class: "restart-field",
+                                    "First delay (s)"
+                                    input {
+                                        class: "form-control input-field",
+                                        r#type: "number",
+                                        min: "0",
+                                        step: "0.5",
+                                        value: "{initial_backoff}",
+                                        oninput: move |e| initial_backoff.set(e.value())
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    
+                    div { class: "form-group",
+                        label { for: "server-health", "Health Check" }
+                        div { class: "restart-policy",
+                            label { class: "restart-field",
+                                input {
+                                    id: "server-health",
+                                    r#type: "checkbox",
+                                    checked: *health_enabled.read(),
+                                    onchange: move |e| health_enabled.set(e.checked())
+                                }
+                                "Ping the server"
+                            }
+                            if *health_enabled.read() {
+                                label { class: "restart-field",
+                                    "Every (s)"
+                                    input {
+                                        class: "form-control input-field",
+                                        r#type: "number",
+                                        min: "1",
+                                        value: "{health_interval}",
+                                        oninput: move |e| health_interval.set(e.value())
+                                    }
+                                }
+                                label { class: "restart-field",
+                                    "Timeout (s)"
+                                    input {
+                                        class: "form-control input-field",
+                                        r#type: "number",
+                                        min: "0.5",
+                                        step: "0.5",
+                                        value: "{health_timeout}",
+                                        oninput: move |e| health_timeout.set(e.value())
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    
                     div { class: "form-check",
                         input { 
                             id: "server-default",
@@ -696,58 +1275,197 @@ fn ServerDialog(props: ServerDialogProps) -> Element {
                         }
                         label { for: "server-default", "Set as default server" }
                     }
-                    
-                    // Environment variables
+                }
+                
+                if let Some(error) = secret_error.read().as_ref() {
+                    div { class: "error-alert", "{error}" }
+                }
+                
+                if let Some(error) = props.error.as_ref() {
+                    div { class: "error-alert", "{error}" }
+                }
+                
+                div { class: "dialog-footer",
+                    button {
+                        class: "btn-cancel",
+                        onclick: move |_| props.on_cancel.call(()),
+                        "Cancel"
+                    }
+                    button {
+                        class: "btn-submit",
+                        onclick: submit,
+                        disabled: !can_submit,
+                        if props.is_editing { "Update" } else { "Save" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Server import dialog component
+#[derive(Props, Clone, PartialEq)]
+pub struct ServerImportDialogProps {
+    /// The configuration the servers are imported into, to find conflicting ids
+    pub configs: ServerConfigs,
+    /// Why the import couldn't be saved
+    #[props(default)]
+    pub error: Option<String>,
+    pub on_submit: EventHandler<Vec<ImportItem>>,
+    pub on_cancel: EventHandler<()>,
+}
+
+/// Dialog that reads servers from a file or pasted JSON and previews them before they are added
+#[component]
+fn ServerImportDialog(props: ServerImportDialogProps) -> Element {
+    let mut json = use_signal(String::new);
+    let mut items = use_signal(|| None::<Vec<ImportItem>>);
+    let mut parse_error = use_signal(|| None::<String>);
+    
+    // Read the servers and check them against the existing ones
+    let mut preview = move |configs: &ServerConfigs, text: String| {
+        match server_config::parse_import(&text) {
+            Ok(servers) => {
+                items.set(Some(configs.preview_import(servers)));
+                parse_error.set(None);
+            }
+            Err(e) => {
+                items.set(None);
+                parse_error.set(Some(e.to_string()));
+            }
+        }
+        json.set(text);
+    };
+    
+    let selected_count = items.read().as_ref().map_or(0, |items| {
+        items
+            .iter()
+            .filter(|item| item.selected && !(item.conflicts && item.resolution == ConflictResolution::Skip))
+            .count()
+    });
+    
+    rsx! {
+        div { class: "dialog-overlay",
+            div { class: "server-dialog",
+                div { class: "dialog-header",
+                    h3 { class: "dialog-title", "Import Servers" }
+                    button {
+                        class: "dialog-close",
+                        onclick: move |_| props.on_cancel.call(()),
+                        "×"
+                    }
+                }
+                
+                div { class: "dialog-content",
                     div { class: "form-group",
                         div { class: "form-group-header",
-                            label { "Environment Variables" }
-                            button {
-                                class: "btn-add-env",
-                                onclick: move |_| add_env_var(()),
-                                "+"
+                            label { for: "server-import-json", "Paste JSON" }
+                            label {
+                                class: "server-action",
+                                title: "Read the servers from a file, e.g. claude_desktop_config.json",
+                                "From file..."
+                                input {
+                                    r#type: "file",
+                                    accept: ".json,application/json",
+                                    style: "display: none;",
+                                    onchange: {
+                                        let configs = props.configs.clone();
+                                        move |evt: FormEvent| {
+                                            let configs = configs.clone();
+                                            async move {
+                                                let Some(engine) = evt.files() else { return };
+                                                let Some(name) = engine.files().into_iter().next() else { return };
+                                                match engine.read_file_to_string(&name).await {
+                                                    Some(text) => preview(&configs, text),
+                                                    None => error!("Failed to read imported file {}", name),
+                                                }
+                                            }
+                                        }
+                                    },
+                                }
                             }
                         }
-                        
-                        div { class: "env-vars-list",
-                            for (idx, key) in env_keys.read().clone().iter().enumerate() {
+                        textarea {
+                            id: "server-import-json",
+                            class: "form-control",
+                            value: "{json}",
+                            placeholder: "An mcpServers object as used by Claude Desktop and Cursor, or an mDesk servers list",
+                            oninput: {
+                                let configs = props.configs.clone();
+                                move |e: FormEvent| preview(&configs, e.value())
+                            }
+                        }
+                    }
+                    
+                    if let Some(error) = parse_error.read().as_ref().filter(|_| !json.read().trim().is_empty()) {
+                        div { class: "error-alert", "{error}" }
+                    }
+                    
+                    if let Some(preview_items) = items.read().clone() {
+                        div { class: "import-preview",
+                            for (idx, item, id, transport, endpoint) in preview_items
+                                .into_iter()
+                                .enumerate()
+                                .map(|(idx, item)| {
+                                    let id = item.config.id.clone();
+                                    let transport = item.config.transport.label();
+                                    let endpoint = item.config.endpoint();
+                                    (idx, item, id, transport, endpoint)
+                                })
+                            {
                                 div {
                                     key: "{idx}",
-                                    class: "env-var-item",
-                                    input {
-                                        class: "form-control env-key",
-                                        placeholder: "Key",
-                                        value: "{key}",
-                                        oninput: move |e| {
-                                            let mut keys = env_keys.read().clone();
-                                            if idx < keys.len() {
-                                                keys[idx] = e.value().clone();
-                                                env_keys.set(keys);
-                                            }
+                                    class: if item.conflicts { "import-item conflict" } else { "import-item" },
+                                    label { class: "import-item-name",
+                                        input {
+                                            r#type: "checkbox",
+                                            checked: item.selected,
+                                            onchange: move |e| items.with_mut(|preview| {
+                                                if let Some(preview) = preview {
+                                                    preview[idx].selected = e.checked();
+                                                }
+                                            })
                                         }
+                                        span { class: "server-name", "{id}" }
+                                        span { class: "server-transport", "{transport}" }
                                     }
-                                    input {
-                                        class: "form-control env-value",
-                                        placeholder: "Value",
-                                        value: if idx < env_values.read().len() { env_values.read()[idx].clone() } else { "".to_string() },
-                                        oninput: move |e| {
-                                            let mut values = env_values.read().clone();
-                                            if idx < values.len() {
-                                                values[idx] = e.value().clone();
-                                                env_values.set(values);
+                                    div { class: "server-endpoint", "{endpoint}" }
+                                    if item.conflicts {
+                                        div { class: "import-conflict",
+                                            "A server with this id already exists:"
+                                            select {
+                                                class: "form-control",
+                                                disabled: !item.selected,
+                                                value: match item.resolution {
+                                                    ConflictResolution::Skip => "skip",
+                                                    ConflictResolution::Replace => "replace",
+                                                    ConflictResolution::KeepBoth => "keep-both",
+                                                },
+                                                onchange: move |e| items.with_mut(|preview| {
+                                                    if let Some(preview) = preview {
+                                                        preview[idx].resolution = match e.value().as_str() {
+                                                            "replace" => ConflictResolution::Replace,
+                                                            "keep-both" => ConflictResolution::KeepBoth,
+                                                            _ => ConflictResolution::Skip,
+                                                        };
+                                                    }
+                                                }),
+                                                option { value: "skip", "Keep the existing server" }
+                                                option { value: "replace", "Replace it" }
+                                                option { value: "keep-both", "Import under a new id" }
                                             }
                                         }
                                     }
-                                    button {
-                                        class: "btn-remove-env",
-                                        onclick: move |_| remove_env_var(idx),
-                                        "×"
-                                    }
                                 }
                             }
                         }
                     }
                 }
                 
+                if let Some(error) = props.error.as_ref() {
+                    div { class: "error-alert", "{error}" }
+                }
+                
                 div { class: "dialog-footer",
                     button {
                         class: "btn-cancel",
@@ -756,9 +1474,13 @@ fn ServerDialog(props: ServerDialogProps) -> Element {
                     }
                     button {
                         class: "btn-submit",
-                        onclick: submit,
-                        disabled: id.read().is_empty() || name.read().is_empty() || command.read().is_empty(),
-                        if props.is_editing { "Update" } else { "Save" }
+                        disabled: selected_count == 0,
+                        onclick: move |_| {
+                            if let Some(items) = items.read().clone() {
+                                props.on_submit.call(items);
+                            }
+                        },
+                        "Import {selected_count}"
                     }
                 }
             }
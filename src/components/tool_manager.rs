@@ -1,17 +1,55 @@
 use dioxus::prelude::*;
 use mcp_client::{McpClientTrait, Error as McpError};
 use mcp_core::{Tool, protocol::CallToolResult, content::Content};
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use regex::Regex;
 use tracing::{debug, info, warn, error, trace, instrument};
 use crate::components::tool_suggestion::{ToolSuggestionProps, ToolExecutionProps, ToolExecutionStatus};
+use crate::components::agent::AgentStep;
+use crate::components::tool_registry::{ToolRegistry, ToolResolveError, QUALIFIED_NAME_SEPARATOR};
+use crate::openrouter::FunctionNames;
+use crate::connection_manager::ConnectionManager;
+use crate::transport::ServerClient;
 use crate::McpState;
 use anyhow::Result;
 
+/// Errors that can occur when executing a tool
+#[derive(Debug, thiserror::Error)]
+pub enum ToolExecutionError {
+    #[error(transparent)]
+    Resolve(#[from] ToolResolveError),
+    
+    #[error(transparent)]
+    Mcp(#[from] McpError),
+}
+
+/// The clients of the running servers and the registry routing tool calls to them
+///
+/// Copied out of `McpState`, so a tool call doesn't hold a borrow of the state while it runs.
+#[derive(Clone)]
+pub struct ToolRouter {
+    pub clients: HashMap<String, Arc<Mutex<ServerClient>>>,
+    pub registry: ToolRegistry,
+    /// Asked for the tools again when a call names one the registry doesn't know
+    pub connections: ConnectionManager,
+}
+
+impl From<&McpState> for ToolRouter {
+    fn from(state: &McpState) -> Self {
+        Self {
+            clients: state.active_clients.clone(),
+            registry: state.tool_registry.clone(),
+            connections: state.connections.clone(),
+        }
+    }
+}
+
 /// Types of tool interactions detected in messages
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ToolInteraction {
     /// AI suggests using a tool
     Suggestion { 
@@ -26,6 +64,9 @@ pub enum ToolInteraction {
         status: ToolExecutionStatus,
         result: Option<String>,
         message_idx: usize,
+        /// Step of the agent run this execution belongs to, if any
+        #[serde(default)]
+        step: Option<AgentStep>,
     },
 }
 
@@ -135,32 +176,53 @@ impl ToolManager {
     }
     
     /// Execute a tool with the given name and arguments
-    #[instrument(level = "debug", skip(arguments, mcp_state), fields(tool_name = %tool_name))]
+    ///
+    /// The tool name may be bare (`read_file`) or qualified (`filesystem/read_file`).
+    /// The call is routed to the server that owns the tool according to the
+    /// router's registry. When the tool is not known yet, the idle servers are asked
+    /// for their tools again, which updates the registry of the state as well.
+    #[instrument(level = "debug", skip(arguments, router), fields(tool_name = %tool_name))]
     pub async fn execute_tool(
         tool_name: String,
         arguments: Value,
-        mcp_state: &McpState,
-    ) -> Result<CallToolResult, McpError> {
+        router: &ToolRouter,
+    ) -> Result<CallToolResult, ToolExecutionError> {
         info!("Executing tool: {} with arguments: {}", tool_name, arguments);
         
-        let client = mcp_state.client.as_ref()
+        if router.clients.is_empty() {
+            error!("No active MCP clients");
+            return Err(ToolExecutionError::Mcp(McpError::NotInitialized));
+        }
+        
+        let (server_id, bare_name) = match router.registry.resolve(&tool_name) {
+            Ok(resolved) => resolved,
+            Err(ToolResolveError::UnknownTool(_)) => {
+                // The registry may be stale, the refreshed tools reach the state with the next sync
+                debug!("Tool {} not in registry, refreshing the tools of the running servers", tool_name);
+                let registry = router.connections.refresh_idle_tools().await;
+                registry.resolve(&tool_name)?
+            }
+            Err(e) => return Err(e.into()),
+        };
+        
+        let client = router.clients.get(&server_id)
             .ok_or_else(|| {
-                error!("MCP client not initialized");
-                McpError::NotInitialized
+                error!("Server {} owning tool {} is not running", server_id, bare_name);
+                ToolResolveError::UnknownServer(server_id.clone())
             })?;
         
-        debug!("Got MCP client, acquiring lock");
+        debug!("Routing {} to server {}, acquiring lock", bare_name, server_id);
         let client = client.lock().await;
         debug!("Lock acquired, calling tool");
         
-        match client.call_tool(&tool_name, arguments).await {
+        match client.call_tool(&bare_name, arguments).await {
             Ok(result) => {
-                info!("Tool execution successful: {}", tool_name);
+                info!("Tool execution successful: {} on {}", bare_name, server_id);
                 Ok(result)
             }
             Err(e) => {
-                error!("Tool execution failed: {} - Error: {}", tool_name, e);
-                Err(e)
+                error!("Tool execution failed: {} on {} - Error: {}", bare_name, server_id, e);
+                Err(e.into())
             }
         }
     }
@@ -197,7 +259,20 @@ impl ToolManager {
     }
     
     /// Find a tool by name from a list of available tools
+    ///
+    /// Qualified `server/tool` names also match a tool listed under its bare name.
     pub fn find_tool_by_name<'a>(name: &str, tools: &'a [Tool]) -> Option<&'a Tool> {
-        tools.iter().find(|t| t.name == name)
+        tools.iter().find(|t| t.name == name).or_else(|| {
+            let (_, bare_name) = name.split_once(QUALIFIED_NAME_SEPARATOR)?;
+            tools.iter().find(|t| t.name == bare_name)
+        })
+    }
+    
+    /// Find the tool a native function call refers to
+    ///
+    /// `names` are the function names the tools were offered under.
+    pub fn find_tool_by_function_name<'a>(function_name: &str, names: &FunctionNames, tools: &'a [Tool]) -> Option<&'a Tool> {
+        let tool_name = names.tool_name(function_name)?;
+        tools.iter().find(|t| t.name == tool_name)
     }
 } 
\ No newline at end of file
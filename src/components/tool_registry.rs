@@ -0,0 +1,151 @@
+use mcp_core::Tool;
+use std::collections::{BTreeMap, HashMap};
+use tracing::debug;
+
+/// Separator used between the server id and the tool name in qualified names
+pub const QUALIFIED_NAME_SEPARATOR: char = '/';
+
+/// Errors that can occur while resolving a tool name to its owning server
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ToolResolveError {
+    #[error("Tool '{0}' is not provided by any running server")]
+    UnknownTool(String),
+
+    #[error("Tool '{name}' is provided by several servers ({}), use a qualified name like 'server/{name}'", .servers.join(", "))]
+    AmbiguousTool { name: String, servers: Vec<String> },
+
+    #[error("Server '{0}' is not running")]
+    UnknownServer(String),
+}
+
+/// Registry of tools keyed by server id and tool name
+///
+/// The registry knows which server owns which tool, so tool calls can be routed to
+/// the right client instead of the currently selected one. When several servers
+/// expose a tool with the same name, the tool is addressed as `server/tool`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolRegistry {
+    /// server id -> tool name -> tool definition
+    servers: BTreeMap<String, BTreeMap<String, Tool>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a registry from a mapping of server id to the tools it provides
+    pub fn from_server_tools(server_tools: &HashMap<String, Vec<Tool>>) -> Self {
+        let mut registry = Self::new();
+        for (server_id, tools) in server_tools {
+            registry.register_server_tools(server_id, tools.clone());
+        }
+        registry
+    }
+
+    /// Replace the tools registered for a server
+    pub fn register_server_tools(&mut self, server_id: &str, tools: Vec<Tool>) {
+        let tools = tools.into_iter().map(|t| (t.name.clone(), t)).collect();
+        self.servers.insert(server_id.to_string(), tools);
+    }
+
+    /// Remove all tools belonging to a server
+    pub fn remove_server(&mut self, server_id: &str) {
+        if self.servers.remove(server_id).is_some() {
+            debug!("Removed tools of server {} from registry", server_id);
+        }
+    }
+
+    /// Remove every server from the registry
+    pub fn clear(&mut self) {
+        self.servers.clear();
+    }
+
+    /// Check whether the registry has no tools at all
+    pub fn is_empty(&self) -> bool {
+        self.servers.values().all(|tools| tools.is_empty())
+    }
+
+    /// Total number of registered tools across all servers
+    pub fn len(&self) -> usize {
+        self.servers.values().map(|tools| tools.len()).sum()
+    }
+
+    /// Ids of all servers that have registered tools
+    pub fn server_ids(&self) -> Vec<String> {
+        self.servers.keys().cloned().collect()
+    }
+
+    /// Tools registered for a single server
+    pub fn server_tools(&self, server_id: &str) -> Vec<Tool> {
+        self.servers
+            .get(server_id)
+            .map(|tools| tools.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Build the qualified `server/tool` name for a tool
+    pub fn qualified_name(server_id: &str, tool_name: &str) -> String {
+        format!("{}{}{}", server_id, QUALIFIED_NAME_SEPARATOR, tool_name)
+    }
+
+    /// Ids of all servers that provide a tool with the given bare name
+    pub fn servers_providing(&self, tool_name: &str) -> Vec<String> {
+        self.servers
+            .iter()
+            .filter(|(_, tools)| tools.contains_key(tool_name))
+            .map(|(server_id, _)| server_id.clone())
+            .collect()
+    }
+
+    /// Resolve a bare or qualified tool name to `(server_id, tool_name)`
+    pub fn resolve(&self, name: &str) -> Result<(String, String), ToolResolveError> {
+        // Qualified names take priority when the prefix is a known server
+        if let Some((server_id, tool_name)) = name.split_once(QUALIFIED_NAME_SEPARATOR) {
+            if let Some(tools) = self.servers.get(server_id) {
+                return if tools.contains_key(tool_name) {
+                    Ok((server_id.to_string(), tool_name.to_string()))
+                } else {
+                    Err(ToolResolveError::UnknownTool(name.to_string()))
+                };
+            }
+        }
+
+        let mut servers = self.servers_providing(name);
+        match servers.len() {
+            0 => Err(ToolResolveError::UnknownTool(name.to_string())),
+            1 => Ok((servers.remove(0), name.to_string())),
+            _ => Err(ToolResolveError::AmbiguousTool {
+                name: name.to_string(),
+                servers,
+            }),
+        }
+    }
+
+    /// Look up a tool definition by bare or qualified name
+    pub fn get(&self, name: &str) -> Option<&Tool> {
+        let (server_id, tool_name) = self.resolve(name).ok()?;
+        self.servers.get(&server_id)?.get(&tool_name)
+    }
+
+    /// All tools as they should be presented to the user and the model
+    ///
+    /// Tool names that are unique across servers are kept as-is. Names that
+    /// collide are replaced by their qualified `server/tool` form so each entry
+    /// resolves to exactly one server.
+    pub fn tools(&self) -> Vec<Tool> {
+        let mut all_tools = Vec::new();
+
+        for (server_id, tools) in &self.servers {
+            for (tool_name, tool) in tools {
+                let mut tool = tool.clone();
+                if self.servers_providing(tool_name).len() > 1 {
+                    tool.name = Self::qualified_name(server_id, tool_name);
+                }
+                all_tools.push(tool);
+            }
+        }
+
+        all_tools
+    }
+}
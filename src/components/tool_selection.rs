@@ -285,14 +285,8 @@ impl LLMToolSelector {
         );
 
         let messages = vec![
-            ChatMessage {
-                role: "system".to_string(),
-                content: "You are a parameter fixing expert. Return only valid JSON matching the schema.".to_string(),
-            },
-            ChatMessage {
-                role: "user".to_string(),
-                content: prompt,
-            },
+            ChatMessage::new("system", "You are a parameter fixing expert. Return only valid JSON matching the schema.".to_string()),
+            ChatMessage::new("user", prompt),
         ];
 
         let response = self.client.chat_completion(
@@ -364,14 +358,8 @@ impl LLMToolSelector {
             let system_prompt = self.create_optimized_prompt(&available_tools, query, validation_feedback.as_deref());
             
             let messages = vec![
-                ChatMessage {
-                    role: "system".to_string(),
-                    content: system_prompt,
-                },
-                ChatMessage {
-                    role: "user".to_string(),
-                    content: query.to_string(),
-                },
+                ChatMessage::new("system", system_prompt),
+                ChatMessage::new("user", query.to_string()),
             ];
 
             let response = self.client.chat_completion(
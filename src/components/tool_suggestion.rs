@@ -1,6 +1,8 @@
 use dioxus::prelude::*;
 use mcp_core::Tool;
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use crate::components::agent::AgentStep;
 
 #[derive(PartialEq, Props, Clone)]
 pub struct ToolSuggestionProps {
@@ -8,6 +10,9 @@ pub struct ToolSuggestionProps {
     pub suggested_args: Value,
     pub on_execute: EventHandler<(String, Value)>,
     pub on_cancel: EventHandler<()>,
+    /// Run the tool and let it run without asking for the rest of the conversation
+    #[props(default)]
+    pub on_always_allow: Option<EventHandler<(String, Value)>>,
 }
 
 /// Component for displaying a tool suggestion from the AI with execute/cancel buttons
@@ -18,12 +23,12 @@ pub fn ToolSuggestion(props: ToolSuggestionProps) -> Element {
     let mut args_text = use_signal(|| initial_args);
     let mut parse_error = use_signal(|| None::<String>);
     
-    let execute_with_args = move |_| {
-        // Try to parse the current args text as JSON
+    // Parse the current args text as JSON and hand the call to `handler`
+    let submit = move |handler: EventHandler<(String, Value)>, tool_name: String| {
         match serde_json::from_str::<Value>(&args_text.read()) {
             Ok(parsed_args) => {
                 parse_error.set(None);
-                props.on_execute.call((tool_name.clone(), parsed_args));
+                handler.call((tool_name, parsed_args));
             },
             Err(err) => {
                 parse_error.set(Some(format!("Invalid JSON: {}", err)));
@@ -75,9 +80,20 @@ pub fn ToolSuggestion(props: ToolSuggestionProps) -> Element {
                     onclick: move |_| props.on_cancel.call(()),
                     "Cancel"
                 }
+                if let Some(always_allow) = props.on_always_allow {
+                    button {
+                        class: "btn-always-allow",
+                        title: "Run this tool without asking for the rest of the conversation",
+                        onclick: {
+                            let tool_name = tool_name.clone();
+                            move |_| submit(always_allow, tool_name.clone())
+                        },
+                        "Always Allow in Conversation"
+                    }
+                }
                 button {
                     class: "btn-execute",
-                    onclick: execute_with_args,
+                    onclick: move |_| submit(props.on_execute, tool_name.clone()),
                     "Execute Tool"
                 }
             }
@@ -91,9 +107,12 @@ pub struct ToolExecutionProps {
     pub tool_name: String,
     pub status: ToolExecutionStatus,
     pub result: Option<String>,
+    /// Step of the agent run, shown as a badge when present
+    #[props(default)]
+    pub step: Option<AgentStep>,
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum ToolExecutionStatus {
     Running,
     Completed,
@@ -105,6 +124,9 @@ pub fn ToolExecution(props: ToolExecutionProps) -> Element {
     rsx! {
         div { class: "tool-execution",
             div { class: "execution-header",
+                if let Some(step) = &props.step {
+                    span { class: "agent-step-badge", "{step.label()}" }
+                }
                 match props.status {
                     ToolExecutionStatus::Running => {
                         rsx! {
@@ -2,13 +2,17 @@ use dioxus::prelude::*;
 use mcp_core::Tool;
 use serde_json::{Value, json};
 use crate::McpState;
-use crate::components::tool_manager::ToolManager;
+use crate::components::tool_manager::{ToolManager, ToolRouter};
+use crate::components::tool_registry::ToolRegistry;
+use crate::components::validation_pipeline::{ValidationPipeline, ValidationState};
 
 /// Props for ToolTestModal component
 #[derive(Props, Clone, PartialEq)]
 pub struct ToolTestModalProps {
     /// The tool to test
     pub tool: Tool,
+    /// The server that provides the tool
+    pub server_id: String,
     /// Event handler for when the dialog is closed
     pub on_close: EventHandler<()>,
     /// MCP state for accessing the client
@@ -66,18 +70,37 @@ pub fn ToolTestModal(props: ToolTestModalProps) -> Element {
         // Parse parameters as JSON
         match serde_json::from_str::<Value>(&tool_params.read()) {
             Ok(params) => {
+                // Test calls are sanitized and follow the argument rules like every other call
+                let pipeline = {
+                    let state = props.mcp_state.peek();
+                    ValidationPipeline::new()
+                        .with_auto_fix(true)
+                        .with_suggest_alternatives(false)
+                        .with_sanitization(&state.server_configs.sanitization)
+                        .with_argument_rules(state.server_configs.argument_rules.clone())
+                        .with_server(Some(props.server_id.clone()))
+                };
+                let params = match pipeline.validate_input(&props.tool, params) {
+                    ValidationState::Invalid { errors, .. } => {
+                        execution_result.set(Some(Err(errors.join("\n"))));
+                        return;
+                    }
+                    validated => validated.get_value().cloned().unwrap_or_else(|| json!({})),
+                };
+                
                 is_executing.set(true);
                 execution_result.set(None);
                 
-                // Clone values for the async block
-                let tool_name_value = tool_name.to_string();
-                let mcp_state = props.mcp_state.clone();
+                // Clone values for the async block, qualifying the name so the
+                // call goes to the server this tool was listed under
+                let tool_name_value = ToolRegistry::qualified_name(&props.server_id, &tool_name.read());
+                let router = ToolRouter::from(&*props.mcp_state.peek());
                 
                 // Execute the tool
                 spawn({
                     to_owned![is_executing, execution_result];
                     async move {
-                        match ToolManager::execute_tool(tool_name_value, params, &mcp_state.read()).await {
+                        match ToolManager::execute_tool(tool_name_value, params, &router).await {
                             Ok(result) => {
                                 let formatted = ToolManager::format_tool_result(&result);
                                 execution_result.set(Some(Ok(formatted)));
@@ -1,8 +1,13 @@
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use anyhow::{Result, anyhow};
 use tracing::{debug, error, info, warn, instrument};
+use crate::approvals::glob_matches;
+use crate::argument_rules::{self, ArgumentRule};
 use crate::components::parameter_validation::ParameterValidator;
+use crate::components::tool_registry::QUALIFIED_NAME_SEPARATOR;
 use mcp_core::Tool;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 /// Represents the state of input validation
@@ -108,13 +113,107 @@ impl RecoveryStrategy {
     }
 }
 
+/// How a string argument is cleaned up before it is sent to a tool
+///
+/// Nothing is escaped, arguments go to tools rather than into markup; the views escape what they
+/// display. A field gets its profile from the pipeline's configuration, otherwise from the `format`
+/// or `contentMediaType` in the tool's schema, otherwise it gets the pipeline's default profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SanitizationProfile {
+    /// Passed on as given, for code, queries, patterns and file contents
+    #[default]
+    Raw,
+    /// Control characters other than newlines and tabs are removed
+    Text,
+    /// A single value like a date, address or identifier: trimmed, every control character removed
+    Line,
+}
+
+/// Formats of single-line values, whose fields get the `Line` profile
+const LINE_FORMATS: &[&str] = &[
+    "date", "date-time", "time", "duration", "email", "idn-email", "hostname", "idn-hostname",
+    "ipv4", "ipv6", "uri", "uri-reference", "iri", "iri-reference", "uuid",
+];
+
+impl SanitizationProfile {
+    /// The profile the schema of a field asks for, if any
+    pub fn from_schema(schema: &Value) -> Option<Self> {
+        if let Some(media_type) = schema.get("contentMediaType").and_then(Value::as_str) {
+            return Some(if media_type.starts_with("text/plain") {
+                SanitizationProfile::Text
+            } else {
+                SanitizationProfile::Raw
+            });
+        }
+        schema.get("format").and_then(Value::as_str).map(|format| {
+            if LINE_FORMATS.contains(&format) {
+                SanitizationProfile::Line
+            } else {
+                SanitizationProfile::Raw
+            }
+        })
+    }
+
+    pub fn apply(&self, input: &str) -> String {
+        match self {
+            SanitizationProfile::Raw => input.to_string(),
+            SanitizationProfile::Text => input
+                .chars()
+                .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
+                .collect(),
+            SanitizationProfile::Line => input.trim().chars().filter(|c| !c.is_control()).collect(),
+        }
+    }
+}
+
+/// How the string arguments of tool calls are cleaned up, the `sanitization` section of the configuration
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SanitizationConfig {
+    /// Maximum length in characters of string arguments, longer ones are cut off, none without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_string_length: Option<usize>,
+    /// Profile of the fields neither the configuration nor the schema give one, `raw` without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<SanitizationProfile>,
+    /// Profiles of string fields by field name, for every tool
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub fields: HashMap<String, SanitizationProfile>,
+    /// Profiles of string fields of some tools, taking precedence over `fields`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolSanitization>,
+}
+
+/// Profiles of the string fields of the tools matching `tool`
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ToolSanitization {
+    /// Glob on the tool name, `*` matches any run of characters and `?` a single one
+    pub tool: String,
+    /// Id of the server providing the tool, any server without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    pub fields: HashMap<String, SanitizationProfile>,
+}
+
+impl SanitizationConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 /// Pipeline for validating and sanitizing tool inputs
 #[derive(Debug, Clone, PartialEq)]
 pub struct ValidationPipeline {
     /// Maximum depth for nested JSON structures
     max_depth: usize,
-    /// Maximum length for string values
-    max_string_length: usize,
+    /// Maximum length in characters for string values, longer ones are cut off
+    max_string_length: Option<usize>,
+    /// Profile of the string fields neither the configuration nor the schema give one
+    default_profile: SanitizationProfile,
+    /// Profiles of string fields by field name, taking precedence over the schema
+    field_profiles: HashMap<String, SanitizationProfile>,
+    /// Profiles of string fields of some tools, taking precedence over `field_profiles`
+    tool_profiles: Vec<ToolSanitization>,
     /// Whether to attempt fixing common issues
     auto_fix: bool,
     /// Fallback values for specific fields when validation fails
@@ -125,18 +224,39 @@ pub struct ValidationPipeline {
     max_alternatives: usize,
     /// Available tools for alternative suggestions
     available_tools: Vec<Tool>,
+    /// Rules the arguments have to follow once validated
+    argument_rules: Vec<ArgumentRule>,
+    /// Server providing the tool, for the rules that only apply to one server
+    server_id: Option<String>,
+}
+
+/// Path filled in for a filesystem tool called without one: the Projects folder in the user's home
+/// directory, or a file in it for tools that read or write files
+fn default_filesystem_path(tool_name: &str) -> Value {
+    let projects = dirs::home_dir().unwrap_or_default().join("Projects");
+    let path = if tool_name.contains("read_file") || tool_name.contains("write_file") {
+        projects.join("file.txt")
+    } else {
+        projects
+    };
+    Value::String(path.display().to_string())
 }
 
 impl Default for ValidationPipeline {
     fn default() -> Self {
         Self {
             max_depth: 10,
-            max_string_length: 1000,
+            max_string_length: None,
+            default_profile: SanitizationProfile::Raw,
+            field_profiles: HashMap::new(),
+            tool_profiles: Vec::new(),
             auto_fix: true,
             fallback_values: HashMap::new(),
             suggest_alternatives: true,
             max_alternatives: 3,
             available_tools: Vec::new(),
+            argument_rules: Vec::new(),
+            server_id: None,
         }
     }
 }
@@ -151,8 +271,34 @@ impl ValidationPipeline {
         self
     }
 
+    /// Cuts off longer strings, which are passed on whole without it
     pub fn with_max_string_length(mut self, length: usize) -> Self {
-        self.max_string_length = length;
+        self.max_string_length = Some(length);
+        self
+    }
+
+    /// Sets the profile of the fields the schema doesn't give one, `Raw` without it
+    pub fn with_default_profile(mut self, profile: SanitizationProfile) -> Self {
+        self.default_profile = profile;
+        self
+    }
+
+    /// Sets the profile of every string field named `field`, and of the strings in it
+    pub fn with_field_profile(mut self, field: &str, profile: SanitizationProfile) -> Self {
+        self.field_profiles.insert(field.to_string(), profile);
+        self
+    }
+
+    /// Applies the `sanitization` section of the configuration on top of the profiles set so far
+    pub fn with_sanitization(mut self, config: &SanitizationConfig) -> Self {
+        if config.max_string_length.is_some() {
+            self.max_string_length = config.max_string_length;
+        }
+        if let Some(profile) = config.default_profile {
+            self.default_profile = profile;
+        }
+        self.field_profiles.extend(config.fields.clone());
+        self.tool_profiles.extend(config.tools.iter().cloned());
         self
     }
 
@@ -181,14 +327,79 @@ impl ValidationPipeline {
         self
     }
     
+    pub fn with_argument_rules(mut self, rules: Vec<ArgumentRule>) -> Self {
+        self.argument_rules = rules;
+        self
+    }
+
+    /// Sets the server providing the tool, tools named `server/tool` are checked by their bare name
+    pub fn with_server(mut self, server_id: Option<String>) -> Self {
+        self.server_id = server_id;
+        self
+    }
+    
     /// Updates the list of available tools
     pub fn update_available_tools(&mut self, tools: Vec<Tool>) {
         self.available_tools = tools;
     }
 
-    /// Validates and sanitizes input for a specific tool
+    /// Validates and sanitizes input for a specific tool, then checks it against the argument rules
+    ///
+    /// Input breaking a rule is invalid however it was fixed, without alternatives to suggest.
     #[instrument(skip(self, input), fields(tool_name = %tool.name))]
     pub fn validate_input(&self, tool: &Tool, input: Value) -> ValidationState {
+        let tool_name = self
+            .server_id
+            .as_deref()
+            .and_then(|server_id| tool.name.strip_prefix(server_id))
+            .and_then(|name| name.strip_prefix(QUALIFIED_NAME_SEPARATOR))
+            .unwrap_or(&tool.name);
+        let state = self.for_tool(tool_name).validate_schema(tool, input.clone());
+        let Some(value) = state.get_value() else {
+            return state;
+        };
+
+        match argument_rules::check_arguments(&self.argument_rules, self.server_id.as_deref(), tool_name, value, &tool.input_schema) {
+            Ok(()) => state,
+            Err(violations) => {
+                let errors: Vec<String> = violations.iter().map(ToString::to_string).collect();
+                warn!("Arguments break the argument rules: {}", errors.join("; "));
+                ValidationState::Invalid {
+                    input,
+                    errors,
+                    alternative_tools: Vec::new(),
+                }
+            }
+        }
+    }
+
+    /// The pipeline with the field profiles configured for the tool named `tool_name` by its server
+    fn for_tool(&self, tool_name: &str) -> Cow<'_, Self> {
+        let server_id = self.server_id.as_deref();
+        let mut matching = self
+            .tool_profiles
+            .iter()
+            .filter(|profiles| {
+                let server_matches = match &profiles.server {
+                    Some(server) => server_id == Some(server.as_str()),
+                    None => true,
+                };
+                server_matches && glob_matches(&profiles.tool, tool_name)
+            })
+            .peekable();
+        if matching.peek().is_none() {
+            return Cow::Borrowed(self);
+        }
+
+        let mut pipeline = self.clone();
+        for profiles in matching {
+            pipeline.field_profiles.extend(profiles.fields.clone());
+        }
+        Cow::Owned(pipeline)
+    }
+
+    /// Validates and sanitizes input against the schema of the tool
+    fn validate_schema(&self, tool: &Tool, input: Value) -> ValidationState {
         let mut changes = Vec::new();
         let mut errors = Vec::new();
         
@@ -214,13 +425,7 @@ impl ValidationPipeline {
             // For filesystem tools, path is critically important
             if path_value {
                 // Try to use a sensible default path
-                let default_path = match tool.name.as_str() {
-                    name if name.contains("create_directory") => Value::String("/Users/martinmaurer/Projects".to_string()),
-                    name if name.contains("list_directory") => Value::String("/Users/martinmaurer/Projects".to_string()),
-                    name if name.contains("read_file") => Value::String("/Users/martinmaurer/Projects/file.txt".to_string()),
-                    name if name.contains("write_file") => Value::String("/Users/martinmaurer/Projects/file.txt".to_string()),
-                    _ => Value::String("/Users/martinmaurer/Projects".to_string()),
-                };
+                let default_path = default_filesystem_path(&tool.name);
                 
                 obj.insert("path".to_string(), default_path);
                 changes.push("Added default path parameter for filesystem operation".to_string());
@@ -232,7 +437,7 @@ impl ValidationPipeline {
         };
 
         // First pass: Check basic structure and sanitize
-        let sanitized = match self.sanitize_value(input.clone(), 0, &mut changes, &mut errors) {
+        let sanitized = match self.sanitize_value(input.clone(), Some(&tool.input_schema), None, 0, &mut changes, &mut errors) {
             Ok(v) => v,
             Err(e) => {
                 error!("Critical validation error: {}", e);
@@ -361,13 +566,7 @@ impl ValidationPipeline {
         
         if is_filesystem_tool && !obj.contains_key("path") {
             // Add path parameter for filesystem tools
-            let default_path = match tool.name.as_str() {
-                name if name.contains("create_directory") => Value::String("/Users/martinmaurer/Projects".to_string()),
-                name if name.contains("list_directory") => Value::String("/Users/martinmaurer/Projects".to_string()),
-                name if name.contains("read_file") => Value::String("/Users/martinmaurer/Projects/file.txt".to_string()),
-                name if name.contains("write_file") => Value::String("/Users/martinmaurer/Projects/file.txt".to_string()),
-                _ => Value::String("/Users/martinmaurer/Projects".to_string()),
-            };
+            let default_path = default_filesystem_path(&tool.name);
             
             obj.insert("path".to_string(), default_path.clone());
             strategies.push(RecoveryStrategy::DefaultValue { 
@@ -657,6 +856,8 @@ impl ValidationPipeline {
     fn sanitize_value(
         &self,
         value: Value,
+        schema: Option<&Value>,
+        field: Option<&str>,
         depth: usize,
         changes: &mut Vec<String>,
         errors: &mut Vec<String>,
@@ -670,16 +871,13 @@ impl ValidationPipeline {
             Value::Object(map) => {
                 let mut new_map = serde_json::Map::new();
                 for (key, val) in map {
-                    // Sanitize key
-                    let clean_key = self.sanitize_string(&key);
-                    if clean_key != key {
-                        changes.push(format!("Sanitized object key: {} -> {}", key, clean_key));
-                    }
-
-                    // Recursively sanitize value
-                    match self.sanitize_value(val, depth + 1, changes, errors) {
+                    // Recursively sanitize value, following the schema of the field
+                    let field_schema = schema
+                        .and_then(|s| s.get("properties"))
+                        .and_then(|properties| properties.get(&key));
+                    match self.sanitize_value(val, field_schema, Some(&key), depth + 1, changes, errors) {
                         Ok(clean_val) => {
-                            new_map.insert(clean_key, clean_val);
+                            new_map.insert(key, clean_val);
                         }
                         Err(e) => {
                             errors.push(format!("Error in field '{}': {}", key, e));
@@ -689,9 +887,10 @@ impl ValidationPipeline {
                 Ok(Value::Object(new_map))
             }
             Value::Array(arr) => {
+                let items_schema = schema.and_then(|s| s.get("items"));
                 let mut new_arr = Vec::with_capacity(arr.len());
                 for (i, val) in arr.into_iter().enumerate() {
-                    match self.sanitize_value(val, depth + 1, changes, errors) {
+                    match self.sanitize_value(val, items_schema, field, depth + 1, changes, errors) {
                         Ok(clean_val) => new_arr.push(clean_val),
                         Err(e) => {
                             errors.push(format!("Error in array index {}: {}", i, e));
@@ -701,9 +900,13 @@ impl ValidationPipeline {
                 Ok(Value::Array(new_arr))
             }
             Value::String(s) => {
-                let clean = self.sanitize_string(&s);
+                let profile = self.profile_for(field, schema);
+                let clean = self.sanitize_string(&s, profile);
                 if clean != s {
-                    changes.push(format!("Sanitized string value"));
+                    changes.push(match field {
+                        Some(field) => format!("Sanitized string value in field '{}'", field),
+                        None => "Sanitized string value".to_string(),
+                    });
                 }
                 Ok(Value::String(clean))
             }
@@ -713,26 +916,25 @@ impl ValidationPipeline {
         }
     }
 
+    /// The profile of a string field: configured, from its schema or the default
+    fn profile_for(&self, field: Option<&str>, schema: Option<&Value>) -> SanitizationProfile {
+        field
+            .and_then(|field| self.field_profiles.get(field).copied())
+            .or_else(|| schema.and_then(SanitizationProfile::from_schema))
+            .unwrap_or(self.default_profile)
+    }
+
     /// Sanitizes a string value
-    fn sanitize_string(&self, input: &str) -> String {
-        let mut output = input.trim().to_string();
+    fn sanitize_string(&self, input: &str, profile: SanitizationProfile) -> String {
+        let mut output = profile.apply(input);
 
-        // Truncate if too long
-        if output.len() > self.max_string_length {
-            output.truncate(self.max_string_length);
+        // Truncate if too long, on a character boundary
+        if let Some(max_length) = self.max_string_length {
+            if let Some((end, _)) = output.char_indices().nth(max_length) {
+                output.truncate(end);
+            }
         }
 
-        // Remove control characters
-        output.retain(|c| !c.is_control() || c == '\n' || c == '\t');
-
-        // Basic HTML escape (prevent XSS)
-        output = output
-            .replace('&', "&amp;")
-            .replace('<', "&lt;")
-            .replace('>', "&gt;")
-            .replace('"', "&quot;")
-            .replace('\'', "&#x27;");
-
         output
     }
 } 
\ No newline at end of file
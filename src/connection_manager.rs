@@ -0,0 +1,900 @@
+//! Owns the connections to MCP servers: starting, stopping and restarting them,
+//! their `ServerStatus` and the tools they provide.
+//!
+//! The manager keeps no UI state, so it can be driven and tested without Dioxus.
+//! The UI subscribes to changes and copies a snapshot into `McpState`.
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use mcp_client::{McpClientTrait, McpService};
+use mcp_core::protocol::{JsonRpcMessage, JsonRpcRequest};
+use mcp_core::Tool;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, Weak};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::{watch, Mutex};
+use tower::timeout::error::Elapsed;
+use tower::timeout::Timeout;
+use tower::ServiceExt;
+use tracing::{debug, info, warn};
+
+use crate::components::ToolRegistry;
+use crate::server_config::{ConfigDiff, HealthCheckConfig, ServerConfig};
+use crate::inspector::Inspector;
+use crate::secrets::SecretStore;
+use crate::variables::VariableContext;
+use crate::server_logs::ServerLogs;
+use crate::transport::{self, AnyTransportHandle, ClosedSignal, ConnectError, ServerClient, ShutdownOutcome};
+use crate::ServerStatus;
+
+/// Interval between two rounds of supervision, each server is only pinged once its
+/// own health check interval elapsed
+pub const SUPERVISOR_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pings whose latency is kept per server
+pub const HEALTH_HISTORY_LEN: usize = 30;
+
+/// A server whose connection is lost after running this long counts as recovered since
+/// its last failure, its restarts count from zero again
+pub const RECOVERED_AFTER: Duration = Duration::from_secs(60);
+
+/// Ids of health check pings start here, far from the ids clients count up from one
+const PING_ID_BASE: u64 = 1 << 40;
+
+/// Upper bound on how long stopping every server may block, enough for the longest
+/// shutdown that ends with killing a server
+pub const STOP_ALL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Errors that can occur while managing a connection
+#[derive(Debug, Error)]
+pub enum ConnectionError {
+    #[error(transparent)]
+    Connect(#[from] ConnectError),
+
+    #[error("Server {0} is already starting")]
+    AlreadyStarting(String),
+
+    #[error("Server {0} is not running")]
+    NotRunning(String),
+
+    #[error("Server {0} is still stopping")]
+    Stopping(String),
+
+    #[error("Failed to list tools: {0}")]
+    Tools(String),
+}
+
+/// Why a server didn't answer a ping
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum PingError {
+    /// The server may still answer, it is only slow or stuck
+    #[error("no response in time")]
+    Timeout,
+
+    /// The connection to the server is gone
+    #[error("{0}")]
+    Disconnected(String),
+}
+
+/// Establishes connections to servers and talks to connected clients
+#[async_trait]
+pub trait Connector: Send + Sync + 'static {
+    type Client: Send + Sync + 'static;
+    /// Shuts a connected server down again and reaches it without the client
+    type Handle: Clone + Send + Sync + 'static;
+
+    /// Connect to a server and run the initialization handshake
+    async fn connect(&self, config: &ServerConfig) -> Result<(Self::Client, Self::Handle), ConnectError>;
+
+    /// Shut a server down and report how it went away
+    async fn shutdown(&self, handle: Self::Handle) -> ShutdownOutcome;
+
+    /// Tools a connected server provides
+    async fn list_tools(&self, client: &Self::Client) -> Result<Vec<Tool>, String>;
+
+    /// Send a server an MCP `ping` through its handle, so a client busy with a long
+    /// tool call doesn't hold the check up
+    async fn ping(&self, server_id: &str, handle: &Self::Handle, timeout: Duration) -> Result<(), PingError>;
+
+    /// Signal closed when the connection to a server is lost, e.g. because its process
+    /// exited; without one only failed pings notice
+    fn closed_signal(&self, _handle: &Self::Handle) -> Option<ClosedSignal> {
+        None
+    }
+}
+
+/// Connects to MCP servers over their configured transport
+pub struct McpConnector {
+    /// Where the stderr of local servers is captured
+    logs: ServerLogs,
+    /// Where the traffic with every server is recorded
+    inspector: Inspector,
+    /// Secrets the environment and headers of a server refer to
+    secrets: SecretStore,
+    next_ping_id: AtomicU64,
+}
+
+impl McpConnector {
+    pub fn new(logs: ServerLogs, inspector: Inspector, secrets: SecretStore) -> Self {
+        Self {
+            logs,
+            inspector,
+            secrets,
+            next_ping_id: AtomicU64::new(PING_ID_BASE),
+        }
+    }
+}
+
+#[async_trait]
+impl Connector for McpConnector {
+    type Client = ServerClient;
+    type Handle = AnyTransportHandle;
+
+    async fn connect(&self, config: &ServerConfig) -> Result<(ServerClient, AnyTransportHandle), ConnectError> {
+        // Expanded and resolved only now, so the configuration stays portable and the secrets are never kept in it
+        let config = VariableContext::current()
+            .expand_config(config)
+            .map_err(ConnectError::Variable)?;
+        let config = self.secrets.resolve_config(&config).map_err(ConnectError::Secret)?;
+        transport::connect_with_handle(&config, &self.logs, &self.inspector).await
+    }
+
+    async fn shutdown(&self, handle: AnyTransportHandle) -> ShutdownOutcome {
+        handle.shutdown().await
+    }
+
+    async fn list_tools(&self, client: &ServerClient) -> Result<Vec<Tool>, String> {
+        client
+            .list_tools(None)
+            .await
+            .map(|result| result.tools)
+            .map_err(|e| e.to_string())
+    }
+
+    async fn ping(&self, server_id: &str, handle: &AnyTransportHandle, timeout: Duration) -> Result<(), PingError> {
+        let request = JsonRpcMessage::Request(JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(self.next_ping_id.fetch_add(1, Ordering::Relaxed)),
+            method: "ping".to_string(),
+            params: None,
+        });
+        // Not recorded by the inspector, pings every few seconds would push the traffic worth seeing out of it
+        let service = Timeout::new(McpService::new(handle.clone()), timeout);
+
+        match service.oneshot(request).await {
+            // Even an error shows the server is there to answer
+            Ok(JsonRpcMessage::Error(_)) => {
+                debug!("Server {} answered ping with an error", server_id);
+                Ok(())
+            }
+            Ok(_) => Ok(()),
+            Err(e) if e.is::<Elapsed>() => Err(PingError::Timeout),
+            Err(e) => Err(PingError::Disconnected(e.to_string())),
+        }
+    }
+
+    fn closed_signal(&self, handle: &AnyTransportHandle) -> Option<ClosedSignal> {
+        Some(handle.closed_signal())
+    }
+}
+
+/// Automatic restarts of a server, as shown in the server status panel
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetryState {
+    /// Restarts attempted since the server was last healthy
+    pub retries: u32,
+    /// Most recent error the server failed with
+    pub last_error: Option<String>,
+    /// When the supervisor restarts the server next, `None` when no restart is pending
+    pub next_retry: Option<Instant>,
+}
+
+/// Recent health checks of a server
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HealthState {
+    /// Latency of the most recent pings, oldest first, `None` for pings that got no answer
+    pub history: VecDeque<Option<Duration>>,
+    /// Pings in a row that got no answer
+    pub missed: u32,
+    /// When the server was last pinged
+    pub last_check: Option<Instant>,
+}
+
+impl HealthState {
+    fn record(&mut self, latency: Option<Duration>) {
+        if self.history.len() == HEALTH_HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(latency);
+        self.last_check = Some(Instant::now());
+        self.missed = if latency.is_some() { 0 } else { self.missed + 1 };
+    }
+
+    /// Latency of the most recent answered ping
+    pub fn last_latency(&self) -> Option<Duration> {
+        self.history.iter().rev().flatten().next().copied()
+    }
+
+    /// Average latency of the answered pings in the history
+    pub fn average_latency(&self) -> Option<Duration> {
+        let answered: Vec<Duration> = self.history.iter().flatten().copied().collect();
+        if answered.is_empty() {
+            return None;
+        }
+        Some(answered.iter().sum::<Duration>() / answered.len() as u32)
+    }
+
+    /// Highest latency of the answered pings in the history
+    pub fn max_latency(&self) -> Option<Duration> {
+        self.history.iter().flatten().max().copied()
+    }
+
+    /// Pings in the history that got no answer
+    pub fn missed_total(&self) -> usize {
+        self.history.iter().filter(|latency| latency.is_none()).count()
+    }
+}
+
+/// A server the manager knows about
+struct Connection<C, H> {
+    config: ServerConfig,
+    status: ServerStatus,
+    client: Option<Arc<Mutex<C>>>,
+    /// Shuts the server down, kept while it may still be running
+    handle: Option<H>,
+    tools: Vec<Tool>,
+    retry: RetryState,
+    /// Whether the server was running at some point since the user started it
+    had_started: bool,
+    health: HealthState,
+    /// When the server is pinged next
+    next_check: Instant,
+    /// When the server last started running, `None` while it isn't
+    running_since: Option<Instant>,
+}
+
+impl<C, H> Connection<C, H> {
+    /// Mark the connection failed and schedule a restart if its policy allows one
+    fn fail(&mut self, reason: String) {
+        // Also resets servers that are never pinged
+        if self.running_since.take().is_some_and(|since| since.elapsed() >= RECOVERED_AFTER) {
+            self.retry.retries = 0;
+        }
+        self.status = ServerStatus::Failed(reason.clone());
+        self.retry.last_error = Some(reason);
+
+        let policy = &self.config.restart;
+        self.retry.next_retry = if policy.should_restart(self.retry.retries, self.had_started) {
+            Some(Instant::now() + policy.backoff(self.retry.retries))
+        } else {
+            None
+        };
+    }
+}
+
+/// Point-in-time copy of the managed connections
+pub struct ConnectionSnapshot<C> {
+    /// Clients of the running servers
+    pub clients: HashMap<String, Arc<Mutex<C>>>,
+    /// Status of every server the manager knows about
+    pub status: HashMap<String, ServerStatus>,
+    /// Tools of the running servers
+    pub server_tools: HashMap<String, Vec<Tool>>,
+    /// Restart bookkeeping of every server the manager knows about
+    pub retries: HashMap<String, RetryState>,
+    /// Recent health checks of every server the manager knows about
+    pub health: HashMap<String, HealthState>,
+}
+
+impl<C> ConnectionSnapshot<C> {
+    /// Registry routing the tools of the running servers
+    pub fn tool_registry(&self) -> ToolRegistry {
+        ToolRegistry::from_server_tools(&self.server_tools)
+    }
+}
+
+/// Starts, stops and restarts server connections and tracks their status
+pub struct ConnectionManager<K: Connector = McpConnector> {
+    connector: Arc<K>,
+    connections: Arc<StdMutex<HashMap<String, Connection<K::Client, K::Handle>>>>,
+    changes: Arc<watch::Sender<u64>>,
+    /// Notified for every health check, which only changes `changes` when a status changed
+    health_changes: Arc<watch::Sender<u64>>,
+}
+
+impl<K: Connector> Clone for ConnectionManager<K> {
+    fn clone(&self) -> Self {
+        Self {
+            connector: self.connector.clone(),
+            connections: self.connections.clone(),
+            changes: self.changes.clone(),
+            health_changes: self.health_changes.clone(),
+        }
+    }
+}
+
+impl Default for ConnectionManager<McpConnector> {
+    fn default() -> Self {
+        let secrets = SecretStore::default();
+        let logs = ServerLogs::default().with_secrets(secrets.clone());
+        Self::new(McpConnector::new(logs, Inspector::default(), secrets))
+    }
+}
+
+impl<K: Connector> ConnectionManager<K> {
+    pub fn new(connector: K) -> Self {
+        let (changes, _) = watch::channel(0);
+        let (health_changes, _) = watch::channel(0);
+        Self {
+            connector: Arc::new(connector),
+            connections: Arc::new(StdMutex::new(HashMap::new())),
+            changes: Arc::new(changes),
+            health_changes: Arc::new(health_changes),
+        }
+    }
+
+    /// Receiver that is notified whenever a status, client or tool list changes
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    /// Receiver that is notified whenever a server was pinged, e.g. to show its latency
+    pub fn subscribe_health(&self) -> watch::Receiver<u64> {
+        self.health_changes.subscribe()
+    }
+
+    fn notify(&self) {
+        self.changes.send_modify(|revision| *revision += 1);
+    }
+
+    /// Apply a change to a known server and notify subscribers
+    fn update<F>(&self, server_id: &str, change: F) -> bool
+    where
+        F: FnOnce(&mut Connection<K::Client, K::Handle>),
+    {
+        let updated = match self.connections.lock().unwrap().get_mut(server_id) {
+            Some(connection) => {
+                change(connection);
+                true
+            }
+            None => false,
+        };
+        if updated {
+            self.notify();
+        }
+        updated
+    }
+
+    /// Connect to a server, initialize it and fetch its tools
+    ///
+    /// Starting a running server returns its existing client.
+    pub async fn start(&self, config: &ServerConfig) -> Result<Arc<Mutex<K::Client>>, ConnectionError> {
+        self.start_connection(config, false).await
+    }
+
+    /// Start a server, either on request or as an automatic restart that keeps its retry count
+    async fn start_connection(
+        &self,
+        config: &ServerConfig,
+        is_restart: bool,
+    ) -> Result<Arc<Mutex<K::Client>>, ConnectionError> {
+        let server_id = config.id.clone();
+        let stale_handle = {
+            let mut connections = self.connections.lock().unwrap();
+            let mut retry = RetryState::default();
+            let mut had_started = false;
+            let mut stale_handle = None;
+            if let Some(connection) = connections.get_mut(&server_id) {
+                match (&connection.status, &connection.client) {
+                    (ServerStatus::Running | ServerStatus::Degraded(_), Some(client)) => return Ok(client.clone()),
+                    (ServerStatus::Starting, _) => return Err(ConnectionError::AlreadyStarting(server_id)),
+                    (ServerStatus::Stopping, _) => return Err(ConnectionError::Stopping(server_id)),
+                    _ => {}
+                }
+                if is_restart {
+                    retry = connection.retry.clone();
+                    had_started = connection.had_started;
+                }
+                // A failed server may still be running and has to go before it is replaced
+                stale_handle = connection.handle.take();
+            }
+            connections.insert(
+                server_id.clone(),
+                Connection {
+                    config: config.clone(),
+                    status: ServerStatus::Starting,
+                    client: None,
+                    handle: None,
+                    tools: Vec::new(),
+                    retry,
+                    had_started,
+                    health: HealthState::default(),
+                    next_check: Instant::now(),
+                    running_since: None,
+                },
+            );
+            stale_handle
+        };
+        self.notify();
+
+        if let Some(handle) = stale_handle {
+            let outcome = self.connector.shutdown(handle).await;
+            debug!("Shut down the previous instance of server {}: {}", server_id, outcome);
+        }
+
+        info!("Starting MCP server: {}", config.name);
+        let (client, handle) = match self.connector.connect(config).await {
+            Ok(connected) => connected,
+            Err(e) => {
+                warn!("Failed to start server {}: {}", server_id, e);
+                self.update(&server_id, |connection| {
+                    // Only fail attempts that weren't cancelled by a stop in the meantime
+                    if connection.status == ServerStatus::Starting {
+                        connection.fail(e.to_string());
+                    }
+                });
+                return Err(e.into());
+            }
+        };
+
+        // A server without tools is still usable for resources and prompts
+        let tools = match self.connector.list_tools(&client).await {
+            Ok(tools) => tools,
+            Err(e) => {
+                warn!("Failed to list tools for server {}: {}", server_id, e);
+                Vec::new()
+            }
+        };
+        info!("Server {} is running with {} tools", server_id, tools.len());
+
+        let closed = self.connector.closed_signal(&handle);
+        let client = Arc::new(Mutex::new(client));
+        let mut handle = Some(handle);
+        let mut started = false;
+        self.update(&server_id, |connection| {
+            // The server may have been stopped while it was connecting
+            if connection.status == ServerStatus::Starting {
+                connection.status = ServerStatus::Running;
+                connection.client = Some(client.clone());
+                connection.handle = handle.take();
+                connection.tools = tools;
+                connection.had_started = true;
+                connection.retry.next_retry = None;
+                connection.next_check = Instant::now() + connection.config.health.interval();
+                connection.running_since = Some(Instant::now());
+                started = true;
+            }
+        });
+
+        if started {
+            if let Some(signal) = closed {
+                self.watch_connection(server_id, Arc::downgrade(&client), signal);
+            }
+            Ok(client)
+        } else {
+            if let Some(handle) = handle {
+                self.connector.shutdown(handle).await;
+            }
+            Err(ConnectionError::NotRunning(server_id))
+        }
+    }
+
+    /// Start several servers concurrently
+    pub async fn start_all(
+        &self,
+        configs: &[ServerConfig],
+    ) -> Vec<(ServerConfig, Result<Arc<Mutex<K::Client>>, ConnectionError>)> {
+        let results = join_all(configs.iter().map(|config| self.start(config))).await;
+        configs.iter().cloned().zip(results).collect()
+    }
+
+    /// Stop a server: drop its client, forget its tools and shut it down
+    ///
+    /// The server is `Stopping` until it went away, then `Stopped`, or `Terminated`
+    /// when it had to be sent a signal.
+    pub async fn stop(&self, server_id: &str) -> bool {
+        let mut handle = None;
+        let mut stopping = false;
+        let known = self.update(server_id, |connection| {
+            // Another stop is already shutting the server down
+            if connection.status == ServerStatus::Stopping {
+                return;
+            }
+            stopping = true;
+            connection.status = ServerStatus::Stopping;
+            connection.client = None;
+            connection.tools.clear();
+            connection.retry.next_retry = None;
+            handle = connection.handle.take();
+        });
+        if !stopping {
+            return known;
+        }
+
+        let outcome = match handle {
+            Some(handle) => Some(self.connector.shutdown(handle).await),
+            None => None,
+        };
+        self.update(server_id, |connection| {
+            if connection.status == ServerStatus::Stopping {
+                connection.status = match &outcome {
+                    Some(outcome) if !outcome.is_graceful() => ServerStatus::Terminated(outcome.to_string()),
+                    _ => ServerStatus::Stopped,
+                };
+            }
+        });
+
+        match outcome {
+            Some(outcome) => info!("Stopped MCP server {}: {}", server_id, outcome),
+            None => info!("Stopped MCP server: {}", server_id),
+        }
+        true
+    }
+
+    /// Stop every server concurrently, e.g. when the app exits
+    pub async fn stop_all(&self) {
+        let server_ids = self.server_ids();
+        join_all(server_ids.iter().map(|server_id| self.stop(server_id))).await;
+    }
+
+    /// Stop every server and wait until they went away, from code that can't await,
+    /// e.g. when the app exits
+    pub fn stop_all_blocking(&self) {
+        let manager = self.clone();
+        let stopped = std::thread::spawn(move || {
+            // The caller may be running on a runtime, so the servers are stopped on one of their own
+            match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => {
+                    let stopped = runtime.block_on(tokio::time::timeout(STOP_ALL_TIMEOUT, manager.stop_all()));
+                    if stopped.is_err() {
+                        warn!("Gave up waiting for the servers to stop after {}s", STOP_ALL_TIMEOUT.as_secs());
+                    }
+                }
+                Err(e) => warn!("Failed to create a runtime to stop the servers: {}", e),
+            }
+        })
+        .join();
+        if stopped.is_err() {
+            warn!("Stopping the servers panicked");
+        }
+    }
+
+    /// Stop a server and start it again with the configuration it was started with
+    pub async fn restart(&self, server_id: &str) -> Result<Arc<Mutex<K::Client>>, ConnectionError> {
+        let config = self
+            .config(server_id)
+            .ok_or_else(|| ConnectionError::NotRunning(server_id.to_string()))?;
+        self.stop(server_id).await;
+        self.start(&config).await
+    }
+
+    /// Stop a server and forget it, e.g. when it was removed from the configuration
+    pub async fn remove(&self, server_id: &str) -> bool {
+        let known = self.stop(server_id).await;
+        if self.connections.lock().unwrap().remove(server_id).is_some() {
+            self.notify();
+        }
+        known
+    }
+
+    /// Apply a reloaded configuration: start the added servers, remove the servers that
+    /// are gone and restart the changed servers that weren't stopped
+    pub async fn apply_config_diff(&self, diff: &ConfigDiff) {
+        join_all(diff.removed.iter().map(|server_id| self.remove(server_id))).await;
+
+        let restarts = diff.changed.iter().map(|config| async move {
+            match self.status(&config.id) {
+                Some(ServerStatus::Stopped | ServerStatus::Stopping | ServerStatus::Terminated(_)) | None => {
+                    // Used when the server is started again
+                    self.update(&config.id, |connection| connection.config = config.clone());
+                    None
+                }
+                Some(_) => {
+                    info!("Restarting server {} for its changed configuration", config.id);
+                    self.stop(&config.id).await;
+                    Some((config, self.start(config).await))
+                }
+            }
+        });
+        let starts = diff.added.iter().map(|config| async move {
+            info!("Starting server {} added to the configuration", config.id);
+            Some((config, self.start(config).await))
+        });
+
+        let (restarted, started) = futures::join!(join_all(restarts), join_all(starts));
+        for (config, result) in restarted.into_iter().chain(started).flatten() {
+            if let Err(e) = result {
+                warn!("Failed to start server {} after the configuration changed: {}", config.id, e);
+            }
+        }
+    }
+
+    /// Fetch the tools of a running server again
+    pub async fn refresh_tools(&self, server_id: &str) -> Result<Vec<Tool>, ConnectionError> {
+        let client = self
+            .client(server_id)
+            .ok_or_else(|| ConnectionError::NotRunning(server_id.to_string()))?;
+
+        let tools = {
+            let client = client.lock().await;
+            self.connector.list_tools(&client).await.map_err(ConnectionError::Tools)?
+        };
+
+        self.update(server_id, |connection| connection.tools = tools.clone());
+        Ok(tools)
+    }
+
+    /// Ask the running servers for their tools again, all at once, and return the
+    /// registry routing them
+    ///
+    /// Servers busy with a call keep the tools they had, waiting for them would hold up
+    /// whoever needs the registry.
+    pub async fn refresh_idle_tools(&self) -> ToolRegistry {
+        let snapshot = self.snapshot();
+        let running = snapshot
+            .server_tools
+            .keys()
+            .filter_map(|server_id| Some((server_id.clone(), snapshot.clients.get(server_id)?.clone())));
+
+        join_all(running.map(|(server_id, client)| async move {
+            let Ok(idle) = client.try_lock() else {
+                debug!("Server {} is busy, keeping the tools it had", server_id);
+                return;
+            };
+            match self.connector.list_tools(&idle).await {
+                Ok(tools) => {
+                    self.update(&server_id, |connection| connection.tools = tools);
+                }
+                Err(e) => warn!("Failed to list tools for server {}: {}", server_id, e),
+            }
+        }))
+        .await;
+
+        self.snapshot().tool_registry()
+    }
+
+    /// Fail the server once its connection is lost, without waiting for a ping to notice
+    ///
+    /// `client` tells the instance apart from one started in its place later.
+    fn watch_connection(&self, server_id: String, client: Weak<Mutex<K::Client>>, signal: ClosedSignal) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let reason = signal.closed().await;
+            manager.connection_lost(&server_id, &client, &reason);
+        });
+    }
+
+    fn connection_lost(&self, server_id: &str, client: &Weak<Mutex<K::Client>>, reason: &str) {
+        let failed = {
+            let mut connections = self.connections.lock().unwrap();
+            let Some(connection) = connections.get_mut(server_id) else { return };
+            let same_instance = connection.client.as_ref().is_some_and(|c| Arc::as_ptr(c) == client.as_ptr());
+            // Stopping a server closes its connection too
+            if !same_instance || !matches!(connection.status, ServerStatus::Running | ServerStatus::Degraded(_)) {
+                return;
+            }
+            connection.fail(format!("Connection lost: {}", reason));
+            true
+        };
+        if failed {
+            info!("Lost the connection to server {}: {}", server_id, reason);
+            self.notify();
+        }
+    }
+
+    /// Check the health of servers and restart failed ones until the task is dropped
+    pub async fn supervise(&self) {
+        loop {
+            tokio::time::sleep(SUPERVISOR_INTERVAL).await;
+            self.supervise_once().await;
+        }
+    }
+
+    /// One round of supervision: ping the running servers whose health check is due,
+    /// then restart the failed servers whose backoff has elapsed
+    pub async fn supervise_once(&self) {
+        self.check_health().await;
+        self.restart_due().await;
+    }
+
+    /// Ping the running servers whose health check is due, all at once so a slow
+    /// server doesn't delay the others
+    async fn check_health(&self) {
+        let now = Instant::now();
+        let due: Vec<(String, K::Handle, HealthCheckConfig)> = self
+            .connections
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter(|(_, c)| matches!(c.status, ServerStatus::Running | ServerStatus::Degraded(_)))
+            .filter(|(_, c)| c.config.health.enabled && c.next_check <= now)
+            .filter_map(|(id, c)| {
+                c.next_check = now + c.config.health.interval();
+                c.handle.clone().map(|handle| (id.clone(), handle, c.config.health.clone()))
+            })
+            .collect();
+
+        let results = join_all(due.into_iter().map(|(server_id, handle, health)| async move {
+            let started = Instant::now();
+            let result = self.connector.ping(&server_id, &handle, health.timeout()).await;
+            (server_id, health, result, started.elapsed())
+        }))
+        .await;
+
+        for (server_id, health, result, latency) in results {
+            if let Err(e) = &result {
+                info!("Server {} did not answer a ping: {}", server_id, e);
+            }
+            let changed = {
+                let mut connections = self.connections.lock().unwrap();
+                let Some(connection) = connections.get_mut(&server_id) else { continue };
+                // The server may have been stopped or restarted while it was pinged
+                if !matches!(connection.status, ServerStatus::Running | ServerStatus::Degraded(_)) {
+                    continue;
+                }
+                let before = (connection.status.clone(), connection.retry.clone());
+                match result {
+                    Ok(()) => {
+                        connection.health.record(Some(latency));
+                        connection.status = ServerStatus::Running;
+                        // A healthy server has recovered, later failures start counting from zero
+                        connection.retry.retries = 0;
+                    }
+                    Err(PingError::Timeout) => {
+                        connection.health.record(None);
+                        let missed = connection.health.missed;
+                        let max_missed = health.max_missed.max(1);
+                        if missed >= max_missed {
+                            connection.fail(format!("Connection lost: no answer to {} pings in a row", missed));
+                        } else {
+                            connection.status = ServerStatus::Degraded(format!(
+                                "No answer to a ping within {} ms ({} of {} missed)",
+                                health.timeout_ms, missed, max_missed
+                            ));
+                        }
+                    }
+                    Err(PingError::Disconnected(e)) => {
+                        connection.health.record(None);
+                        connection.fail(format!("Connection lost: {}", e));
+                    }
+                }
+                (connection.status.clone(), connection.retry.clone()) != before
+            };
+            // A latency sample alone doesn't warrant mirroring every connection into the UI again
+            if changed {
+                self.notify();
+            }
+            self.health_changes.send_modify(|revision| *revision += 1);
+        }
+    }
+
+    async fn restart_due(&self) {
+        let now = Instant::now();
+        let due: Vec<ServerConfig> = {
+            let mut connections = self.connections.lock().unwrap();
+            connections
+                .values_mut()
+                .filter(|c| matches!(c.status, ServerStatus::Failed(_)))
+                .filter(|c| c.retry.next_retry.map_or(false, |at| at <= now))
+                .map(|connection| {
+                    connection.retry.retries += 1;
+                    connection.retry.next_retry = None;
+                    connection.config.clone()
+                })
+                .collect()
+        };
+
+        for config in due {
+            let attempt = self.retry_state(&config.id).map_or(0, |r| r.retries);
+            info!(
+                "Restarting server {} (attempt {} of {})",
+                config.id, attempt, config.restart.max_retries
+            );
+            if let Err(e) = self.start_connection(&config, true).await {
+                debug!("Restart of server {} failed: {}", config.id, e);
+            }
+        }
+    }
+
+    /// Ping a running server right away, outside its health check schedule
+    pub async fn ping(&self, server_id: &str) -> Result<Duration, PingError> {
+        let target = self
+            .connections
+            .lock()
+            .unwrap()
+            .get(server_id)
+            .and_then(|c| c.handle.clone().map(|handle| (handle, c.config.health.timeout())));
+        let Some((handle, timeout)) = target else {
+            return Err(PingError::Disconnected(format!("server {} is not running", server_id)));
+        };
+
+        let started = Instant::now();
+        self.connector.ping(server_id, &handle, timeout).await?;
+        Ok(started.elapsed())
+    }
+
+    pub fn status(&self, server_id: &str) -> Option<ServerStatus> {
+        self.connections.lock().unwrap().get(server_id).map(|c| c.status.clone())
+    }
+
+    pub fn client(&self, server_id: &str) -> Option<Arc<Mutex<K::Client>>> {
+        self.connections.lock().unwrap().get(server_id).and_then(|c| c.client.clone())
+    }
+
+    pub fn retry_state(&self, server_id: &str) -> Option<RetryState> {
+        self.connections.lock().unwrap().get(server_id).map(|c| c.retry.clone())
+    }
+
+    /// Recent health checks of every server the manager knows about
+    pub fn health_all(&self) -> HashMap<String, HealthState> {
+        let connections = self.connections.lock().unwrap();
+        connections.iter().map(|(id, c)| (id.clone(), c.health.clone())).collect()
+    }
+
+    pub fn health(&self, server_id: &str) -> Option<HealthState> {
+        self.connections.lock().unwrap().get(server_id).map(|c| c.health.clone())
+    }
+
+    /// Configuration a server was last started with
+    pub fn config(&self, server_id: &str) -> Option<ServerConfig> {
+        self.connections.lock().unwrap().get(server_id).map(|c| c.config.clone())
+    }
+
+    /// Ids of all servers the manager knows about, sorted
+    pub fn server_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.connections.lock().unwrap().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    pub fn snapshot(&self) -> ConnectionSnapshot<K::Client> {
+        let connections = self.connections.lock().unwrap();
+        let mut snapshot = ConnectionSnapshot {
+            clients: HashMap::new(),
+            status: HashMap::new(),
+            server_tools: HashMap::new(),
+            retries: HashMap::new(),
+            health: HashMap::new(),
+        };
+
+        for (server_id, connection) in connections.iter() {
+            snapshot.status.insert(server_id.clone(), connection.status.clone());
+            snapshot.retries.insert(server_id.clone(), connection.retry.clone());
+            snapshot.health.insert(server_id.clone(), connection.health.clone());
+            if let Some(client) = &connection.client {
+                snapshot.clients.insert(server_id.clone(), client.clone());
+                // A degraded server is slow to answer, but its tools still work
+                if matches!(connection.status, ServerStatus::Running | ServerStatus::Degraded(_)) {
+                    snapshot.server_tools.insert(server_id.clone(), connection.tools.clone());
+                }
+            }
+        }
+        snapshot
+    }
+}
+
+/// Publish the manager's connections to the UI state
+///
+/// Returns the tools of the running servers so the caller can update its tool lists.
+pub fn sync_state(state: &mut crate::McpState) -> HashMap<String, Vec<Tool>> {
+    let snapshot = state.connections.snapshot();
+    state.tool_registry = snapshot.tool_registry();
+    state.server_status = snapshot.status;
+    state.retry_state = snapshot.retries;
+    state.health = snapshot.health;
+    state.active_clients = snapshot.clients;
+
+    // Drop the selection when its server went away
+    let selected_gone = state
+        .selected_server
+        .as_ref()
+        .map_or(false, |server| !state.active_clients.contains_key(&server.id));
+    if selected_gone {
+        state.client = None;
+        state.selected_server = None;
+    }
+
+    snapshot.server_tools
+}
@@ -0,0 +1,222 @@
+use crate::components::message::{Message, MessageRole};
+use crate::components::tool_manager::ToolInteraction;
+use crate::components::tool_suggestion::ToolExecutionStatus;
+use crate::conversation_store::Conversation;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::info;
+use uuid::Uuid;
+
+/// Marker identifying an mDesk conversation export
+pub const EXPORT_FORMAT: &str = "mdesk-conversation";
+
+/// Version of the JSON export layout
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("Invalid conversation JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Not an mDesk conversation export (format: {0})")]
+    UnsupportedFormat(String),
+
+    #[error("Unsupported export version {0}")]
+    UnsupportedVersion(u32),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// File formats a conversation can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Human readable transcript, for bug reports
+    Markdown,
+    /// Lossless export that can be imported again
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Envelope written around a conversation in JSON exports
+#[derive(Debug, Serialize, Deserialize)]
+struct ConversationExport {
+    format: String,
+    version: u32,
+    exported_at: chrono::DateTime<chrono::Utc>,
+    conversation: Conversation,
+}
+
+/// Export a conversation as JSON, keeping tool interactions, arguments, statuses and timestamps
+pub fn to_json(conversation: &Conversation) -> Result<String, ExportError> {
+    let export = ConversationExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        exported_at: chrono::Utc::now(),
+        conversation: conversation.clone(),
+    };
+    Ok(serde_json::to_string_pretty(&export)?)
+}
+
+/// Rebuild a conversation from a JSON export
+///
+/// The imported conversation gets a fresh id so it never replaces the conversation it
+/// was exported from. The tools allowed for the conversation are dropped, a file never
+/// grants running a tool without asking.
+pub fn from_json(json: &str) -> Result<Conversation, ExportError> {
+    let export: ConversationExport = serde_json::from_str(json)?;
+    if export.format != EXPORT_FORMAT {
+        return Err(ExportError::UnsupportedFormat(export.format));
+    }
+    if export.version > EXPORT_VERSION {
+        return Err(ExportError::UnsupportedVersion(export.version));
+    }
+
+    let mut conversation = export.conversation;
+    conversation.id = Uuid::new_v4().to_string();
+    conversation.allowed_tools.clear();
+    Ok(conversation)
+}
+
+/// Export a conversation as a Markdown transcript, tool calls and results go in fenced blocks
+pub fn to_markdown(conversation: &Conversation) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "# {}\n", conversation.title);
+    let _ = writeln!(out, "- Model: `{}`", conversation.model);
+    if !conversation.servers.is_empty() {
+        let _ = writeln!(out, "- Servers: {}", conversation.servers.join(", "));
+    }
+    let _ = writeln!(out, "- Created: {}", conversation.created_at.format("%Y-%m-%d %H:%M:%S UTC"));
+    let _ = writeln!(out, "- Updated: {}", conversation.updated_at.format("%Y-%m-%d %H:%M:%S UTC"));
+
+    for message in &conversation.messages {
+        // Thinking placeholders only exist while a reply is pending
+        if message.role == MessageRole::Thinking {
+            continue;
+        }
+        out.push_str("\n---\n\n");
+        write_message(&mut out, message);
+    }
+
+    out
+}
+
+fn write_message(out: &mut String, message: &Message) {
+    let role = match message.role {
+        MessageRole::User => "User",
+        MessageRole::Assistant => "Assistant",
+        MessageRole::System => "System",
+        MessageRole::Thinking => "Thinking",
+        MessageRole::Tool => "Tool",
+    };
+    let _ = writeln!(out, "### {} · {}\n", role, message.timestamp.format("%Y-%m-%d %H:%M:%S"));
+
+    // Tool messages are fully described by their interaction below
+    let has_execution = matches!(message.tool_interaction, Some(ToolInteraction::Execution { .. }));
+    if !message.content.trim().is_empty() && !(message.role == MessageRole::Tool && has_execution) {
+        let _ = writeln!(out, "{}\n", message.content.trim_end());
+    }
+
+    for attachment in &message.attachments {
+        let _ = writeln!(out, "**Attached resource** `{}` from {}\n", attachment.uri, attachment.provider);
+    }
+
+    for call in &message.tool_calls {
+        let _ = writeln!(out, "**Tool call** `{}` (id `{}`)\n", call.function.name, call.id);
+        out.push_str(&fenced(&pretty_json(&call.parsed_arguments()), "json"));
+    }
+
+    match &message.tool_interaction {
+        Some(ToolInteraction::Suggestion { tool_name, suggested_args, .. }) => {
+            let _ = writeln!(out, "**Tool suggestion** `{}`\n", tool_name);
+            out.push_str(&fenced(&pretty_json(suggested_args), "json"));
+        }
+        Some(ToolInteraction::Execution { tool_name, arguments, status, result, step, .. }) => {
+            let status = match status {
+                ToolExecutionStatus::Running => "running".to_string(),
+                ToolExecutionStatus::Completed => "completed".to_string(),
+                ToolExecutionStatus::Failed(error) => format!("failed: {}", error),
+            };
+            let step = step.as_ref().map(|s| format!(" ({})", s.label())).unwrap_or_default();
+            let _ = writeln!(out, "**Tool execution** `{}` {}{}\n", tool_name, status, step);
+            out.push_str(&fenced(&pretty_json(arguments), "json"));
+            if let Some(result) = result {
+                out.push_str("Result:\n\n");
+                out.push_str(&fenced(result, ""));
+            }
+        }
+        None => {}
+    }
+}
+
+fn pretty_json(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// Wrap content in a code fence longer than any backtick run inside it
+fn fenced(content: &str, lang: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in content.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    let fence = "`".repeat((longest + 1).max(3));
+    format!("{fence}{lang}\n{}\n{fence}\n\n", content.trim_end_matches('\n'))
+}
+
+/// Directory exports are written to, `~/.mdesk/exports`
+pub fn default_export_dir() -> PathBuf {
+    crate::logging::get_app_directory().join("exports")
+}
+
+/// File name for an export, built from the title so exports are easy to find
+pub fn export_file_name(conversation: &Conversation, format: ExportFormat) -> String {
+    let slug: String = conversation
+        .title
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let slug = slug.split('-').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("-");
+    let slug: String = slug.chars().take(40).collect();
+    let short_id: String = conversation.id.chars().take(8).collect();
+
+    if slug.is_empty() {
+        format!("conversation-{}.{}", short_id, format.extension())
+    } else {
+        format!("{}-{}.{}", slug, short_id, format.extension())
+    }
+}
+
+/// Write an export into `dir` and return the path of the written file
+pub fn write_export(dir: &Path, conversation: &Conversation, format: ExportFormat) -> Result<PathBuf, ExportError> {
+    let content = match format {
+        ExportFormat::Markdown => to_markdown(conversation),
+        ExportFormat::Json => to_json(conversation)?,
+    };
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join(export_file_name(conversation, format));
+    fs::write(&path, content)?;
+
+    info!("Exported conversation {} to {}", conversation.id, path.display());
+    Ok(path)
+}
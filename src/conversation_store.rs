@@ -0,0 +1,233 @@
+use crate::components::message::{Message, MessageRole};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
+
+/// Title used until the first user message names the conversation
+pub const DEFAULT_CONVERSATION_TITLE: &str = "New conversation";
+
+/// Maximum length of a title derived from the first user message
+const DERIVED_TITLE_LEN: usize = 60;
+
+/// A saved chat conversation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Model the conversation was last used with
+    pub model: String,
+    /// Ids of the servers that were running when the conversation was saved
+    #[serde(default)]
+    pub servers: Vec<String>,
+    /// Tools the user allowed to run without asking for the rest of the conversation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_tools: Vec<String>,
+    #[serde(default)]
+    pub messages: Vec<Message>,
+}
+
+impl Conversation {
+    pub fn new(model: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            title: DEFAULT_CONVERSATION_TITLE.to_string(),
+            created_at: now,
+            updated_at: now,
+            model,
+            servers: Vec::new(),
+            allowed_tools: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    /// Name the conversation after its first user message, unless it was renamed
+    pub fn derive_title(&mut self) {
+        if self.title != DEFAULT_CONVERSATION_TITLE {
+            return;
+        }
+
+        if let Some(first) = self.messages.iter().find(|m| m.role == MessageRole::User) {
+            let line = first.content.lines().next().unwrap_or_default().trim();
+            if line.is_empty() {
+                return;
+            }
+
+            self.title = if line.chars().count() > DERIVED_TITLE_LEN {
+                let truncated: String = line.chars().take(DERIVED_TITLE_LEN).collect();
+                format!("{}...", truncated.trim_end())
+            } else {
+                line.to_string()
+            };
+        }
+    }
+
+    /// Check whether the title or any message contains the query, ignoring case
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return true;
+        }
+
+        self.title.to_lowercase().contains(&query)
+            || self.messages.iter().any(|m| m.content.to_lowercase().contains(&query))
+    }
+
+    pub fn summary(&self) -> ConversationSummary {
+        ConversationSummary {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            updated_at: self.updated_at,
+            model: self.model.clone(),
+            servers: self.servers.clone(),
+            message_count: self.messages.len(),
+        }
+    }
+}
+
+/// What the history sidebar shows for a conversation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: String,
+    pub updated_at: DateTime<Utc>,
+    pub model: String,
+    pub servers: Vec<String>,
+    pub message_count: usize,
+}
+
+/// Stores conversations as one JSON file each
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationStore {
+    dir: PathBuf,
+}
+
+impl ConversationStore {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Store in `~/.mdesk/conversations`, next to the logs
+    pub fn default_location() -> Self {
+        Self::new(crate::logging::get_app_directory().join("conversations"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_for(&self, id: &str) -> io::Result<PathBuf> {
+        // Ids become file names, so anything that could escape the directory is rejected
+        let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid conversation id: {}", id),
+            ));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    /// Save a conversation, replacing any previous version
+    #[instrument(level = "debug", skip(self, conversation), fields(id = %conversation.id, messages = conversation.messages.len()))]
+    pub fn save(&self, conversation: &Conversation) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path_for(&conversation.id)?;
+
+        let json = serde_json::to_string_pretty(conversation)?;
+
+        // Write to a temporary file first so a crash never leaves a truncated conversation
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, &path)?;
+
+        debug!("Saved conversation to {}", path.display());
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub fn load(&self, id: &str) -> io::Result<Conversation> {
+        let path = self.path_for(id)?;
+        let json = fs::read_to_string(&path)?;
+        let conversation = serde_json::from_str(&json)?;
+        Ok(conversation)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub fn delete(&self, id: &str) -> io::Result<()> {
+        let path = self.path_for(id)?;
+        fs::remove_file(&path)?;
+        info!("Deleted conversation {}", id);
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    pub fn rename(&self, id: &str, title: &str) -> io::Result<()> {
+        let title = title.trim();
+        if title.is_empty() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Title must not be empty"));
+        }
+
+        let mut conversation = self.load(id)?;
+        conversation.title = title.to_string();
+        self.save(&conversation)
+    }
+
+    /// All conversations, most recently updated first
+    pub fn list(&self) -> io::Result<Vec<ConversationSummary>> {
+        self.search("")
+    }
+
+    /// Conversations whose title or messages contain the query, most recently updated first
+    #[instrument(level = "debug", skip(self))]
+    pub fn search(&self, query: &str) -> io::Result<Vec<ConversationSummary>> {
+        let mut summaries: Vec<ConversationSummary> = self
+            .load_all()?
+            .iter()
+            .filter(|c| c.matches(query))
+            .map(|c| c.summary())
+            .collect();
+
+        summaries.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        Ok(summaries)
+    }
+
+    /// The most recently updated conversation, if there is one
+    pub fn most_recent(&self) -> Option<Conversation> {
+        let summaries = self.list().ok()?;
+        let latest = summaries.first()?;
+        self.load(&latest.id).ok()
+    }
+
+    fn load_all(&self) -> io::Result<Vec<Conversation>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut conversations = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            match fs::read_to_string(&path).map(|json| serde_json::from_str::<Conversation>(&json)) {
+                Ok(Ok(conversation)) => conversations.push(conversation),
+                Ok(Err(e)) => warn!("Skipping unreadable conversation {}: {}", path.display(), e),
+                Err(e) => warn!("Failed to read conversation {}: {}", path.display(), e),
+            }
+        }
+
+        Ok(conversations)
+    }
+}
@@ -0,0 +1,388 @@
+//! Records the JSON-RPC traffic between mDesk and its MCP servers for the protocol inspector.
+//!
+//! `InspectorLayer` wraps the service a client sends its messages through, so every
+//! request, response and notification is recorded with its server and timing.
+//! The annotations servers give their tools in `tools/list` responses are kept apart from the
+//! bounded recording, for the tool approval rules.
+
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use mcp_core::protocol::JsonRpcMessage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::sync::watch;
+use tower::{Layer, Service};
+
+use crate::approvals::ToolAnnotationStore;
+
+/// Messages kept before the oldest are dropped
+pub const DEFAULT_CAPACITY: usize = 2000;
+
+/// Whether a message was sent to or received from a server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Outgoing,
+    Incoming,
+}
+
+impl Direction {
+    pub fn arrow(&self) -> &'static str {
+        match self {
+            Direction::Outgoing => "→",
+            Direction::Incoming => "←",
+        }
+    }
+}
+
+/// Kind of a recorded message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageKind {
+    Request,
+    Response,
+    Notification,
+    /// A JSON-RPC error response, or a request that failed without any response
+    Error,
+}
+
+impl MessageKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MessageKind::Request => "request",
+            MessageKind::Response => "response",
+            MessageKind::Notification => "notification",
+            MessageKind::Error => "error",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "request" => Some(MessageKind::Request),
+            "response" => Some(MessageKind::Response),
+            "notification" => Some(MessageKind::Notification),
+            "error" => Some(MessageKind::Error),
+            _ => None,
+        }
+    }
+}
+
+/// A recorded message, also the layout of a line in JSONL exports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InspectorEntry {
+    /// Position in the recording, increasing
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub server_id: String,
+    pub direction: Direction,
+    pub kind: MessageKind,
+    /// Method of the request, also set on the response or error answering it
+    pub method: Option<String>,
+    /// JSON-RPC id of the request, also set on the response or error answering it
+    pub request_id: Option<u64>,
+    /// Time between sending the request and receiving this response
+    pub duration_ms: Option<u64>,
+    /// The message as it went over the wire, or the failure when no response came
+    pub message: Value,
+}
+
+impl InspectorEntry {
+    /// Message pretty printed, for the expanded view and copying
+    pub fn pretty_message(&self) -> String {
+        serde_json::to_string_pretty(&self.message).unwrap_or_else(|_| self.message.to_string())
+    }
+}
+
+/// Which recorded messages to show
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InspectorFilter {
+    pub server_id: Option<String>,
+    pub kind: Option<MessageKind>,
+    /// Text the method or message has to contain, ignoring case
+    pub query: String,
+}
+
+impl InspectorFilter {
+    pub fn matches(&self, entry: &InspectorEntry) -> bool {
+        if self.server_id.as_ref().map_or(false, |id| id != &entry.server_id) {
+            return false;
+        }
+        if self.kind.map_or(false, |kind| kind != entry.kind) {
+            return false;
+        }
+        let query = self.query.trim().to_lowercase();
+        if query.is_empty() {
+            return true;
+        }
+        entry.method.as_ref().map_or(false, |m| m.to_lowercase().contains(&query))
+            || entry.message.to_string().to_lowercase().contains(&query)
+    }
+}
+
+struct Recording {
+    entries: VecDeque<InspectorEntry>,
+    next_seq: u64,
+}
+
+/// Bounded recording of the traffic of every server, shared by all clients and the UI
+#[derive(Clone)]
+pub struct Inspector {
+    recording: Arc<Mutex<Recording>>,
+    capacity: usize,
+    changes: Arc<watch::Sender<u64>>,
+    annotations: ToolAnnotationStore,
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl Inspector {
+    pub fn new(capacity: usize) -> Self {
+        let (changes, _) = watch::channel(0);
+        Self {
+            recording: Arc::new(Mutex::new(Recording {
+                entries: VecDeque::new(),
+                next_seq: 1,
+            })),
+            capacity: capacity.max(1),
+            changes: Arc::new(changes),
+            annotations: ToolAnnotationStore::default(),
+        }
+    }
+
+    /// Receiver that is notified whenever a message is recorded or the recording is cleared
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    /// Annotations of the tools the servers listed, kept when the recording is cleared
+    pub fn annotations(&self) -> &ToolAnnotationStore {
+        &self.annotations
+    }
+
+    /// Record a message sent to or received from a server
+    ///
+    /// `method` and `duration_ms` annotate responses with the request they answer.
+    pub fn record(
+        &self,
+        server_id: &str,
+        direction: Direction,
+        message: &JsonRpcMessage,
+        method: Option<String>,
+        duration_ms: Option<u64>,
+    ) {
+        let (kind, request_id, own_method) = describe(message);
+        let message = serde_json::to_value(message).unwrap_or(Value::Null);
+        if direction == Direction::Incoming && method.as_deref() == Some("tools/list") {
+            self.annotations.record_tools_list(server_id, &message["result"]);
+        }
+        self.push(InspectorEntry {
+            seq: 0,
+            timestamp: Utc::now(),
+            server_id: server_id.to_string(),
+            direction,
+            kind,
+            method: own_method.or(method),
+            request_id,
+            duration_ms,
+            message,
+        });
+    }
+
+    /// Record a request that failed without a response, e.g. because it timed out
+    pub fn record_failure(
+        &self,
+        server_id: &str,
+        method: Option<String>,
+        request_id: Option<u64>,
+        error: &str,
+        duration_ms: u64,
+    ) {
+        self.push(InspectorEntry {
+            seq: 0,
+            timestamp: Utc::now(),
+            server_id: server_id.to_string(),
+            direction: Direction::Incoming,
+            kind: MessageKind::Error,
+            method,
+            request_id,
+            duration_ms: Some(duration_ms),
+            message: serde_json::json!({ "error": error }),
+        });
+    }
+
+    fn push(&self, mut entry: InspectorEntry) {
+        {
+            let mut recording = self.recording.lock().unwrap();
+            entry.seq = recording.next_seq;
+            recording.next_seq += 1;
+            if recording.entries.len() == self.capacity {
+                recording.entries.pop_front();
+            }
+            recording.entries.push_back(entry);
+        }
+        self.changes.send_modify(|revision| *revision += 1);
+    }
+
+    /// Every recorded message, oldest first
+    pub fn entries(&self) -> Vec<InspectorEntry> {
+        self.recording.lock().unwrap().entries.iter().cloned().collect()
+    }
+
+    /// Recorded messages the filter matches, oldest first
+    pub fn filtered(&self, filter: &InspectorFilter) -> Vec<InspectorEntry> {
+        self.recording
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .cloned()
+            .collect()
+    }
+
+    /// Ids of the servers with recorded traffic, sorted
+    pub fn server_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .recording
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|entry| entry.server_id.clone())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    pub fn clear(&self) {
+        self.recording.lock().unwrap().entries.clear();
+        self.changes.send_modify(|revision| *revision += 1);
+    }
+
+    /// Wrap a client's service so its traffic is recorded under `server_id`
+    pub fn layer(&self, server_id: impl Into<String>) -> InspectorLayer {
+        InspectorLayer {
+            inspector: self.clone(),
+            server_id: server_id.into(),
+        }
+    }
+}
+
+/// Kind, id and method of a message
+fn describe(message: &JsonRpcMessage) -> (MessageKind, Option<u64>, Option<String>) {
+    match message {
+        JsonRpcMessage::Request(request) => (MessageKind::Request, request.id, Some(request.method.clone())),
+        JsonRpcMessage::Notification(notification) => {
+            (MessageKind::Notification, None, Some(notification.method.clone()))
+        }
+        JsonRpcMessage::Response(response) => (MessageKind::Response, response.id, None),
+        JsonRpcMessage::Error(error) => (MessageKind::Error, error.id, None),
+        JsonRpcMessage::Nil => (MessageKind::Response, None, None),
+    }
+}
+
+/// One JSON object per line, in the layout of `InspectorEntry`
+pub fn to_jsonl(entries: &[InspectorEntry]) -> Result<String, serde_json::Error> {
+    let mut jsonl = String::new();
+    for entry in entries {
+        jsonl.push_str(&serde_json::to_string(entry)?);
+        jsonl.push('\n');
+    }
+    Ok(jsonl)
+}
+
+/// Read entries back from a JSONL export, skipping blank lines
+pub fn from_jsonl(jsonl: &str) -> Result<Vec<InspectorEntry>, serde_json::Error> {
+    jsonl
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+/// Write entries to a timestamped JSONL file in `dir` and return its path
+pub fn write_jsonl(dir: &Path, entries: &[InspectorEntry]) -> io::Result<PathBuf> {
+    let jsonl = to_jsonl(entries)?;
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("mcp-traffic-{}.jsonl", Utc::now().format("%Y%m%d-%H%M%S")));
+    fs::write(&path, jsonl)?;
+    Ok(path)
+}
+
+/// Tower layer adding an `InspectorService` around a client's service
+#[derive(Clone)]
+pub struct InspectorLayer {
+    inspector: Inspector,
+    server_id: String,
+}
+
+impl<S> Layer<S> for InspectorLayer {
+    type Service = InspectorService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InspectorService {
+            inner,
+            inspector: self.inspector.clone(),
+            server_id: Arc::from(self.server_id.as_str()),
+        }
+    }
+}
+
+/// Service recording every message that passes through it, and the answer to it
+#[derive(Clone)]
+pub struct InspectorService<S> {
+    inner: S,
+    inspector: Inspector,
+    server_id: Arc<str>,
+}
+
+impl<S> Service<JsonRpcMessage> for InspectorService<S>
+where
+    S: Service<JsonRpcMessage, Response = JsonRpcMessage> + Send + 'static,
+    S::Error: Display + Send,
+    S::Future: Send + 'static,
+{
+    type Response = JsonRpcMessage;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<JsonRpcMessage, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, message: JsonRpcMessage) -> Self::Future {
+        let (_, request_id, method) = describe(&message);
+        self.inspector
+            .record(&self.server_id, Direction::Outgoing, &message, None, None);
+
+        let inspector = self.inspector.clone();
+        let server_id = self.server_id.clone();
+        let started = Instant::now();
+        let response = self.inner.call(message);
+
+        Box::pin(async move {
+            let result = response.await;
+            let duration_ms = started.elapsed().as_millis() as u64;
+            match &result {
+                // Notifications are only acknowledged, there is nothing to record
+                Ok(JsonRpcMessage::Nil) => {}
+                Ok(response) => inspector.record(&server_id, Direction::Incoming, response, method, Some(duration_ms)),
+                Err(e) => inspector.record_failure(&server_id, method, request_id, &e.to_string(), duration_ms),
+            }
+            result
+        })
+    }
+}
@@ -1,40 +1,86 @@
 // Re-export modules for testing purposes
 
+pub mod approvals;
+pub mod argument_rules;
 pub mod components;
+pub mod connection_manager;
+pub mod conversation_export;
+pub mod conversation_store;
+pub mod event_stream;
+pub mod inspector;
+pub mod logging;
 pub mod openrouter;
+pub mod resources;
+pub mod secrets;
 pub mod server_config;
+pub mod server_logs;
+pub mod transport;
+pub mod variables;
 
 // Re-export common types and structures
 pub use crate::components::*;
 
 // Re-export McpState and ServerStatus from main.rs
 use dioxus::prelude::*;
-use mcp_client::{
-    ClientCapabilities, ClientInfo, McpClient, McpClientTrait, McpService,
-    transport::stdio::{StdioTransport, StdioTransportHandle},
-    Transport,
-};
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::Mutex;
-use tower::timeout::Timeout;
+use crate::connection_manager::{ConnectionManager, HealthState, McpConnector, RetryState};
+use crate::inspector::Inspector;
+use crate::secrets::SecretStore;
+use crate::server_config::ServerConfigs;
+use crate::server_logs::ServerLogs;
+use crate::transport::ServerClient;
 
 // Define McpState here for testing purposes
 #[derive(Clone)]
 pub struct McpState {
-    pub client: Option<Arc<Mutex<McpClient<Timeout<McpService<StdioTransportHandle>>>>>>,
+    pub client: Option<Arc<Mutex<ServerClient>>>,
     pub selected_server: Option<server_config::ServerConfig>,
-    pub active_clients: HashMap<String, Arc<Mutex<McpClient<Timeout<McpService<StdioTransportHandle>>>>>>,
+    pub active_clients: HashMap<String, Arc<Mutex<ServerClient>>>,
     // Track the status of each server (id -> status)
     pub server_status: HashMap<String, ServerStatus>,
+    // Automatic restarts of each server (id -> retries, last error)
+    pub retry_state: HashMap<String, RetryState>,
+    // Recent health checks of each server (id -> ping latencies)
+    pub health: HashMap<String, HealthState>,
+    // Which server owns which tool, used to route tool calls
+    pub tool_registry: ToolRegistry,
+    // Server configuration in effect, reloaded when its file changes
+    pub server_configs: ServerConfigs,
+    // Why the configuration file couldn't be loaded, the previous configuration stays in effect
+    pub config_error: Option<String>,
+    // Profile whose servers are started, all servers without one
+    pub active_profile: Option<String>,
+    // Starts and stops servers, the fields above mirror its state
+    pub connections: ConnectionManager,
+    // Stderr output of the servers the manager starts
+    pub server_logs: ServerLogs,
+    // JSON-RPC traffic with every server, for the protocol inspector
+    pub inspector: Inspector,
+    // API keys and other secrets that server configurations refer to
+    pub secrets: SecretStore,
 }
 
 impl Default for McpState {
     fn default() -> Self {
+        let secrets = SecretStore::default();
+        let server_logs = ServerLogs::default().with_secrets(secrets.clone());
+        let inspector = Inspector::default();
         Self {
             client: None,
             selected_server: None,
             active_clients: HashMap::new(),
             server_status: HashMap::new(),
+            retry_state: HashMap::new(),
+            health: HashMap::new(),
+            tool_registry: ToolRegistry::new(),
+            server_configs: ServerConfigs::default(),
+            config_error: None,
+            active_profile: None,
+            connections: ConnectionManager::new(McpConnector::new(server_logs.clone(), inspector.clone(), secrets.clone())),
+            server_logs,
+            inspector,
+            secrets,
         }
     }
 }
@@ -43,7 +89,13 @@ impl Default for McpState {
 #[derive(Clone, Debug, PartialEq)]
 pub enum ServerStatus {
     Running,
+    // Running, but missed recent pings (holds why)
+    Degraded(String),
     Failed(String),
     Stopped,
     Starting,
+    // Shutting down, waiting for the server to exit
+    Stopping,
+    // Stopped, but only after being sent a signal (holds how it went away)
+    Terminated(String),
 } 
\ No newline at end of file
@@ -100,10 +100,35 @@ pub fn init_simple(level: Level) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Initialize logging for the command line interface: warnings on stderr, so they don't mix
+/// with the output of commands
+pub fn init_stderr() -> Result<(), Box<dyn std::error::Error>> {
+    let filter_layer = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::builder().parse("warn"))?;
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_target(false))
+        .try_init()?;
+    Ok(())
+}
+
+/// Get the root directory for all application data (`~/.mdesk`)
+pub fn get_app_directory() -> PathBuf {
+    let home_dir = dirs::home_dir().expect("Failed to find home directory");
+    home_dir.join(".mdesk")
+}
+
 /// Get the directory where log files will be stored
 fn get_log_directory() -> PathBuf {
-    let home_dir = dirs::home_dir().expect("Failed to find home directory");
-    home_dir.join(".mdesk").join("logs")
+    get_app_directory().join("logs")
+}
+
+/// Get the directory where the stderr output of MCP servers is stored
+pub fn get_server_log_directory() -> PathBuf {
+    get_log_directory().join("servers")
 }
 
 /// A helper function that logs with both eprintln and tracing during migration
@@ -1,36 +1,55 @@
 use dioxus::prelude::*;
 use tracing::{info, debug, warn, error, trace, instrument};
 
-use mcp_client::{
-    ClientCapabilities, ClientInfo, Error as McpError, McpClient, McpClientTrait, McpService,
-    transport::stdio::{StdioTransport, StdioTransportHandle},
-    Transport,
-};
-use mcp_core::{protocol::JsonRpcMessage, Resource as McpResource, Tool};
-use std::{collections::HashMap, sync::Arc, time::Duration, env, path::Path};
+use mcp_client::{Error as McpError, McpClientTrait};
+use mcp_core::{protocol::JsonRpcMessage, Tool};
+use std::{collections::HashMap, sync::Arc};
 use tokio::sync::Mutex;
-use tower::{timeout::Timeout, ServiceExt};
+use tower::ServiceExt;
 use serde_json::Value;
 use dotenv::dotenv;
-use crate::server_config::{ServerConfigs};
+use crate::server_config::{ConfigWatcher, ServerConfigs};
 
+mod approvals;
+mod argument_rules;
 mod components;
+mod connection_manager;
+mod conversation_export;
+mod conversation_store;
+mod event_stream;
+mod inspector;
 mod logging;
 mod openrouter;
+mod resources;
+mod secrets;
 mod server_config;
+mod server_logs;
+mod transport;
+mod variables;
 
 use components::ChatTab;
+use components::message::Message;
+use resources::{Resource, ResourceAttachment, ResourceService};
+use components::ToolRegistry;
 use components::server_manager::ServerManager;
+use connection_manager::{ConnectionError, ConnectionManager, HealthState, McpConnector, RetryState};
+use inspector::Inspector;
+use secrets::SecretStore;
+use server_logs::ServerLogs;
+use transport::ConnectError;
 use server_config::ServerConfig;
+use transport::ServerClient;
 
-/// Updates the global tools list based on the server_tools mapping
-fn update_global_tools(server_tools: &HashMap<String, Vec<Tool>>, tools: &mut Signal<Vec<Tool>>) {
-    let mut all_tools = Vec::new();
-    
-    // Add tools from all active servers
-    for (_server_id, server_tools) in server_tools {
-        all_tools.extend(server_tools.clone());
-    }
+/// Updates the global tools list and the tool registry based on the server_tools mapping
+fn update_global_tools(
+    server_tools: &HashMap<String, Vec<Tool>>,
+    tools: &mut Signal<Vec<Tool>>,
+    mcp_state: &mut Signal<McpState>,
+) {
+    // The registry qualifies tool names that collide across servers
+    let registry = ToolRegistry::from_server_tools(server_tools);
+    let all_tools = registry.tools();
+    mcp_state.write().tool_registry = registry;
     
     // Only update if tools have changed - need to do a deep comparison here in real-world code
     if tools.read().len() != all_tools.len() {
@@ -107,20 +126,46 @@ fn App() -> Element {
 
 #[derive(Clone)]
 struct McpState {
-    client: Option<Arc<Mutex<McpClient<Timeout<McpService<StdioTransportHandle>>>>>>,
+    client: Option<Arc<Mutex<ServerClient>>>,
     selected_server: Option<ServerConfig>,
-    active_clients: HashMap<String, Arc<Mutex<McpClient<Timeout<McpService<StdioTransportHandle>>>>>>,
+    active_clients: HashMap<String, Arc<Mutex<ServerClient>>>,
     // Track the status of each server (id -> status)
     server_status: HashMap<String, ServerStatus>,
+    // Automatic restarts of each server (id -> retries, last error)
+    retry_state: HashMap<String, RetryState>,
+    // Recent health checks of each server (id -> ping latencies)
+    health: HashMap<String, HealthState>,
+    // Which server owns which tool, used to route tool calls
+    tool_registry: ToolRegistry,
+    // Server configuration in effect, reloaded when its file changes
+    server_configs: ServerConfigs,
+    // Why the configuration file couldn't be loaded, the previous configuration stays in effect
+    config_error: Option<String>,
+    // Profile whose servers are started, all servers without one
+    active_profile: Option<String>,
+    // Starts and stops servers, the fields above mirror its state
+    connections: ConnectionManager,
+    // Stderr output of the servers the manager starts
+    server_logs: ServerLogs,
+    // JSON-RPC traffic with every server, for the protocol inspector
+    inspector: Inspector,
+    // API keys and other secrets that server configurations refer to
+    secrets: SecretStore,
 }
 
 // Status of each server
 #[derive(Clone, Debug, PartialEq)]
 enum ServerStatus {
     Running,
+    // Running, but missed recent pings (holds why)
+    Degraded(String),
     Failed(String),
     Stopped,
     Starting,
+    // Shutting down, waiting for the server to exit
+    Stopping,
+    // Stopped, but only after being sent a signal (holds how it went away)
+    Terminated(String),
 }
 
 // Add this component above the McpDemo component
@@ -218,11 +263,16 @@ fn McpDemo() -> Element {
     let mut error_message = use_signal(|| None::<String>);
     let mut show_resources = use_signal(|| false);
     let mut show_tools = use_signal(|| false);
-    let mut resources = use_signal(Vec::<McpResource>::new);
+    let mut resources = use_signal(Vec::<Resource>::new);
     let mut tools = use_signal(Vec::<Tool>::new);
     let mut server_tools = use_signal(|| HashMap::<String, Vec<Tool>>::new());
     let mut active_section = use_signal(|| "chat");
-    let mut active_tool_modal = use_signal(|| None::<Tool>);
+    let mut active_tool_modal = use_signal(|| None::<(String, Tool)>);
+    // Messages of a filled in prompt, handed over to the chat
+    let mut prompt_messages = use_signal(Vec::<Message>::new);
+    // Resource open in the viewer, and resources attached to the next chat message
+    let mut active_resource = use_signal(|| None::<Resource>);
+    let mut chat_attachments = use_signal(Vec::<ResourceAttachment>::new);
     
     // Add sidebar collapsed state
     let mut sidebar_collapsed = use_signal(|| false);
@@ -231,62 +281,91 @@ fn McpDemo() -> Element {
     let mut openrouter_credit = use_signal(|| None::<openrouter::CreditBalanceResponse>);
     let mut is_loading_credit = use_signal(|| false);
     
-    let mut mcp_state = use_signal(|| McpState { 
-        client: None,
-        selected_server: None,
-        active_clients: HashMap::new(),
-        server_status: HashMap::new(),
+    let mut mcp_state = use_signal(|| {
+        let config_path = server_config::config_path();
+        let (server_configs, config_error) = match ServerConfigs::load_or_init(&config_path) {
+            Ok(configs) => (configs, None),
+            Err(e) => {
+                error!("Failed to load the server configuration: {}", e);
+                (ServerConfigs::default(), Some(e.to_string()))
+            }
+        };
+        // Unlocked right away if the passphrase is remembered in the OS keyring
+        let secrets = SecretStore::default();
+        secrets::try_unlock_at_startup(&secrets);
+        let server_logs = ServerLogs::default().with_secrets(secrets.clone());
+        let inspector = Inspector::default();
+        McpState { 
+            client: None,
+            selected_server: None,
+            active_clients: HashMap::new(),
+            server_status: HashMap::new(),
+            retry_state: HashMap::new(),
+            health: HashMap::new(),
+            tool_registry: ToolRegistry::new(),
+            server_configs,
+            config_error,
+            active_profile: server_config::load_active_profile(),
+            connections: ConnectionManager::new(McpConnector::new(server_logs.clone(), inspector.clone(), secrets.clone())),
+            server_logs,
+            inspector,
+            secrets,
+        }
     });
     
-    // Get OpenRouter API key from environment variables 
-    let openrouter_api_key_original = env::var("OPENROUTER_API_KEY").ok();
+    // Re-render when the secrets are unlocked or changed in Settings
+    let mut secrets_revision = use_signal(|| 0u64);
+    use_future(move || async move {
+        let mut changes = mcp_state.peek().secrets.subscribe();
+        while changes.changed().await.is_ok() {
+            secrets_revision.with_mut(|r| *r += 1);
+        }
+    });
+    
+    // Get the OpenRouter API key from the secret store, or else from the environment
+    let _ = secrets_revision.read();
+    let openrouter_api_key_original = mcp_state.peek().secrets.openrouter_api_key();
     
     // Function to load OpenRouter credit balance
-    let mut load_credit_balance = {
-        // Clone for this closure
-        let openrouter_api_key = openrouter_api_key_original.clone();
-        
-        move |_| {
-            // Use the cloned value in this closure
-            if let Some(api_key) = &openrouter_api_key {
-                // Set loading state
-                is_loading_credit.set(true);
-                
-                // Clone API key for the async block
-                let api_key = api_key.clone();
-                
-                spawn({
-                    to_owned![openrouter_credit, is_loading_credit];
-                    async move {
-                        // Create client inside the async block with the cloned API key
-                        let client = openrouter::OpenRouterClient::new(api_key);
-                        
-                        match client.get_credit_balance().await {
-                            Ok(balance) => {
-                                openrouter_credit.set(Some(balance));
-                            }
-                            Err(e) => {
-                                error!("Error fetching OpenRouter credit balance: {}", e);
-                                openrouter_credit.set(None);
-                            }
+    let mut load_credit_balance = move |_| {
+        // Read when called, the key may have been unlocked since the first render
+        if let Some(api_key) = &mcp_state.peek().secrets.openrouter_api_key() {
+            // Set loading state
+            is_loading_credit.set(true);
+            
+            // Clone API key for the async block
+            let api_key = api_key.clone();
+            
+            spawn({
+                to_owned![openrouter_credit, is_loading_credit];
+                async move {
+                    // Create client inside the async block with the cloned API key
+                    let client = openrouter::OpenRouterClient::new(api_key);
+                    
+                    match client.get_credit_balance().await {
+                        Ok(balance) => {
+                            openrouter_credit.set(Some(balance));
+                        }
+                        Err(e) => {
+                            error!("Error fetching OpenRouter credit balance: {}", e);
+                            openrouter_credit.set(None);
                         }
-                        
-                        // Set loading state to false regardless of result
-                        is_loading_credit.set(false);
                     }
-                });
-            }
+                    
+                    // Set loading state to false regardless of result
+                    is_loading_credit.set(false);
+                }
+            });
         }
     };
     
-    // Load credit balance on component mount if API key is available
+    // Load credit balance on component mount, and again when the key is unlocked, if an API key is available
     use_effect({
-        // Use a different clone for this closure
-        let openrouter_api_key = openrouter_api_key_original.clone();
         let mut load_credit_balance = load_credit_balance.clone();
         
         move || {
-            if openrouter_api_key.is_some() {
+            let _ = secrets_revision.read();
+            if mcp_state.peek().secrets.openrouter_api_key().is_some() {
                 load_credit_balance(());
             }
             
@@ -295,90 +374,78 @@ fn McpDemo() -> Element {
         }
     });
     
-    // Server health check coroutine
-    use_coroutine({
-        to_owned![mcp_state, server_tools, tools];
-        move |_rx: dioxus::prelude::UnboundedReceiver<()>| async move {
-            loop {
-                // Check each server's health every 5 seconds
-                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-                
-                // Get a snapshot of current servers
-                let active_clients = mcp_state.read().active_clients.clone();
-                let mut running_server_ids = Vec::new();
-                
-                // Check each server's responsiveness
-                for (server_id, client) in &active_clients {
-                    if let Ok(client_lock) = client.try_lock() {
-                        // Try to ping the server with a timeout
-                        match tokio::time::timeout(
-                            std::time::Duration::from_millis(500),
-                            client_lock.list_resources(None)
-                        ).await {
-                            Ok(Ok(_)) => {
-                                // Server is responsive
-                                running_server_ids.push(server_id.clone());
-                                
-                                // Update status to Running if it's not already
-                                let should_update = if let Some(status) = mcp_state.read().server_status.get(server_id) {
-                                    !matches!(status, ServerStatus::Running)
-                                } else {
-                                    true
-                                };
-                                
-                                if should_update {
-                                    mcp_state.write().server_status.insert(
-                                        server_id.clone(), 
-                                        ServerStatus::Running
-                                    );
-                                }
-                            },
-                            _ => {
-                                // Server is not responsive
-                                info!("Server {} is not responsive, marking as failed", server_id);
-                                mcp_state.write().server_status.insert(
-                                    server_id.clone(), 
-                                    ServerStatus::Failed("Connection lost".to_string())
-                                );
-                            }
-                        }
-                    } else {
-                        // Client is busy - assume it's still working for now
-                        running_server_ids.push(server_id.clone());
+    // Mirror the connection manager in the UI state whenever a server changes
+    use_future(move || async move {
+        let connections = mcp_state.peek().connections.clone();
+        let mut changes = connections.subscribe();
+        
+        while changes.changed().await.is_ok() {
+            let running_tools = connection_manager::sync_state(&mut mcp_state.write());
+            server_tools.set(running_tools.clone());
+            update_global_tools(&running_tools, &mut tools, &mut mcp_state);
+        }
+    });
+    
+    // Supervise the servers: health checks and restarts with backoff
+    use_future(move || async move {
+        let connections = mcp_state.peek().connections.clone();
+        connections.supervise().await;
+    });
+    
+    // Reload the server configuration when its file changes and apply the difference:
+    // start added servers, stop removed ones and restart changed ones
+    use_future(move || async move {
+        let mut watcher = ConfigWatcher::new(server_config::config_path());
+        let mut applied = mcp_state.peek().server_configs.clone();
+        
+        loop {
+            tokio::time::sleep(server_config::CONFIG_POLL_INTERVAL).await;
+            let Some(reloaded) = watcher.check() else { continue };
+            
+            match reloaded {
+                Ok(configs) => {
+                    // Only the servers of the active profile are running
+                    let active = mcp_state.peek().active_profile.clone();
+                    let diff = applied
+                        .for_profile(active.as_deref())
+                        .diff(&configs.for_profile(active.as_deref()));
+                    info!(
+                        "Reloaded {}: {} added, {} removed, {} changed",
+                        watcher.path().display(), diff.added.len(), diff.removed.len(), diff.changed.len()
+                    );
+                    applied = configs.clone();
+                    {
+                        let mut state = mcp_state.write();
+                        state.server_configs = configs;
+                        state.config_error = None;
                     }
-                }
-                
-                // If the running server list changed, update the tools
-                let previous_running_count = mcp_state.read().server_status.values()
-                    .filter(|s| matches!(s, ServerStatus::Running))
-                    .count();
-                
-                if previous_running_count != running_server_ids.len() {
-                    info!("Server status changed, refreshing tools list");
-                    // Update server_tools by keeping only running servers
-                    let mut current_server_tools = server_tools.read().clone();
-                    
-                    // Remove tools from servers that are no longer running
-                    let server_ids_to_remove: Vec<String> = current_server_tools.keys()
-                        .filter(|id| !running_server_ids.contains(id))
-                        .cloned()
-                        .collect();
-                    
-                    for id in server_ids_to_remove {
-                        info!("Removing tools from disconnected server: {}", id);
-                        current_server_tools.remove(&id);
+                    if !diff.is_empty() {
+                        let connections = mcp_state.peek().connections.clone();
+                        connections.apply_config_diff(&diff).await;
                     }
-                    
-                    // Update the server_tools signal
-                    server_tools.set(current_server_tools.clone());
-                    
-                    // Update the global tools list
-                    update_global_tools(&current_server_tools, &mut tools);
+                }
+                Err(e) => {
+                    // Keep the servers as they are until the file is fixed
+                    warn!("Ignoring invalid server configuration: {}", e);
+                    mcp_state.write().config_error = Some(e.to_string());
                 }
             }
         }
     });
     
+    // Shut every server down before the window closes, so no server process outlives the app
+    #[cfg(feature = "desktop")]
+    {
+        let connections = mcp_state.peek().connections.clone();
+        dioxus::desktop::use_wry_event_handler(move |event, _| {
+            use dioxus::desktop::tao::event::{Event, WindowEvent};
+            if let Event::WindowEvent { event: WindowEvent::CloseRequested, .. } = event {
+                info!("Window closing, stopping all servers");
+                connections.stop_all_blocking();
+            }
+        });
+    }
+    
     // Server action handles both start and stop
     let server_action = move |_| {
         let has_client = mcp_state.read().client.is_some();
@@ -390,19 +457,15 @@ fn McpDemo() -> Element {
             show_resources.set(false);
             show_tools.set(false);
             
-            // Clear all clients and mark all servers as stopped
+            // Stop every server, the manager reports the new statuses back
+            let connections = mcp_state.read().connections.clone();
+            spawn(async move {
+                connections.stop_all().await;
+            });
+            
             let mut state = mcp_state.write();
             state.client = None;
-            
-            // First collect all server IDs to avoid the mutable/immutable borrow conflict
-            let server_ids: Vec<String> = state.active_clients.keys().cloned().collect();
-            
-            // Update all server statuses to Stopped
-            for id in server_ids {
-                state.server_status.insert(id, ServerStatus::Stopped);
-            }
-            
-            state.active_clients.clear();
+            state.selected_server = None;
             client_status.set("Not initialized".to_string());
             return;
         }
@@ -416,130 +479,66 @@ fn McpDemo() -> Element {
         spawn({
             to_owned![mcp_state, client_status, error_message];
             async move {
-                // Load server configurations from the file
-                let configs = match server_config::ServerConfigs::load_from_file("servers.json") {
-                    Ok(configs) => configs,
-                    Err(e) => {
-                        // If there's an error (likely file not found), create default configs
-                        warn!("Error loading server configurations: {}", e);
-                        server_config::ServerConfigs::initialize_default()
-                    }
+                // The configuration in effect, loaded when the app started and kept up to date by the config watcher,
+                // narrowed to the servers of the active profile
+                let configs = {
+                    let state = mcp_state.read();
+                    state.server_configs.for_profile(state.active_profile.as_deref())
                 };
+                if let Some(e) = mcp_state.read().config_error.clone() {
+                    error_message.set(Some(format!("Server configuration: {}", e)));
+                }
                 
                 // Log the number of servers to start
                 info!("Starting {} MCP servers", configs.servers.len());
                 
-                // Create a hashmap to store all active clients
-                let mut active_clients = HashMap::new();
-                let mut default_server = None;
-                let mut server_status = HashMap::new();
+                // Start every server through the connection manager
+                let connections = mcp_state.read().connections.clone();
+                let results = connections.start_all(&configs.servers).await;
                 
-                // Start each server configuration - use & to borrow instead of moving
-                for server_config in &configs.servers {
-                    // Clone the server config to avoid borrowing issues
-                    let server_config = server_config.clone();
-                    let server_id = server_config.id.clone();
-                    
-                    // Mark this server as starting
-                    server_status.insert(server_id.clone(), ServerStatus::Starting);
-                    
-                    // Log which server we're connecting to
-                    info!("Connecting to MCP server: {}", server_config.name);
-                    
-                    // Create transport with the server's configuration
-                    let env_vars = server_config.env.clone();
-                    
-                    let transport = StdioTransport::new(
-                        &server_config.command,
-                        server_config.args.clone(),
-                        env_vars
-                    );
-                    
-                    match transport.start().await {
-                        Ok(handle) => {
-                            let service = McpService::with_timeout(handle, Duration::from_secs(30));
-                            let mut client = McpClient::new(service);
-                            
-                            match client.initialize(
-                            ClientInfo {
-                            name: "mDesk".to_string(),
-                            version: "0.1.0".to_string(),
-                            },
-                            ClientCapabilities::default()
-                            ).await {
-                            Ok(_) => {
-                            // Successfully started the server
+                let mut default_server = None;
+                let mut first_server = None;
+                for (server_config, result) in results {
+                    match result {
+                        Ok(client) => {
                             info!("Successfully connected to MCP server: {}", server_config.name);
                             client_status.set(format!("Connected to {} (MCP v1.0)", server_config.name));
                             
-                            // Store the client in our HashMap
-                            let client_arc = Arc::new(Mutex::new(client));
-                            active_clients.insert(server_id.clone(), client_arc.clone());
-                            
-                            // Update server status to Running
-                            server_status.insert(server_id.clone(), ServerStatus::Running);
-                            
                             // Remember default server
                             if server_config.is_default {
                                 debug!("Setting {} as default server", server_config.name);
-                                    default_server = Some((server_config, client_arc));
-                                }
-                            }
-                            Err(e) => {
-                            let error_msg = format!("Failed to initialize: {}", e);
-                            error!("Failed to initialize client for server {}: {}", server_config.name, e);
-                            
-                            // Update server status to Failed
-                            server_status.insert(server_id, ServerStatus::Failed(error_msg));
-                            
-                            if configs.servers.len() == 1 {
-                            // Only show error in UI if this is the only server
-                                client_status.set("Error".to_string());
-                                    error_message.set(Some(format!("Failed to initialize client: {}", e)));
-                                        }
-                                    }
+                                default_server = Some((server_config, client));
+                            } else if first_server.is_none() {
+                                first_server = Some((server_config, client));
                             }
                         }
                         Err(e) => {
-                            let error_msg = format!("Failed to start: {}", e);
-                            error!("Failed to start transport for server {}: {}", server_config.name, e);
-                            
-                            // Update server status to Failed
-                            server_status.insert(server_id, ServerStatus::Failed(error_msg));
+                            error!("Failed to connect to server {}: {}", server_config.name, e);
                             
                             if configs.servers.len() == 1 {
                                 // Only show error in UI if this is the only server
                                 client_status.set("Error".to_string());
-                                error_message.set(Some(format!("Failed to start transport: {}", e)));
+                                error_message.set(Some(match e {
+                                    ConnectionError::Connect(ConnectError::Start(e)) => format!("Failed to start transport: {}", e),
+                                    ConnectionError::Connect(ConnectError::Initialize(e)) => format!("Failed to initialize client: {}", e),
+                                    e => e.to_string(),
+                                }));
                             }
                         }
                     }
                 }
                 
-                // After the loop, update the mcp_state 
+                // Publish the clients and select the default server if available, otherwise the first one
                 {
                     let mut state = mcp_state.write();
-                    state.active_clients = active_clients;
-                    state.server_status = server_status;
+                    connection_manager::sync_state(&mut state);
                     
-                    // Select the default server if available, otherwise select the first one
-                    if let Some((server, client)) = default_server {
+                    if let Some((server, client)) = default_server.or(first_server) {
                         state.client = Some(client);
                         state.selected_server = Some(server);
-                    } else {
-                        // Find the first available client
-                        if let Some((id, client)) = state.active_clients.iter().next() {
-                            let id = id.clone();
-                            // Get the server config for this client
-                            if let Ok(configs) = server_config::ServerConfigs::load_from_file("servers.json") {
-                                if let Some(server) = configs.get_by_id(&id) {
-                                    state.client = Some(client.clone());
-                                    state.selected_server = Some(server.clone());
-                                }
-                            }
-                        }
                     }
                 }
+                info!("Tool registry contains {} tools", mcp_state.read().tool_registry.len());
                 
                 // Final check - if no clients were successfully started, show an error
                 if mcp_state.read().client.is_none() {
@@ -550,13 +549,8 @@ fn McpDemo() -> Element {
         });
     };
     
-    // List resources using real client
+    // List resources from every resource provider
     let mut list_resources = move |_: ()| {
-        if mcp_state.read().active_clients.is_empty() {
-            error_message.set(Some("No active servers. Please start at least one server.".to_string()));
-            return;
-        }
-        
         // Use a separate effect to update these signals to avoid infinite rerenders
         use_effect(move || {
             client_status.set("Fetching resources...".to_string());
@@ -568,30 +562,23 @@ fn McpDemo() -> Element {
         spawn({
             to_owned![mcp_state, client_status, error_message, resources];
             async move {
-                // If we have active clients but no selected client, use the first available client
-                let client_to_use = if mcp_state.read().client.is_none() && !mcp_state.read().active_clients.is_empty() {
-                    // Get the first client from active_clients
-                    let first_server_id = mcp_state.read().active_clients.keys().next().cloned();
-                    first_server_id.and_then(|id| mcp_state.read().active_clients.get(&id).cloned())
-                } else {
-                    mcp_state.read().client.clone()
+                // List the resources of every provider: running servers, local files and databases
+                let service = ResourceService::with_defaults(&mcp_state.read().active_clients);
+                let listing = match service.list_resources(None, None).await {
+                    Ok(listing) => listing,
+                    Err(e) => {
+                        error_message.set(Some(format!("Failed to list resources: {}", e)));
+                        return;
+                    }
                 };
+                let errors = listing.errors;
                 
-                if let Some(client) = client_to_use {
-                    let client_lock = client.lock().await;
-                    
-                    match client_lock.list_resources(None).await {
-                        Ok(result) => {
-                            resources.set(result.resources);
-                            client_status.set("Connected to MCP Server v1.0".to_string());
-                        }
-                        Err(e) => {
-                            client_status.set("Error".to_string());
-                            error_message.set(Some(format!("Failed to list resources: {}", e)));
-                        }
-                    }
+                resources.set(listing.resources);
+                if errors.is_empty() {
+                    client_status.set("Connected to MCP Server v1.0".to_string());
                 } else {
-                    error_message.set(Some("Client not initialized".to_string()));
+                    let failed: Vec<String> = errors.iter().map(|(id, e)| format!("{}: {}", id, e)).collect();
+                    error_message.set(Some(format!("Failed to list resources: {}", failed.join("; "))));
                 }
             }
         });
@@ -773,7 +760,6 @@ fn McpDemo() -> Element {
                     button {
                         class: if *active_section.read() == "resources" { "nav-item active" } else { "nav-item" },
                         onclick: set_section("resources"),
-                        disabled: mcp_state.read().active_clients.is_empty(),
                         svg {
                             class: "nav-icon",
                             xmlns: "http://www.w3.org/2000/svg",
@@ -823,6 +809,30 @@ fn McpDemo() -> Element {
                         span { "Tools" }
                     }
                     
+                    button {
+                        class: if *active_section.read() == "prompts" { "nav-item active" } else { "nav-item" },
+                        onclick: set_section("prompts"),
+                        disabled: mcp_state.read().active_clients.is_empty(),
+                        svg {
+                            class: "nav-icon",
+                            xmlns: "http://www.w3.org/2000/svg",
+                            width: "20",
+                            height: "20",
+                            view_box: "0 0 24 24",
+                            fill: "none",
+                            stroke: "currentColor",
+                            stroke_width: "2",
+                            stroke_linecap: "round",
+                            stroke_linejoin: "round",
+                            path {
+                                d: "M21 15a2 2 0 0 1-2 2H7l-4 4V5a2 2 0 0 1 2-2h14a2 2 0 0 1 2 2z"
+                            }
+                            line { x1: "8", y1: "9", x2: "16", y2: "9" }
+                            line { x1: "8", y1: "13", x2: "13", y2: "13" }
+                        }
+                        span { "Prompts" }
+                    }
+                    
                     button {
                         class: if *active_section.read() == "servers" { "nav-item active" } else { "nav-item" },
                         onclick: set_section("servers"),
@@ -869,6 +879,48 @@ fn McpDemo() -> Element {
                         span { "Server Settings" }
                     }
                     
+                    button {
+                        class: if *active_section.read() == "inspector" { "nav-item active" } else { "nav-item" },
+                        onclick: set_section("inspector"),
+                        svg {
+                            class: "nav-icon",
+                            xmlns: "http://www.w3.org/2000/svg",
+                            width: "20",
+                            height: "20",
+                            view_box: "0 0 24 24",
+                            fill: "none",
+                            stroke: "currentColor",
+                            stroke_width: "2",
+                            stroke_linecap: "round",
+                            stroke_linejoin: "round",
+                            polyline { points: "17 1 21 5 17 9" }
+                            path { d: "M3 11V9a4 4 0 0 1 4-4h14" }
+                            polyline { points: "7 23 3 19 7 15" }
+                            path { d: "M21 13v2a4 4 0 0 1-4 4H3" }
+                        }
+                        span { "Inspector" }
+                    }
+                    
+                    button {
+                        class: if *active_section.read() == "settings" { "nav-item active" } else { "nav-item" },
+                        onclick: set_section("settings"),
+                        svg {
+                            class: "nav-icon",
+                            xmlns: "http://www.w3.org/2000/svg",
+                            width: "20",
+                            height: "20",
+                            view_box: "0 0 24 24",
+                            fill: "none",
+                            stroke: "currentColor",
+                            stroke_width: "2",
+                            stroke_linecap: "round",
+                            stroke_linejoin: "round",
+                            rect { x: "3", y: "11", width: "18", height: "11", rx: "2", ry: "2" }
+                            path { d: "M7 11V7a5 5 0 0 1 10 0v4" }
+                        }
+                        span { "Settings" }
+                    }
+                    
                 }
 
                 div { class: "sidebar-section",
@@ -879,7 +931,7 @@ fn McpDemo() -> Element {
                             class: {
                                 // Check if any servers are running for the main status indicator
                                 let any_running = mcp_state.read().server_status.iter()
-                                    .any(|(_, status)| matches!(status, ServerStatus::Running));
+                                    .any(|(_, status)| matches!(status, ServerStatus::Running | ServerStatus::Degraded(_)));
                                 
                                 if any_running {
                                     "status-dot online"
@@ -887,7 +939,7 @@ fn McpDemo() -> Element {
                                     .any(|(_, status)| matches!(status, ServerStatus::Failed(_))) {
                                     "status-dot error"
                                 } else if mcp_state.read().server_status.iter()
-                                    .any(|(_, status)| matches!(status, ServerStatus::Starting)) {
+                                    .any(|(_, status)| matches!(status, ServerStatus::Starting | ServerStatus::Stopping)) {
                                     "status-dot starting"
                                 } else {
                                     "status-dot offline"
@@ -899,7 +951,7 @@ fn McpDemo() -> Element {
                             div { class: "status-value", 
                                 {
                                     let running_count = mcp_state.read().server_status.iter()
-                                        .filter(|(_, status)| matches!(status, ServerStatus::Running))
+                                        .filter(|(_, status)| matches!(status, ServerStatus::Running | ServerStatus::Degraded(_)))
                                         .count();
                                     
                                     if running_count > 0 {
@@ -914,34 +966,29 @@ fn McpDemo() -> Element {
                             {
                                 let server_statuses = mcp_state.read().server_status.clone();
                                 let running_server_ids: Vec<String> = server_statuses.iter()
-                                    .filter(|(_, status)| matches!(status, ServerStatus::Running))
+                                    .filter(|(_, status)| matches!(status, ServerStatus::Running | ServerStatus::Degraded(_)))
                                     .map(|(id, _)| id.clone())
                                     .collect();
                                 
                                 if !running_server_ids.is_empty() {
-                                    // Load server configs to get names
-                                    if let Ok(configs) = server_config::ServerConfigs::load_from_file("servers.json") {
-                                        rsx! {
-                                            div { class: "running-servers-list",
-                                                for id in running_server_ids {
-                                                    if let Some(server) = configs.get_by_id(&id) {
-                                                        div { class: "running-server-item", 
-                                                            span { class: "running-server-dot" }
-                                                            "{server.name}" 
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    } else {
-                                        // If we can't load configs, just show IDs
-                                        rsx! {
-                                            div { class: "running-servers-list",
-                                                for id in running_server_ids {
-                                                    div { class: "running-server-item", 
-                                                        span { class: "running-server-dot" }
-                                                        "{id}" 
-                                                    }
+                                    // Show names from the configuration, or the id of a server that was removed from it
+                                    let running_servers: Vec<(String, String)> = {
+                                        let state = mcp_state.read();
+                                        running_server_ids.into_iter()
+                                            .map(|id| {
+                                                let name = state.server_configs.get_by_id(&id)
+                                                    .map(|server| server.name.clone())
+                                                    .unwrap_or_else(|| id.clone());
+                                                (id, name)
+                                            })
+                                            .collect()
+                                    };
+                                    rsx! {
+                                        div { class: "running-servers-list",
+                                            for (id, name) in running_servers {
+                                                div { key: "{id}", class: "running-server-item", 
+                                                    span { class: "running-server-dot" }
+                                                    "{name}" 
                                                 }
                                             }
                                         }
@@ -953,84 +1000,113 @@ fn McpDemo() -> Element {
                         }
                     }
 
-                    // Show a list of all servers from servers.json with their status
+                    // Show a list of all configured servers with their status
                     div { class: "server-status-list",
                         div { class: "server-status-heading", "Individual Servers" }
                         
-                        // Load all server configs from servers.json
+                        // All servers of the configuration in effect
                         {
                             // Get the statuses for comparison
                             let server_statuses = mcp_state.read().server_status.clone();
+                            let retry_states = mcp_state.read().retry_state.clone();
                             let selected_server = mcp_state.read().selected_server.clone();
                             
-                            // Load all configs from the file
-                            let configs_result = server_config::ServerConfigs::load_from_file("servers.json");
+                            let servers = mcp_state.read().server_configs.servers.clone();
                             
-                            if let Ok(configs) = configs_result {
+                            rsx! {
                                 // Show all servers from config file
-                                for server in &configs.servers {
-                                    // Get the server status (default to Stopped if not found)
-                                    let status = server_statuses.get(&server.id).cloned().unwrap_or(ServerStatus::Stopped);
-                                    let is_selected = selected_server.as_ref().map_or(false, |s| s.id == server.id);
-                                    
-                                    let status_class = {
-                                        let base_class = match status {
-                                            ServerStatus::Running => "server-status-item running",
-                                            ServerStatus::Failed(_) => "server-status-item failed",
-                                            ServerStatus::Stopped => "server-status-item stopped",
-                                            ServerStatus::Starting => "server-status-item starting",
+                                for server in servers {
+                                    {
+                                        // Get the server status (default to Stopped if not found)
+                                        let status = server_statuses.get(&server.id).cloned().unwrap_or(ServerStatus::Stopped);
+                                        let retry = retry_states.get(&server.id).cloned().unwrap_or_default();
+                                        let is_selected = selected_server.as_ref().map_or(false, |s| s.id == server.id);
+                                        
+                                        let status_class = {
+                                            let base_class = match status {
+                                                ServerStatus::Running => "server-status-item running",
+                                                ServerStatus::Degraded(_) => "server-status-item degraded",
+                                                ServerStatus::Failed(_) => "server-status-item failed",
+                                                ServerStatus::Stopped => "server-status-item stopped",
+                                                ServerStatus::Starting => "server-status-item starting",
+                                                ServerStatus::Stopping => "server-status-item stopping",
+                                                ServerStatus::Terminated(_) => "server-status-item terminated",
+                                            };
+                                            if is_selected {
+                                                format!("{} selected", base_class)
+                                            } else {
+                                                base_class.to_string()
+                                            }
+                                        };
+                                        
+                                        let status_text = match status {
+                                            ServerStatus::Running => "Running",
+                                            ServerStatus::Degraded(_) => "Degraded",
+                                            ServerStatus::Failed(_) => "Failed",
+                                            ServerStatus::Stopped => "Stopped",
+                                            ServerStatus::Starting => "Starting",
+                                            ServerStatus::Stopping => "Stopping",
+                                            ServerStatus::Terminated(_) => "Stopped",
+                                        };
+                                        
+                                        let error_icon = match &status {
+                                            ServerStatus::Failed(error) | ServerStatus::Degraded(error) | ServerStatus::Terminated(error) => Some(error.clone()),
+                                            _ => None,
                                         };
-                                        if is_selected {
-                                            format!("{} selected", base_class)
+                                        
+                                        // Restart attempts and the error that caused them
+                                        let max_retries = server.restart.max_retries;
+                                        let retry_text = if retry.next_retry.is_some() {
+                                            Some(format!("Retry {}/{} pending", retry.retries + 1, max_retries))
+                                        } else if retry.retries > 0 && matches!(status, ServerStatus::Failed(_)) {
+                                            Some(format!("Gave up after {} retries", retry.retries))
+                                        } else if retry.retries > 0 {
+                                            Some(format!("Restarted {} time{}", retry.retries, if retry.retries > 1 { "s" } else { "" }))
                                         } else {
-                                            base_class.to_string()
-                                        }
-                                    };
-                                    
-                                    let status_text = match status {
-                                        ServerStatus::Running => "Running",
-                                        ServerStatus::Failed(_) => "Failed",
-                                        ServerStatus::Stopped => "Stopped",
-                                        ServerStatus::Starting => "Starting",
-                                    };
-                                    
-                                    let error_icon = if let ServerStatus::Failed(error) = &status {
-                                        Some(error.clone())
-                                    } else {
-                                        None
-                                    };
-                                    
-                                    rsx! {
-                                        div { 
-                                            key: "{server.id}",
-                                            class: status_class,
-                                            onclick: {
-                                                let server_config = server.clone();
-                                                let mut on_select_server = select_server.clone();
-                                                move |_| {
-                                                    on_select_server(server_config.clone());
+                                            None
+                                        };
+                                        let last_error = retry.last_error.clone().filter(|_| retry_text.is_some());
+                                        
+                                        rsx! {
+                                            div { 
+                                                key: "{server.id}",
+                                                class: status_class,
+                                                onclick: {
+                                                    let server_config = server.clone();
+                                                    let mut on_select_server = select_server.clone();
+                                                    move |_| {
+                                                        on_select_server(server_config.clone());
+                                                    }
+                                                },
+                                                div {
+                                                    class: "server-status-name",
+                                                    "{server.name}"
+                                                    if is_selected {
+                                                        span { class: "selected-indicator", "✓" }
+                                                    }
                                                 }
-                                            },
-                                            div {
-                                                class: "server-status-name",
-                                                "{server.name}"
-                                                if is_selected {
-                                                    span { class: "selected-indicator", "✓" }
+                                                div {
+                                                    class: "server-status-value",
+                                                    "{status_text}"
+                                                    if let Some(error_msg) = error_icon {
+                                                        span {
+                                                            class: "server-status-error",
+                                                            title: "{error_msg}",
+                                                            "!"
+                                                        }
+                                                    }
                                                 }
-                                            }
-                                            div {
-                                                class: "server-status-value",
-                                                "{status_text}"
-                                                if let Some(error_msg) = error_icon {
-                                                    span {
-                                                        class: "server-status-error",
-                                                        title: "{error_msg}",
-                                                        "!"
+                                                if let Some(retry_text) = retry_text {
+                                                    div { class: "server-status-retry",
+                                                        "{retry_text}"
+                                                        if let Some(last_error) = last_error {
+                                                            div { class: "server-status-last-error", title: "{last_error}", "{last_error}" }
+                                                        }
                                                     }
                                                 }
                                             }
                                         }
-                                    };
+                                    }
                                 }
                             }
                         }
@@ -1354,7 +1430,7 @@ fn McpDemo() -> Element {
                 div { class: if *active_section.read() == "resources" { "content-section active" } else { "content-section" },
                     div { class: "section-header",
                         h1 { class: "section-title", "Resources" }
-                        p { class: "section-description", "Explore available resources in the MCP server" }
+                        p { class: "section-description", "Explore resources from MCP servers, local files and databases" }
                     }
 
                     div { class: "resource-container",
@@ -1379,7 +1455,7 @@ fn McpDemo() -> Element {
                                     }
                                 }
                                 div { class: "empty-title", "No Resources Found" }
-                                div { class: "empty-message", "There are currently no resources available in the MCP server." }
+                                div { class: "empty-message", "None of the resource providers have any resources available." }
                                 button {
                                     class: "reload-button",
                                     onclick: move |_| list_resources(()),
@@ -1410,39 +1486,56 @@ fn McpDemo() -> Element {
                         } else {
                             div { class: "resource-grid",
                                 for resource in resources.read().iter() {
-                                    div {
-                                        key: format!("resource-{}", &resource.name),
-                                        class: "resource-card",
-                                        div { class: "resource-header",
-                                            div { class: "resource-icon",
-                                                svg {
-                                                    xmlns: "http://www.w3.org/2000/svg",
-                                                    width: "20",
-                                                    height: "20",
-                                                    view_box: "0 0 24 24",
-                                                    fill: "none",
-                                                    stroke: "currentColor",
-                                                    stroke_width: "2",
-                                                    stroke_linecap: "round",
-                                                    stroke_linejoin: "round",
-                                                    path {
-                                                        d: "M13 2H6a2 2 0 0 0-2 2v16a2 2 0 0 0 2 2h12a2 2 0 0 0 2-2V9z"
-                                                    }
-                                                    polyline {
-                                                        points: "13 2 13 9 20 9"
-                                                    }
-                                                }
-                                            }
-                                            h3 { class: "resource-name", "{resource.name}" }
+                                    {
+                                        let resource_to_view = resource.clone();
+                                        let is_attached = chat_attachments.read().iter().any(|a| a.uri == resource.uri);
+                                        let mut metadata_lines = vec![resource.mime_type.clone()];
+                                        if !resource.metadata.tags.is_empty() {
+                                            metadata_lines.push(format!("Tags: {}", resource.metadata.tags.join(", ")));
                                         }
-                                        if let Some(desc) = &resource.description {
-                                            p { class: "resource-description", "{desc}" }
+                                        if let Some(timestamp) = resource.metadata.timestamp {
+                                            metadata_lines.push(format!("Modified: {}", timestamp.format("%Y-%m-%d %H:%M")));
                                         }
-                                        if let Some(annotations) = &resource.annotations {
-                                            div { class: "resource-annotations",
-                                                h4 { class: "annotations-title", "Annotations" }
-                                                pre { class: "annotations-content", 
-                                                    "{annotations:?}" 
+                                        let metadata_text = metadata_lines.join("\n");
+                                        
+                                        rsx! {
+                                            div {
+                                                key: format!("resource-{}", &resource.uri),
+                                                class: "resource-card",
+                                                div { class: "resource-header",
+                                                    div { class: "resource-icon",
+                                                        svg {
+                                                            xmlns: "http://www.w3.org/2000/svg",
+                                                            width: "20",
+                                                            height: "20",
+                                                            view_box: "0 0 24 24",
+                                                            fill: "none",
+                                                            stroke: "currentColor",
+                                                            stroke_width: "2",
+                                                            stroke_linecap: "round",
+                                                            stroke_linejoin: "round",
+                                                            path {
+                                                                d: "M13 2H6a2 2 0 0 0-2 2v16a2 2 0 0 0 2 2h12a2 2 0 0 0 2-2V9z"
+                                                            }
+                                                            polyline {
+                                                                points: "13 2 13 9 20 9"
+                                                            }
+                                                        }
+                                                    }
+                                                    h3 { class: "resource-name", "{resource.name}" }
+                                                }
+                                                div { class: "resource-uri", "{resource.uri} · {resource.provider}" }
+                                                if let Some(desc) = &resource.description {
+                                                    p { class: "resource-description", "{desc}" }
+                                                }
+                                                div { class: "resource-annotations",
+                                                    h4 { class: "annotations-title", "Metadata" }
+                                                    pre { class: "annotations-content", "{metadata_text}" }
+                                                }
+                                                button {
+                                                    class: "tool-test-button",
+                                                    onclick: move |_| active_resource.set(Some(resource_to_view.clone())),
+                                                    if is_attached { "View (attached)" } else { "View" }
                                                 }
                                             }
                                         }
@@ -1453,6 +1546,24 @@ fn McpDemo() -> Element {
                     }
                 }
 
+                // Prompts section
+                div { class: if *active_section.read() == "prompts" { "content-section active" } else { "content-section" },
+                    div { class: "section-header",
+                        h1 { class: "section-title", "Prompts" }
+                        p { class: "section-description", "Fill in prompt templates from running MCP servers and insert them into the chat" }
+                    }
+
+                    if *active_section.read() == "prompts" {
+                        components::PromptBrowser {
+                            mcp_state: mcp_state.clone(),
+                            on_insert: move |inserted: Vec<Message>| {
+                                prompt_messages.set(inserted);
+                                active_section.set("chat");
+                            },
+                        }
+                    }
+                }
+
                 // Tools section
                 div { class: if *active_section.read() == "tools" { "content-section active" } else { "content-section" },
                     div { class: "section-header",
@@ -1513,26 +1624,23 @@ fn McpDemo() -> Element {
                                 let mut server_tools = use_signal(|| HashMap::<String, Vec<Tool>>::new());
                                 
                                 let load_server_tools = move |server_id: String| {
-                                    let client_opt = mcp_state.read().active_clients.get(&server_id).cloned();
+                                    let connections = mcp_state.read().connections.clone();
                                     
-                                    if let Some(client) = client_opt {
-                                        spawn({
-                                            to_owned![server_id, server_tools];
-                                            async move {
-                                                let client_lock = client.lock().await;
-                                                
-                                                match client_lock.list_tools(None).await {
-                                                    Ok(result) => {
-                                                        let mut map = server_tools.write();
-                                                        map.insert(server_id.clone(), result.tools);
-                                                    }
-                                                    Err(e) => {
-                                                        error!("Failed to load tools for server {}: {}", server_id, e);
-                                                    }
+                                    spawn({
+                                        to_owned![server_id, server_tools];
+                                        async move {
+                                            // The manager publishes the refreshed tools to the registry
+                                            match connections.refresh_tools(&server_id).await {
+                                                Ok(tools) => {
+                                                    let mut map = server_tools.write();
+                                                    map.insert(server_id.clone(), tools);
+                                                }
+                                                Err(e) => {
+                                                    error!("Failed to load tools for server {}: {}", server_id, e);
                                                 }
                                             }
-                                        });
-                                    }
+                                        }
+                                    });
                                 };
                                 
                                 // Load tools for all running servers
@@ -1578,9 +1686,8 @@ fn McpDemo() -> Element {
                                                         if !tools.is_empty() {
                                                             // Get server name
                                                             let server_name = {
-                                                                let configs = ServerConfigs::load_from_file(Path::new("servers.json"))
-                                                                    .unwrap_or_default();
-                                                                let server_config = configs.get_by_id(server_id);
+                                                                let state = mcp_state.read();
+                                                                let server_config = state.server_configs.get_by_id(server_id);
                                                                 
                                                                 server_config.map(|c| c.name.clone())
                                                                     .unwrap_or_else(|| server_id.clone())
@@ -1635,13 +1742,9 @@ fn McpDemo() -> Element {
                                                                                         button {
                                                                                             class: "tool-test-button",
                                                                                             onclick: move |_| {
-                                                                                                // Set the client for this server
-                                                                                                let client_opt = mcp_state.read().active_clients.get(&server_id_clone).cloned();
-                                                                                                
-                                                                                                if let Some(client) = client_opt {
-                                                                                                    // Temporarily set as active client
-                                                                                                    mcp_state.write().client = Some(client);
-                                                                                                    active_tool_modal.set(Some(tool_clone.clone()));
+                                                                                                // Only test tools of servers that are still running
+                                                                                                if mcp_state.read().active_clients.contains_key(&server_id_clone) {
+                                                                                                    active_tool_modal.set(Some((server_id_clone.clone(), tool_clone.clone())));
                                                                                                 }
                                                                                             },
                                                                                             svg {
@@ -1696,6 +1799,35 @@ fn McpDemo() -> Element {
                     }
                 }
 
+                // Protocol inspector section
+                div { class: if *active_section.read() == "inspector" { "content-section active" } else { "content-section" },
+                    div { class: "section-header",
+                        h1 { class: "section-title", "Protocol Inspector" }
+                        p { class: "section-description", "JSON-RPC messages exchanged with the MCP servers" }
+                    }
+
+                    if *active_section.read() == "inspector" {
+                        components::ProtocolInspector {
+                            mcp_state: mcp_state.clone(),
+                        }
+                    }
+                }
+
+                // Settings section
+                div { class: if *active_section.read() == "settings" { "content-section active" } else { "content-section" },
+                    div { class: "section-header",
+                        h1 { class: "section-title", "Settings" }
+                        p { class: "section-description", "Manage API keys and other secrets, and which tools run without asking" }
+                    }
+
+                    components::SecretsSettings {
+                        mcp_state: mcp_state.clone(),
+                    }
+                    components::ApprovalSettings {
+                        mcp_state: mcp_state.clone(),
+                    }
+                }
+
                 // Chat section
                 div { class: if *active_section.read() == "chat" { "content-section active" } else { "content-section" },
                     div { class: "section-header",
@@ -1703,125 +1835,40 @@ fn McpDemo() -> Element {
                         p { class: "section-description", "Interact with AI models using MCP tools" }
                     }
                     
-                    // Load tools if needed and show chat component
-                    {
-                        // Ensure we fetch tools before rendering the chat tab if needed
-                        if tools.read().is_empty() && !mcp_state.read().active_clients.is_empty() {
-                        warn!("Tools not loaded yet, fetching them for chat");
-                            
-                            // Create a function to fetch tools from all servers
-                            let mut fetch_all_servers_tools = || {
-                                // Use a static variable to track the last fetch time
-                                static mut LAST_FETCH_TIME: Option<std::time::Instant> = None;
-                                
-                                // Only fetch if we don't have tools or if it's been at least 5 seconds
-                                let should_fetch = unsafe {
-                                    if !tools.read().is_empty() {
-                                        // We already have tools - check if we should refresh
-                                        match LAST_FETCH_TIME {
-                                            Some(last_time) => {
-                                                if last_time.elapsed() >= std::time::Duration::from_secs(5) {
-                                                    LAST_FETCH_TIME = Some(std::time::Instant::now());
-                                                    true
-                                                } else {
-                                                    false
-                                                }
-                                            },
-                                            None => {
-                                                // First time fetching
-                                                LAST_FETCH_TIME = Some(std::time::Instant::now());
-                                                true
-                                            }
-                                        }
-                                    } else {
-                                        // No tools yet - definitely fetch
-                                        LAST_FETCH_TIME = Some(std::time::Instant::now());
-                                        true
-                                    }
-                                };
-                                
-                                // Skip if we recently fetched tools and already have some
-                                if !should_fetch {
-                                    debug!("Skipping tool fetch in main - last fetch was too recent");
-                                    return;
-                                }
-                                
-                                info!("Initiating tool fetch from all servers in main");
-                                let active_clients = mcp_state.read().active_clients.clone();
-                                let mut server_tool_map = HashMap::new();
-                                
-                                // Create a oneshot channel to get the result back
-                                let (tx, mut rx) = tokio::sync::oneshot::channel();
-                                
-                                // Spawn a task to fetch all tools
-                                spawn({
-                                    to_owned![active_clients];
-                                    async move {
-                                        for (server_id, client_arc) in active_clients {
-                                            let client = client_arc.lock().await;
-                                            match client.list_tools(None).await {
-                                                Ok(result) => {
-                                                    info!("Loaded {} tools from server {}", result.tools.len(), server_id);
-                                                    server_tool_map.insert(server_id.clone(), result.tools);
-                                                },
-                                                Err(e) => {
-                                                    error!("Error fetching tools from {}: {}", server_id, e);
-                                                }
-                                            }
-                                        }
-                                        let _ = tx.send(server_tool_map);
-                                    }
-                                });
-                                
-                                // Wait a short time for tools to load
-                                std::thread::sleep(std::time::Duration::from_millis(500));
-                                
-                                // Try to receive the tools
-                                match rx.try_recv() {
-                                    Ok(received_server_tools) => {
-                                        // Update server_tools signal with the received mapping
-                                        server_tools.set(received_server_tools);
-                                        
-                                        // Update the global tools list based on server_tools
-                                        update_global_tools(&server_tools.read(), &mut tools);
-                                    },
-                                    Err(_) => {
-                                        error!("Failed to receive tools in time");
-                                    }
-                                }
-                            };
-                            
-                            // Call the function
-                            fetch_all_servers_tools();
-                        }
-                        
-                        // Debug logs
-                        debug!("Sending {} tools to ChatTab", tools.read().len());
-                        for tool in tools.read().iter() {
-                            trace!("  - Sending Tool to ChatTab: {} ({})", tool.name, tool.description);
-                        }
-                        
-                        // Render ChatTab component
-                        rsx! {
-                            ChatTab {
-                                mcp_tools: tools.read().to_vec(),
-                                api_key: openrouter_api_key_original.clone(),
-                                mcp_state: mcp_state.clone(),
-                            }
-                        }
+                    // The chat reads the tools from the registry the connection manager keeps in sync
+                    ChatTab {
+                        api_key: openrouter_api_key_original.clone(),
+                        mcp_state: mcp_state.clone(),
+                        prompt_messages: prompt_messages,
+                        attachments: chat_attachments,
                     }
                 }
             }
         }
         
         // Modal outside the wrapper but still inside the main rsx! block
-        if let Some(tool) = active_tool_modal.read().clone() {
+        if let Some((server_id, tool)) = active_tool_modal.read().clone() {
             components::ToolTestModal {
                 tool: tool,
+                server_id: server_id,
                 on_close: move |_| active_tool_modal.set(None),
                 mcp_state: mcp_state.clone(),
             }
         }
+        
+        if let Some(resource) = active_resource.read().clone() {
+            components::ResourceViewer {
+                resource: resource,
+                mcp_state: mcp_state.clone(),
+                on_close: move |_| active_resource.set(None),
+                on_attach: move |attachment: ResourceAttachment| {
+                    // Attaching the same resource again replaces the older contents
+                    chat_attachments.write().retain(|a| a.uri != attachment.uri);
+                    chat_attachments.write().push(attachment);
+                    active_resource.set(None);
+                },
+            }
+        }
     }
 }
 
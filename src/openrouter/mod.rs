@@ -1,10 +1,13 @@
 use serde::{Deserialize, Serialize};
 use reqwest::Client;
-use std::{sync::Arc, time::{Duration, Instant}};
+use std::{collections::{HashMap, VecDeque}, sync::Arc, time::{Duration, Instant}};
 use tokio::sync::Mutex;
+use futures::{stream::BoxStream, StreamExt};
 use mcp_core::Tool;
 use tracing::{debug, info, warn, error, instrument};
 
+use crate::event_stream::SseParser;
+
 #[derive(Debug, Clone)]
 pub struct OpenRouterClient {
     api_key: String,
@@ -24,12 +27,236 @@ pub struct ChatCompletionRequest {
     pub max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ToolDefinition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct ChatMessage {
     pub role: String,
+    // Assistant messages that only carry tool calls come back with a null content
+    #[serde(default, deserialize_with = "deserialize_null_as_empty")]
     pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    pub fn new(role: &str, content: String) -> Self {
+        Self {
+            role: role.to_string(),
+            content,
+            ..Default::default()
+        }
+    }
+
+    /// Assistant message that requested tool calls
+    pub fn assistant_tool_calls(content: String, tool_calls: Vec<ToolCall>) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            content,
+            tool_calls: Some(tool_calls),
+            tool_call_id: None,
+        }
+    }
+
+    /// Result of a tool call, answering the assistant message that requested it
+    pub fn tool_result(tool_call_id: String, content: String) -> Self {
+        Self {
+            role: "tool".to_string(),
+            content,
+            tool_calls: None,
+            tool_call_id: Some(tool_call_id),
+        }
+    }
+}
+
+/// How the model may use the tools offered in a request
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolChoice {
+    /// The model decides whether to call a tool
+    Auto,
+    /// The model must answer without calling a tool
+    None,
+}
+
+/// A function call requested by the model
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type", default = "default_tool_type")]
+    pub call_type: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FunctionCall {
+    pub name: String,
+    // JSON encoded arguments, as produced by the model
+    #[serde(default)]
+    pub arguments: String,
+}
+
+impl ToolCall {
+    /// Parse the JSON arguments of the call, falling back to an empty object
+    pub fn parsed_arguments(&self) -> serde_json::Value {
+        if self.function.arguments.trim().is_empty() {
+            return serde_json::json!({});
+        }
+
+        match serde_json::from_str::<serde_json::Value>(&self.function.arguments) {
+            Ok(value) if value.is_object() => value,
+            Ok(value) => {
+                warn!("Tool call {} has non-object arguments: {}", self.id, value);
+                serde_json::json!({})
+            }
+            Err(e) => {
+                warn!("Failed to parse arguments of tool call {}: {}", self.id, e);
+                serde_json::json!({})
+            }
+        }
+    }
+}
+
+/// A tool the model may call, in the OpenAI function calling format
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ToolDefinition {
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    pub function: FunctionDefinition,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+impl ToolDefinition {
+    /// Build a function definition from an MCP tool and its input schema
+    pub fn from_tool(tool: &Tool) -> Self {
+        Self::named(tool, function_name(&tool.name))
+    }
+
+    /// Build the definitions of several tools under the names `names` gave them
+    pub fn from_tools(tools: &[Tool], names: &FunctionNames) -> Vec<Self> {
+        tools
+            .iter()
+            .map(|tool| {
+                let name = names
+                    .function_name(&tool.name)
+                    .map_or_else(|| function_name(&tool.name), str::to_string);
+                Self::named(tool, name)
+            })
+            .collect()
+    }
+
+    fn named(tool: &Tool, name: String) -> Self {
+        // Some servers omit the schema entirely, but functions need an object schema
+        let parameters = if tool.input_schema.is_object() {
+            tool.input_schema.clone()
+        } else {
+            serde_json::json!({ "type": "object", "properties": {} })
+        };
+
+        Self {
+            tool_type: default_tool_type(),
+            function: FunctionDefinition {
+                name,
+                description: tool.description.clone(),
+                parameters,
+            },
+        }
+    }
+}
+
+/// Function names given to a set of tools, and the tools they stand for
+///
+/// Encoding a tool name is lossy, `a.b` and `a_b` both become `a_b`, so a name that is
+/// taken already gets a numeric suffix. Function calls in the reply are looked up here
+/// rather than by encoding the tool names again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionNames {
+    by_tool: HashMap<String, String>,
+    by_function: HashMap<String, String>,
+}
+
+impl FunctionNames {
+    pub fn new(tools: &[Tool]) -> Self {
+        let mut names = Self::default();
+        // Tools whose names are valid function names already keep them
+        let (unchanged, encoded): (Vec<&Tool>, Vec<&Tool>) =
+            tools.iter().partition(|tool| function_name(&tool.name) == tool.name);
+        for tool in unchanged.into_iter().chain(encoded) {
+            if names.by_tool.contains_key(&tool.name) {
+                continue;
+            }
+            let base = function_name(&tool.name);
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while names.by_function.contains_key(&name) {
+                let suffix_text = format!("_{}", suffix);
+                // Encoded names are ASCII, so any length is a character boundary
+                let kept = base.len().min(MAX_FUNCTION_NAME_LEN - suffix_text.len());
+                name = format!("{}{}", &base[..kept], suffix_text);
+                suffix += 1;
+            }
+            names.by_tool.insert(tool.name.clone(), name.clone());
+            names.by_function.insert(name, tool.name.clone());
+        }
+        names
+    }
+
+    /// Name the tool is offered under
+    pub fn function_name(&self, tool_name: &str) -> Option<&str> {
+        self.by_tool.get(tool_name).map(String::as_str)
+    }
+
+    /// Tool a function name was given to
+    pub fn tool_name(&self, function_name: &str) -> Option<&str> {
+        self.by_function.get(function_name).map(String::as_str)
+    }
+}
+
+/// Maximum length of a function name accepted by the API
+pub const MAX_FUNCTION_NAME_LEN: usize = 64;
+
+/// Map a tool name to a valid function name
+///
+/// Function names may only contain `a-z`, `A-Z`, `0-9`, `_` and `-`, so the `/`
+/// of qualified `server/tool` names becomes `__` and any other character becomes `_`.
+/// Different tools can end up with the same name, `FunctionNames` keeps them apart.
+pub fn function_name(tool_name: &str) -> String {
+    let mut name = String::with_capacity(tool_name.len());
+    for c in tool_name.chars() {
+        match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' => name.push(c),
+            '/' => name.push_str("__"),
+            _ => name.push('_'),
+        }
+    }
+    name.truncate(MAX_FUNCTION_NAME_LEN);
+    name
+}
+
+fn default_tool_type() -> String {
+    "function".to_string()
+}
+
+// Helper function to deserialize a null string as an empty one
+fn deserialize_null_as_empty<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<String>::deserialize(deserializer)?.unwrap_or_default())
 }
 
 #[derive(Deserialize, Debug)]
@@ -49,6 +276,152 @@ pub struct ChatCompletionChoice {
     pub finish_reason: Option<String>,
 }
 
+/// One chunk of a streamed chat completion
+#[derive(Deserialize, Debug)]
+pub struct ChatCompletionChunk {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub choices: Vec<ChatCompletionChunkChoice>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChatCompletionChunkChoice {
+    #[serde(default)]
+    pub index: usize,
+    #[serde(default)]
+    pub delta: ChatMessageDelta,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ChatMessageDelta {
+    pub role: Option<String>,
+    pub content: Option<String>,
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+/// Partial tool call, the arguments arrive in pieces across several chunks
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ToolCallDelta {
+    #[serde(default)]
+    pub index: usize,
+    pub id: Option<String>,
+    pub function: Option<FunctionCallDelta>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct FunctionCallDelta {
+    pub name: Option<String>,
+    pub arguments: Option<String>,
+}
+
+/// Events produced while streaming a chat completion
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// Text to append to the assistant message
+    Content(String),
+    /// Piece of a tool call
+    ToolCall(ToolCallDelta),
+    /// The model finished, with the reason it stopped
+    Finished(Option<String>),
+}
+
+/// Turn a `data:` payload into stream events, `None` marks the end of the stream
+pub fn parse_stream_payload(data: &str) -> Result<Option<Vec<StreamEvent>>, OpenRouterError> {
+    if data == "[DONE]" {
+        return Ok(None);
+    }
+
+    let value: serde_json::Value = serde_json::from_str(data)
+        .map_err(|e| OpenRouterError::Unknown(format!("Invalid stream chunk: {}", e)))?;
+
+    // Errors after the stream started are sent as a regular chunk
+    if let Some(error) = value.get("error") {
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| error.to_string());
+        return Err(OpenRouterError::ApiError(message));
+    }
+
+    let chunk: ChatCompletionChunk = serde_json::from_value(value)
+        .map_err(|e| OpenRouterError::Unknown(format!("Invalid stream chunk: {}", e)))?;
+
+    let mut events = Vec::new();
+    for choice in chunk.choices {
+        if let Some(content) = choice.delta.content {
+            if !content.is_empty() {
+                events.push(StreamEvent::Content(content));
+            }
+        }
+        for tool_call in choice.delta.tool_calls.unwrap_or_default() {
+            events.push(StreamEvent::ToolCall(tool_call));
+        }
+        if choice.finish_reason.is_some() {
+            events.push(StreamEvent::Finished(choice.finish_reason));
+        }
+    }
+
+    Ok(Some(events))
+}
+
+/// Assembles stream events into the complete assistant reply
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamAccumulator {
+    pub content: String,
+    pub tool_calls: Vec<ToolCall>,
+    pub finish_reason: Option<String>,
+}
+
+impl StreamAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply(&mut self, event: &StreamEvent) {
+        match event {
+            StreamEvent::Content(text) => self.content.push_str(text),
+            StreamEvent::ToolCall(delta) => {
+                while self.tool_calls.len() <= delta.index {
+                    self.tool_calls.push(ToolCall {
+                        id: String::new(),
+                        call_type: default_tool_type(),
+                        function: FunctionCall {
+                            name: String::new(),
+                            arguments: String::new(),
+                        },
+                    });
+                }
+
+                let tool_call = &mut self.tool_calls[delta.index];
+                if let Some(id) = &delta.id {
+                    tool_call.id = id.clone();
+                }
+                if let Some(function) = &delta.function {
+                    if let Some(name) = &function.name {
+                        tool_call.function.name.push_str(name);
+                    }
+                    if let Some(arguments) = &function.arguments {
+                        tool_call.function.arguments.push_str(arguments);
+                    }
+                }
+            }
+            StreamEvent::Finished(reason) => self.finish_reason = reason.clone(),
+        }
+    }
+
+    /// Tool calls that were streamed completely enough to be executed
+    pub fn complete_tool_calls(&self) -> Vec<ToolCall> {
+        self.tool_calls
+            .iter()
+            .filter(|call| !call.id.is_empty() && !call.function.name.is_empty())
+            .cloned()
+            .collect()
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Usage {
     pub prompt_tokens: u32,
@@ -68,6 +441,21 @@ pub struct ModelInfo {
     pub description: Option<String>,
     pub context_length: Option<usize>,
     pub pricing: Option<ModelPricing>,
+    #[serde(default)]
+    pub supported_parameters: Option<Vec<String>>,
+}
+
+impl ModelInfo {
+    /// Whether the model accepts native tool definitions
+    ///
+    /// Models that don't report their supported parameters are assumed to support
+    /// tools, a request that is rejected falls back to prompt based tool selection.
+    pub fn supports_tools(&self) -> bool {
+        self.supported_parameters
+            .as_ref()
+            .map(|params| params.iter().any(|p| p == "tools"))
+            .unwrap_or(true)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -178,6 +566,19 @@ pub enum OpenRouterError {
     Unknown(String),
 }
 
+impl OpenRouterError {
+    /// Check whether the API rejected the request because the model can't use tools
+    pub fn is_tools_unsupported(&self) -> bool {
+        match self {
+            OpenRouterError::ApiError(message) => {
+                let message = message.to_lowercase();
+                message.contains("tool use") || message.contains("support tools")
+            }
+            _ => false,
+        }
+    }
+}
+
 // Credit balance response
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct CreditBalanceResponse {
@@ -190,6 +591,9 @@ pub struct CreditBalanceData {
     pub total_usage: f64,
 }
 
+// Streams can run much longer than the default request timeout
+const STREAM_TIMEOUT: Duration = Duration::from_secs(600);
+
 impl OpenRouterClient {
     pub fn new(api_key: String) -> Self {
         let client = Client::builder()
@@ -222,24 +626,33 @@ impl OpenRouterClient {
         Ok(())
     }
     
-    #[instrument(level = "debug", skip(self, messages), fields(model = model, msg_count = messages.len(), max_tokens = ?max_tokens))]
     pub async fn chat_completion(
         &self, 
         model: &str, 
         messages: Vec<ChatMessage>,
         temperature: Option<f32>,
         max_tokens: Option<u32>,
+    ) -> Result<ChatCompletionResponse, OpenRouterError> {
+        self.chat_completion_with_tools(model, messages, None, ToolChoice::Auto, temperature, max_tokens).await
+    }
+    
+    /// Chat completion that offers the given tools for native function calling
+    ///
+    /// `tool_choice` only applies when tools are offered.
+    #[instrument(level = "debug", skip(self, messages, tools), fields(model = model, msg_count = messages.len(), tools_count = ?tools.as_ref().map(|t| t.len()), max_tokens = ?max_tokens))]
+    pub async fn chat_completion_with_tools(
+        &self, 
+        model: &str, 
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        tool_choice: ToolChoice,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
     ) -> Result<ChatCompletionResponse, OpenRouterError> {
         // Throttle requests to avoid rate limiting
         self.throttle().await?;
         
-        let request = ChatCompletionRequest {
-            model: model.to_string(),
-            messages,
-            temperature,
-            max_tokens,
-            stream: Some(false),
-        };
+        let request = Self::build_request(model, messages, tools, tool_choice, temperature, max_tokens);
         
         let response = self.client
             .post(&format!("{}/chat/completions", self.base_url))
@@ -264,6 +677,117 @@ impl OpenRouterClient {
         Ok(completion)
     }
     
+    /// Chat completion streamed as server-sent events
+    ///
+    /// Dropping the returned stream cancels the request. `tool_choice` only applies
+    /// when tools are offered.
+    #[instrument(level = "debug", skip(self, messages, tools), fields(model = model, msg_count = messages.len(), tools_count = ?tools.as_ref().map(|t| t.len()), max_tokens = ?max_tokens))]
+    pub async fn chat_completion_stream(
+        &self, 
+        model: &str, 
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        tool_choice: ToolChoice,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> Result<BoxStream<'static, Result<StreamEvent, OpenRouterError>>, OpenRouterError> {
+        // Throttle requests to avoid rate limiting
+        self.throttle().await?;
+        
+        let request = ChatCompletionRequest {
+            stream: Some(true),
+            ..Self::build_request(model, messages, tools, tool_choice, temperature, max_tokens)
+        };
+        
+        let response = self.client
+            .post(&format!("{}/chat/completions", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .header("HTTP-Referer", "https://mdesk.app") // Identifying the application
+            .timeout(STREAM_TIMEOUT)
+            .json(&request)
+            .send()
+            .await?;
+        
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            
+            return Err(match status.as_u16() {
+                429 => OpenRouterError::RateLimitExceeded,
+                _ => OpenRouterError::ApiError(format!("HTTP {}: {}", status, error_text)),
+            });
+        }
+        
+        let bytes = Box::pin(response.bytes_stream());
+        let events = futures::stream::unfold(
+            (bytes, SseParser::new(), VecDeque::new(), false),
+            |(mut bytes, mut parser, mut pending, mut done)| async move {
+                loop {
+                    if let Some(event) = pending.pop_front() {
+                        return Some((event, (bytes, parser, pending, done)));
+                    }
+                    if done {
+                        return None;
+                    }
+                    
+                    match bytes.next().await {
+                        Some(Ok(chunk)) => {
+                            // OpenRouter only sends `data`, without naming events
+                            for event in parser.push(&chunk) {
+                                match parse_stream_payload(&event.data) {
+                                    Ok(Some(events)) => pending.extend(events.into_iter().map(Ok)),
+                                    Ok(None) => {
+                                        done = true;
+                                        break;
+                                    }
+                                    Err(e) => {
+                                        pending.push_back(Err(e));
+                                        done = true;
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                        Some(Err(e)) => {
+                            pending.push_back(Err(e.into()));
+                            done = true;
+                        }
+                        None => done = true,
+                    }
+                }
+            },
+        );
+        
+        Ok(events.boxed())
+    }
+    
+    /// Build a non-streaming chat completion request, `tool_choice` is only sent along with tools
+    pub fn build_request(
+        model: &str,
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<ToolDefinition>>,
+        tool_choice: ToolChoice,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> ChatCompletionRequest {
+        // An empty tool list is rejected by some providers, so leave it out entirely
+        let tools = tools.filter(|t| !t.is_empty());
+        let has_tools = tools.is_some();
+        
+        ChatCompletionRequest {
+            model: model.to_string(),
+            messages,
+            temperature,
+            max_tokens,
+            stream: Some(false),
+            tools,
+            tool_choice: has_tools.then_some(tool_choice),
+            // One call per turn keeps the approve/execute flow in the chat simple
+            parallel_tool_calls: has_tools.then_some(false),
+        }
+    }
+    
     #[instrument(level = "debug", skip(self))]
     pub async fn list_models(&self) -> Result<Vec<ModelInfo>, OpenRouterError> {
         // Throttle requests to avoid rate limiting
@@ -310,6 +834,7 @@ impl OpenRouterClient {
                                     description: None,
                                     context_length: None,
                                     pricing: None,
+                                    supported_parameters: None,
                                 })
                             })
                             .collect::<Vec<_>>();
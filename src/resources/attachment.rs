@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Resource, ResourceContent};
+
+/// Rough number of characters per token, used to estimate context size before sending
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Content of an attached resource
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AttachmentContent {
+    Text(String),
+    /// Binary content is described to the model but not sent
+    Blob { mime_type: Option<String>, size_bytes: usize },
+}
+
+/// A resource attached to a user message as context for the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceAttachment {
+    /// Provider the resource was read from
+    pub provider: String,
+    pub uri: String,
+    pub name: String,
+    pub contents: Vec<AttachmentContent>,
+}
+
+impl ResourceAttachment {
+    pub fn new(resource: &Resource, content: &ResourceContent) -> Self {
+        let content = match content {
+            ResourceContent::Text(text) => AttachmentContent::Text(text.clone()),
+            ResourceContent::Binary(data) => AttachmentContent::Blob {
+                mime_type: Some(resource.mime_type.clone()),
+                size_bytes: data.len(),
+            },
+        };
+
+        Self {
+            provider: resource.provider.clone(),
+            uri: resource.uri.clone(),
+            name: resource.name.clone(),
+            contents: vec![content],
+        }
+    }
+
+    /// The resource as it is sent to the model
+    pub fn context_text(&self) -> String {
+        let body: Vec<String> = self
+            .contents
+            .iter()
+            .map(|content| match content {
+                AttachmentContent::Text(text) => text.clone(),
+                AttachmentContent::Blob { mime_type, size_bytes } => format!(
+                    "[Binary content omitted: {} bytes of {}]",
+                    size_bytes,
+                    mime_type.as_deref().unwrap_or("unknown type")
+                ),
+            })
+            .collect();
+
+        format!("<resource uri=\"{}\" name=\"{}\">\n{}\n</resource>", self.uri, self.name, body.join("\n"))
+    }
+
+    pub fn estimated_tokens(&self) -> usize {
+        estimate_tokens(&self.context_text())
+    }
+}
+
+/// Estimate the number of tokens a text takes up in a request
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
@@ -0,0 +1,195 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use super::{
+    mime_type_for_path, Resource, ResourceContent, ResourceError, ResourceMetadata, ResourceProvider,
+    ResourceProviderCapabilities, ResourceQuery,
+};
+
+/// How deep below the root directory files are listed
+const MAX_DEPTH: usize = 4;
+
+/// Upper bound on listed files, so a large root doesn't stall the Resources page
+const MAX_LISTED_FILES: usize = 500;
+
+/// Provides access to the files below a root directory as `file://` resources
+pub struct LocalResourceProvider {
+    root_path: PathBuf,
+}
+
+impl LocalResourceProvider {
+    pub fn new<P: AsRef<Path>>(root_path: P) -> Self {
+        Self {
+            root_path: root_path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+
+    /// `file://` URI of a path
+    pub fn file_uri(path: &Path) -> String {
+        format!("file://{}", path.display())
+    }
+
+    /// Resolve a `file://` URI to a path, rejecting anything outside the root
+    fn resolve(&self, uri: &str) -> Result<PathBuf, ResourceError> {
+        let path = uri
+            .strip_prefix("file://")
+            .ok_or_else(|| ResourceError::InvalidUri(uri.to_string()))?;
+        let path = PathBuf::from(path);
+
+        let root = self
+            .root_path
+            .canonicalize()
+            .map_err(|e| ResourceError::ReadError(format!("{}: {}", self.root_path.display(), e)))?;
+
+        // The file may not exist yet when writing, so canonicalize its parent instead
+        let canonical = match path.canonicalize() {
+            Ok(canonical) => canonical,
+            Err(_) => {
+                let parent = path.parent().ok_or_else(|| ResourceError::InvalidUri(uri.to_string()))?;
+                let file_name = path.file_name().ok_or_else(|| ResourceError::InvalidUri(uri.to_string()))?;
+                let parent = parent
+                    .canonicalize()
+                    .map_err(|_| ResourceError::NotFound(uri.to_string()))?;
+                parent.join(file_name)
+            }
+        };
+
+        if !canonical.starts_with(&root) {
+            return Err(ResourceError::PermissionDenied(format!(
+                "{} is outside of {}",
+                uri,
+                root.display()
+            )));
+        }
+        Ok(canonical)
+    }
+
+    fn resource_for(path: &Path, metadata: &std::fs::Metadata) -> Resource {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string());
+
+        Resource {
+            uri: Self::file_uri(path),
+            name,
+            description: Some(format!("{} bytes", metadata.len())),
+            mime_type: mime_type_for_path(path).to_string(),
+            provider: String::new(),
+            metadata: ResourceMetadata {
+                timestamp: metadata.modified().ok().map(DateTime::<Utc>::from),
+                ..ResourceMetadata::default()
+            },
+        }
+    }
+
+    fn collect(dir: &Path, depth: usize, resources: &mut Vec<Resource>) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Skipping unreadable directory {}: {}", dir.display(), e);
+                return;
+            }
+        };
+
+        let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+        paths.sort();
+
+        for path in paths {
+            if resources.len() >= MAX_LISTED_FILES {
+                return;
+            }
+
+            // Hidden files and directories like .git are left out
+            let hidden = path
+                .file_name()
+                .map(|n| n.to_string_lossy().starts_with('.'))
+                .unwrap_or(false);
+            if hidden {
+                continue;
+            }
+
+            // Symbolic links are left out, they may lead outside the root
+            let Ok(metadata) = std::fs::symlink_metadata(&path) else { continue };
+            if metadata.is_dir() {
+                if depth < MAX_DEPTH {
+                    Self::collect(&path, depth + 1, resources);
+                }
+            } else if metadata.is_file() {
+                resources.push(Self::resource_for(&path, &metadata));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ResourceProvider for LocalResourceProvider {
+    async fn list_resources(&self, query: Option<ResourceQuery>) -> Result<Vec<Resource>, ResourceError> {
+        let root = self
+            .root_path
+            .canonicalize()
+            .map_err(|e| ResourceError::NotFound(format!("{}: {}", self.root_path.display(), e)))?;
+
+        let resources = tokio::task::spawn_blocking(move || {
+            let mut resources = Vec::new();
+            Self::collect(&root, 0, &mut resources);
+            resources
+        })
+        .await
+        .map_err(|e| ResourceError::ReadError(e.to_string()))?;
+
+        debug!("Listed {} local files below {}", resources.len(), self.root_path.display());
+        Ok(query.unwrap_or_default().apply(resources))
+    }
+
+    async fn read_resource(&self, uri: &str) -> Result<ResourceContent, ResourceError> {
+        let path = self.resolve(uri)?;
+        let bytes = tokio::fs::read(&path).await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ResourceError::NotFound(uri.to_string()),
+            std::io::ErrorKind::PermissionDenied => ResourceError::PermissionDenied(uri.to_string()),
+            _ => ResourceError::ReadError(format!("{}: {}", uri, e)),
+        })?;
+
+        Ok(match String::from_utf8(bytes) {
+            Ok(text) => ResourceContent::Text(text),
+            Err(e) => ResourceContent::Binary(e.into_bytes()),
+        })
+    }
+
+    async fn write_resource(&self, uri: &str, content: ResourceContent) -> Result<Resource, ResourceError> {
+        let path = self.resolve(uri)?;
+        let bytes = match content {
+            ResourceContent::Text(text) => text.into_bytes(),
+            ResourceContent::Binary(data) => data,
+        };
+
+        tokio::fs::write(&path, bytes)
+            .await
+            .map_err(|e| ResourceError::WriteError(format!("{}: {}", uri, e)))?;
+        let metadata = tokio::fs::metadata(&path)
+            .await
+            .map_err(|e| ResourceError::WriteError(format!("{}: {}", uri, e)))?;
+
+        Ok(Self::resource_for(&path, &metadata))
+    }
+
+    fn supports_scheme(&self, scheme: &str) -> bool {
+        scheme == "file"
+    }
+
+    fn capabilities(&self) -> ResourceProviderCapabilities {
+        ResourceProviderCapabilities {
+            readable: true,
+            writable: true,
+            searchable: true,
+            supports_metadata: true,
+            ..ResourceProviderCapabilities::default()
+        }
+    }
+}
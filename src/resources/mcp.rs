@@ -0,0 +1,149 @@
+use async_trait::async_trait;
+use base64::Engine;
+use mcp_client::McpClientTrait;
+use mcp_core::resource::ResourceContents;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, error};
+
+use super::{
+    Resource, ResourceContent, ResourceError, ResourceMetadata, ResourceProvider,
+    ResourceProviderCapabilities, ResourceQuery,
+};
+
+/// Scheme of the URIs this provider hands out, `mcp://<server id>/<resource uri>`
+pub const MCP_SCHEME: &str = "mcp";
+
+/// Wrap the URI of a server's resource so the registry can route it back to the server
+pub fn mcp_uri(server_id: &str, uri: &str) -> String {
+    format!("{}://{}/{}", MCP_SCHEME, server_id, uri)
+}
+
+/// Split an `mcp://` URI into the server id and the server's own resource URI
+pub fn parse_mcp_uri(uri: &str) -> Result<(&str, &str), ResourceError> {
+    let rest = uri
+        .strip_prefix(MCP_SCHEME)
+        .and_then(|rest| rest.strip_prefix("://"))
+        .ok_or_else(|| ResourceError::InvalidUri(uri.to_string()))?;
+
+    match rest.split_once('/') {
+        Some((server_id, resource_uri)) if !server_id.is_empty() && !resource_uri.is_empty() => {
+            Ok((server_id, resource_uri))
+        }
+        _ => Err(ResourceError::InvalidUri(uri.to_string())),
+    }
+}
+
+/// Exposes the resources of all running MCP servers
+pub struct McpResourceProvider<C: ?Sized> {
+    clients: HashMap<String, Arc<Mutex<C>>>,
+}
+
+impl<C: ?Sized> McpResourceProvider<C> {
+    pub fn new(clients: HashMap<String, Arc<Mutex<C>>>) -> Self {
+        Self { clients }
+    }
+}
+
+#[async_trait]
+impl<C> ResourceProvider for McpResourceProvider<C>
+where
+    C: McpClientTrait + Send + 'static,
+{
+    async fn list_resources(&self, query: Option<ResourceQuery>) -> Result<Vec<Resource>, ResourceError> {
+        let mut server_ids: Vec<&String> = self.clients.keys().collect();
+        server_ids.sort();
+
+        let mut resources = Vec::new();
+        let mut failures = Vec::new();
+        for server_id in server_ids {
+            let client = self.clients[server_id].lock().await;
+            match client.list_resources(None).await {
+                Ok(result) => {
+                    debug!("Listed {} resources from server {}", result.resources.len(), server_id);
+                    resources.extend(result.resources.into_iter().map(|resource| Resource {
+                        uri: mcp_uri(server_id, &resource.uri),
+                        name: resource.name,
+                        description: resource.description,
+                        mime_type: resource.mime_type,
+                        provider: String::new(),
+                        metadata: ResourceMetadata {
+                            priority: resource.annotations.as_ref().and_then(|a| a.priority),
+                            timestamp: resource.annotations.as_ref().and_then(|a| a.timestamp),
+                            tags: vec![server_id.clone()],
+                            ..ResourceMetadata::default()
+                        },
+                    }));
+                }
+                Err(e) => {
+                    error!("Error listing resources from server {}: {}", server_id, e);
+                    failures.push(format!("{}: {}", server_id, e));
+                }
+            }
+        }
+
+        // Only fail when no server could be listed at all
+        if resources.is_empty() && !failures.is_empty() {
+            return Err(ResourceError::ReadError(failures.join("; ")));
+        }
+
+        Ok(query.unwrap_or_default().apply(resources))
+    }
+
+    async fn read_resource(&self, uri: &str) -> Result<ResourceContent, ResourceError> {
+        let (server_id, resource_uri) = parse_mcp_uri(uri)?;
+        let client = self
+            .clients
+            .get(server_id)
+            .ok_or_else(|| ResourceError::NotFound(format!("server {} is not running", server_id)))?;
+
+        let client = client.lock().await;
+        let result = client
+            .read_resource(resource_uri)
+            .await
+            .map_err(|e| ResourceError::ReadError(e.to_string()))?;
+
+        merge_contents(result.contents)
+    }
+
+    fn supports_scheme(&self, scheme: &str) -> bool {
+        scheme == MCP_SCHEME
+    }
+
+    fn capabilities(&self) -> ResourceProviderCapabilities {
+        ResourceProviderCapabilities {
+            readable: true,
+            searchable: true,
+            supports_metadata: true,
+            ..ResourceProviderCapabilities::default()
+        }
+    }
+}
+
+/// Merge the contents a server returned into one
+///
+/// Text parts are joined. A single binary part is decoded, binary parts next to
+/// text are replaced by a note since they can't be shown inline.
+fn merge_contents(contents: Vec<ResourceContents>) -> Result<ResourceContent, ResourceError> {
+    if let [ResourceContents::BlobResourceContents { blob, .. }] = contents.as_slice() {
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(blob.trim())
+            .map_err(|e| ResourceError::ReadError(format!("invalid base64 content: {}", e)))?;
+        return Ok(ResourceContent::Binary(data));
+    }
+
+    let parts: Vec<String> = contents
+        .into_iter()
+        .map(|content| match content {
+            ResourceContents::TextResourceContents { text, .. } => text,
+            ResourceContents::BlobResourceContents { uri, mime_type, .. } => format!(
+                "[Binary content of {} ({}) omitted]",
+                uri,
+                mime_type.as_deref().unwrap_or("unknown type")
+            ),
+        })
+        .collect();
+
+    Ok(ResourceContent::Text(parts.join("\n")))
+}
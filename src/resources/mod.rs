@@ -0,0 +1,413 @@
+//! Unified access to resources from MCP servers, local files and databases
+//!
+//! Providers are registered by name and URI schemes are mapped to them, so the
+//! rest of the app reads every resource the same way. See
+//! `data_access_abstraction.md` for the design.
+
+pub mod attachment;
+pub mod local;
+pub mod mcp;
+pub mod sqlite;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mcp_client::McpClientTrait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, info, instrument, warn};
+
+pub use attachment::{estimate_tokens, AttachmentContent, ResourceAttachment};
+pub use local::LocalResourceProvider;
+pub use mcp::McpResourceProvider;
+pub use sqlite::SqliteResourceProvider;
+
+/// A resource with metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resource {
+    /// URI representing the resource location (e.g., "file:///path/to/file")
+    pub uri: String,
+    /// Name of the resource
+    pub name: String,
+    /// Optional description of the resource
+    pub description: Option<String>,
+    /// MIME type of the resource content
+    pub mime_type: String,
+    /// Name of the provider that listed the resource
+    pub provider: String,
+    /// Additional metadata
+    pub metadata: ResourceMetadata,
+}
+
+/// Metadata for resources
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceMetadata {
+    /// Priority of the resource (0.0 to 1.0)
+    pub priority: Option<f32>,
+    /// Creation/modification timestamp
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Tags for organizing resources
+    pub tags: Vec<String>,
+    /// Custom metadata as key-value pairs
+    pub custom: HashMap<String, Value>,
+}
+
+/// Content of a resource
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceContent {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// Error types for resource operations
+#[derive(Debug, thiserror::Error)]
+pub enum ResourceError {
+    #[error("Resource not found: {0}")]
+    NotFound(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("Invalid URI: {0}")]
+    InvalidUri(String),
+
+    #[error("Failed to read resource: {0}")]
+    ReadError(String),
+
+    #[error("Failed to write resource: {0}")]
+    WriteError(String),
+
+    #[error("No provider for {0}")]
+    NoProvider(String),
+
+    #[error("Operation not supported by provider: {0}")]
+    Unsupported(String),
+}
+
+/// Capabilities of a resource provider
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceProviderCapabilities {
+    pub readable: bool,
+    pub writable: bool,
+    pub searchable: bool,
+    pub supports_metadata: bool,
+    pub supports_streaming: bool,
+}
+
+/// Query parameters for listing resources
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceQuery {
+    pub tags: Option<Vec<String>>,
+    pub mime_types: Option<Vec<String>>,
+    pub text_search: Option<String>,
+    pub min_priority: Option<f32>,
+    pub limit: Option<usize>,
+}
+
+impl ResourceQuery {
+    pub fn text(search: &str) -> Self {
+        Self {
+            text_search: Some(search.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// Check whether a resource passes every filter of the query
+    pub fn matches(&self, resource: &Resource) -> bool {
+        if let Some(tags) = &self.tags {
+            if !tags.iter().all(|tag| resource.metadata.tags.contains(tag)) {
+                return false;
+            }
+        }
+
+        if let Some(mime_types) = &self.mime_types {
+            if !mime_types.iter().any(|m| m == &resource.mime_type) {
+                return false;
+            }
+        }
+
+        if let Some(search) = &self.text_search {
+            let search = search.trim().to_lowercase();
+            let found = resource.name.to_lowercase().contains(&search)
+                || resource.uri.to_lowercase().contains(&search)
+                || resource.description.as_deref().unwrap_or_default().to_lowercase().contains(&search);
+            if !found {
+                return false;
+            }
+        }
+
+        if let Some(min_priority) = self.min_priority {
+            if resource.metadata.priority.unwrap_or(0.0) < min_priority {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Filter resources by the query and apply its limit
+    pub fn apply(&self, resources: Vec<Resource>) -> Vec<Resource> {
+        let filtered = resources.into_iter().filter(|r| self.matches(r));
+        match self.limit {
+            Some(limit) => filtered.take(limit).collect(),
+            None => filtered.collect(),
+        }
+    }
+}
+
+/// Primary trait for resource access
+#[async_trait]
+pub trait ResourceProvider: Send + Sync {
+    /// Get a list of available resources
+    async fn list_resources(&self, query: Option<ResourceQuery>) -> Result<Vec<Resource>, ResourceError>;
+
+    /// Read the content of a resource
+    async fn read_resource(&self, uri: &str) -> Result<ResourceContent, ResourceError>;
+
+    /// Write content to a resource (if supported)
+    async fn write_resource(&self, uri: &str, _content: ResourceContent) -> Result<Resource, ResourceError> {
+        Err(ResourceError::Unsupported(format!("writing {}", uri)))
+    }
+
+    /// Update metadata for a resource (if supported)
+    async fn update_metadata(&self, uri: &str, _metadata: ResourceMetadata) -> Result<Resource, ResourceError> {
+        Err(ResourceError::Unsupported(format!("updating metadata of {}", uri)))
+    }
+
+    /// Check if the provider can handle a specific URI scheme
+    fn supports_scheme(&self, scheme: &str) -> bool;
+
+    /// Get capabilities of this provider
+    fn capabilities(&self) -> ResourceProviderCapabilities;
+}
+
+/// The scheme of a URI, e.g. `file` for `file:///tmp/notes.txt`
+pub fn uri_scheme(uri: &str) -> Option<&str> {
+    let (scheme, _) = uri.split_once(':')?;
+    let valid = !scheme.is_empty()
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then_some(scheme)
+}
+
+/// Registry for resource providers
+#[derive(Clone, Default)]
+pub struct ResourceProviderRegistry {
+    providers: HashMap<String, Arc<dyn ResourceProvider>>,
+    scheme_mappings: HashMap<String, String>,
+}
+
+impl ResourceProviderRegistry {
+    /// Create a new empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a provider with a name
+    pub fn register(&mut self, name: &str, provider: Arc<dyn ResourceProvider>) {
+        debug!("Registered resource provider {}", name);
+        self.providers.insert(name.to_string(), provider);
+    }
+
+    /// Map a URI scheme to a specific provider
+    pub fn map_scheme(&mut self, scheme: &str, provider_name: &str) {
+        self.scheme_mappings.insert(scheme.to_lowercase(), provider_name.to_string());
+    }
+
+    /// Get a provider by name
+    pub fn get_provider(&self, name: &str) -> Option<Arc<dyn ResourceProvider>> {
+        self.providers.get(name).cloned()
+    }
+
+    /// Name of the provider responsible for a URI
+    ///
+    /// Explicit scheme mappings win, otherwise the first provider (by name) that
+    /// supports the scheme is used.
+    pub fn provider_name_for_uri(&self, uri: &str) -> Option<String> {
+        let scheme = uri_scheme(uri)?.to_lowercase();
+
+        if let Some(name) = self.scheme_mappings.get(&scheme) {
+            if self.providers.contains_key(name) {
+                return Some(name.clone());
+            }
+        }
+
+        let mut names: Vec<&String> = self.providers.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .find(|name| self.providers[*name].supports_scheme(&scheme))
+            .cloned()
+    }
+
+    /// Get the appropriate provider for a URI
+    pub fn provider_for_uri(&self, uri: &str) -> Option<Arc<dyn ResourceProvider>> {
+        self.provider_name_for_uri(uri).and_then(|name| self.get_provider(&name))
+    }
+
+    /// List all registered providers
+    pub fn list_providers(&self) -> Vec<(String, ResourceProviderCapabilities)> {
+        let mut providers: Vec<(String, ResourceProviderCapabilities)> = self
+            .providers
+            .iter()
+            .map(|(name, provider)| (name.clone(), provider.capabilities()))
+            .collect();
+        providers.sort_by(|a, b| a.0.cmp(&b.0));
+        providers
+    }
+}
+
+/// Resources of several providers, and the providers that failed to list theirs
+#[derive(Debug, Default)]
+pub struct ResourceListing {
+    pub resources: Vec<Resource>,
+    /// Provider name and error
+    pub errors: Vec<(String, ResourceError)>,
+}
+
+/// Main service that the app interacts with
+#[derive(Clone, Default)]
+pub struct ResourceService {
+    registry: ResourceProviderRegistry,
+}
+
+impl ResourceService {
+    /// Create a new service with the given registry
+    pub fn new(registry: ResourceProviderRegistry) -> Self {
+        Self { registry }
+    }
+
+    /// The service the app uses: running MCP servers, local files and an optional SQLite database
+    ///
+    /// Local files are served from `MDESK_FILES_ROOT` (the working directory by default),
+    /// the database is only registered when `MDESK_SQLITE_PATH` is set.
+    pub fn with_defaults<C>(clients: &HashMap<String, Arc<Mutex<C>>>) -> Self
+    where
+        C: McpClientTrait + Send + 'static,
+    {
+        let mut registry = ResourceProviderRegistry::new();
+
+        registry.register("mcp", Arc::new(McpResourceProvider::new(clients.clone())));
+        registry.map_scheme(mcp::MCP_SCHEME, "mcp");
+
+        let files_root = std::env::var("MDESK_FILES_ROOT")
+            .map(PathBuf::from)
+            .or_else(|_| std::env::current_dir())
+            .unwrap_or_else(|_| PathBuf::from("."));
+        registry.register("local", Arc::new(LocalResourceProvider::new(files_root)));
+        registry.map_scheme("file", "local");
+
+        if let Ok(path) = std::env::var("MDESK_SQLITE_PATH") {
+            registry.register("sqlite", Arc::new(SqliteResourceProvider::new(path)));
+            registry.map_scheme(sqlite::SQLITE_SCHEME, "sqlite");
+        }
+
+        Self::new(registry)
+    }
+
+    pub fn registry(&self) -> &ResourceProviderRegistry {
+        &self.registry
+    }
+
+    /// List resources across all providers or from a specific provider
+    ///
+    /// Providers that fail are reported in the listing instead of failing the whole call.
+    #[instrument(level = "debug", skip(self, query))]
+    pub async fn list_resources(
+        &self,
+        query: Option<ResourceQuery>,
+        provider: Option<&str>,
+    ) -> Result<ResourceListing, ResourceError> {
+        let names: Vec<String> = match provider {
+            Some(name) => {
+                if self.registry.get_provider(name).is_none() {
+                    return Err(ResourceError::NoProvider(name.to_string()));
+                }
+                vec![name.to_string()]
+            }
+            None => self.registry.list_providers().into_iter().map(|(name, _)| name).collect(),
+        };
+
+        let mut listing = ResourceListing::default();
+        for name in names {
+            let Some(provider) = self.registry.get_provider(&name) else { continue };
+            match provider.list_resources(query.clone()).await {
+                Ok(resources) => {
+                    info!("Listed {} resources from provider {}", resources.len(), name);
+                    listing.resources.extend(resources.into_iter().map(|mut r| {
+                        r.provider = name.clone();
+                        r
+                    }));
+                }
+                Err(e) => {
+                    warn!("Failed to list resources from provider {}: {}", name, e);
+                    listing.errors.push((name, e));
+                }
+            }
+        }
+
+        if let Some(limit) = query.and_then(|q| q.limit) {
+            listing.resources.truncate(limit);
+        }
+        Ok(listing)
+    }
+
+    /// Read a resource from the appropriate provider
+    #[instrument(level = "debug", skip(self))]
+    pub async fn read_resource(&self, uri: &str) -> Result<ResourceContent, ResourceError> {
+        let provider = self
+            .registry
+            .provider_for_uri(uri)
+            .ok_or_else(|| ResourceError::NoProvider(uri.to_string()))?;
+        provider.read_resource(uri).await
+    }
+
+    /// Write to a resource using the appropriate provider
+    #[instrument(level = "debug", skip(self, content))]
+    pub async fn write_resource(&self, uri: &str, content: ResourceContent) -> Result<Resource, ResourceError> {
+        let provider = self
+            .registry
+            .provider_for_uri(uri)
+            .ok_or_else(|| ResourceError::NoProvider(uri.to_string()))?;
+        provider.write_resource(uri, content).await
+    }
+
+    /// Search across all providers
+    pub async fn search(&self, query: ResourceQuery) -> Result<Vec<Resource>, ResourceError> {
+        Ok(self.list_resources(Some(query), None).await?.resources)
+    }
+}
+
+/// Guess a MIME type from a file extension
+pub fn mime_type_for_path(path: &std::path::Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "rs" => "text/x-rust",
+        "py" => "text/x-python",
+        "js" => "text/javascript",
+        "ts" => "text/typescript",
+        "toml" => "application/toml",
+        "json" => "application/json",
+        "yaml" | "yml" => "application/yaml",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "xml" => "application/xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "db" | "sqlite" | "sqlite3" => "application/vnd.sqlite3",
+        _ => "application/octet-stream",
+    }
+}
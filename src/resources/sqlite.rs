@@ -0,0 +1,153 @@
+use async_trait::async_trait;
+use rusqlite::{types::ValueRef, Connection, OpenFlags};
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use super::{
+    Resource, ResourceContent, ResourceError, ResourceMetadata, ResourceProvider,
+    ResourceProviderCapabilities, ResourceQuery,
+};
+
+/// Scheme of the URIs this provider hands out, `sqlite://<table>`
+pub const SQLITE_SCHEME: &str = "sqlite";
+
+/// Rows returned when reading a table
+const MAX_ROWS: usize = 100;
+
+/// Exposes the tables and views of a SQLite database as JSON resources
+pub struct SqliteResourceProvider {
+    path: PathBuf,
+}
+
+impl SqliteResourceProvider {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    fn open(path: &Path) -> Result<Connection, ResourceError> {
+        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| ResourceError::ReadError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Names and kinds (`table` or `view`) of the user tables in the database
+    fn tables(connection: &Connection) -> Result<Vec<(String, String)>, ResourceError> {
+        let mut statement = connection
+            .prepare("SELECT name, type FROM sqlite_master WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY name")
+            .map_err(read_error)?;
+        let tables = statement
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(read_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(read_error)?;
+        Ok(tables)
+    }
+
+    fn list_blocking(path: &Path) -> Result<Vec<Resource>, ResourceError> {
+        let connection = Self::open(path)?;
+
+        Self::tables(&connection)?
+            .into_iter()
+            .map(|(name, kind)| {
+                let count: i64 = connection
+                    .query_row(&format!("SELECT COUNT(*) FROM {}", quote_identifier(&name)), [], |row| row.get(0))
+                    .map_err(read_error)?;
+
+                Ok(Resource {
+                    uri: format!("{}://{}", SQLITE_SCHEME, name),
+                    name: name.clone(),
+                    description: Some(format!("{} with {} rows", kind, count)),
+                    mime_type: "application/json".to_string(),
+                    provider: String::new(),
+                    metadata: ResourceMetadata {
+                        tags: vec![kind],
+                        ..ResourceMetadata::default()
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn read_blocking(path: &Path, uri: &str) -> Result<ResourceContent, ResourceError> {
+        let table = uri
+            .strip_prefix(SQLITE_SCHEME)
+            .and_then(|rest| rest.strip_prefix("://"))
+            .filter(|table| !table.is_empty())
+            .ok_or_else(|| ResourceError::InvalidUri(uri.to_string()))?;
+
+        let connection = Self::open(path)?;
+
+        // Only tables that exist can be read, the name is still quoted when used in SQL
+        if !Self::tables(&connection)?.iter().any(|(name, _)| name == table) {
+            return Err(ResourceError::NotFound(uri.to_string()));
+        }
+
+        let mut statement = connection
+            .prepare(&format!("SELECT * FROM {} LIMIT {}", quote_identifier(table), MAX_ROWS))
+            .map_err(read_error)?;
+        let columns: Vec<String> = statement.column_names().iter().map(|c| c.to_string()).collect();
+
+        let mut rows = statement.query([]).map_err(read_error)?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next().map_err(read_error)? {
+            let mut record = Map::new();
+            for (idx, column) in columns.iter().enumerate() {
+                let value = match row.get_ref(idx).map_err(read_error)? {
+                    ValueRef::Null => Value::Null,
+                    ValueRef::Integer(i) => json!(i),
+                    ValueRef::Real(f) => json!(f),
+                    ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).to_string()),
+                    ValueRef::Blob(b) => Value::String(format!("<{} bytes>", b.len())),
+                };
+                record.insert(column.clone(), value);
+            }
+            records.push(Value::Object(record));
+        }
+
+        debug!("Read {} rows from table {}", records.len(), table);
+        let json = serde_json::to_string_pretty(&records).map_err(|e| ResourceError::ReadError(e.to_string()))?;
+        Ok(ResourceContent::Text(json))
+    }
+}
+
+fn read_error(e: rusqlite::Error) -> ResourceError {
+    ResourceError::ReadError(e.to_string())
+}
+
+/// Quote an identifier for use in SQL
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[async_trait]
+impl ResourceProvider for SqliteResourceProvider {
+    async fn list_resources(&self, query: Option<ResourceQuery>) -> Result<Vec<Resource>, ResourceError> {
+        let path = self.path.clone();
+        let resources = tokio::task::spawn_blocking(move || Self::list_blocking(&path))
+            .await
+            .map_err(|e| ResourceError::ReadError(e.to_string()))??;
+        Ok(query.unwrap_or_default().apply(resources))
+    }
+
+    async fn read_resource(&self, uri: &str) -> Result<ResourceContent, ResourceError> {
+        let path = self.path.clone();
+        let uri = uri.to_string();
+        tokio::task::spawn_blocking(move || Self::read_blocking(&path, &uri))
+            .await
+            .map_err(|e| ResourceError::ReadError(e.to_string()))?
+    }
+
+    fn supports_scheme(&self, scheme: &str) -> bool {
+        scheme == SQLITE_SCHEME
+    }
+
+    fn capabilities(&self) -> ResourceProviderCapabilities {
+        ResourceProviderCapabilities {
+            readable: true,
+            searchable: true,
+            ..ResourceProviderCapabilities::default()
+        }
+    }
+}
@@ -0,0 +1,539 @@
+//! Secrets such as API keys, kept in a local file encrypted with a passphrase.
+//!
+//! Server configurations refer to a secret as `${secret:NAME}` in the values of
+//! their environment variables and headers, which are resolved when the server is
+//! started so the secret itself never ends up in `servers.json`. The passphrase can
+//! be remembered in the OS keyring when mDesk is built with the `keyring` feature.
+
+use argon2::Argon2;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use thiserror::Error;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::server_config::{ServerConfig, TransportConfig};
+
+/// Secret the OpenRouter API key is read from before falling back to the environment
+pub const OPENROUTER_API_KEY: &str = "OPENROUTER_API_KEY";
+
+/// What secret values are replaced with wherever they would be shown
+pub const MASK: &str = "********";
+
+/// Values shorter than this are not masked, they would mask half of every log line
+const MIN_MASKED_LEN: usize = 4;
+
+/// Version of the encrypted file format
+const FILE_VERSION: u32 = 1;
+
+/// Service name the passphrase is stored under in the OS keyring
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "mdesk";
+
+/// The secrets file, `secrets.json` next to the server configuration
+pub fn secrets_path() -> PathBuf {
+    let config_path = crate::server_config::config_path();
+    match config_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => dir.join("secrets.json"),
+        None => PathBuf::from("secrets.json"),
+    }
+}
+
+/// Errors that can occur while using the secret store
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("Can't access {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("{} is not a secrets file: {message}", .path.display())]
+    Corrupt { path: PathBuf, message: String },
+
+    #[error("Wrong passphrase")]
+    WrongPassphrase,
+
+    #[error("The passphrase must not be empty")]
+    EmptyPassphrase,
+
+    #[error("The secrets are locked, unlock them in Settings")]
+    Locked,
+
+    #[error("\"{0}\" is not a valid secret name, use letters, digits, '_', '-' and '.'")]
+    InvalidName(String),
+
+    #[error("There is no secret named \"{0}\"")]
+    Missing(String),
+
+    #[error("Encryption failed: {0}")]
+    Crypto(String),
+
+    #[error("OS keyring: {0}")]
+    Keyring(String),
+}
+
+/// The secrets file: the secrets as JSON, encrypted with a key derived from the passphrase
+#[derive(Serialize, Deserialize)]
+struct EncryptedFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+struct State {
+    /// Passphrase the secrets were unlocked with, `None` while locked
+    passphrase: Option<String>,
+    secrets: BTreeMap<String, String>,
+}
+
+/// Secrets shared between the settings, the connector resolving them and the logs masking them
+#[derive(Clone)]
+pub struct SecretStore {
+    path: PathBuf,
+    state: Arc<RwLock<State>>,
+    changes: Arc<watch::Sender<u64>>,
+}
+
+impl PartialEq for SecretStore {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl Default for SecretStore {
+    /// The store in `secrets_path()`
+    fn default() -> Self {
+        Self::new(secrets_path())
+    }
+}
+
+impl SecretStore {
+    /// A locked store kept in the file at `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let (changes, _) = watch::channel(0);
+        Self {
+            path: path.into(),
+            state: Arc::new(RwLock::new(State {
+                passphrase: None,
+                secrets: BTreeMap::new(),
+            })),
+            changes: Arc::new(changes),
+        }
+    }
+
+    /// Receiver that is notified whenever the store is unlocked, locked or changed
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    fn notify(&self) {
+        self.changes.send_modify(|revision| *revision += 1);
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the secrets file has been created
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.state.read().unwrap().passphrase.is_some()
+    }
+
+    /// Decrypt the secrets, or start an empty store protected by `passphrase` if there is no file yet
+    pub fn unlock(&self, passphrase: &str) -> Result<(), SecretError> {
+        if passphrase.is_empty() {
+            return Err(SecretError::EmptyPassphrase);
+        }
+        let secrets = match fs::read_to_string(&self.path) {
+            Ok(contents) => decrypt(&self.path, &contents, passphrase)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(self.io_error(e)),
+        };
+
+        {
+            let mut state = self.state.write().unwrap();
+            state.passphrase = Some(passphrase.to_string());
+            state.secrets = secrets;
+            info!("Unlocked {} secrets", state.secrets.len());
+        }
+        self.notify();
+        Ok(())
+    }
+
+    /// Forget the decrypted secrets until the store is unlocked again
+    pub fn lock(&self) {
+        {
+            let mut state = self.state.write().unwrap();
+            state.passphrase = None;
+            state.secrets.clear();
+        }
+        self.notify();
+    }
+
+    /// Names of the secrets, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.state.read().unwrap().secrets.keys().cloned().collect()
+    }
+
+    /// A secret's value, `None` while locked
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.state.read().unwrap().secrets.get(name).cloned()
+    }
+
+    /// Add or replace a secret and save the store
+    pub fn set(&self, name: &str, value: &str) -> Result<(), SecretError> {
+        if !is_valid_name(name) {
+            return Err(SecretError::InvalidName(name.to_string()));
+        }
+        {
+            let mut state = self.state.write().unwrap();
+            let passphrase = state.passphrase.clone().ok_or(SecretError::Locked)?;
+            let mut secrets = state.secrets.clone();
+            secrets.insert(name.to_string(), value.to_string());
+            self.save(&secrets, &passphrase)?;
+            state.secrets = secrets;
+        }
+        self.notify();
+        Ok(())
+    }
+
+    /// Remove a secret and save the store, returning whether it existed
+    pub fn remove(&self, name: &str) -> Result<bool, SecretError> {
+        {
+            let mut state = self.state.write().unwrap();
+            let passphrase = state.passphrase.clone().ok_or(SecretError::Locked)?;
+            if !state.secrets.contains_key(name) {
+                return Ok(false);
+            }
+            let mut secrets = state.secrets.clone();
+            secrets.remove(name);
+            self.save(&secrets, &passphrase)?;
+            state.secrets = secrets;
+        }
+        self.notify();
+        Ok(true)
+    }
+
+    /// Encrypt the secrets with a new passphrase
+    pub fn change_passphrase(&self, passphrase: &str) -> Result<(), SecretError> {
+        if passphrase.is_empty() {
+            return Err(SecretError::EmptyPassphrase);
+        }
+        let mut state = self.state.write().unwrap();
+        if state.passphrase.is_none() {
+            return Err(SecretError::Locked);
+        }
+        self.save(&state.secrets, passphrase)?;
+        state.passphrase = Some(passphrase.to_string());
+        Ok(())
+    }
+
+    /// Replace every `${secret:NAME}` in `value` with the secret
+    pub fn resolve(&self, value: &str) -> Result<String, SecretError> {
+        if secret_refs(value).is_empty() {
+            return Ok(value.to_string());
+        }
+        let state = self.state.read().unwrap();
+        if state.passphrase.is_none() {
+            return Err(SecretError::Locked);
+        }
+
+        let mut missing = None;
+        let resolved = reference_pattern().replace_all(value, |captures: &regex::Captures| {
+            let name = &captures[1];
+            state.secrets.get(name).cloned().unwrap_or_else(|| {
+                missing.get_or_insert_with(|| name.to_string());
+                String::new()
+            })
+        });
+        match missing {
+            Some(name) => Err(SecretError::Missing(name)),
+            None => Ok(resolved.into_owned()),
+        }
+    }
+
+    /// The configuration a server is started with: its environment variables and
+    /// headers with the secrets they refer to filled in
+    pub fn resolve_config(&self, config: &ServerConfig) -> Result<ServerConfig, SecretError> {
+        let resolve_map = |map: &HashMap<String, String>| -> Result<HashMap<String, String>, SecretError> {
+            map.iter()
+                .map(|(key, value)| Ok((key.clone(), self.resolve(value)?)))
+                .collect()
+        };
+
+        let mut resolved = config.clone();
+        resolved.env = resolve_map(&config.env)?;
+        resolved.transport = match &config.transport {
+            TransportConfig::Stdio => TransportConfig::Stdio,
+            TransportConfig::Sse { url, headers } => TransportConfig::Sse {
+                url: url.clone(),
+                headers: resolve_map(headers)?,
+            },
+            TransportConfig::Http { url, headers } => TransportConfig::Http {
+                url: url.clone(),
+                headers: resolve_map(headers)?,
+            },
+        };
+        Ok(resolved)
+    }
+
+    /// `text` with the value of every secret replaced by `MASK`
+    pub fn mask(&self, text: &str) -> String {
+        let state = self.state.read().unwrap();
+        // Longer values first, so a secret containing another one is masked as a whole
+        let mut values: Vec<&String> = state
+            .secrets
+            .values()
+            .filter(|value| value.len() >= MIN_MASKED_LEN)
+            .collect();
+        values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+
+        values
+            .into_iter()
+            .fold(text.to_string(), |text, value| text.replace(value.as_str(), MASK))
+    }
+
+    /// The OpenRouter API key: the `OPENROUTER_API_KEY` secret if it is set, otherwise
+    /// the environment variable of that name, which `.env` may set
+    pub fn openrouter_api_key(&self) -> Option<String> {
+        self.get(OPENROUTER_API_KEY)
+            .or_else(|| std::env::var(OPENROUTER_API_KEY).ok())
+            .filter(|key| !key.is_empty())
+    }
+
+    /// Unlock the store with the passphrase remembered in the OS keyring, if there is one
+    pub fn unlock_from_keyring(&self) -> Result<bool, SecretError> {
+        match keyring::remembered(&self.path)? {
+            Some(passphrase) => self.unlock(&passphrase).map(|()| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Remember the passphrase the store is unlocked with in the OS keyring
+    pub fn remember_passphrase(&self) -> Result<(), SecretError> {
+        let passphrase = self.state.read().unwrap().passphrase.clone().ok_or(SecretError::Locked)?;
+        keyring::remember(&self.path, &passphrase)
+    }
+
+    /// Remove the passphrase from the OS keyring
+    pub fn forget_passphrase(&self) -> Result<(), SecretError> {
+        keyring::forget(&self.path)
+    }
+
+    /// Encrypt `secrets` and replace the file at once, so it is never left half written
+    fn save(&self, secrets: &BTreeMap<String, String>, passphrase: &str) -> Result<(), SecretError> {
+        let file = encrypt(secrets, passphrase)?;
+        // A map of strings always serializes
+        let json = serde_json::to_string_pretty(&file).unwrap_or_default();
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| self.io_error(e))?;
+        }
+
+        let temp_path = self.path.with_extension("json.tmp");
+        write_private(&temp_path, json.as_bytes())
+            .and_then(|()| fs::rename(&temp_path, &self.path))
+            .map_err(|e| self.io_error(e))
+    }
+
+    fn io_error(&self, source: io::Error) -> SecretError {
+        SecretError::Io {
+            path: self.path.clone(),
+            source,
+        }
+    }
+}
+
+/// Whether `name` can be used in a `${secret:NAME}` reference
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// Names of the secrets `value` refers to
+pub fn secret_refs(value: &str) -> Vec<&str> {
+    reference_pattern()
+        .captures_iter(value)
+        .filter_map(|captures| captures.get(1))
+        .map(|name| name.as_str())
+        .collect()
+}
+
+/// A reference to the secret `name`
+pub fn secret_ref(name: &str) -> String {
+    format!("${{secret:{}}}", name)
+}
+
+/// Name suggested for a secret taking the place of a server's environment variable or header
+pub fn suggested_name(server_id: &str, key: &str) -> String {
+    format!("{}_{}", server_id, key)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+fn reference_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\$\{secret:([A-Za-z0-9_.\-]+)\}").unwrap())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], SecretError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| SecretError::Crypto(e.to_string()))?;
+    Ok(key)
+}
+
+fn encrypt(secrets: &BTreeMap<String, String>, passphrase: &str) -> Result<EncryptedFile, SecretError> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt)?;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let plaintext = serde_json::to_vec(secrets).unwrap_or_default();
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| SecretError::Crypto("can't encrypt the secrets".to_string()))?;
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    Ok(EncryptedFile {
+        version: FILE_VERSION,
+        salt: engine.encode(salt),
+        nonce: engine.encode(nonce),
+        ciphertext: engine.encode(ciphertext),
+    })
+}
+
+fn decrypt(path: &Path, contents: &str, passphrase: &str) -> Result<BTreeMap<String, String>, SecretError> {
+    let corrupt = |message: String| SecretError::Corrupt {
+        path: path.to_path_buf(),
+        message,
+    };
+    let file: EncryptedFile = serde_json::from_str(contents).map_err(|e| corrupt(e.to_string()))?;
+    if file.version != FILE_VERSION {
+        return Err(corrupt(format!("unsupported version {}", file.version)));
+    }
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let decode = |field: &str, value: &str| engine.decode(value).map_err(|e| corrupt(format!("{}: {}", field, e)));
+    let salt = decode("salt", &file.salt)?;
+    let nonce = decode("nonce", &file.nonce)?;
+    let ciphertext = decode("ciphertext", &file.ciphertext)?;
+    if nonce.len() != 24 {
+        return Err(corrupt(format!("nonce has {} bytes instead of 24", nonce.len())));
+    }
+
+    let key = derive_key(passphrase, &salt)?;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    // The tag doesn't match for a wrong passphrase as well as for a tampered file
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| SecretError::WrongPassphrase)?;
+    serde_json::from_slice(&plaintext).map_err(|e| corrupt(e.to_string()))
+}
+
+/// Write a file only the current user can read
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut options = File::options();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+/// The passphrase in the OS keyring, keyed by the secrets file it unlocks
+#[cfg(feature = "keyring")]
+mod keyring {
+    use super::{SecretError, KEYRING_SERVICE};
+    use std::path::Path;
+
+    fn entry(path: &Path) -> Result<::keyring::Entry, SecretError> {
+        ::keyring::Entry::new(KEYRING_SERVICE, &path.display().to_string())
+            .map_err(|e| SecretError::Keyring(e.to_string()))
+    }
+
+    pub fn is_available() -> bool {
+        true
+    }
+
+    pub fn remembered(path: &Path) -> Result<Option<String>, SecretError> {
+        match entry(path)?.get_password() {
+            Ok(passphrase) => Ok(Some(passphrase)),
+            Err(::keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(SecretError::Keyring(e.to_string())),
+        }
+    }
+
+    pub fn remember(path: &Path, passphrase: &str) -> Result<(), SecretError> {
+        entry(path)?
+            .set_password(passphrase)
+            .map_err(|e| SecretError::Keyring(e.to_string()))
+    }
+
+    pub fn forget(path: &Path) -> Result<(), SecretError> {
+        match entry(path)?.delete_password() {
+            Ok(()) | Err(::keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(SecretError::Keyring(e.to_string())),
+        }
+    }
+}
+
+/// Without the `keyring` feature nothing is remembered
+#[cfg(not(feature = "keyring"))]
+mod keyring {
+    use super::SecretError;
+    use std::path::Path;
+
+    pub fn is_available() -> bool {
+        false
+    }
+
+    pub fn remembered(_path: &Path) -> Result<Option<String>, SecretError> {
+        Ok(None)
+    }
+
+    pub fn remember(_path: &Path, _passphrase: &str) -> Result<(), SecretError> {
+        Err(SecretError::Keyring("mDesk was built without the keyring feature".to_string()))
+    }
+
+    pub fn forget(_path: &Path) -> Result<(), SecretError> {
+        Ok(())
+    }
+}
+
+/// Whether the passphrase can be remembered in the OS keyring
+pub fn keyring_available() -> bool {
+    keyring::is_available()
+}
+
+/// Unlock `store` from the OS keyring at startup, logging why it stays locked
+pub fn try_unlock_at_startup(store: &SecretStore) {
+    match store.unlock_from_keyring() {
+        Ok(true) => info!("Unlocked the secrets with the passphrase from the OS keyring"),
+        Ok(false) => {}
+        Err(e) => warn!("Can't unlock the secrets from the OS keyring: {}", e),
+    }
+}
+
@@ -1,31 +1,394 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs::File;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs::{self, File};
 use std::io::{self, Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
 use uuid::Uuid;
-use tracing::{debug, info, instrument};
+use tracing::{debug, info, instrument, warn};
+
+use crate::approvals::ApprovalRule;
+use crate::argument_rules::ArgumentRule;
+use crate::components::validation_pipeline::SanitizationConfig;
+
+/// Environment variable pointing to the server configuration file to use instead of the default
+pub const CONFIG_PATH_ENV: &str = "MDESK_SERVERS_CONFIG";
+
+/// Where earlier versions kept the configuration, relative to the working directory
+pub const LEGACY_CONFIG_PATH: &str = "servers.json";
+
+/// How often the configuration file is checked for changes
+pub const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Environment variable selecting the profile to use instead of the one chosen in the app
+pub const PROFILE_ENV: &str = "MDESK_PROFILE";
+
+/// The server configuration file: `$MDESK_SERVERS_CONFIG` if set, otherwise
+/// `servers.json` in the platform config directory, e.g. `~/.config/mdesk` on Linux
+pub fn config_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_ENV).filter(|path| !path.is_empty()) {
+        return PathBuf::from(path);
+    }
+    match dirs::config_dir() {
+        Some(dir) => dir.join("mdesk").join("servers.json"),
+        None => PathBuf::from(LEGACY_CONFIG_PATH),
+    }
+}
+
+/// File remembering the profile chosen on this machine, kept apart from the configuration
+/// so a configuration shared by a team doesn't decide it
+pub fn active_profile_path() -> PathBuf {
+    match config_path().parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => dir.join("active_profile"),
+        None => PathBuf::from("active_profile"),
+    }
+}
+
+/// The profile in use: `$MDESK_PROFILE` if set, otherwise the one last chosen in the app
+pub fn load_active_profile() -> Option<String> {
+    if let Some(profile) = std::env::var(PROFILE_ENV).ok().filter(|profile| !profile.trim().is_empty()) {
+        return Some(profile.trim().to_string());
+    }
+    fs::read_to_string(active_profile_path())
+        .ok()
+        .map(|profile| profile.trim().to_string())
+        .filter(|profile| !profile.is_empty())
+}
+
+/// Remember the profile chosen in the app, `None` for all servers
+pub fn save_active_profile(profile: Option<&str>) -> io::Result<()> {
+    let path = active_profile_path();
+    match profile {
+        Some(profile) => {
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, profile)
+        }
+        None => match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Something wrong with one field of the configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    /// Where the problem is, e.g. `servers[1].command`
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Errors that can occur while loading or saving the server configuration
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Can't access {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("{}:{line}:{column}: {message}", .path.display())]
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
+    #[error("{} is invalid: {}", .path.display(), .problems.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    Invalid {
+        path: PathBuf,
+        problems: Vec<ConfigProblem>,
+    },
+}
+
+impl ConfigError {
+    fn io(path: &Path, source: io::Error) -> Self {
+        ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    /// Whether the file doesn't exist yet
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, ConfigError::Io { source, .. } if source.kind() == io::ErrorKind::NotFound)
+    }
+}
 
 /// Type alias for convenience when accessing global state
 #[allow(non_upper_case_globals)]
 pub const g: fn() -> () = || ();
 
+/// How mDesk connects to an MCP server
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TransportConfig {
+    /// Spawn the server's command and talk over its stdin/stdout
+    #[default]
+    Stdio,
+    /// HTTP+SSE: responses arrive on an event stream, requests are posted to the endpoint it announces
+    Sse {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// Streamable HTTP: every message is posted to a single endpoint
+    Http {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+}
+
+impl TransportConfig {
+    /// Short name shown in the server list
+    pub fn label(&self) -> &'static str {
+        match self {
+            TransportConfig::Stdio => "stdio",
+            TransportConfig::Sse { .. } => "sse",
+            TransportConfig::Http { .. } => "http",
+        }
+    }
+
+    /// URL of a remote server, `None` for stdio
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            TransportConfig::Stdio => None,
+            TransportConfig::Sse { url, .. } | TransportConfig::Http { url, .. } => Some(url),
+        }
+    }
+
+    /// Headers sent with every request to a remote server
+    pub fn headers(&self) -> Option<&HashMap<String, String>> {
+        match self {
+            TransportConfig::Stdio => None,
+            TransportConfig::Sse { headers, .. } | TransportConfig::Http { headers, .. } => Some(headers),
+        }
+    }
+}
+
+/// When a server that stopped working is restarted
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartMode {
+    /// Never restart automatically
+    Never,
+    /// Restart servers that failed after they had been running
+    #[default]
+    OnFailure,
+    /// Also keep retrying servers that failed to start in the first place
+    Always,
+}
+
+/// Restart policy of a server with exponential backoff between attempts
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RestartPolicy {
+    pub mode: RestartMode,
+    /// Restarts attempted before giving up
+    pub max_retries: u32,
+    /// Delay before the first restart, doubled for every further attempt
+    pub initial_backoff_ms: u64,
+    /// Upper bound on the delay between attempts
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            mode: RestartMode::OnFailure,
+            max_retries: 5,
+            initial_backoff_ms: 1_000,
+            max_backoff_ms: 60_000,
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// A policy that never restarts
+    pub fn never() -> Self {
+        Self {
+            mode: RestartMode::Never,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before restart attempt `attempt`, counting from zero
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64.checked_shl(attempt).unwrap_or(u64::MAX);
+        let delay = self.initial_backoff_ms.saturating_mul(factor).min(self.max_backoff_ms);
+        Duration::from_millis(delay)
+    }
+
+    /// Whether a failed server is restarted after `retries` attempts so far
+    ///
+    /// `had_started` tells whether the server was running before it failed.
+    pub fn should_restart(&self, retries: u32, had_started: bool) -> bool {
+        let mode_allows = match self.mode {
+            RestartMode::Never => false,
+            RestartMode::OnFailure => had_started,
+            RestartMode::Always => true,
+        };
+        mode_allows && retries < self.max_retries
+    }
+}
+
+/// How often a running server is pinged and how long it gets to answer
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct HealthCheckConfig {
+    pub enabled: bool,
+    /// Time between two pings
+    pub interval_ms: u64,
+    /// How long the server gets to answer a ping
+    pub timeout_ms: u64,
+    /// Unanswered pings in a row after which the server counts as failed, before that it is degraded
+    pub max_missed: u32,
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_ms: 15_000,
+            timeout_ms: 5_000,
+            max_missed: 3,
+        }
+    }
+}
+
+impl HealthCheckConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
 /// Configuration for an MCP server
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ServerConfig {
     pub id: String,
     pub name: String,
+    /// Transport used to reach the server, configs without one are stdio servers
+    #[serde(default)]
+    pub transport: TransportConfig,
+    /// Command, arguments and environment of a stdio server
+    #[serde(default)]
     pub command: String,
+    #[serde(default)]
     pub args: Vec<String>,
+    #[serde(default)]
     pub env: HashMap<String, String>,
     pub description: Option<String>,
     pub is_default: bool,
+    /// What happens when the server crashes or stops responding
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    /// How the server is checked for still responding
+    #[serde(default)]
+    pub health: HealthCheckConfig,
+}
+
+/// A named subset of the servers, e.g. the ones used at work or on a laptop
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Ids of the servers the profile enables
+    #[serde(default)]
+    pub servers: Vec<String>,
 }
 
 /// Collection of server configurations
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ServerConfigs {
     pub servers: Vec<ServerConfig>,
+    /// Profiles enabling different subsets of the servers, without one every server is enabled
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<Profile>,
+    /// Rules deciding which tool calls run without asking, the first one that matches applies
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approval_rules: Vec<ApprovalRule>,
+    /// Rules on the arguments of tool calls, a call breaking one is never run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub argument_rules: Vec<ArgumentRule>,
+    /// How the string arguments of tool calls are cleaned up before they are sent
+    #[serde(default, skip_serializing_if = "SanitizationConfig::is_empty")]
+    pub sanitization: SanitizationConfig,
+}
+
+/// Servers that differ between two configurations
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigDiff {
+    /// Servers that are new
+    pub added: Vec<ServerConfig>,
+    /// Ids of the servers that are gone
+    pub removed: Vec<String>,
+    /// New configuration of the servers that have to be restarted for it
+    pub changed: Vec<ServerConfig>,
+}
+
+impl ConfigDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Notices when the configuration file changes, by comparing its contents
+pub struct ConfigWatcher {
+    path: PathBuf,
+    last_seen: Option<String>,
+}
+
+impl ConfigWatcher {
+    /// Watch `path`, taking its current contents as already applied
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let last_seen = fs::read_to_string(&path).ok();
+        Self { path, last_seen }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The configuration if the file changed since the last check, or why it can't be used
+    ///
+    /// A missing file is not a change, editors briefly remove it while saving.
+    pub fn check(&mut self) -> Option<Result<ServerConfigs, ConfigError>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!("Can't read {}: {}", self.path.display(), e);
+                return None;
+            }
+        };
+        if self.last_seen.as_deref() == Some(contents.as_str()) {
+            return None;
+        }
+
+        debug!("{} changed, reloading it", self.path.display());
+        let reloaded = ServerConfigs::parse(&self.path, &contents);
+        self.last_seen = Some(contents);
+        Some(reloaded)
+    }
 }
 
 impl ServerConfig {
@@ -41,11 +404,55 @@ impl ServerConfig {
         Self {
             id: Uuid::new_v4().to_string(),
             name,
+            transport: TransportConfig::Stdio,
             command,
             args,
             env,
             description,
             is_default,
+            restart: RestartPolicy::default(),
+            health: HealthCheckConfig::default(),
+        }
+    }
+
+    /// Create a configuration for a remote server reached over SSE or Streamable HTTP
+    pub fn remote(
+        name: String,
+        transport: TransportConfig,
+        description: Option<String>,
+        is_default: bool,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            transport,
+            command: String::new(),
+            args: Vec::new(),
+            env: HashMap::new(),
+            description,
+            is_default,
+            restart: RestartPolicy::default(),
+            health: HealthCheckConfig::default(),
+        }
+    }
+
+    /// Whether a running server has to be restarted to apply `new`, e.g. not when
+    /// only its name or description changed
+    pub fn requires_restart(&self, new: &ServerConfig) -> bool {
+        self.transport != new.transport
+            || self.command != new.command
+            || self.args != new.args
+            || self.env != new.env
+            || self.restart != new.restart
+            || self.health != new.health
+    }
+
+    /// Where the server is reached: its command line for stdio, otherwise its URL
+    pub fn endpoint(&self) -> String {
+        match self.transport.url() {
+            Some(url) => url.to_string(),
+            None if self.args.is_empty() => self.command.clone(),
+            None => format!("{} {}", self.command, self.args.join(" ")),
         }
     }
 
@@ -56,22 +463,25 @@ impl ServerConfig {
         Self {
             id: "filesystem".to_string(),
             name: "Filesystem MCP".to_string(),
+            transport: TransportConfig::Stdio,
             command: "docker".to_string(),
             args: vec![
                 "run".to_string(),
                 "-i".to_string(),
                 "--rm".to_string(),
                 "--mount".to_string(),
-                "type=bind,src=/Users/martinmaurer/Desktop,dst=/Users/martinmaurer/Desktop".to_string(),
+                "type=bind,src=${HOME}/Desktop,dst=${HOME}/Desktop".to_string(),
                 "--mount".to_string(),
-                "type=bind,src=/Users/martinmaurer/Projects,dst=/Users/martinmaurer/Projects".to_string(),
+                "type=bind,src=${HOME}/Projects,dst=${HOME}/Projects".to_string(),
                 "mcp/filesystem".to_string(),
-                "/Users/martinmaurer/Desktop".to_string(),
-                "/Users/martinmaurer/Projects".to_string(),
+                "${HOME}/Desktop".to_string(),
+                "${HOME}/Projects".to_string(),
             ],
             env,
             description: Some("Default filesystem MCP provider".to_string()),
             is_default: true,
+            restart: RestartPolicy::default(),
+            health: HealthCheckConfig::default(),
         }
     }
 }
@@ -80,6 +490,10 @@ impl Default for ServerConfigs {
     fn default() -> Self {
         Self {
             servers: Vec::new(),
+            profiles: Vec::new(),
+            approval_rules: Vec::new(),
+            argument_rules: Vec::new(),
+            sanitization: SanitizationConfig::default(),
         }
     }
 }
@@ -89,27 +503,284 @@ impl ServerConfigs {
     pub fn initialize_default() -> Self {
         Self {
             servers: vec![ServerConfig::default_filesystem()],
+            ..Self::default()
         }
     }
 
-    /// Load server configurations from a file
+    /// Load server configurations from a file and validate them
     #[instrument(level = "info", fields(config_path = %path.as_ref().display()))]
-    pub fn load_from_file<P: AsRef<Path> + std::fmt::Debug>(path: P) -> io::Result<Self> {
-        let mut file = File::open(path)?;
+    pub fn load_from_file<P: AsRef<Path> + std::fmt::Debug>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
         let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        
-        let configs: ServerConfigs = serde_json::from_str(&contents)?;
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| ConfigError::io(path, e))?;
+
+        Self::parse(path, &contents)
+    }
+
+    /// Parse and validate the contents of the configuration file at `path`
+    pub fn parse(path: &Path, contents: &str) -> Result<Self, ConfigError> {
+        let configs: ServerConfigs = serde_json::from_str(contents).map_err(|e| ConfigError::Parse {
+            path: path.to_path_buf(),
+            line: e.line(),
+            column: e.column(),
+            message: error_message(&e),
+        })?;
+
+        let problems = configs.validate();
+        if !problems.is_empty() {
+            return Err(ConfigError::Invalid {
+                path: path.to_path_buf(),
+                problems,
+            });
+        }
+        Ok(configs)
+    }
+
+    /// Load the configuration, creating it on first run
+    ///
+    /// A missing file is created from `servers.json` in the working directory, where
+    /// earlier versions kept it, or else from the default configuration.
+    pub fn load_or_init(path: &Path) -> Result<Self, ConfigError> {
+        match Self::load_from_file(path) {
+            Err(e) if e.is_not_found() => {}
+            loaded => return loaded,
+        }
+
+        let legacy = Path::new(LEGACY_CONFIG_PATH);
+        let configs = if legacy != path && legacy.exists() {
+            info!("Moving the server configuration from {} to {}", legacy.display(), path.display());
+            Self::load_from_file(legacy)?
+        } else {
+            info!("Creating the default server configuration in {}", path.display());
+            Self::initialize_default()
+        };
+        configs.save_to_file(path)?;
         Ok(configs)
     }
 
     /// Save server configurations to a file
+    ///
+    /// The file is replaced at once, so the config watcher never reads half of it.
     #[instrument(level = "info", fields(config_path = %path.as_ref().display()))]
-    pub fn save_to_file<P: AsRef<Path> + std::fmt::Debug>(&self, path: P) -> io::Result<()> {
-        let json = serde_json::to_string_pretty(self)?;
-        let mut file = File::create(path)?;
-        file.write_all(json.as_bytes())?;
-        Ok(())
+    pub fn save_to_file<P: AsRef<Path> + std::fmt::Debug>(&self, path: P) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self).map_err(|e| ConfigError::io(path, e.into()))?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| ConfigError::io(dir, e))?;
+        }
+
+        let temp_path = path.with_extension("json.tmp");
+        File::create(&temp_path)
+            .and_then(|mut file| file.write_all(json.as_bytes()))
+            .and_then(|()| fs::rename(&temp_path, path))
+            .map_err(|e| ConfigError::io(path, e))
+    }
+
+    /// Everything that keeps the configuration from being used, empty when it is valid
+    pub fn validate(&self) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+        let mut problem = |index: usize, field: &str, message: String| {
+            problems.push(ConfigProblem {
+                field: format!("servers[{}].{}", index, field),
+                message,
+            });
+        };
+
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for (index, server) in self.servers.iter().enumerate() {
+            if server.id.trim().is_empty() {
+                problem(index, "id", "must not be empty".to_string());
+            } else if let Some(first) = seen.get(server.id.as_str()) {
+                problem(index, "id", format!("\"{}\" is already used by servers[{}]", server.id, first));
+            } else {
+                seen.insert(&server.id, index);
+            }
+
+            if server.name.trim().is_empty() {
+                problem(index, "name", "must not be empty".to_string());
+            }
+
+            match &server.transport {
+                TransportConfig::Stdio => {
+                    if server.command.trim().is_empty() {
+                        problem(index, "command", "must not be empty for a stdio server".to_string());
+                    }
+                }
+                TransportConfig::Sse { url, .. } | TransportConfig::Http { url, .. } => {
+                    let url = url.trim();
+                    if url.is_empty() {
+                        problem(index, "transport.url", "must not be empty".to_string());
+                    } else if !(url.starts_with("http://") || url.starts_with("https://")) {
+                        problem(index, "transport.url", format!("\"{}\" is not an http or https URL", url));
+                    }
+                }
+            }
+
+            if server.health.enabled && server.health.timeout_ms == 0 {
+                problem(index, "health.timeout_ms", "must be greater than 0".to_string());
+            }
+
+            let fields = std::iter::once(("command".to_string(), &server.command))
+                .chain(server.args.iter().enumerate().map(|(i, arg)| (format!("args[{}]", i), arg)))
+                .chain(server.env.iter().map(|(key, value)| (format!("env.{}", key), value)));
+            for (field, value) in fields {
+                for variable in crate::variables::unknown_variables(value) {
+                    problem(index, &field, format!("{} is not a known variable", variable));
+                }
+            }
+        }
+
+        let mut names: HashMap<&str, usize> = HashMap::new();
+        for (index, profile) in self.profiles.iter().enumerate() {
+            let mut profile_problem = |field: &str, message: String| {
+                problems.push(ConfigProblem {
+                    field: format!("profiles[{}].{}", index, field),
+                    message,
+                });
+            };
+            if profile.name.trim().is_empty() {
+                profile_problem("name", "must not be empty".to_string());
+            } else if let Some(first) = names.get(profile.name.as_str()) {
+                profile_problem("name", format!("\"{}\" is already used by profiles[{}]", profile.name, first));
+            } else {
+                names.insert(&profile.name, index);
+            }
+            for (i, id) in profile.servers.iter().enumerate() {
+                if self.get_by_id(id).is_none() {
+                    profile_problem(&format!("servers[{}]", i), format!("\"{}\" is not a configured server", id));
+                }
+            }
+        }
+
+        for (index, rule) in self.approval_rules.iter().enumerate() {
+            let mut rule_problem = |field: &str, message: String| {
+                problems.push(ConfigProblem {
+                    field: format!("approval_rules[{}].{}", index, field),
+                    message,
+                });
+            };
+            if let Some(server) = &rule.server {
+                if self.get_by_id(server).is_none() {
+                    rule_problem("server", format!("\"{}\" is not a configured server", server));
+                }
+            }
+            if rule.tool.as_deref().is_some_and(|tool| tool.trim().is_empty()) {
+                rule_problem("tool", "must not be empty, leave it out to match every tool".to_string());
+            }
+        }
+
+        for (index, rule) in self.argument_rules.iter().enumerate() {
+            let mut rule_problem = |field: &str, message: String| {
+                problems.push(ConfigProblem {
+                    field: format!("argument_rules[{}].{}", index, field),
+                    message,
+                });
+            };
+            if let Some(server) = &rule.server {
+                if self.get_by_id(server).is_none() {
+                    rule_problem("server", format!("\"{}\" is not a configured server", server));
+                }
+            }
+            for (field, message) in rule.problems() {
+                rule_problem(&field, message);
+            }
+        }
+
+        if self.sanitization.max_string_length == Some(0) {
+            problems.push(ConfigProblem {
+                field: "sanitization.max_string_length".to_string(),
+                message: "must be at least 1, leave it out to keep strings whole".to_string(),
+            });
+        }
+        for (index, tool) in self.sanitization.tools.iter().enumerate() {
+            let mut tool_problem = |field: &str, message: String| {
+                problems.push(ConfigProblem {
+                    field: format!("sanitization.tools[{}].{}", index, field),
+                    message,
+                });
+            };
+            if let Some(server) = &tool.server {
+                if self.get_by_id(server).is_none() {
+                    tool_problem("server", format!("\"{}\" is not a configured server", server));
+                }
+            }
+            if tool.tool.trim().is_empty() {
+                tool_problem("tool", "must not be empty, use * for every tool".to_string());
+            }
+        }
+
+        problems
+    }
+
+    /// The profile named `name`
+    pub fn get_profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    /// Whether a server is enabled in `profile`, every server is without a profile
+    pub fn is_enabled(&self, server_id: &str, profile: Option<&str>) -> bool {
+        match profile.and_then(|name| self.get_profile(name)) {
+            Some(profile) => profile.servers.iter().any(|id| id == server_id),
+            None => true,
+        }
+    }
+
+    /// The servers enabled in `profile`, all of them without a profile or for one that doesn't exist
+    pub fn for_profile(&self, profile: Option<&str>) -> ServerConfigs {
+        ServerConfigs {
+            servers: self
+                .servers
+                .iter()
+                .filter(|server| self.is_enabled(&server.id, profile))
+                .cloned()
+                .collect(),
+            profiles: self.profiles.clone(),
+            approval_rules: self.approval_rules.clone(),
+            argument_rules: self.argument_rules.clone(),
+            sanitization: self.sanitization.clone(),
+        }
+    }
+
+    /// Add or remove a server from a profile
+    pub fn set_enabled(&mut self, profile: &str, server_id: &str, enabled: bool) {
+        if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == profile) {
+            profile.servers.retain(|id| id != server_id);
+            if enabled {
+                profile.servers.push(server_id.to_string());
+            }
+        }
+    }
+
+    /// What changed from this configuration to `new`
+    pub fn diff(&self, new: &ServerConfigs) -> ConfigDiff {
+        let old_ids: HashSet<&str> = self.servers.iter().map(|server| server.id.as_str()).collect();
+        let new_ids: HashSet<&str> = new.servers.iter().map(|server| server.id.as_str()).collect();
+
+        ConfigDiff {
+            added: new
+                .servers
+                .iter()
+                .filter(|server| !old_ids.contains(server.id.as_str()))
+                .cloned()
+                .collect(),
+            removed: self
+                .servers
+                .iter()
+                .filter(|server| !new_ids.contains(server.id.as_str()))
+                .map(|server| server.id.clone())
+                .collect(),
+            changed: new
+                .servers
+                .iter()
+                .filter(|server| {
+                    self.get_by_id(&server.id)
+                        .map_or(false, |old| old.requires_restart(server))
+                })
+                .cloned()
+                .collect(),
+        }
     }
 
     /// Get a server configuration by ID
@@ -167,9 +838,15 @@ impl ServerConfigs {
             return false;
         }
         
-        // Find and remove
+        // Find and remove, also from the profiles and with the rules and sanitization for it
         if let Some(index) = self.servers.iter().position(|s| s.id == id) {
             self.servers.remove(index);
+            for profile in &mut self.profiles {
+                profile.servers.retain(|server_id| server_id != id);
+            }
+            self.approval_rules.retain(|rule| rule.server.as_deref() != Some(id));
+            self.argument_rules.retain(|rule| rule.server.as_deref() != Some(id));
+            self.sanitization.tools.retain(|tool| tool.server.as_deref() != Some(id));
             return true;
         }
         
@@ -188,4 +865,306 @@ impl ServerConfigs {
             self.servers[0].is_default = true;
         }
     }
-}
\ No newline at end of file
+}
+
+/// A serde error without the location it appends, which is reported separately
+fn error_message(e: &serde_json::Error) -> String {
+    let message = e.to_string();
+    let suffix = format!(" at line {} column {}", e.line(), e.column());
+    message.strip_suffix(&suffix).unwrap_or(&message).to_string()
+}
+
+/// A server in the `mcpServers` format shared by Claude Desktop, Cursor and other MCP clients
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct McpServerEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// URL of a remote server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// `stdio`, `sse`, or `http` / `streamable-http`, guessed when missing
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub transport: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+/// The `{"mcpServers": {"name": {...}}}` document other MCP clients read and write
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct McpServersFile {
+    #[serde(rename = "mcpServers")]
+    pub mcp_servers: BTreeMap<String, McpServerEntry>,
+}
+
+impl McpServerEntry {
+    /// The entry as an mDesk server, using its name as id and name
+    pub fn to_config(&self, name: &str) -> Result<ServerConfig, ImportError> {
+        let entry_error = |message: String| ImportError::Entry {
+            name: name.to_string(),
+            message,
+        };
+        let command = self.command.as_deref().map(str::trim).filter(|command| !command.is_empty());
+        let url = self.url.as_deref().map(str::trim).filter(|url| !url.is_empty());
+
+        let transport = match (command, url, self.transport.as_deref()) {
+            (Some(_), _, None | Some("stdio")) => TransportConfig::Stdio,
+            (_, Some(url), Some("sse")) => TransportConfig::Sse {
+                url: url.to_string(),
+                headers: self.headers.clone().into_iter().collect(),
+            },
+            (_, Some(url), Some("http" | "streamable-http")) => TransportConfig::Http {
+                url: url.to_string(),
+                headers: self.headers.clone().into_iter().collect(),
+            },
+            // Servers still speaking the older SSE transport usually serve it at /sse
+            (None, Some(url), None) if url.trim_end_matches('/').ends_with("/sse") => TransportConfig::Sse {
+                url: url.to_string(),
+                headers: self.headers.clone().into_iter().collect(),
+            },
+            (None, Some(url), None) => TransportConfig::Http {
+                url: url.to_string(),
+                headers: self.headers.clone().into_iter().collect(),
+            },
+            (None, None, _) => return Err(entry_error("has neither a command nor a url".to_string())),
+            (_, _, Some(other)) => {
+                return Err(entry_error(format!("has transport type \"{}\" without what it needs", other)))
+            }
+        };
+
+        let (command, args, env) = match transport {
+            TransportConfig::Stdio => (
+                command.unwrap_or_default().to_string(),
+                self.args.clone(),
+                self.env.clone().into_iter().collect(),
+            ),
+            _ => (String::new(), Vec::new(), HashMap::new()),
+        };
+        Ok(ServerConfig {
+            id: name.to_string(),
+            name: name.to_string(),
+            transport,
+            command,
+            args,
+            env,
+            description: None,
+            is_default: false,
+            restart: RestartPolicy::default(),
+            health: HealthCheckConfig::default(),
+        })
+    }
+
+    /// A server in the shared format, which has no place for its restart and health settings
+    pub fn from_config(config: &ServerConfig) -> Self {
+        match &config.transport {
+            TransportConfig::Stdio => Self {
+                command: Some(config.command.clone()),
+                args: config.args.clone(),
+                env: config.env.clone().into_iter().collect(),
+                ..Self::default()
+            },
+            TransportConfig::Sse { url, headers } | TransportConfig::Http { url, headers } => Self {
+                url: Some(url.clone()),
+                transport: Some(config.transport.label().to_string()),
+                headers: headers.clone().into_iter().collect(),
+                ..Self::default()
+            },
+        }
+    }
+}
+
+/// Errors that can occur while reading servers to import
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum ImportError {
+    #[error("Line {line}, column {column}: {message}")]
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
+    #[error("Expected an \"mcpServers\" object or a \"servers\" list")]
+    UnknownFormat,
+
+    #[error("Server \"{name}\" {message}")]
+    Entry { name: String, message: String },
+
+    #[error("{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    Invalid(Vec<ConfigProblem>),
+
+    #[error("There are no servers to import")]
+    Empty,
+}
+
+/// Read servers to import from an `mcpServers` document or from mDesk's own `servers` list
+pub fn parse_import(json: &str) -> Result<Vec<ServerConfig>, ImportError> {
+    let parse_error = |e: serde_json::Error| ImportError::Parse {
+        line: e.line(),
+        column: e.column(),
+        message: error_message(&e),
+    };
+    let document: serde_json::Value = serde_json::from_str(json).map_err(parse_error)?;
+
+    let servers = if let Some(entries) = document.get("mcpServers").and_then(|entries| entries.as_object()) {
+        entries
+            .iter()
+            .map(|(name, entry)| {
+                let entry: McpServerEntry = serde_json::from_value(entry.clone()).map_err(|e| ImportError::Entry {
+                    name: name.clone(),
+                    message: format!("is invalid: {}", e),
+                })?;
+                entry.to_config(name)
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    } else if document.get("servers").map_or(false, |servers| servers.is_array()) {
+        // Parsed from the text again, so errors point at the right line
+        serde_json::from_str::<ServerConfigs>(json).map_err(parse_error)?.servers
+    } else {
+        return Err(ImportError::UnknownFormat);
+    };
+
+    if servers.is_empty() {
+        return Err(ImportError::Empty);
+    }
+    let problems = ServerConfigs {
+        servers: servers.clone(),
+        ..ServerConfigs::default()
+    }
+    .validate();
+    if !problems.is_empty() {
+        return Err(ImportError::Invalid(problems));
+    }
+    Ok(servers)
+}
+
+/// What happens to an imported server whose id is already used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictResolution {
+    /// Keep the existing server
+    #[default]
+    Skip,
+    /// Replace the existing server with the imported one
+    Replace,
+    /// Add the imported server under a new id
+    KeepBoth,
+}
+
+/// A server in the import preview
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportItem {
+    pub config: ServerConfig,
+    /// Whether the server is imported at all
+    pub selected: bool,
+    /// Whether a server with the same id already exists
+    pub conflicts: bool,
+    /// What happens if it does
+    pub resolution: ConflictResolution,
+}
+
+/// Ids of the servers an import added, replaced and skipped
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub added: Vec<String>,
+    pub replaced: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+impl fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Imported {} server{}",
+            self.added.len() + self.replaced.len(),
+            if self.added.len() + self.replaced.len() == 1 { "" } else { "s" }
+        )?;
+        if !self.replaced.is_empty() {
+            write!(f, ", replaced {}", self.replaced.join(", "))?;
+        }
+        if !self.skipped.is_empty() {
+            write!(f, ", skipped {}", self.skipped.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl ServerConfigs {
+    /// Preview of importing `servers`, marking the ones whose id is already used
+    pub fn preview_import(&self, servers: Vec<ServerConfig>) -> Vec<ImportItem> {
+        servers
+            .into_iter()
+            .map(|config| ImportItem {
+                conflicts: self.get_by_id(&config.id).is_some(),
+                config,
+                selected: true,
+                resolution: ConflictResolution::default(),
+            })
+            .collect()
+    }
+
+    /// Add the selected servers of an import preview, resolving id conflicts as chosen
+    pub fn apply_import(&mut self, items: &[ImportItem]) -> ImportSummary {
+        let mut summary = ImportSummary::default();
+        for item in items {
+            let mut config = item.config.clone();
+            let existing = self.servers.iter().position(|server| server.id == config.id);
+            match (existing, item.resolution) {
+                _ if !item.selected => summary.skipped.push(config.id),
+                (None, _) => {
+                    config.is_default = false;
+                    summary.added.push(config.id.clone());
+                    self.add_server(config);
+                }
+                (Some(_), ConflictResolution::Skip) => summary.skipped.push(config.id),
+                (Some(index), ConflictResolution::Replace) => {
+                    // The replaced server keeps its place and whether it is the default
+                    config.is_default = self.servers[index].is_default;
+                    summary.replaced.push(config.id.clone());
+                    self.servers[index] = config;
+                }
+                (Some(_), ConflictResolution::KeepBoth) => {
+                    config.id = self.unused_id(&config.id);
+                    config.is_default = false;
+                    summary.added.push(config.id.clone());
+                    self.add_server(config);
+                }
+            }
+        }
+        summary
+    }
+
+    /// `id`, or `id-2`, `id-3`... if it is taken
+    fn unused_id(&self, id: &str) -> String {
+        (2..)
+            .map(|n| format!("{}-{}", id, n))
+            .find(|candidate| self.get_by_id(candidate).is_none())
+            .unwrap_or_else(|| Uuid::new_v4().to_string())
+    }
+
+    /// The servers in the `mcpServers` format, keyed by id
+    pub fn to_mcp_servers(&self) -> McpServersFile {
+        McpServersFile {
+            mcp_servers: self
+                .servers
+                .iter()
+                .map(|server| (server.id.clone(), McpServerEntry::from_config(server)))
+                .collect(),
+        }
+    }
+
+    /// The servers as an `mcpServers` document other MCP clients can read
+    pub fn to_mcp_servers_json(&self) -> String {
+        // A map of strings and string lists always serializes
+        serde_json::to_string_pretty(&self.to_mcp_servers()).unwrap_or_default()
+    }
+
+    /// Write the servers as a timestamped `mcpServers` file in `dir` and return its path
+    pub fn write_mcp_servers(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("mcp-servers-{}.json", chrono::Utc::now().format("%Y%m%d-%H%M%S")));
+        fs::write(&path, self.to_mcp_servers_json())?;
+        Ok(path)
+    }
+}
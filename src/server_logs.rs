@@ -0,0 +1,238 @@
+//! Output MCP servers write to stderr, kept per server in a bounded ring buffer
+//! for the log viewer and appended to `~/.mdesk/logs/servers/<id>.log`.
+
+use chrono::{DateTime, Local};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tracing::warn;
+
+use crate::secrets::SecretStore;
+
+/// Lines kept in memory per server
+pub const DEFAULT_CAPACITY: usize = 1000;
+
+/// Longer lines are cut off so a single line can't exhaust the buffer
+pub const MAX_LINE_LEN: usize = 4096;
+
+/// A line a server wrote to stderr
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    pub timestamp: DateTime<Local>,
+    pub text: String,
+    /// Marks where a new process of the server started
+    pub is_start: bool,
+}
+
+impl LogLine {
+    /// Whether the line contains `query`, ignoring case
+    pub fn matches(&self, query: &str) -> bool {
+        query.is_empty() || self.text.to_lowercase().contains(&query.to_lowercase())
+    }
+}
+
+/// Ring buffer of the most recent lines of one server
+#[derive(Debug, Clone)]
+struct ServerLog {
+    lines: VecDeque<LogLine>,
+    /// Lines dropped because the buffer was full
+    dropped: usize,
+}
+
+#[derive(Default)]
+struct LogFiles {
+    /// Open log file per server, `None` once opening it failed
+    files: HashMap<String, Option<File>>,
+}
+
+/// Stderr output of every server, shared between the transports writing it and the viewer
+#[derive(Clone)]
+pub struct ServerLogs {
+    logs: Arc<Mutex<HashMap<String, ServerLog>>>,
+    files: Arc<Mutex<LogFiles>>,
+    dir: Option<PathBuf>,
+    capacity: usize,
+    changes: Arc<watch::Sender<u64>>,
+    /// Secrets masked in every line, servers may print the keys they were given
+    secrets: Option<SecretStore>,
+}
+
+impl Default for ServerLogs {
+    /// Logs written to `~/.mdesk/logs/servers`
+    fn default() -> Self {
+        Self::new(Some(crate::logging::get_server_log_directory()), DEFAULT_CAPACITY)
+    }
+}
+
+impl ServerLogs {
+    /// Keep `capacity` lines per server, and append every line to a file in `dir` if given
+    pub fn new(dir: Option<PathBuf>, capacity: usize) -> Self {
+        let (changes, _) = watch::channel(0);
+        Self {
+            logs: Arc::new(Mutex::new(HashMap::new())),
+            files: Arc::new(Mutex::new(LogFiles::default())),
+            dir,
+            capacity: capacity.max(1),
+            changes: Arc::new(changes),
+            secrets: None,
+        }
+    }
+
+    /// Mask the values of `secrets` in every line before it is kept or written
+    pub fn with_secrets(mut self, secrets: SecretStore) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Logs that are only kept in memory
+    pub fn in_memory() -> Self {
+        Self::new(None, DEFAULT_CAPACITY)
+    }
+
+    /// Receiver that is notified whenever a line is added or a log is cleared
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    /// File a server's log is written to
+    pub fn log_path(&self, server_id: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.log", file_name(server_id))))
+    }
+
+    /// Record that a new process of a server starts, separating its output from the last run
+    pub fn start_session(&self, server_id: &str, command_line: &str) {
+        self.push(server_id, format!("Starting {}", command_line), true);
+    }
+
+    /// Add a line a server wrote to stderr
+    pub fn append(&self, server_id: &str, line: &str) {
+        self.push(server_id, line.to_string(), false);
+    }
+
+    fn push(&self, server_id: &str, mut text: String, is_start: bool) {
+        if let Some(secrets) = &self.secrets {
+            text = secrets.mask(&text);
+        }
+        if text.len() > MAX_LINE_LEN {
+            let mut end = MAX_LINE_LEN;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text.truncate(end);
+            text.push_str("...");
+        }
+        let line = LogLine {
+            timestamp: Local::now(),
+            text,
+            is_start,
+        };
+
+        self.write_to_file(server_id, &line);
+
+        {
+            let mut logs = self.logs.lock().unwrap();
+            let log = logs.entry(server_id.to_string()).or_insert_with(|| ServerLog {
+                lines: VecDeque::new(),
+                dropped: 0,
+            });
+            if log.lines.len() == self.capacity {
+                log.lines.pop_front();
+                log.dropped += 1;
+            }
+            log.lines.push_back(line);
+        }
+        self.changes.send_modify(|revision| *revision += 1);
+    }
+
+    fn write_to_file(&self, server_id: &str, line: &LogLine) {
+        let Some(path) = self.log_path(server_id) else { return };
+        let mut files = self.files.lock().unwrap();
+        let file = files
+            .files
+            .entry(server_id.to_string())
+            .or_insert_with(|| match open_log_file(&path) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    warn!("Can't write server log {}: {}", path.display(), e);
+                    None
+                }
+            });
+
+        if let Some(handle) = file {
+            let timestamp = line.timestamp.format("%Y-%m-%d %H:%M:%S%.3f");
+            let written = if line.is_start {
+                writeln!(handle, "[{}] --- {} ---", timestamp, line.text)
+            } else {
+                writeln!(handle, "[{}] {}", timestamp, line.text)
+            };
+            if let Err(e) = written {
+                warn!("Can't write server log {}: {}", path.display(), e);
+                *file = None;
+            }
+        }
+    }
+
+    /// Lines currently kept for a server, oldest first
+    pub fn lines(&self, server_id: &str) -> Vec<LogLine> {
+        self.logs
+            .lock()
+            .unwrap()
+            .get(server_id)
+            .map(|log| log.lines.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Lines of a server containing `query`, ignoring case
+    pub fn search(&self, server_id: &str, query: &str) -> Vec<LogLine> {
+        self.lines(server_id).into_iter().filter(|line| line.matches(query)).collect()
+    }
+
+    /// The last `count` lines of a server, e.g. to explain why it failed
+    pub fn tail(&self, server_id: &str, count: usize) -> Vec<LogLine> {
+        let lines = self.lines(server_id);
+        let skip = lines.len().saturating_sub(count);
+        lines.into_iter().skip(skip).collect()
+    }
+
+    /// Number of lines that no longer fit in a server's buffer
+    pub fn dropped(&self, server_id: &str) -> usize {
+        self.logs.lock().unwrap().get(server_id).map_or(0, |log| log.dropped)
+    }
+
+    /// Forget the lines kept in memory for a server, the log file is kept
+    pub fn clear(&self, server_id: &str) {
+        if self.logs.lock().unwrap().remove(server_id).is_some() {
+            self.changes.send_modify(|revision| *revision += 1);
+        }
+    }
+
+    /// Ids of the servers that have written anything, sorted
+    pub fn server_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.logs.lock().unwrap().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+}
+
+fn open_log_file(path: &Path) -> std::io::Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Server ids are user defined, so anything that isn't safe in a file name is replaced
+fn file_name(server_id: &str) -> String {
+    let name: String = server_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if name.is_empty() {
+        "_".to_string()
+    } else {
+        name
+    }
+}
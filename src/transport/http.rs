@@ -0,0 +1,199 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use mcp_client::transport::{Error, TransportHandle};
+use mcp_client::Transport;
+use mcp_core::protocol::JsonRpcMessage;
+use reqwest::header::{HeaderMap, ACCEPT, CONTENT_TYPE};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+use super::{header_map, response_id, ClosedSignal};
+use crate::event_stream::SseParser;
+
+/// Header carrying the session the server assigned during initialization
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Streamable HTTP transport: each message is POSTed to the server's endpoint
+/// and the response comes back either as JSON or as an event stream
+pub struct StreamableHttpTransport {
+    url: String,
+    headers: HashMap<String, String>,
+    client: reqwest::Client,
+    session_id: Arc<Mutex<Option<String>>>,
+    closed: ClosedSignal,
+}
+
+impl StreamableHttpTransport {
+    pub fn new<S: Into<String>>(url: S, headers: HashMap<String, String>) -> Self {
+        Self {
+            url: url.into(),
+            headers,
+            client: reqwest::Client::new(),
+            session_id: Arc::new(Mutex::new(None)),
+            closed: ClosedSignal::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for StreamableHttpTransport {
+    type Handle = StreamableHttpTransportHandle;
+
+    async fn start(&self) -> Result<Self::Handle, Error> {
+        Ok(StreamableHttpTransportHandle {
+            url: self.url.clone(),
+            headers: header_map(&self.headers)?,
+            client: self.client.clone(),
+            session_id: self.session_id.clone(),
+            closed: self.closed.clone(),
+        })
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        self.start().await?.end_session().await;
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct StreamableHttpTransportHandle {
+    url: String,
+    headers: HeaderMap,
+    client: reqwest::Client,
+    session_id: Arc<Mutex<Option<String>>>,
+    /// Closed once the server can't be reached anymore, there is no stream that could end
+    closed: ClosedSignal,
+}
+
+impl StreamableHttpTransportHandle {
+    /// Closed once the server couldn't be reached or the session was ended
+    pub fn closed_signal(&self) -> ClosedSignal {
+        self.closed.clone()
+    }
+
+    /// End the session the server handed out, servers expect this to happen explicitly
+    pub async fn end_session(&self) {
+        self.closed.close("the session was ended");
+        let session_id = self.session_id.lock().unwrap().take();
+        if let Some(session_id) = session_id {
+            let result = self
+                .client
+                .delete(&self.url)
+                .headers(self.headers.clone())
+                .header(SESSION_HEADER, &session_id)
+                .send()
+                .await;
+            if let Err(e) = result {
+                warn!("Failed to end session {} at {}: {}", session_id, self.url, e);
+            }
+        }
+    }
+
+    async fn post(&self, message: &JsonRpcMessage) -> Result<reqwest::Response, Error> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(message);
+
+        let session_id = self.session_id.lock().unwrap().clone();
+        if let Some(session_id) = session_id {
+            request = request.header(SESSION_HEADER, session_id);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            if e.is_connect() {
+                self.closed.close(format!("can't reach the server: {}", e));
+            }
+            Error::SseConnection(format!("{}: {}", self.url, e))
+        })?;
+
+        if let Some(session_id) = response.headers().get(SESSION_HEADER).and_then(|v| v.to_str().ok()) {
+            *self.session_id.lock().unwrap() = Some(session_id.to_string());
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(Error::HttpError {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        Ok(response)
+    }
+
+    /// Read an event stream until the response to `request_id` arrives
+    async fn read_event_stream(response: reqwest::Response, request_id: u64) -> Result<JsonRpcMessage, Error> {
+        let mut parser = SseParser::new();
+        let mut body = response.bytes_stream();
+
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.map_err(|e| Error::SseConnection(e.to_string()))?;
+            for event in parser.push(&chunk) {
+                if event.event != "message" {
+                    continue;
+                }
+                let message: JsonRpcMessage = serde_json::from_str(&event.data)?;
+                if response_id(&message) == Some(request_id) {
+                    return Ok(message);
+                }
+                // Server requests and notifications on the stream aren't handled yet
+                debug!("Ignoring message without matching id on response stream");
+            }
+        }
+
+        Err(Error::ChannelClosed)
+    }
+
+    /// Pick the response to `request_id` out of a JSON body, which may be a batch
+    fn read_json(body: &str, request_id: u64) -> Result<JsonRpcMessage, Error> {
+        let messages: Vec<JsonRpcMessage> = if body.trim_start().starts_with('[') {
+            serde_json::from_str(body)?
+        } else {
+            vec![serde_json::from_str(body)?]
+        };
+
+        messages
+            .into_iter()
+            .find(|message| response_id(message) == Some(request_id))
+            .ok_or_else(|| Error::SseConnection(format!("no response to request {}", request_id)))
+    }
+}
+
+#[async_trait]
+impl TransportHandle for StreamableHttpTransportHandle {
+    async fn send(&self, message: JsonRpcMessage) -> Result<JsonRpcMessage, Error> {
+        let request_id = match &message {
+            JsonRpcMessage::Request(request) => request.id,
+            JsonRpcMessage::Notification(_) => None,
+            _ => return Err(Error::UnsupportedMessage),
+        };
+
+        let response = self.post(&message).await?;
+
+        // Notifications are only acknowledged, there is nothing to wait for
+        let Some(request_id) = request_id else {
+            return Ok(JsonRpcMessage::Nil);
+        };
+
+        let is_event_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map_or(false, |v| v.starts_with("text/event-stream"));
+
+        if is_event_stream {
+            Self::read_event_stream(response, request_id).await
+        } else {
+            let body = response
+                .text()
+                .await
+                .map_err(|e| Error::SseConnection(e.to_string()))?;
+            Self::read_json(&body, request_id)
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Transports for reaching MCP servers over stdio, HTTP+SSE and Streamable HTTP
+
+pub mod http;
+pub mod process;
+pub mod sse;
+
+use async_trait::async_trait;
+use mcp_client::transport::{Error as TransportError, TransportHandle};
+use mcp_client::{ClientCapabilities, ClientInfo, Error as McpError, McpClient, McpService, Transport};
+use mcp_core::protocol::JsonRpcMessage;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::watch;
+use tower::timeout::Timeout;
+use tower::Layer;
+use tracing::info;
+
+use crate::inspector::{Inspector, InspectorService};
+use crate::secrets::SecretError;
+use crate::variables::VariableError;
+use crate::server_config::{ServerConfig, TransportConfig};
+use crate::server_logs::ServerLogs;
+
+pub use http::{StreamableHttpTransport, StreamableHttpTransportHandle};
+pub use process::{ProcessTransport, ProcessTransportHandle, ShutdownOutcome};
+pub use sse::{SseTransport, SseTransportHandle};
+
+/// Timeout applied to every request sent to a server
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Client for a connected server, whatever transport it uses, with its traffic recorded
+pub type ServerClient = McpClient<InspectorService<Timeout<McpService<AnyTransportHandle>>>>;
+
+/// Errors that can occur while connecting to a server
+#[derive(Debug, Error)]
+pub enum ConnectError {
+    #[error("Failed to start: {0}")]
+    Start(TransportError),
+
+    #[error("Failed to initialize: {0}")]
+    Initialize(McpError),
+
+    #[error("Can't resolve secrets: {0}")]
+    Secret(SecretError),
+
+    #[error("Can't expand variables: {0}")]
+    Variable(VariableError),
+}
+
+/// Handle of one of the supported transports
+#[derive(Clone)]
+pub enum AnyTransportHandle {
+    Stdio(ProcessTransportHandle),
+    Sse(SseTransportHandle),
+    Http(StreamableHttpTransportHandle),
+}
+
+#[async_trait]
+impl TransportHandle for AnyTransportHandle {
+    async fn send(&self, message: JsonRpcMessage) -> Result<JsonRpcMessage, TransportError> {
+        match self {
+            AnyTransportHandle::Stdio(handle) => handle.send(message).await,
+            AnyTransportHandle::Sse(handle) => handle.send(message).await,
+            AnyTransportHandle::Http(handle) => handle.send(message).await,
+        }
+    }
+}
+
+impl AnyTransportHandle {
+    /// Closed when the connection to the server is lost
+    pub fn closed_signal(&self) -> ClosedSignal {
+        match self {
+            AnyTransportHandle::Stdio(handle) => handle.closed_signal(),
+            AnyTransportHandle::Sse(handle) => handle.closed_signal(),
+            AnyTransportHandle::Http(handle) => handle.closed_signal(),
+        }
+    }
+
+    /// Shut the server down: stop a local process, or end the session with a remote server
+    pub async fn shutdown(&self) -> ShutdownOutcome {
+        match self {
+            AnyTransportHandle::Stdio(handle) => handle.shutdown().await,
+            AnyTransportHandle::Sse(handle) => {
+                handle.close();
+                ShutdownOutcome::Disconnected
+            }
+            AnyTransportHandle::Http(handle) => {
+                handle.end_session().await;
+                ShutdownOutcome::Disconnected
+            }
+        }
+    }
+}
+
+/// Tells when the connection of a transport went away, and why
+///
+/// Clones share the signal, the first reason given is kept.
+#[derive(Clone)]
+pub struct ClosedSignal {
+    reason: Arc<watch::Sender<Option<String>>>,
+}
+
+impl Default for ClosedSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClosedSignal {
+    pub fn new() -> Self {
+        let (reason, _) = watch::channel(None);
+        Self { reason: Arc::new(reason) }
+    }
+
+    /// Report the connection lost, later reports are ignored
+    pub fn close(&self, reason: impl Into<String>) {
+        let reason = reason.into();
+        self.reason.send_if_modified(|current| {
+            if current.is_some() {
+                return false;
+            }
+            *current = Some(reason);
+            true
+        });
+    }
+
+    /// Why the connection was lost, `None` while it is still there
+    pub fn reason(&self) -> Option<String> {
+        self.reason.borrow().clone()
+    }
+
+    /// Wait until the connection is lost, returning why
+    pub async fn closed(&self) -> String {
+        let mut receiver = self.reason.subscribe();
+        loop {
+            if let Some(reason) = receiver.borrow_and_update().clone() {
+                return reason;
+            }
+            // The sender lives as long as `self`, so this only waits for the next change
+            let _ = receiver.changed().await;
+        }
+    }
+}
+
+/// Start the transport a server is configured with, capturing a local server's stderr in `logs`
+pub async fn start_transport(config: &ServerConfig, logs: &ServerLogs) -> Result<AnyTransportHandle, TransportError> {
+    match &config.transport {
+        TransportConfig::Stdio => {
+            let transport = ProcessTransport::new(&config.command, config.args.clone(), config.env.clone())
+                .with_log(logs.clone(), &config.id);
+            Ok(AnyTransportHandle::Stdio(transport.start().await?))
+        }
+        TransportConfig::Sse { url, headers } => {
+            let transport = SseTransport::new(url.clone(), headers.clone());
+            Ok(AnyTransportHandle::Sse(transport.start().await?))
+        }
+        TransportConfig::Http { url, headers } => {
+            let transport = StreamableHttpTransport::new(url.clone(), headers.clone());
+            Ok(AnyTransportHandle::Http(transport.start().await?))
+        }
+    }
+}
+
+/// Start a server's transport and run the MCP initialization handshake
+///
+/// The server's stderr and traffic are only kept in memory, `connect_with_handle` takes
+/// the logs and inspector to use.
+pub async fn connect(config: &ServerConfig) -> Result<ServerClient, ConnectError> {
+    connect_with_handle(config, &ServerLogs::in_memory(), &Inspector::default())
+        .await
+        .map(|(client, _)| client)
+}
+
+/// Connect to a server, also returning the transport handle that shuts it down again
+///
+/// Everything sent to and received from the server is recorded in `inspector`,
+/// including the initialization handshake.
+pub async fn connect_with_handle(
+    config: &ServerConfig,
+    logs: &ServerLogs,
+    inspector: &Inspector,
+) -> Result<(ServerClient, AnyTransportHandle), ConnectError> {
+    info!("Connecting to {} over {}", config.name, config.transport.label());
+
+    let handle = start_transport(config, logs).await.map_err(ConnectError::Start)?;
+    // Outside the timeout, so requests that time out are recorded as failed
+    let service = inspector
+        .layer(&config.id)
+        .layer(McpService::with_timeout(handle.clone(), REQUEST_TIMEOUT));
+    let mut client = McpClient::new(service);
+
+    let initialized = client
+        .initialize(
+            ClientInfo {
+                name: "mDesk".to_string(),
+                version: "0.1.0".to_string(),
+            },
+            ClientCapabilities::default(),
+        )
+        .await;
+    if let Err(e) = initialized {
+        // Don't leave a server behind that started but can't be used
+        handle.shutdown().await;
+        return Err(ConnectError::Initialize(e));
+    }
+
+    Ok((client, handle))
+}
+
+/// Id of the request a response or error answers
+pub(crate) fn response_id(message: &JsonRpcMessage) -> Option<u64> {
+    match message {
+        JsonRpcMessage::Response(response) => response.id,
+        JsonRpcMessage::Error(error) => error.id,
+        _ => None,
+    }
+}
+
+/// Turn configured headers into a header map, rejecting names or values HTTP doesn't allow
+pub(crate) fn header_map(headers: &HashMap<String, String>) -> Result<HeaderMap, TransportError> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|e| TransportError::SseConnection(format!("invalid header name {:?}: {}", name, e)))?;
+        let header_value = HeaderValue::from_str(value)
+            .map_err(|e| TransportError::SseConnection(format!("invalid value for header {}: {}", name, e)))?;
+        map.insert(header_name, header_value);
+    }
+    Ok(map)
+}
@@ -0,0 +1,429 @@
+use async_trait::async_trait;
+use mcp_client::transport::{Error, TransportHandle};
+use mcp_client::Transport;
+use mcp_core::protocol::JsonRpcMessage;
+use std::collections::HashMap;
+use std::fmt;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
+use tokio::sync::{oneshot, Mutex as AsyncMutex};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::{response_id, ClosedSignal};
+use crate::server_logs::ServerLogs;
+
+/// How long a server gets to exit on its own once its input is closed
+pub const EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a server gets to exit after SIGTERM before it is killed
+pub const TERMINATE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How often a shutting down server is checked for having exited
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+type PendingRequests = Arc<Mutex<HashMap<u64, oneshot::Sender<JsonRpcMessage>>>>;
+
+/// How a server went away when it was shut down
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShutdownOutcome {
+    /// The process exited by itself, with its exit code if it had one
+    Exited(Option<i32>),
+    /// The process only exited after SIGTERM
+    Terminated,
+    /// The process had to be killed
+    Killed,
+    /// The connection to a remote server was closed
+    Disconnected,
+}
+
+impl ShutdownOutcome {
+    /// Whether the server went away without being sent a signal
+    pub fn is_graceful(&self) -> bool {
+        matches!(self, ShutdownOutcome::Exited(_) | ShutdownOutcome::Disconnected)
+    }
+}
+
+impl fmt::Display for ShutdownOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownOutcome::Exited(Some(code)) => write!(f, "Exited with code {}", code),
+            ShutdownOutcome::Exited(None) => write!(f, "Exited"),
+            ShutdownOutcome::Terminated => write!(
+                f,
+                "Terminated after not exiting within {}s of its input closing",
+                EXIT_TIMEOUT.as_secs()
+            ),
+            ShutdownOutcome::Killed => write!(
+                f,
+                "Killed after not exiting within {}s of SIGTERM",
+                TERMINATE_TIMEOUT.as_secs()
+            ),
+            ShutdownOutcome::Disconnected => write!(f, "Disconnected"),
+        }
+    }
+}
+
+/// Stdio transport that starts the server in its own process group, so that
+/// everything it spawns can be shut down with it
+pub struct ProcessTransport {
+    command: String,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+    /// Where the server's stderr goes, with the id of the server
+    log: Option<(ServerLogs, String)>,
+}
+
+impl ProcessTransport {
+    pub fn new<S: Into<String>>(command: S, args: Vec<String>, env: HashMap<String, String>) -> Self {
+        Self {
+            command: command.into(),
+            args,
+            env,
+            log: None,
+        }
+    }
+
+    /// Capture the server's stderr in `logs` under `server_id`
+    pub fn with_log(mut self, logs: ServerLogs, server_id: impl Into<String>) -> Self {
+        self.log = Some((logs, server_id.into()));
+        self
+    }
+}
+
+#[async_trait]
+impl Transport for ProcessTransport {
+    type Handle = ProcessTransportHandle;
+
+    async fn start(&self) -> Result<Self::Handle, Error> {
+        let mut command = Command::new(&self.command);
+        command
+            .args(&self.args)
+            .envs(&self.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        command.process_group(0);
+
+        if let Some((logs, server_id)) = &self.log {
+            let command_line = std::iter::once(&self.command).chain(&self.args).cloned().collect::<Vec<_>>().join(" ");
+            logs.start_session(server_id, &command_line);
+        }
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let message = format!("{}: {}", self.command, e);
+                if let Some((logs, server_id)) = &self.log {
+                    logs.append(server_id, &format!("Failed to start {}", message));
+                }
+                return Err(Error::StdioProcessError(message));
+            }
+        };
+        let (Some(stdin), Some(stdout), Some(stderr)) = (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return Err(Error::StdioProcessError("missing stdio pipes".to_string()));
+        };
+        debug!("Started {} with pid {:?}", self.command, child.id());
+
+        let pending: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+        let closed = ClosedSignal::new();
+        let reader = tokio::spawn(read_messages(stdout, self.command.clone(), pending.clone(), closed.clone()));
+        let stderr_reader = tokio::spawn(read_stderr(stderr, self.log.clone()));
+
+        Ok(ProcessTransportHandle {
+            stdin: Arc::new(AsyncMutex::new(Some(stdin))),
+            pending,
+            closed,
+            process: Arc::new(ServerProcess {
+                command: self.command.clone(),
+                pid: child.id(),
+                child: Mutex::new(child),
+                tasks: vec![reader, stderr_reader],
+            }),
+        })
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        // The process is shut down through its handle
+        Ok(())
+    }
+}
+
+/// A running server process, killed with its process group once no handle uses it anymore
+struct ServerProcess {
+    command: String,
+    pid: Option<u32>,
+    child: Mutex<Child>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl ServerProcess {
+    fn has_exited(&self) -> Option<Option<i32>> {
+        match self.child.lock().unwrap().try_wait() {
+            Ok(Some(status)) => Some(status.code()),
+            Ok(None) => None,
+            // The process can't be waited for, so there's nothing left to stop
+            Err(_) => Some(None),
+        }
+    }
+
+    /// Poll until the process exited or the timeout elapsed
+    async fn wait_for_exit(&self, timeout: Duration) -> Option<Option<i32>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(code) = self.has_exited() {
+                return Some(code);
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+        }
+    }
+
+    #[cfg(unix)]
+    fn signal(&self, signal: libc::c_int) {
+        if let Some(pid) = self.pid {
+            // The server leads its own process group, so this reaches its children too
+            unsafe {
+                libc::killpg(pid as libc::pid_t, signal);
+            }
+        }
+    }
+
+    fn terminate(&self) {
+        #[cfg(unix)]
+        self.signal(libc::SIGTERM);
+        #[cfg(not(unix))]
+        let _ = self.child.lock().unwrap().start_kill();
+    }
+
+    fn kill(&self) {
+        #[cfg(unix)]
+        self.signal(libc::SIGKILL);
+        let _ = self.child.lock().unwrap().start_kill();
+    }
+
+    /// Whether processes the server started are left in its process group
+    fn group_is_alive(&self) -> bool {
+        self.pid.is_some_and(group_is_alive)
+    }
+
+    /// Poll until the process group is empty or the timeout elapsed
+    async fn wait_for_group(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while self.group_is_alive() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+        }
+        true
+    }
+
+    /// Send SIGTERM and then SIGKILL to what the server left behind once it exited
+    async fn stop_leftovers(&self) {
+        if !self.group_is_alive() {
+            return;
+        }
+        warn!("{} left processes behind, terminating them", self.command);
+        self.terminate();
+        if self.wait_for_group(TERMINATE_TIMEOUT).await {
+            return;
+        }
+        warn!("Processes left behind by {} ignored SIGTERM, killing them", self.command);
+        self.kill();
+        if !self.wait_for_group(TERMINATE_TIMEOUT).await {
+            warn!("Processes left behind by {} are still running after SIGKILL", self.command);
+        }
+    }
+}
+
+/// Whether a process group still has members, checked once its leader was waited for
+#[cfg(unix)]
+fn group_is_alive(pgid: u32) -> bool {
+    unsafe { libc::killpg(pgid as libc::pid_t, 0) == 0 }
+}
+
+/// Without process groups the server's children can't be found
+#[cfg(not(unix))]
+fn group_is_alive(_pgid: u32) -> bool {
+    false
+}
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+        if self.has_exited().is_none() {
+            warn!("Killing {} which was never shut down", self.command);
+            self.kill();
+            return;
+        }
+
+        // Processes the server left behind get the same chance as in a shutdown,
+        // without blocking whoever dropped the last handle
+        #[cfg(unix)]
+        if let Some(pgid) = self.pid.filter(|pgid| group_is_alive(*pgid)) {
+            warn!("Terminating the processes {} left behind", self.command);
+            self.signal(libc::SIGTERM);
+            std::thread::spawn(move || {
+                let deadline = Instant::now() + TERMINATE_TIMEOUT;
+                while group_is_alive(pgid) && Instant::now() < deadline {
+                    std::thread::sleep(EXIT_POLL_INTERVAL);
+                }
+                if group_is_alive(pgid) {
+                    unsafe {
+                        libc::killpg(pgid as libc::pid_t, libc::SIGKILL);
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ProcessTransportHandle {
+    stdin: Arc<AsyncMutex<Option<ChildStdin>>>,
+    pending: PendingRequests,
+    /// Closed once the server's output ends, which is when it exited or crashed
+    closed: ClosedSignal,
+    process: Arc<ServerProcess>,
+}
+
+impl ProcessTransportHandle {
+    /// Id of the server process, if it is still known
+    pub fn pid(&self) -> Option<u32> {
+        self.process.pid
+    }
+
+    /// Closed once the server closed its output
+    pub fn closed_signal(&self) -> ClosedSignal {
+        self.closed.clone()
+    }
+
+    /// Shut the server down: close its input and wait for it to exit, then send
+    /// SIGTERM and finally SIGKILL to its process group
+    pub async fn shutdown(&self) -> ShutdownOutcome {
+        let command = &self.process.command;
+
+        // Closing stdin is how the MCP stdio transport asks a server to exit
+        self.stdin.lock().await.take();
+        if let Some(code) = self.process.wait_for_exit(EXIT_TIMEOUT).await {
+            info!("{} exited with code {:?}", command, code);
+            // Its children may outlive it, e.g. when it was a wrapper script
+            self.process.stop_leftovers().await;
+            return ShutdownOutcome::Exited(code);
+        }
+
+        warn!("{} didn't exit within {}s, terminating it", command, EXIT_TIMEOUT.as_secs());
+        self.process.terminate();
+        if self.process.wait_for_exit(TERMINATE_TIMEOUT).await.is_some() {
+            self.process.stop_leftovers().await;
+            return ShutdownOutcome::Terminated;
+        }
+
+        warn!("{} ignored SIGTERM, killing it", command);
+        self.process.kill();
+        if self.process.wait_for_exit(TERMINATE_TIMEOUT).await.is_none() {
+            warn!("{} is still running after SIGKILL", command);
+        }
+        ShutdownOutcome::Killed
+    }
+}
+
+#[async_trait]
+impl TransportHandle for ProcessTransportHandle {
+    async fn send(&self, message: JsonRpcMessage) -> Result<JsonRpcMessage, Error> {
+        let request_id = match &message {
+            JsonRpcMessage::Request(request) => request.id,
+            JsonRpcMessage::Notification(_) => None,
+            _ => return Err(Error::UnsupportedMessage),
+        };
+
+        let mut line = serde_json::to_string(&message)?;
+        line.push('\n');
+
+        // Register before writing so a fast response can't arrive unclaimed
+        let response_rx = request_id.map(|id| {
+            let (response_tx, response_rx) = oneshot::channel();
+            self.pending.lock().unwrap().insert(id, response_tx);
+            response_rx
+        });
+
+        let written = {
+            let mut stdin = self.stdin.lock().await;
+            match stdin.as_mut() {
+                Some(stdin) => match stdin.write_all(line.as_bytes()).await {
+                    Ok(()) => stdin.flush().await.map_err(Error::from),
+                    Err(e) => Err(Error::from(e)),
+                },
+                None => Err(Error::NotConnected),
+            }
+        };
+        if let Err(e) = written {
+            if let Some(id) = request_id {
+                self.pending.lock().unwrap().remove(&id);
+            }
+            return Err(e);
+        }
+
+        match response_rx {
+            Some(response_rx) => response_rx.await.map_err(|_| Error::ChannelClosed),
+            None => Ok(JsonRpcMessage::Nil),
+        }
+    }
+}
+
+/// Read newline delimited messages from the server and hand responses to their requests
+///
+/// `closed` is closed once the output ends.
+async fn read_messages(stdout: ChildStdout, command: String, pending: PendingRequests, closed: ClosedSignal) {
+    let mut lines = BufReader::new(stdout).lines();
+    let reason = loop {
+        match lines.next_line().await {
+            Ok(Some(line)) if line.trim().is_empty() => {}
+            Ok(Some(line)) => match serde_json::from_str::<JsonRpcMessage>(&line) {
+                Ok(message) => {
+                    let waiting = response_id(&message).and_then(|id| pending.lock().unwrap().remove(&id));
+                    match waiting {
+                        Some(tx) => {
+                            let _ = tx.send(message);
+                        }
+                        None => debug!("Ignoring message without a waiting request from {}", command),
+                    }
+                }
+                Err(e) => warn!("Ignoring malformed message from {}: {}", command, e),
+            },
+            Ok(None) => break "the server closed its output".to_string(),
+            Err(e) => {
+                warn!("Failed to read from {}: {}", command, e);
+                break format!("failed to read from the server: {}", e);
+            }
+        }
+    };
+
+    // Dropping the senders fails every request still waiting for a response
+    debug!("{} closed its output", command);
+    pending.lock().unwrap().clear();
+    closed.close(reason);
+}
+
+/// Pass what the server writes to stderr on to its log, which masks the secrets in it
+///
+/// The lines are not traced, they may hold secrets. Without a log they are dropped.
+async fn read_stderr(stderr: ChildStderr, log: Option<(ServerLogs, String)>) {
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if let Some((logs, server_id)) = &log {
+            logs.append(server_id, &line);
+        }
+    }
+}
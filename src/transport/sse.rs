@@ -0,0 +1,237 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use mcp_client::transport::{Error, TransportHandle};
+use mcp_client::Transport;
+use mcp_core::protocol::JsonRpcMessage;
+use reqwest::header::{HeaderMap, ACCEPT};
+use reqwest::Url;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+
+use super::{header_map, response_id, ClosedSignal};
+use crate::event_stream::SseParser;
+
+/// How long to wait for the server to announce where messages are posted
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(10);
+
+type PendingRequests = Arc<Mutex<HashMap<u64, oneshot::Sender<JsonRpcMessage>>>>;
+
+/// HTTP+SSE transport: the server streams responses on an event stream and
+/// announces an endpoint that requests are POSTed to
+pub struct SseTransport {
+    url: String,
+    headers: HashMap<String, String>,
+    client: reqwest::Client,
+}
+
+impl SseTransport {
+    pub fn new<S: Into<String>>(url: S, headers: HashMap<String, String>) -> Self {
+        Self {
+            url: url.into(),
+            headers,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for SseTransport {
+    type Handle = SseTransportHandle;
+
+    async fn start(&self) -> Result<Self::Handle, Error> {
+        let base = Url::parse(&self.url).map_err(|e| Error::SseConnection(format!("{}: {}", self.url, e)))?;
+        let headers = header_map(&self.headers)?;
+
+        let response = self
+            .client
+            .get(base.clone())
+            .headers(headers.clone())
+            .header(ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .map_err(|e| Error::SseConnection(format!("{}: {}", self.url, e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(Error::HttpError {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        let pending: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+        let (endpoint_tx, endpoint_rx) = oneshot::channel();
+        let closed = ClosedSignal::new();
+        let reader = tokio::spawn(read_events(response, base, endpoint_tx, pending.clone(), closed.clone()));
+
+        let endpoint = match tokio::time::timeout(ENDPOINT_TIMEOUT, endpoint_rx).await {
+            Ok(Ok(endpoint)) => endpoint,
+            Ok(Err(_)) => {
+                reader.abort();
+                return Err(Error::SseConnection(format!("{} closed the stream before announcing an endpoint", self.url)));
+            }
+            Err(_) => {
+                reader.abort();
+                return Err(Error::SseConnection(format!("{} didn't announce an endpoint", self.url)));
+            }
+        };
+        debug!("SSE server at {} accepts messages at {}", self.url, endpoint);
+
+        Ok(SseTransportHandle {
+            endpoint,
+            headers,
+            client: self.client.clone(),
+            pending,
+            closed,
+            reader: Arc::new(ReaderTask(reader)),
+        })
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        // The event stream is closed when the last handle is dropped
+        Ok(())
+    }
+}
+
+/// Aborts the event stream reader once no handle uses it anymore
+struct ReaderTask(JoinHandle<()>);
+
+impl Drop for ReaderTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[derive(Clone)]
+pub struct SseTransportHandle {
+    endpoint: Url,
+    headers: HeaderMap,
+    client: reqwest::Client,
+    pending: PendingRequests,
+    /// Closed once the event stream ends
+    closed: ClosedSignal,
+    reader: Arc<ReaderTask>,
+}
+
+impl SseTransportHandle {
+    /// Close the event stream, failing requests that still wait for a response
+    pub fn close(&self) {
+        self.reader.0.abort();
+        self.pending.lock().unwrap().clear();
+        self.closed.close("the event stream was closed");
+    }
+
+    /// Closed once the event stream ended
+    pub fn closed_signal(&self) -> ClosedSignal {
+        self.closed.clone()
+    }
+
+    async fn post(&self, message: &JsonRpcMessage) -> Result<(), Error> {
+        let response = self
+            .client
+            .post(self.endpoint.clone())
+            .headers(self.headers.clone())
+            .json(message)
+            .send()
+            .await
+            .map_err(|e| Error::SseConnection(format!("{}: {}", self.endpoint, e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(Error::HttpError {
+                status: status.as_u16(),
+                message,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TransportHandle for SseTransportHandle {
+    async fn send(&self, message: JsonRpcMessage) -> Result<JsonRpcMessage, Error> {
+        let request_id = match &message {
+            JsonRpcMessage::Request(request) => request.id,
+            JsonRpcMessage::Notification(_) => None,
+            _ => return Err(Error::UnsupportedMessage),
+        };
+
+        let Some(request_id) = request_id else {
+            self.post(&message).await?;
+            return Ok(JsonRpcMessage::Nil);
+        };
+
+        // Register before posting so a fast response can't arrive unclaimed
+        let (response_tx, response_rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(request_id, response_tx);
+
+        if let Err(e) = self.post(&message).await {
+            self.pending.lock().unwrap().remove(&request_id);
+            return Err(e);
+        }
+
+        response_rx.await.map_err(|_| Error::ChannelClosed)
+    }
+}
+
+/// Read the event stream, report the endpoint and hand responses to their requests
+async fn read_events(
+    response: reqwest::Response,
+    base: Url,
+    endpoint_tx: oneshot::Sender<Url>,
+    pending: PendingRequests,
+    closed: ClosedSignal,
+) {
+    let mut endpoint_tx = Some(endpoint_tx);
+    let mut parser = SseParser::new();
+    let mut body = response.bytes_stream();
+
+    let mut reason = "the server closed the event stream".to_string();
+    while let Some(chunk) = body.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                error!("SSE stream from {} failed: {}", base, e);
+                reason = format!("the event stream failed: {}", e);
+                break;
+            }
+        };
+
+        for event in parser.push(&chunk) {
+            match event.event.as_str() {
+                "endpoint" => match base.join(event.data.trim()) {
+                    Ok(endpoint) => {
+                        if let Some(tx) = endpoint_tx.take() {
+                            let _ = tx.send(endpoint);
+                        }
+                    }
+                    Err(e) => warn!("Ignoring invalid endpoint {:?}: {}", event.data, e),
+                },
+                "message" => match serde_json::from_str::<JsonRpcMessage>(&event.data) {
+                    Ok(message) => {
+                        let waiting = response_id(&message).and_then(|id| pending.lock().unwrap().remove(&id));
+                        match waiting {
+                            Some(tx) => {
+                                let _ = tx.send(message);
+                            }
+                            None => debug!("Ignoring message without a waiting request"),
+                        }
+                    }
+                    Err(e) => warn!("Ignoring malformed message from {}: {}", base, e),
+                },
+                other => debug!("Ignoring SSE event {}", other),
+            }
+        }
+    }
+
+    // Dropping the senders fails every request still waiting for a response
+    debug!("SSE stream from {} ended", base);
+    pending.lock().unwrap().clear();
+    closed.close(reason);
+}
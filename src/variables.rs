@@ -0,0 +1,116 @@
+//! Variables expanded in the command, arguments and environment of a server, so one
+//! configuration file works across machines with different home directories.
+//!
+//! - `${HOME}`: the user's home directory
+//! - `${env:VAR}`: the environment variable `VAR`
+//! - `${workspace}`: `$MDESK_WORKSPACE` if set, otherwise the directory mDesk was started in
+//!
+//! References to secrets, `${secret:NAME}`, are left alone for the secret store.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+use crate::server_config::ServerConfig;
+
+/// Environment variable naming the directory `${workspace}` stands for
+pub const WORKSPACE_ENV: &str = "MDESK_WORKSPACE";
+
+/// Errors that can occur while expanding variables
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum VariableError {
+    #[error("Unknown variable ${{{0}}}, use ${{HOME}}, ${{env:VAR}} or ${{workspace}}")]
+    Unknown(String),
+
+    #[error("Environment variable {0} is not set")]
+    MissingEnv(String),
+
+    #[error("The home directory can't be determined")]
+    NoHome,
+}
+
+/// What the variables stand for on this machine
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableContext {
+    pub home: Option<PathBuf>,
+    pub workspace: PathBuf,
+}
+
+impl VariableContext {
+    /// The home directory and workspace of the running app
+    pub fn current() -> Self {
+        let workspace = std::env::var_os(WORKSPACE_ENV)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."));
+        Self {
+            home: dirs::home_dir(),
+            workspace,
+        }
+    }
+
+    /// Replace every variable in `value`, leaving references to secrets as they are
+    pub fn expand(&self, value: &str) -> Result<String, VariableError> {
+        let mut error = None;
+        let expanded = variable_pattern().replace_all(value, |captures: &regex::Captures| {
+            let resolved = match (&captures[1], captures.get(2).map(|arg| arg.as_str())) {
+                ("HOME", None) => self
+                    .home
+                    .as_ref()
+                    .map(|home| home.display().to_string())
+                    .ok_or(VariableError::NoHome),
+                ("workspace", None) => Ok(self.workspace.display().to_string()),
+                ("env", Some(name)) => std::env::var(name).map_err(|_| VariableError::MissingEnv(name.to_string())),
+                ("secret", Some(_)) => Ok(captures[0].to_string()),
+                _ => Err(VariableError::Unknown(captures[0][2..captures[0].len() - 1].to_string())),
+            };
+            resolved.unwrap_or_else(|e| {
+                error.get_or_insert(e);
+                String::new()
+            })
+        });
+        match error {
+            Some(e) => Err(e),
+            None => Ok(expanded.into_owned()),
+        }
+    }
+
+    /// The configuration a server is started with, its command, arguments and environment expanded
+    pub fn expand_config(&self, config: &ServerConfig) -> Result<ServerConfig, VariableError> {
+        let mut expanded = config.clone();
+        expanded.command = self.expand(&config.command)?;
+        expanded.args = config
+            .args
+            .iter()
+            .map(|arg| self.expand(arg))
+            .collect::<Result<_, _>>()?;
+        expanded.env = config
+            .env
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), self.expand(value)?)))
+            .collect::<Result<HashMap<_, _>, VariableError>>()?;
+        Ok(expanded)
+    }
+}
+
+/// Variables in `value` that don't exist, without looking at what the known ones stand for
+pub fn unknown_variables(value: &str) -> Vec<String> {
+    variable_pattern()
+        .captures_iter(value)
+        .filter(|captures| {
+            !matches!(
+                (&captures[1], captures.get(2).is_some()),
+                ("HOME", false) | ("workspace", false) | ("env", true) | ("secret", true)
+            )
+        })
+        .map(|captures| captures[0].to_string())
+        .collect()
+}
+
+fn variable_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?::([^}]*))?\}").unwrap())
+}
@@ -0,0 +1,37 @@
+use m_desk_new::components::agent::{AgentDecision, AgentRun};
+
+#[test]
+fn test_agent_run_counts_steps() {
+    let mut run = AgentRun::new(3);
+
+    let first = run.begin_step().unwrap();
+    assert_eq!(first.index, 1);
+    assert_eq!(first.max_steps, 3);
+    assert_eq!(first.run_id, run.id);
+    assert_eq!(first.label(), "Step 1 of 3");
+    assert_eq!(run.decision(), AgentDecision::Continue);
+
+    let second = run.begin_step().unwrap();
+    assert_eq!(second.index, 2);
+    assert_eq!(run.decision(), AgentDecision::Continue);
+}
+
+#[test]
+fn test_agent_run_stops_at_step_limit() {
+    let mut run = AgentRun::new(2);
+    run.begin_step();
+    run.begin_step();
+
+    assert!(run.limit_reached());
+    assert_eq!(run.decision(), AgentDecision::FinalAnswer);
+    assert!(run.begin_step().is_none());
+    assert_eq!(run.steps_taken, 2);
+}
+
+#[test]
+fn test_agent_run_allows_at_least_one_step() {
+    let mut run = AgentRun::new(0);
+    assert_eq!(run.max_steps, 1);
+    assert!(run.begin_step().is_some());
+    assert_eq!(run.decision(), AgentDecision::FinalAnswer);
+}
@@ -0,0 +1,137 @@
+use m_desk_new::approvals::{
+    decide, glob_matches, ApprovalAction, ApprovalDecision, ApprovalRequest, ApprovalRule, ToolAnnotationStore,
+    ToolAnnotations,
+};
+use m_desk_new::components::tool_registry::ToolRegistry;
+use mcp_core::Tool;
+use serde_json::json;
+use std::collections::HashMap;
+
+fn request(server_id: &str, tool_name: &str, annotations: ToolAnnotations) -> ApprovalRequest {
+    ApprovalRequest {
+        server_id: Some(server_id.to_string()),
+        tool_name: tool_name.to_string(),
+        annotations,
+    }
+}
+
+fn read_only() -> ToolAnnotations {
+    ToolAnnotations { read_only: Some(true), destructive: None }
+}
+
+fn rule(action: ApprovalAction) -> ApprovalRule {
+    ApprovalRule { action, ..ApprovalRule::default() }
+}
+
+#[test]
+fn test_glob_matches_tool_names() {
+    assert!(glob_matches("git_*", "git_status"));
+    assert!(glob_matches("*_file", "read_file"));
+    assert!(glob_matches("read_?ile", "read_file"));
+    assert!(glob_matches("*", "anything"));
+    assert!(!glob_matches("git_*", "github_search"));
+    // Regex characters in names are taken literally
+    assert!(glob_matches("a.b", "a.b"));
+    assert!(!glob_matches("a.b", "axb"));
+}
+
+#[test]
+fn test_unannotated_tools_count_as_destructive() {
+    assert!(ToolAnnotations::default().is_destructive());
+    assert!(!ToolAnnotations::default().is_read_only());
+    assert!(!read_only().is_destructive());
+    assert!(!ToolAnnotations { read_only: Some(false), destructive: Some(false) }.is_destructive());
+}
+
+#[test]
+fn test_first_matching_rule_decides() {
+    let rules = vec![
+        ApprovalRule { tool: Some("git_push".to_string()), ..rule(ApprovalAction::Deny) },
+        ApprovalRule { server: Some("git".to_string()), read_only: Some(true), ..rule(ApprovalAction::Allow) },
+        ApprovalRule { destructive: Some(true), ..rule(ApprovalAction::Ask) },
+        rule(ApprovalAction::Allow),
+    ];
+
+    assert!(matches!(
+        decide(&rules, &request("git", "git_push", read_only()), &[]),
+        ApprovalDecision::Deny(_)
+    ));
+    assert_eq!(
+        decide(&rules, &request("git", "git_status", read_only()), &[]),
+        ApprovalDecision::Allow("rule \"allow server git, read-only\"".to_string())
+    );
+    assert_eq!(decide(&rules, &request("git", "git_commit", ToolAnnotations::default()), &[]), ApprovalDecision::Ask);
+    assert!(matches!(
+        decide(&rules, &request("fetch", "fetch", ToolAnnotations { read_only: None, destructive: Some(false) }), &[]),
+        ApprovalDecision::Allow(_)
+    ));
+    // Without rules every call is asked about
+    assert_eq!(decide(&[], &request("git", "git_status", read_only()), &[]), ApprovalDecision::Ask);
+}
+
+#[test]
+fn test_tools_allowed_for_the_conversation_run_unless_denied() {
+    let rules = vec![ApprovalRule { tool: Some("delete_*".to_string()), ..rule(ApprovalAction::Deny) }];
+    let allowed = vec!["filesystem/write_file".to_string(), "filesystem/delete_file".to_string()];
+
+    assert_eq!(
+        decide(&rules, &request("filesystem", "write_file", ToolAnnotations::default()), &allowed),
+        ApprovalDecision::Allow("allowed for this conversation".to_string())
+    );
+    assert!(matches!(
+        decide(&rules, &request("filesystem", "delete_file", ToolAnnotations::default()), &allowed),
+        ApprovalDecision::Deny(_)
+    ));
+    assert_eq!(
+        decide(&rules, &request("other", "write_file", ToolAnnotations::default()), &allowed),
+        ApprovalDecision::Ask
+    );
+}
+
+#[test]
+fn test_requests_resolve_the_server_and_annotations_of_a_tool() {
+    let tool = |name: &str| Tool {
+        name: name.to_string(),
+        description: String::new(),
+        input_schema: json!({"type": "object"}),
+    };
+    let mut server_tools = HashMap::new();
+    server_tools.insert("filesystem".to_string(), vec![tool("read_file")]);
+    server_tools.insert("git".to_string(), vec![tool("git_status"), tool("read_file")]);
+    let registry = ToolRegistry::from_server_tools(&server_tools);
+
+    let annotations = ToolAnnotationStore::default();
+    annotations.record_tools_list(
+        "git",
+        &json!({ "tools": [{ "name": "git_status", "annotations": { "readOnlyHint": true } }] }),
+    );
+
+    let status = ApprovalRequest::for_tool("git_status", &registry, &annotations);
+    assert_eq!(status, request("git", "git_status", read_only()));
+    assert_eq!(status.key(), "git/git_status");
+
+    let qualified = ApprovalRequest::for_tool("filesystem/read_file", &registry, &annotations);
+    assert_eq!(qualified.server_id.as_deref(), Some("filesystem"));
+    assert_eq!(qualified.tool_name, "read_file");
+
+    // A name more than one server provides can't be told apart, only name rules apply to it
+    let ambiguous = ApprovalRequest::for_tool("read_file", &registry, &annotations);
+    assert_eq!(ambiguous.server_id, None);
+    assert_eq!(ambiguous.key(), "read_file");
+}
+
+#[test]
+fn test_rules_round_trip_through_json() {
+    let rules: Vec<ApprovalRule> = serde_json::from_value(json!([
+        { "action": "deny", "tool": "*_delete" },
+        { "action": "allow", "server": "git", "read_only": true }
+    ]))
+    .unwrap();
+    assert_eq!(rules[0].action, ApprovalAction::Deny);
+    assert_eq!(rules[1].read_only, Some(true));
+    assert_eq!(rules[1].to_string(), "allow server git, read-only");
+    assert_eq!(
+        serde_json::to_value(&rules[0]).unwrap(),
+        json!({ "action": "deny", "tool": "*_delete" })
+    );
+}
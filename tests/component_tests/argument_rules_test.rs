@@ -0,0 +1,166 @@
+use m_desk_new::argument_rules::{check_arguments, ArgumentConstraint, ArgumentRule};
+use m_desk_new::variables::VariableContext;
+use serde_json::json;
+use std::path::PathBuf;
+
+fn context() -> VariableContext {
+    VariableContext {
+        home: Some(PathBuf::from("/home/ada")),
+        workspace: PathBuf::from("/work/mdesk"),
+    }
+}
+
+fn rooted(tool: &str, roots: &[&str]) -> ArgumentRule {
+    ArgumentRule {
+        tool: tool.to_string(),
+        allowed_roots: roots.iter().map(ToString::to_string).collect(),
+        ..ArgumentRule::default()
+    }
+}
+
+fn constrained(tool: &str, constraint: ArgumentConstraint) -> ArgumentRule {
+    ArgumentRule {
+        tool: tool.to_string(),
+        constraints: vec![constraint],
+        ..ArgumentRule::default()
+    }
+}
+
+#[test]
+fn test_paths_have_to_be_under_an_allowed_root() {
+    let rule = rooted("*_file", &["${HOME}/Projects", "${workspace}"]);
+    let context = context();
+
+    assert!(rule.check("write_file", &json!({"path": "/home/ada/Projects/notes.md"}), &context).is_empty());
+    assert!(rule.check("write_file", &json!({"path": "~/Projects/notes.md"}), &context).is_empty());
+    assert!(rule.check("read_file", &json!({"path": "/work/mdesk/src/main.rs"}), &context).is_empty());
+    // Strings that aren't paths are left alone
+    assert!(rule.check("write_file", &json!({"path": "/home/ada/Projects/a", "content": "hello"}), &context).is_empty());
+
+    let violations = rule.check("write_file", &json!({"path": "/etc/passwd"}), &context);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].pointer, "/path");
+    assert!(violations[0].to_string().contains("\"/etc/passwd\" is outside ${HOME}/Projects, ${workspace}"));
+
+    // Climbing out of a root with .. doesn't get around it, neither do nested arguments
+    assert_eq!(rule.check("write_file", &json!({"path": "/home/ada/Projects/../.ssh/id_rsa"}), &context).len(), 1);
+    assert_eq!(rule.check("write_file", &json!({"path": "../secrets"}), &context).len(), 1);
+    let violations = rule.check("move_file", &json!({"moves": [{"to": "/tmp/x"}]}), &context);
+    assert_eq!(violations[0].pointer, "/moves/0/to");
+}
+
+#[test]
+fn test_relative_paths_are_not_let_through() {
+    let rule = rooted("*", &["${HOME}/Projects"]);
+    let context = context();
+
+    // Under a path-like key any string is a path, and a relative one is never under a root
+    for arguments in [
+        json!({"path": "etc/passwd"}),
+        json!({"path": "notes.txt"}),
+        json!({"file": ".ssh/id_rsa"}),
+        json!({"sourcePaths": ["/home/ada/Projects/a", "b"]}),
+        json!({"target_dir": "."}),
+    ] {
+        let violations = rule.check("write_file", &arguments, &context);
+        assert_eq!(violations.len(), 1, "{} got through", arguments);
+        assert!(violations[0].reason.contains("is not an absolute path"));
+    }
+    assert!(rule.check("write_file", &json!({"profile": "work", "url": "https://example.com/a"}), &context).is_empty());
+
+    // The schema marks locations the key doesn't give away, file URIs are checked as paths
+    let schema = json!({ "properties": { "location": { "type": "string", "format": "uri" } } });
+    let violations = rule.check_with_schema("fetch", &json!({"location": "notes.txt"}), &schema, &context);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(rule.check_with_schema("fetch", &json!({"location": "file:///etc/passwd"}), &schema, &context).len(), 1);
+    assert!(rule
+        .check_with_schema("fetch", &json!({"location": "file:///home/ada/Projects/a"}), &schema, &context)
+        .is_empty());
+    assert!(rule.check_with_schema("fetch", &json!({"location": "https://example.com"}), &schema, &context).is_empty());
+}
+
+#[test]
+fn test_constraints_check_the_argument_they_point_to() {
+    let context = context();
+    let deny_drop = constrained(
+        "execute_query",
+        ArgumentConstraint {
+            pointer: "/query".to_string(),
+            deny_regex: Some(r"(?i)\bdrop\b".to_string()),
+            ..ArgumentConstraint::default()
+        },
+    );
+    assert!(deny_drop.check("execute_query", &json!({"query": "SELECT * FROM users"}), &context).is_empty());
+    let violations = deny_drop.check("execute_query", &json!({"query": "drop table users"}), &context);
+    assert!(violations[0].reason.contains("contains \"drop\""));
+    // Missing arguments are left to the schema
+    assert!(deny_drop.check("execute_query", &json!({}), &context).is_empty());
+
+    let branches = constrained(
+        "git_*",
+        ArgumentConstraint {
+            pointer: "/branches".to_string(),
+            glob: Some("feature/*".to_string()),
+            message: Some("only feature branches".to_string()),
+            ..ArgumentConstraint::default()
+        },
+    );
+    let violations = branches.check("git_push", &json!({"branches": ["feature/a", "main"]}), &context);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].pointer, "/branches/1");
+    assert_eq!(violations[0].reason, "only feature branches");
+
+    let prefix = constrained(
+        "*",
+        ArgumentConstraint {
+            pointer: "/path".to_string(),
+            path_prefix: vec!["~/Projects".to_string()],
+            ..ArgumentConstraint::default()
+        },
+    );
+    assert!(prefix.check("list_directory", &json!({"path": "/home/ada/Projects"}), &context).is_empty());
+    assert_eq!(prefix.check("list_directory", &json!({"path": "/home/ada/Projects2"}), &context).len(), 1);
+    // Relative paths can't be told to be in a root and are refused
+    assert_eq!(prefix.check("list_directory", &json!({"path": "Projects"}), &context).len(), 1);
+}
+
+#[test]
+fn test_rules_apply_by_server_and_tool() {
+    let rule = ArgumentRule {
+        server: Some("filesystem".to_string()),
+        ..rooted("write_file", &["/srv/data"])
+    };
+    let arguments = json!({"path": "/etc/hosts"});
+
+    assert!(check_arguments(std::slice::from_ref(&rule), Some("other"), "write_file", &arguments, &json!({})).is_ok());
+    assert!(check_arguments(std::slice::from_ref(&rule), Some("filesystem"), "read_file", &arguments, &json!({})).is_ok());
+    assert!(check_arguments(std::slice::from_ref(&rule), None, "write_file", &arguments, &json!({})).is_ok());
+    assert!(check_arguments(&[rule], Some("filesystem"), "write_file", &arguments, &json!({})).is_err());
+}
+
+#[test]
+fn test_rule_problems() {
+    let rule = ArgumentRule {
+        tool: " ".to_string(),
+        constraints: vec![ArgumentConstraint {
+            pointer: "path".to_string(),
+            regex: Some("(".to_string()),
+            ..ArgumentConstraint::default()
+        }],
+        ..ArgumentRule::default()
+    };
+    let fields: Vec<String> = rule.problems().into_iter().map(|(field, _)| field).collect();
+    assert_eq!(fields, vec!["tool", "constraints[0].pointer", "constraints[0].regex"]);
+
+    let rule: ArgumentRule = serde_json::from_value(json!({
+        "tool": "write_file",
+        "allowed_roots": ["${HOME}/Projects"],
+        "constraints": [{"pointer": "/content", "deny_regex": "BEGIN PRIVATE KEY"}]
+    }))
+    .unwrap();
+    assert!(rule.problems().is_empty());
+    assert_eq!(
+        serde_json::to_value(&rule).unwrap()["constraints"][0],
+        json!({"pointer": "/content", "deny_regex": "BEGIN PRIVATE KEY"})
+    );
+}
@@ -0,0 +1,769 @@
+use async_trait::async_trait;
+use m_desk_new::connection_manager::{ConnectionError, ConnectionManager, Connector, PingError, HEALTH_HISTORY_LEN};
+use m_desk_new::server_config::{HealthCheckConfig, RestartMode, RestartPolicy, ServerConfig, ServerConfigs, TransportConfig};
+use m_desk_new::transport::{ClosedSignal, ConnectError, ShutdownOutcome};
+use m_desk_new::ServerStatus;
+use mcp_client::transport::Error as TransportError;
+use mcp_core::Tool;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn create_tool(name: &str) -> Tool {
+    Tool {
+        name: name.to_string(),
+        description: format!("The {} tool", name),
+        input_schema: json!({"type": "object"}),
+    }
+}
+
+fn create_config(id: &str) -> ServerConfig {
+    ServerConfig {
+        id: id.to_string(),
+        name: format!("{} server", id),
+        transport: TransportConfig::Stdio,
+        command: "fake".to_string(),
+        args: Vec::new(),
+        env: HashMap::new(),
+        description: None,
+        is_default: false,
+        restart: RestartPolicy::default(),
+        // Pinged in every round of supervision
+        health: HealthCheckConfig {
+            interval_ms: 0,
+            ..HealthCheckConfig::default()
+        },
+    }
+}
+
+/// Config whose restarts are due immediately
+fn create_restartable_config(id: &str, mode: RestartMode, max_retries: u32) -> ServerConfig {
+    ServerConfig {
+        restart: RestartPolicy {
+            mode,
+            max_retries,
+            initial_backoff_ms: 0,
+            max_backoff_ms: 0,
+        },
+        ..create_config(id)
+    }
+}
+
+/// Client handed out by the fake connector
+struct FakeClient {
+    server_id: String,
+}
+
+/// Shutdown handle handed out by the fake connector
+#[derive(Clone)]
+struct FakeHandle {
+    server_id: String,
+}
+
+/// Connector that "connects" to any server except `broken` and the servers marked down,
+/// and serves a configurable tool list
+#[derive(Clone, Default)]
+struct FakeConnector {
+    tools: Arc<Mutex<HashMap<String, Vec<Tool>>>>,
+    down: Arc<Mutex<HashSet<String>>>,
+    /// Servers that are connected but don't answer pings
+    slow: Arc<Mutex<HashSet<String>>>,
+    /// Servers that only go away when killed
+    stubborn: Arc<Mutex<HashSet<String>>>,
+    connects: Arc<AtomicUsize>,
+    pings: Arc<AtomicUsize>,
+    shutdowns: Arc<Mutex<Vec<String>>>,
+    /// Closed when the latest connection to a server is lost
+    closers: Arc<Mutex<HashMap<String, ClosedSignal>>>,
+}
+
+impl FakeConnector {
+    fn set_tools(&self, server_id: &str, names: &[&str]) {
+        self.tools
+            .lock()
+            .unwrap()
+            .insert(server_id.to_string(), names.iter().map(|n| create_tool(n)).collect());
+    }
+
+    /// Make a server stop responding and refuse new connections, or bring it back
+    fn set_down(&self, server_id: &str, down: bool) {
+        let mut servers = self.down.lock().unwrap();
+        if down {
+            servers.insert(server_id.to_string());
+        } else {
+            servers.remove(server_id);
+        }
+    }
+
+    fn set_slow(&self, server_id: &str, slow: bool) {
+        let mut servers = self.slow.lock().unwrap();
+        if slow {
+            servers.insert(server_id.to_string());
+        } else {
+            servers.remove(server_id);
+        }
+    }
+
+    /// Drop the connection to a server as if its process exited
+    fn crash(&self, server_id: &str) {
+        self.closers.lock().unwrap()[server_id].close("the server closed its output");
+    }
+
+    fn is_down(&self, server_id: &str) -> bool {
+        server_id == "broken" || self.down.lock().unwrap().contains(server_id)
+    }
+}
+
+#[async_trait]
+impl Connector for FakeConnector {
+    type Client = FakeClient;
+    type Handle = FakeHandle;
+
+    async fn connect(&self, config: &ServerConfig) -> Result<(FakeClient, FakeHandle), ConnectError> {
+        self.connects.fetch_add(1, Ordering::SeqCst);
+        if self.is_down(&config.id) {
+            return Err(ConnectError::Start(TransportError::SseConnection("connection refused".to_string())));
+        }
+        self.closers.lock().unwrap().insert(config.id.clone(), ClosedSignal::new());
+        Ok((
+            FakeClient {
+                server_id: config.id.clone(),
+            },
+            FakeHandle {
+                server_id: config.id.clone(),
+            },
+        ))
+    }
+
+    async fn shutdown(&self, handle: FakeHandle) -> ShutdownOutcome {
+        self.shutdowns.lock().unwrap().push(handle.server_id.clone());
+        if let Some(closed) = self.closers.lock().unwrap().get(&handle.server_id) {
+            closed.close("the server was shut down");
+        }
+        if self.stubborn.lock().unwrap().contains(&handle.server_id) {
+            ShutdownOutcome::Killed
+        } else {
+            ShutdownOutcome::Exited(Some(0))
+        }
+    }
+
+    async fn list_tools(&self, client: &FakeClient) -> Result<Vec<Tool>, String> {
+        Ok(self.tools.lock().unwrap().get(&client.server_id).cloned().unwrap_or_default())
+    }
+
+    async fn ping(&self, server_id: &str, handle: &FakeHandle, _timeout: Duration) -> Result<(), PingError> {
+        assert_eq!(server_id, handle.server_id);
+        self.pings.fetch_add(1, Ordering::SeqCst);
+        if self.is_down(server_id) {
+            Err(PingError::Disconnected("broken pipe".to_string()))
+        } else if self.slow.lock().unwrap().contains(server_id) {
+            Err(PingError::Timeout)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn closed_signal(&self, handle: &FakeHandle) -> Option<ClosedSignal> {
+        self.closers.lock().unwrap().get(&handle.server_id).cloned()
+    }
+}
+
+/// Wait for the manager to notice that a server failed and return the reason
+async fn wait_for_failure(manager: &ConnectionManager<FakeConnector>, server_id: &str) -> String {
+    for _ in 0..100 {
+        if let Some(ServerStatus::Failed(reason)) = manager.status(server_id) {
+            return reason;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("server {} did not fail, it is {:?}", server_id, manager.status(server_id));
+}
+
+fn create_manager() -> (ConnectionManager<FakeConnector>, FakeConnector) {
+    let connector = FakeConnector::default();
+    connector.set_tools("filesystem", &["read_file", "list_directory"]);
+    connector.set_tools("git", &["git_status", "read_file"]);
+    (ConnectionManager::new(connector.clone()), connector)
+}
+
+#[tokio::test]
+async fn test_start_publishes_client_and_tools() {
+    let (manager, _) = create_manager();
+
+    let client = manager.start(&create_config("filesystem")).await.unwrap();
+    assert_eq!(client.lock().await.server_id, "filesystem");
+    assert_eq!(manager.status("filesystem"), Some(ServerStatus::Running));
+
+    let snapshot = manager.snapshot();
+    assert!(snapshot.clients.contains_key("filesystem"));
+    assert_eq!(snapshot.server_tools["filesystem"].len(), 2);
+    assert!(snapshot.tool_registry().get("list_directory").is_some());
+}
+
+#[tokio::test]
+async fn test_failed_start_reports_the_error() {
+    let (manager, _) = create_manager();
+
+    let error = manager.start(&create_config("broken")).await.err().unwrap();
+    assert!(matches!(error, ConnectionError::Connect(ConnectError::Start(_))));
+
+    match manager.status("broken") {
+        Some(ServerStatus::Failed(message)) => {
+            assert!(message.starts_with("Failed to start"));
+            assert!(message.contains("connection refused"));
+        }
+        other => panic!("expected a failed status, got {:?}", other),
+    }
+    assert!(manager.client("broken").is_none());
+}
+
+#[tokio::test]
+async fn test_starting_a_running_server_reuses_its_client() {
+    let (manager, connector) = create_manager();
+    let config = create_config("git");
+
+    let first = manager.start(&config).await.unwrap();
+    let second = manager.start(&config).await.unwrap();
+
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(connector.connects.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_stop_drops_client_and_tools() {
+    let (manager, _) = create_manager();
+    manager.start(&create_config("filesystem")).await.unwrap();
+    manager.start(&create_config("git")).await.unwrap();
+
+    assert!(manager.stop("filesystem").await);
+    assert!(!manager.stop("unknown").await);
+
+    assert_eq!(manager.status("filesystem"), Some(ServerStatus::Stopped));
+    let snapshot = manager.snapshot();
+    assert!(!snapshot.clients.contains_key("filesystem"));
+    assert!(!snapshot.server_tools.contains_key("filesystem"));
+
+    // read_file is no longer ambiguous once only git provides it
+    assert_eq!(
+        snapshot.tool_registry().resolve("read_file").unwrap(),
+        ("git".to_string(), "read_file".to_string())
+    );
+
+    manager.stop_all().await;
+    assert_eq!(manager.status("git"), Some(ServerStatus::Stopped));
+    assert!(manager.snapshot().clients.is_empty());
+}
+
+#[tokio::test]
+async fn test_restart_reconnects_with_the_stored_config() {
+    let (manager, connector) = create_manager();
+    let first = manager.start(&create_config("git")).await.unwrap();
+
+    let second = manager.restart("git").await.unwrap();
+    assert!(!Arc::ptr_eq(&first, &second));
+    assert_eq!(connector.connects.load(Ordering::SeqCst), 2);
+    assert_eq!(manager.status("git"), Some(ServerStatus::Running));
+
+    assert!(matches!(manager.restart("unknown").await, Err(ConnectionError::NotRunning(_))));
+}
+
+#[tokio::test]
+async fn test_refresh_tools_picks_up_changes() {
+    let (manager, connector) = create_manager();
+    manager.start(&create_config("git")).await.unwrap();
+
+    connector.set_tools("git", &["git_status", "git_log", "git_diff"]);
+    let tools = manager.refresh_tools("git").await.unwrap();
+
+    assert_eq!(tools.len(), 3);
+    assert_eq!(manager.snapshot().server_tools["git"].len(), 3);
+    assert!(matches!(manager.refresh_tools("unknown").await, Err(ConnectionError::NotRunning(_))));
+}
+
+#[tokio::test]
+async fn test_refresh_idle_tools_skips_busy_servers() {
+    let (manager, connector) = create_manager();
+    manager.start(&create_config("filesystem")).await.unwrap();
+    let git = manager.start(&create_config("git")).await.unwrap();
+
+    connector.set_tools("filesystem", &["read_file", "list_directory", "write_file"]);
+    connector.set_tools("git", &["git_status", "read_file", "git_log"]);
+    let busy = git.lock().await;
+    let registry = manager.refresh_idle_tools().await;
+    drop(busy);
+
+    assert_eq!(registry.resolve("write_file").unwrap().0, "filesystem");
+    assert!(registry.resolve("git_log").is_err());
+    assert_eq!(manager.snapshot().server_tools["git"].len(), 2);
+}
+
+#[tokio::test]
+async fn test_failed_servers_keep_their_client_but_not_their_tools() {
+    let (manager, connector) = create_manager();
+    manager.start(&create_config("git")).await.unwrap();
+
+    connector.crash("git");
+    assert_eq!(wait_for_failure(&manager, "git").await, "Connection lost: the server closed its output");
+    let snapshot = manager.snapshot();
+    assert!(snapshot.clients.contains_key("git"));
+    assert!(snapshot.server_tools.is_empty());
+}
+
+#[tokio::test]
+async fn test_lost_connection_is_noticed_without_health_checks() {
+    let (manager, connector) = create_manager();
+    let config = ServerConfig {
+        health: HealthCheckConfig {
+            enabled: false,
+            ..HealthCheckConfig::default()
+        },
+        ..create_restartable_config("git", RestartMode::OnFailure, 3)
+    };
+    manager.start(&config).await.unwrap();
+
+    connector.crash("git");
+    wait_for_failure(&manager, "git").await;
+    assert_eq!(connector.pings.load(Ordering::SeqCst), 0);
+
+    // The restart is watched like the first connection
+    manager.supervise_once().await;
+    assert_eq!(manager.status("git"), Some(ServerStatus::Running));
+    connector.crash("git");
+    wait_for_failure(&manager, "git").await;
+    assert_eq!(manager.retry_state("git").unwrap().retries, 1);
+}
+
+#[tokio::test]
+async fn test_start_all_reports_each_server() {
+    let (manager, _) = create_manager();
+    let configs = vec![create_config("filesystem"), create_config("broken"), create_config("git")];
+
+    let results = manager.start_all(&configs).await;
+    let outcome: Vec<(String, bool)> = results.iter().map(|(c, r)| (c.id.clone(), r.is_ok())).collect();
+    assert_eq!(
+        outcome,
+        vec![
+            ("filesystem".to_string(), true),
+            ("broken".to_string(), false),
+            ("git".to_string(), true),
+        ]
+    );
+    assert_eq!(manager.server_ids(), vec!["broken", "filesystem", "git"]);
+}
+
+#[tokio::test]
+async fn test_subscribers_are_notified_of_changes() {
+    let (manager, _) = create_manager();
+    let mut changes = manager.subscribe();
+
+    manager.start(&create_config("git")).await.unwrap();
+    assert!(changes.has_changed().unwrap());
+    changes.borrow_and_update();
+
+    manager.stop("git").await;
+    assert!(changes.has_changed().unwrap());
+    changes.borrow_and_update();
+
+    // The connection closing after the server was stopped changes nothing
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(manager.status("git"), Some(ServerStatus::Stopped));
+    assert!(!changes.has_changed().unwrap());
+}
+
+#[test]
+fn test_backoff_doubles_up_to_the_limit() {
+    let policy = RestartPolicy {
+        mode: RestartMode::OnFailure,
+        max_retries: 10,
+        initial_backoff_ms: 500,
+        max_backoff_ms: 3_000,
+    };
+
+    assert_eq!(policy.backoff(0), Duration::from_millis(500));
+    assert_eq!(policy.backoff(1), Duration::from_millis(1_000));
+    assert_eq!(policy.backoff(2), Duration::from_millis(2_000));
+    assert_eq!(policy.backoff(3), Duration::from_millis(3_000));
+    assert_eq!(policy.backoff(64), Duration::from_millis(3_000));
+}
+
+#[test]
+fn test_restart_modes() {
+    let on_failure = RestartPolicy::default();
+    assert!(on_failure.should_restart(0, true));
+    assert!(!on_failure.should_restart(0, false));
+    assert!(!on_failure.should_restart(on_failure.max_retries, true));
+
+    let always = RestartPolicy {
+        mode: RestartMode::Always,
+        ..RestartPolicy::default()
+    };
+    assert!(always.should_restart(0, false));
+
+    assert!(!RestartPolicy::never().should_restart(0, true));
+
+    // Configs written before restart policies existed restart on failure
+    let policy: RestartPolicy = serde_json::from_value(json!({ "mode": "always" })).unwrap();
+    assert_eq!(policy.mode, RestartMode::Always);
+    assert_eq!(policy.max_retries, RestartPolicy::default().max_retries);
+    let config: ServerConfig = serde_json::from_value(json!({
+        "id": "legacy",
+        "name": "Legacy",
+        "command": "docker",
+        "args": [],
+        "env": {},
+        "description": null,
+        "is_default": false
+    }))
+    .unwrap();
+    assert_eq!(config.restart, RestartPolicy::default());
+    assert_eq!(config.health, HealthCheckConfig::default());
+}
+
+#[tokio::test]
+async fn test_crashed_server_is_restarted() {
+    let (manager, connector) = create_manager();
+    manager
+        .start(&create_restartable_config("git", RestartMode::OnFailure, 3))
+        .await
+        .unwrap();
+
+    // The health check notices the crash and the restart is due right away,
+    // but fails while the server is still down
+    connector.set_down("git", true);
+    manager.supervise_once().await;
+    match manager.status("git") {
+        Some(ServerStatus::Failed(message)) => assert!(message.contains("connection refused")),
+        other => panic!("expected a failed status, got {:?}", other),
+    }
+    assert_eq!(connector.connects.load(Ordering::SeqCst), 2);
+    let retry = manager.retry_state("git").unwrap();
+    assert_eq!(retry.retries, 1);
+    assert!(retry.next_retry.is_some());
+
+    // Once the server is back the next round restarts it
+    connector.set_down("git", false);
+    manager.supervise_once().await;
+    assert_eq!(manager.status("git"), Some(ServerStatus::Running));
+    assert_eq!(connector.connects.load(Ordering::SeqCst), 3);
+    let retry = manager.retry_state("git").unwrap();
+    assert_eq!(retry.retries, 2);
+    assert_eq!(retry.next_retry, None);
+    assert!(retry.last_error.is_some());
+
+    // A passing health check resets the count
+    manager.supervise_once().await;
+    assert_eq!(manager.retry_state("git").unwrap().retries, 0);
+}
+
+#[tokio::test]
+async fn test_restarts_give_up_after_max_retries() {
+    let (manager, connector) = create_manager();
+    manager
+        .start(&create_restartable_config("git", RestartMode::OnFailure, 2))
+        .await
+        .unwrap();
+
+    connector.set_down("git", true);
+    for _ in 0..5 {
+        manager.supervise_once().await;
+    }
+
+    // One initial connect and two restarts
+    assert_eq!(connector.connects.load(Ordering::SeqCst), 3);
+    assert!(matches!(manager.status("git"), Some(ServerStatus::Failed(_))));
+    let retry = manager.retry_state("git").unwrap();
+    assert_eq!(retry.retries, 2);
+    assert_eq!(retry.next_retry, None);
+    assert!(retry.last_error.unwrap().contains("connection refused"));
+}
+
+#[tokio::test]
+async fn test_restart_mode_decides_which_failures_are_retried() {
+    let (manager, connector) = create_manager();
+
+    // Servers that never started are only retried in always mode
+    let _ = manager
+        .start(&create_restartable_config("broken", RestartMode::OnFailure, 3))
+        .await;
+    assert_eq!(manager.retry_state("broken").unwrap().next_retry, None);
+
+    manager.stop("broken").await;
+    let _ = manager
+        .start(&create_restartable_config("broken", RestartMode::Always, 3))
+        .await;
+    assert!(manager.retry_state("broken").unwrap().next_retry.is_some());
+
+    // Servers that must never restart stay failed
+    manager
+        .start(&create_restartable_config("git", RestartMode::Never, 3))
+        .await
+        .unwrap();
+    connector.set_down("git", true);
+    manager.supervise_once().await;
+    manager.supervise_once().await;
+    match manager.status("git") {
+        Some(ServerStatus::Failed(message)) => assert_eq!(message, "Connection lost: broken pipe"),
+        other => panic!("expected a failed status, got {:?}", other),
+    }
+    assert_eq!(manager.retry_state("git").unwrap().next_retry, None);
+}
+
+#[tokio::test]
+async fn test_stopping_cancels_a_pending_restart() {
+    let (manager, connector) = create_manager();
+    manager
+        .start(&create_restartable_config("git", RestartMode::OnFailure, 3))
+        .await
+        .unwrap();
+
+    connector.set_down("git", true);
+    manager.supervise_once().await;
+    assert!(manager.retry_state("git").unwrap().next_retry.is_some());
+    let connects = connector.connects.load(Ordering::SeqCst);
+
+    manager.stop("git").await;
+    connector.set_down("git", false);
+    manager.supervise_once().await;
+    assert_eq!(manager.status("git"), Some(ServerStatus::Stopped));
+    assert_eq!(connector.connects.load(Ordering::SeqCst), connects);
+}
+
+#[tokio::test]
+async fn test_stop_shuts_the_server_down() {
+    let (manager, connector) = create_manager();
+    manager.start(&create_config("filesystem")).await.unwrap();
+    manager.start(&create_config("git")).await.unwrap();
+
+    manager.stop("git").await;
+    assert_eq!(*connector.shutdowns.lock().unwrap(), vec!["git"]);
+    assert_eq!(manager.status("git"), Some(ServerStatus::Stopped));
+
+    // Stopping again has nothing left to shut down
+    manager.stop("git").await;
+    assert_eq!(connector.shutdowns.lock().unwrap().len(), 1);
+
+    manager.stop_all_blocking();
+    assert_eq!(*connector.shutdowns.lock().unwrap(), vec!["git", "filesystem"]);
+    assert_eq!(manager.status("filesystem"), Some(ServerStatus::Stopped));
+}
+
+#[tokio::test]
+async fn test_forced_shutdown_is_reported() {
+    let (manager, connector) = create_manager();
+    manager.start(&create_config("git")).await.unwrap();
+    connector.stubborn.lock().unwrap().insert("git".to_string());
+
+    manager.stop("git").await;
+    match manager.status("git") {
+        Some(ServerStatus::Terminated(outcome)) => assert!(outcome.starts_with("Killed")),
+        other => panic!("expected a terminated status, got {:?}", other),
+    }
+
+    // A terminated server can be started again
+    manager.start(&create_config("git")).await.unwrap();
+    assert_eq!(manager.status("git"), Some(ServerStatus::Running));
+}
+
+#[tokio::test]
+async fn test_failed_instance_is_shut_down_before_a_restart() {
+    let (manager, connector) = create_manager();
+    manager
+        .start(&create_restartable_config("git", RestartMode::OnFailure, 3))
+        .await
+        .unwrap();
+
+    // The server stops responding but its process is still around
+    connector.set_down("git", true);
+    manager.supervise_once().await;
+    assert_eq!(*connector.shutdowns.lock().unwrap(), vec!["git"]);
+
+    // The failed restart left nothing behind to shut down
+    connector.set_down("git", false);
+    manager.supervise_once().await;
+    assert_eq!(manager.status("git"), Some(ServerStatus::Running));
+    assert_eq!(connector.shutdowns.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_shutdown_outcomes() {
+    assert!(ShutdownOutcome::Exited(Some(1)).is_graceful());
+    assert!(ShutdownOutcome::Disconnected.is_graceful());
+    assert!(!ShutdownOutcome::Terminated.is_graceful());
+    assert!(!ShutdownOutcome::Killed.is_graceful());
+
+    assert_eq!(ShutdownOutcome::Exited(Some(1)).to_string(), "Exited with code 1");
+    assert!(ShutdownOutcome::Terminated.to_string().starts_with("Terminated after"));
+}
+
+#[tokio::test]
+async fn test_missed_pings_degrade_then_fail_the_server() {
+    let (manager, connector) = create_manager();
+    manager
+        .start(&create_restartable_config("git", RestartMode::Never, 3))
+        .await
+        .unwrap();
+
+    connector.set_slow("git", true);
+    manager.supervise_once().await;
+    match manager.status("git") {
+        Some(ServerStatus::Degraded(reason)) => assert!(reason.contains("1 of 3 missed")),
+        other => panic!("expected a degraded status, got {:?}", other),
+    }
+    // A degraded server keeps its client and tools
+    let snapshot = manager.snapshot();
+    assert!(snapshot.clients.contains_key("git"));
+    assert!(snapshot.server_tools.contains_key("git"));
+
+    manager.supervise_once().await;
+    assert!(matches!(manager.status("git"), Some(ServerStatus::Degraded(_))));
+    manager.supervise_once().await;
+    match manager.status("git") {
+        Some(ServerStatus::Failed(message)) => {
+            assert_eq!(message, "Connection lost: no answer to 3 pings in a row")
+        }
+        other => panic!("expected a failed status, got {:?}", other),
+    }
+    assert_eq!(manager.health("git").unwrap().missed, 3);
+}
+
+#[tokio::test]
+async fn test_answered_ping_recovers_a_degraded_server() {
+    let (manager, connector) = create_manager();
+    manager.start(&create_config("git")).await.unwrap();
+
+    connector.set_slow("git", true);
+    manager.supervise_once().await;
+    assert!(matches!(manager.status("git"), Some(ServerStatus::Degraded(_))));
+
+    connector.set_slow("git", false);
+    manager.supervise_once().await;
+    assert_eq!(manager.status("git"), Some(ServerStatus::Running));
+
+    let health = manager.health("git").unwrap();
+    assert_eq!(health.missed, 0);
+    assert_eq!(health.missed_total(), 1);
+    assert_eq!(health.history.len(), 2);
+    assert!(health.last_latency().is_some());
+    assert!(health.last_check.is_some());
+    assert_eq!(manager.snapshot().health["git"], health);
+}
+
+#[tokio::test]
+async fn test_pings_only_notify_of_status_changes() {
+    let (manager, connector) = create_manager();
+    manager.start(&create_config("git")).await.unwrap();
+    let mut changes = manager.subscribe();
+    let mut pings = manager.subscribe_health();
+
+    manager.supervise_once().await;
+    assert!(!changes.has_changed().unwrap());
+    assert!(pings.has_changed().unwrap());
+    pings.borrow_and_update();
+    assert_eq!(manager.health_all()["git"].history.len(), 1);
+
+    connector.set_slow("git", true);
+    manager.supervise_once().await;
+    assert!(changes.has_changed().unwrap());
+    assert!(pings.has_changed().unwrap());
+}
+
+#[tokio::test]
+async fn test_latency_history_is_bounded() {
+    let (manager, _) = create_manager();
+    manager.start(&create_config("git")).await.unwrap();
+
+    for _ in 0..HEALTH_HISTORY_LEN + 5 {
+        manager.supervise_once().await;
+    }
+
+    let health = manager.health("git").unwrap();
+    assert_eq!(health.history.len(), HEALTH_HISTORY_LEN);
+    assert!(health.average_latency().unwrap() <= health.max_latency().unwrap());
+}
+
+#[tokio::test]
+async fn test_pings_follow_the_server_settings() {
+    let (manager, connector) = create_manager();
+    let mut config = create_config("filesystem");
+    config.health.interval_ms = 60_000;
+    manager.start(&config).await.unwrap();
+    let mut disabled = create_config("git");
+    disabled.health.enabled = false;
+    manager.start(&disabled).await.unwrap();
+
+    // Neither the first interval has elapsed nor is the other server checked at all
+    manager.supervise_once().await;
+    manager.supervise_once().await;
+    assert_eq!(connector.pings.load(Ordering::SeqCst), 0);
+    assert_eq!(manager.health("filesystem").unwrap().history.len(), 0);
+}
+
+#[tokio::test]
+async fn test_busy_client_does_not_hold_up_the_health_check() {
+    let (manager, connector) = create_manager();
+    let client = manager
+        .start(&create_restartable_config("git", RestartMode::Never, 3))
+        .await
+        .unwrap();
+
+    // A long tool call keeps the client locked while the server goes away
+    let _busy = client.lock().await;
+    connector.set_down("git", true);
+    manager.supervise_once().await;
+
+    assert_eq!(connector.pings.load(Ordering::SeqCst), 1);
+    assert!(matches!(manager.status("git"), Some(ServerStatus::Failed(_))));
+}
+
+#[tokio::test]
+async fn test_config_diff_is_applied_to_the_servers() {
+    let (manager, connector) = create_manager();
+    let old = ServerConfigs {
+        servers: vec![create_config("filesystem"), create_config("git"), create_config("fetch")],
+        ..ServerConfigs::default()
+    };
+    manager.start_all(&old.servers).await;
+    manager.stop("fetch").await;
+
+    let mut git = create_config("git");
+    git.args = vec!["--repository".to_string(), "/tmp".to_string()];
+    let mut fetch = create_config("fetch");
+    fetch.args = vec!["--ignore-robots-txt".to_string()];
+    let new = ServerConfigs {
+        servers: vec![git.clone(), fetch.clone(), create_config("time")],
+        ..ServerConfigs::default()
+    };
+    let connects = connector.connects.load(Ordering::SeqCst);
+
+    manager.apply_config_diff(&old.diff(&new)).await;
+
+    // Removed servers are shut down and forgotten
+    assert!(connector.shutdowns.lock().unwrap().contains(&"filesystem".to_string()));
+    assert_eq!(manager.status("filesystem"), None);
+    // Running servers restart with their new configuration, stopped ones stay stopped
+    assert_eq!(manager.status("git"), Some(ServerStatus::Running));
+    assert_eq!(manager.config("git"), Some(git));
+    assert_eq!(manager.status("fetch"), Some(ServerStatus::Stopped));
+    assert_eq!(manager.config("fetch"), Some(fetch));
+    // Added servers are started
+    assert_eq!(manager.status("time"), Some(ServerStatus::Running));
+    assert_eq!(connector.connects.load(Ordering::SeqCst), connects + 2);
+    assert_eq!(manager.server_ids(), vec!["fetch", "git", "time"]);
+}
+
+#[tokio::test]
+async fn test_ping_on_demand() {
+    let (manager, connector) = create_manager();
+    manager.start(&create_config("git")).await.unwrap();
+
+    assert!(manager.ping("git").await.is_ok());
+    assert_eq!(connector.pings.load(Ordering::SeqCst), 1);
+    // Pinging outside the schedule doesn't count as a health check
+    assert!(manager.health("git").unwrap().history.is_empty());
+
+    connector.set_slow("git", true);
+    assert_eq!(manager.ping("git").await, Err(PingError::Timeout));
+    assert!(matches!(manager.ping("fetch").await, Err(PingError::Disconnected(_))));
+}
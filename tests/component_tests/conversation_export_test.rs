@@ -0,0 +1,108 @@
+use m_desk_new::components::agent::AgentStep;
+use m_desk_new::components::message::{Message, MessageRole};
+use m_desk_new::components::tool_manager::ToolInteraction;
+use m_desk_new::components::tool_suggestion::ToolExecutionStatus;
+use m_desk_new::conversation_export::{
+    export_file_name, from_json, to_json, to_markdown, write_export, ExportError, ExportFormat,
+};
+use m_desk_new::conversation_store::Conversation;
+use m_desk_new::openrouter::{FunctionCall, ToolCall};
+use serde_json::json;
+
+use crate::support::TempDir;
+
+fn create_conversation() -> Conversation {
+    let mut conversation = Conversation::new("openai/gpt-4o".to_string());
+    conversation.title = "Read the readme".to_string();
+    conversation.servers = vec!["filesystem".to_string()];
+    conversation.messages = vec![
+        Message::new(MessageRole::User, "What is in README.md?".to_string()),
+        Message::new(MessageRole::Assistant, "".to_string()).with_tool_calls(vec![ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "filesystem__read_file".to_string(),
+                arguments: "{\"path\": \"README.md\"}".to_string(),
+            },
+        }]),
+        Message::new(MessageRole::Tool, "Tool execution completed: read_file".to_string())
+            .with_tool_call_id(Some("call_1".to_string()))
+            .with_tool_interaction(ToolInteraction::Execution {
+                tool_name: "filesystem/read_file".to_string(),
+                arguments: json!({"path": "README.md"}),
+                status: ToolExecutionStatus::Completed,
+                result: Some("# mDesk\n```sh\ncargo run\n```".to_string()),
+                message_idx: 2,
+                step: Some(AgentStep {
+                    run_id: "run".to_string(),
+                    index: 1,
+                    max_steps: 5,
+                }),
+            }),
+        Message::new(MessageRole::Thinking, "".to_string()),
+        Message::new(MessageRole::Assistant, "It describes how to run mDesk.".to_string()),
+    ];
+    conversation
+}
+
+#[test]
+fn test_json_roundtrip_is_lossless() {
+    let conversation = create_conversation();
+    let json = to_json(&conversation).unwrap();
+    let imported = from_json(&json).unwrap();
+
+    // Imports get a fresh id, everything else is kept
+    assert_ne!(imported.id, conversation.id);
+    assert_eq!(Conversation { id: conversation.id.clone(), ..imported }, conversation);
+}
+
+#[test]
+fn test_import_drops_allowed_tools() {
+    let mut conversation = create_conversation();
+    conversation.allowed_tools = vec!["filesystem/write_file".to_string()];
+
+    let imported = from_json(&to_json(&conversation).unwrap()).unwrap();
+    assert!(imported.allowed_tools.is_empty());
+    assert_eq!(imported.messages, conversation.messages);
+}
+
+#[test]
+fn test_import_rejects_foreign_json() {
+    assert!(matches!(from_json("{not json"), Err(ExportError::Json(_))));
+
+    let conversation = serde_json::to_value(create_conversation()).unwrap();
+    let foreign = json!({ "format": "other", "version": 1, "exported_at": "2024-01-01T00:00:00Z", "conversation": conversation });
+    assert!(matches!(from_json(&foreign.to_string()), Err(ExportError::UnsupportedFormat(_))));
+
+    let future = json!({ "format": "mdesk-conversation", "version": 99, "exported_at": "2024-01-01T00:00:00Z", "conversation": conversation });
+    assert!(matches!(from_json(&future.to_string()), Err(ExportError::UnsupportedVersion(99))));
+}
+
+#[test]
+fn test_markdown_puts_tool_calls_in_fenced_blocks() {
+    let markdown = to_markdown(&create_conversation());
+
+    assert!(markdown.starts_with("# Read the readme\n"));
+    assert!(markdown.contains("- Servers: filesystem"));
+    assert!(markdown.contains("**Tool call** `filesystem__read_file` (id `call_1`)"));
+    assert!(markdown.contains("```json\n{\n  \"path\": \"README.md\"\n}\n```"));
+    assert!(markdown.contains("**Tool execution** `filesystem/read_file` completed (Step 1 of 5)"));
+    assert!(markdown.contains("It describes how to run mDesk."));
+    assert!(!markdown.contains("### Thinking"));
+
+    // Results containing fences get a longer fence so the block stays intact
+    assert!(markdown.contains("````\n# mDesk\n```sh\ncargo run\n```\n````"));
+}
+
+#[test]
+fn test_write_export_uses_title_for_file_name() {
+    let conversation = create_conversation();
+    let name = export_file_name(&conversation, ExportFormat::Markdown);
+    assert!(name.starts_with("read-the-readme-"));
+    assert!(name.ends_with(".md"));
+
+    let dir = TempDir::new("mdesk-exports");
+    let path = write_export(&dir, &conversation, ExportFormat::Json).unwrap();
+    let imported = from_json(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(imported.messages, conversation.messages);
+}
@@ -0,0 +1,131 @@
+use chrono::{Duration, Utc};
+use m_desk_new::components::message::{Message, MessageRole};
+use m_desk_new::components::tool_manager::ToolInteraction;
+use m_desk_new::components::tool_suggestion::ToolExecutionStatus;
+use m_desk_new::conversation_store::{Conversation, ConversationStore, DEFAULT_CONVERSATION_TITLE};
+use m_desk_new::openrouter::{FunctionCall, ToolCall};
+use serde_json::json;
+
+use crate::support::TempDir;
+
+fn create_conversation(first_message: &str) -> Conversation {
+    let mut conversation = Conversation::new("openai/gpt-4o".to_string());
+    conversation.messages.push(Message::new(MessageRole::User, first_message.to_string()));
+    conversation.derive_title();
+    conversation
+}
+
+#[test]
+fn test_save_and_load_roundtrip_keeps_tool_interactions() {
+    let dir = TempDir::new("mdesk-conversations");
+    let store = ConversationStore::new(&dir);
+    let mut conversation = create_conversation("Read main.rs");
+    conversation.servers = vec!["filesystem".to_string()];
+    conversation.messages.push(
+        Message::new(MessageRole::Assistant, "".to_string()).with_tool_calls(vec![ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "filesystem__read_file".to_string(),
+                arguments: "{\"path\": \"main.rs\"}".to_string(),
+            },
+        }]),
+    );
+    conversation.messages.push(
+        Message::new(MessageRole::Tool, "Tool execution completed: read_file".to_string())
+            .with_tool_call_id(Some("call_1".to_string()))
+            .with_tool_interaction(ToolInteraction::Execution {
+                tool_name: "filesystem/read_file".to_string(),
+                arguments: json!({"path": "main.rs"}),
+                status: ToolExecutionStatus::Failed("file not found".to_string()),
+                result: None,
+                message_idx: 2,
+                step: None,
+            }),
+    );
+
+    store.save(&conversation).unwrap();
+    let loaded = store.load(&conversation.id).unwrap();
+    assert_eq!(loaded, conversation);
+}
+
+#[test]
+fn test_list_orders_by_last_update() {
+    let dir = TempDir::new("mdesk-conversations");
+    let store = ConversationStore::new(&dir);
+    assert!(store.list().unwrap().is_empty());
+    assert!(store.most_recent().is_none());
+
+    let mut older = create_conversation("First question");
+    older.updated_at = Utc::now() - Duration::hours(1);
+    let newer = create_conversation("Second question");
+    store.save(&newer).unwrap();
+    store.save(&older).unwrap();
+
+    let summaries = store.list().unwrap();
+    assert_eq!(summaries.len(), 2);
+    assert_eq!(summaries[0].id, newer.id);
+    assert_eq!(summaries[0].message_count, 1);
+    assert_eq!(store.most_recent().map(|c| c.id), Some(newer.id));
+}
+
+#[test]
+fn test_search_matches_titles_and_messages() {
+    let dir = TempDir::new("mdesk-conversations");
+    let store = ConversationStore::new(&dir);
+    let mut conversation = create_conversation("Deploy the app");
+    conversation.messages.push(Message::new(MessageRole::Assistant, "Run cargo build first".to_string()));
+    store.save(&conversation).unwrap();
+    store.save(&create_conversation("Unrelated")).unwrap();
+
+    assert_eq!(store.search("deploy").unwrap().len(), 1);
+    assert_eq!(store.search("CARGO").unwrap().len(), 1);
+    assert_eq!(store.search("").unwrap().len(), 2);
+    assert!(store.search("kubernetes").unwrap().is_empty());
+}
+
+#[test]
+fn test_rename_and_delete() {
+    let dir = TempDir::new("mdesk-conversations");
+    let store = ConversationStore::new(&dir);
+    let conversation = create_conversation("Hello");
+    store.save(&conversation).unwrap();
+
+    store.rename(&conversation.id, "  Greetings  ").unwrap();
+    assert_eq!(store.load(&conversation.id).unwrap().title, "Greetings");
+    assert!(store.rename(&conversation.id, "   ").is_err());
+
+    store.delete(&conversation.id).unwrap();
+    assert!(store.load(&conversation.id).is_err());
+    assert!(store.list().unwrap().is_empty());
+}
+
+#[test]
+fn test_derive_title_from_first_user_message() {
+    let mut conversation = Conversation::new("openai/gpt-4o".to_string());
+    conversation.derive_title();
+    assert_eq!(conversation.title, DEFAULT_CONVERSATION_TITLE);
+
+    conversation.messages.push(Message::new(MessageRole::User, format!("{}\nsecond line", "word ".repeat(20))));
+    conversation.derive_title();
+    assert!(conversation.title.ends_with("..."));
+    assert!(conversation.title.chars().count() <= 63);
+    assert!(!conversation.title.contains("second line"));
+
+    // A renamed conversation keeps its title
+    conversation.title = "Custom".to_string();
+    conversation.derive_title();
+    assert_eq!(conversation.title, "Custom");
+}
+
+#[test]
+fn test_rejects_ids_outside_the_store() {
+    let dir = TempDir::new("mdesk-conversations");
+    let store = ConversationStore::new(&dir);
+    assert!(store.load("../secrets").is_err());
+    assert!(store.delete("").is_err());
+
+    let mut conversation = create_conversation("Hello");
+    conversation.id = "../escape".to_string();
+    assert!(store.save(&conversation).is_err());
+}
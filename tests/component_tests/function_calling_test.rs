@@ -0,0 +1,193 @@
+use m_desk_new::components::message::{Message, MessageRole};
+use m_desk_new::components::tool_manager::{ToolInteraction, ToolManager};
+use m_desk_new::components::tool_suggestion::ToolExecutionStatus;
+use m_desk_new::openrouter::{
+    function_name, ChatMessage, FunctionCall, FunctionNames, OpenRouterClient, ToolCall, ToolChoice, ToolDefinition,
+};
+use mcp_core::Tool;
+use serde_json::json;
+
+fn create_tool(name: &str) -> Tool {
+    Tool {
+        name: name.to_string(),
+        description: format!("The {} tool", name),
+        input_schema: json!({
+            "type": "object",
+            "properties": { "path": { "type": "string" } },
+            "required": ["path"]
+        }),
+    }
+}
+
+fn create_tool_call(id: &str, name: &str, arguments: &str) -> ToolCall {
+    ToolCall {
+        id: id.to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+        },
+    }
+}
+
+#[test]
+fn test_function_name_encodes_qualified_names() {
+    assert_eq!(function_name("read_file"), "read_file");
+    assert_eq!(function_name("filesystem/read_file"), "filesystem__read_file");
+    assert_eq!(function_name("my tool.v2"), "my_tool_v2");
+    assert_eq!(function_name(&"a".repeat(100)).len(), 64);
+}
+
+#[test]
+fn test_function_names_keep_colliding_tools_apart() {
+    let long_name = "a".repeat(100);
+    let tools = vec![
+        create_tool("a.b"),
+        create_tool("a_b"),
+        create_tool("srv/x"),
+        create_tool("srv__x"),
+        create_tool(&long_name),
+        create_tool(&format!("{}b", long_name)),
+    ];
+    let names = FunctionNames::new(&tools);
+
+    // Names that are valid already are kept
+    assert_eq!(names.function_name("a_b"), Some("a_b"));
+    assert_eq!(names.function_name("a.b"), Some("a_b_2"));
+    assert_eq!(names.function_name("srv__x"), Some("srv__x"));
+    assert_eq!(names.function_name("srv/x"), Some("srv__x_2"));
+
+    let definitions = ToolDefinition::from_tools(&tools, &names);
+    let mut function_names: Vec<&str> = definitions.iter().map(|d| d.function.name.as_str()).collect();
+    assert!(function_names.iter().all(|name| name.len() <= 64));
+    function_names.sort();
+    function_names.dedup();
+    assert_eq!(function_names.len(), tools.len());
+
+    // Every name leads back to its own tool
+    for definition in &definitions {
+        let tool = ToolManager::find_tool_by_function_name(&definition.function.name, &names, &tools).unwrap();
+        assert_eq!(names.function_name(&tool.name), Some(definition.function.name.as_str()));
+    }
+    assert!(ToolManager::find_tool_by_function_name("a_b_3", &names, &tools).is_none());
+}
+
+#[test]
+fn test_tool_definition_uses_input_schema() {
+    let tool = create_tool("git/read_file");
+    let definition = ToolDefinition::from_tool(&tool);
+
+    assert_eq!(definition.tool_type, "function");
+    assert_eq!(definition.function.name, "git__read_file");
+    assert_eq!(definition.function.parameters, tool.input_schema);
+
+    let tools = vec![tool, create_tool("list_directory")];
+    let names = FunctionNames::new(&tools);
+    let by_function = ToolManager::find_tool_by_function_name("git__read_file", &names, &tools);
+    assert_eq!(by_function.map(|t| t.name.as_str()), Some("git/read_file"));
+}
+
+#[test]
+fn test_request_serializes_tools_only_when_present() {
+    let messages = vec![ChatMessage::new("user", "Read main.rs".to_string())];
+
+    let request = OpenRouterClient::build_request(
+        "openai/gpt-4o",
+        messages.clone(),
+        Some(vec![ToolDefinition::from_tool(&create_tool("read_file"))]),
+        ToolChoice::Auto,
+        Some(0.7),
+        None,
+    );
+    let value = serde_json::to_value(&request).unwrap();
+    assert_eq!(value["tools"][0]["type"], "function");
+    assert_eq!(value["tools"][0]["function"]["name"], "read_file");
+    assert_eq!(value["tool_choice"], "auto");
+    assert!(value["messages"][0].get("tool_calls").is_none());
+
+    // Tools stay defined for a final answer, the history may hold calls of them
+    let request = OpenRouterClient::build_request(
+        "openai/gpt-4o",
+        messages.clone(),
+        Some(vec![ToolDefinition::from_tool(&create_tool("read_file"))]),
+        ToolChoice::None,
+        None,
+        None,
+    );
+    let value = serde_json::to_value(&request).unwrap();
+    assert_eq!(value["tools"].as_array().unwrap().len(), 1);
+    assert_eq!(value["tool_choice"], "none");
+
+    let request = OpenRouterClient::build_request("openai/gpt-4o", messages, Some(vec![]), ToolChoice::Auto, None, None);
+    let value = serde_json::to_value(&request).unwrap();
+    assert!(value.get("tools").is_none());
+    assert!(value.get("tool_choice").is_none());
+}
+
+#[test]
+fn test_response_with_tool_calls_deserializes() {
+    let message: ChatMessage = serde_json::from_value(json!({
+        "role": "assistant",
+        "content": null,
+        "tool_calls": [{
+            "id": "call_1",
+            "type": "function",
+            "function": { "name": "read_file", "arguments": "{\"path\": \"main.rs\"}" }
+        }]
+    }))
+    .unwrap();
+
+    assert_eq!(message.content, "");
+    let tool_call = &message.tool_calls.unwrap()[0];
+    assert_eq!(tool_call.id, "call_1");
+    assert_eq!(tool_call.parsed_arguments(), json!({"path": "main.rs"}));
+
+    let broken = create_tool_call("call_2", "read_file", "{not json");
+    assert_eq!(broken.parsed_arguments(), json!({}));
+}
+
+#[test]
+fn test_history_pairs_tool_calls_with_results() {
+    let tool_call = create_tool_call("call_1", "read_file", "{\"path\": \"main.rs\"}");
+    let messages = vec![
+        Message::new(MessageRole::User, "Read main.rs".to_string()),
+        Message::new(MessageRole::Assistant, "".to_string())
+            .with_tool_calls(vec![tool_call]),
+        Message::new(MessageRole::Tool, "Tool execution completed: read_file".to_string())
+            .with_tool_call_id(Some("call_1".to_string()))
+            .with_tool_interaction(ToolInteraction::Execution {
+                tool_name: "read_file".to_string(),
+                arguments: json!({"path": "main.rs"}),
+                status: ToolExecutionStatus::Completed,
+                result: Some("fn main() {}".to_string()),
+                message_idx: 2,
+                step: None,
+            }),
+        Message::new(MessageRole::Thinking, "".to_string()),
+    ];
+
+    let history = Message::history_for_model(&messages);
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[1].role, "assistant");
+    assert_eq!(history[1].tool_calls.as_ref().map(|c| c.len()), Some(1));
+    assert_eq!(history[2].role, "tool");
+    assert_eq!(history[2].tool_call_id.as_deref(), Some("call_1"));
+    assert_eq!(history[2].content, "fn main() {}");
+}
+
+#[test]
+fn test_history_drops_unanswered_tool_calls() {
+    let tool_call = create_tool_call("call_1", "read_file", "{}");
+    let messages = vec![
+        Message::new(MessageRole::User, "Read main.rs".to_string()),
+        Message::new(MessageRole::Assistant, "Let me read it.".to_string())
+            .with_tool_calls(vec![tool_call]),
+        Message::new(MessageRole::System, "Tool usage rejected: read_file".to_string()),
+    ];
+
+    let history = Message::history_for_model(&messages);
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[1].content, "Let me read it.");
+    assert!(history[1].tool_calls.is_none());
+    assert!(history.iter().all(|m| m.role != "tool"));
+}
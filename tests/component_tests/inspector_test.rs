@@ -0,0 +1,198 @@
+use futures::future::{ready, Ready};
+use m_desk_new::inspector::{
+    from_jsonl, to_jsonl, write_jsonl, Direction, Inspector, InspectorFilter, MessageKind,
+};
+use mcp_core::protocol::JsonRpcMessage;
+use serde_json::json;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::support::TempDir;
+
+/// Answers every request with a fixed message, or fails
+#[derive(Clone)]
+struct FakeService {
+    answer: Result<JsonRpcMessage, String>,
+}
+
+impl Service<JsonRpcMessage> for FakeService {
+    type Response = JsonRpcMessage;
+    type Error = String;
+    type Future = Ready<Result<JsonRpcMessage, String>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), String>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _message: JsonRpcMessage) -> Self::Future {
+        ready(self.answer.clone())
+    }
+}
+
+fn message(value: serde_json::Value) -> JsonRpcMessage {
+    serde_json::from_value(value).expect("valid JSON-RPC message")
+}
+
+fn request(id: u64, method: &str) -> JsonRpcMessage {
+    message(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": {} }))
+}
+
+fn response(id: u64) -> JsonRpcMessage {
+    message(json!({ "jsonrpc": "2.0", "id": id, "result": { "tools": [] } }))
+}
+
+#[tokio::test]
+async fn test_service_records_request_and_response() {
+    let inspector = Inspector::default();
+    let mut service = inspector.layer("git").layer(FakeService { answer: Ok(response(7)) });
+
+    let answer = service.call(request(7, "tools/list")).await.unwrap();
+    assert!(matches!(answer, JsonRpcMessage::Response(_)));
+
+    let entries = inspector.entries();
+    assert_eq!(entries.len(), 2);
+
+    let sent = &entries[0];
+    assert_eq!(sent.server_id, "git");
+    assert_eq!(sent.direction, Direction::Outgoing);
+    assert_eq!(sent.kind, MessageKind::Request);
+    assert_eq!(sent.method.as_deref(), Some("tools/list"));
+    assert_eq!(sent.request_id, Some(7));
+    assert_eq!(sent.duration_ms, None);
+
+    // The response is annotated with the request it answers
+    let received = &entries[1];
+    assert_eq!(received.direction, Direction::Incoming);
+    assert_eq!(received.kind, MessageKind::Response);
+    assert_eq!(received.method.as_deref(), Some("tools/list"));
+    assert_eq!(received.request_id, Some(7));
+    assert!(received.duration_ms.is_some());
+    assert_eq!(received.message["result"], json!({ "tools": [] }));
+    assert!(received.seq > sent.seq);
+}
+
+#[tokio::test]
+async fn test_service_records_notifications_and_failures() {
+    let inspector = Inspector::default();
+
+    let mut acknowledging = inspector.layer("git").layer(FakeService { answer: Ok(JsonRpcMessage::Nil) });
+    let notification = message(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }));
+    acknowledging.call(notification).await.unwrap();
+
+    let mut failing = inspector.layer("fetch").layer(FakeService { answer: Err("request timed out".to_string()) });
+    let failed = failing.call(request(3, "tools/call")).await;
+    assert_eq!(failed.unwrap_err(), "request timed out");
+
+    let entries = inspector.entries();
+    let kinds: Vec<MessageKind> = entries.iter().map(|entry| entry.kind).collect();
+    // Nothing is recorded for the acknowledgement of a notification
+    assert_eq!(kinds, vec![MessageKind::Notification, MessageKind::Request, MessageKind::Error]);
+
+    let error = &entries[2];
+    assert_eq!(error.server_id, "fetch");
+    assert_eq!(error.method.as_deref(), Some("tools/call"));
+    assert_eq!(error.request_id, Some(3));
+    assert_eq!(error.message["error"], "request timed out");
+}
+
+#[test]
+fn test_filter_by_server_kind_and_text() {
+    let inspector = Inspector::default();
+    inspector.record("git", Direction::Outgoing, &request(1, "tools/list"), None, None);
+    inspector.record("git", Direction::Incoming, &response(1), Some("tools/list".to_string()), Some(4));
+    inspector.record("fetch", Direction::Outgoing, &request(1, "resources/list"), None, None);
+
+    assert_eq!(inspector.filtered(&InspectorFilter::default()).len(), 3);
+    assert_eq!(inspector.server_ids(), vec!["fetch", "git"]);
+
+    let git = InspectorFilter {
+        server_id: Some("git".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(inspector.filtered(&git).len(), 2);
+
+    let requests = InspectorFilter {
+        kind: Some(MessageKind::Request),
+        ..Default::default()
+    };
+    assert_eq!(inspector.filtered(&requests).len(), 2);
+
+    // Text matches the method of responses too, ignoring case
+    let tools = InspectorFilter {
+        query: "TOOLS/".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(inspector.filtered(&tools).len(), 2);
+
+    let none = InspectorFilter {
+        server_id: Some("fetch".to_string()),
+        query: "tools".to_string(),
+        ..Default::default()
+    };
+    assert!(inspector.filtered(&none).is_empty());
+}
+
+#[test]
+fn test_recording_is_bounded_and_clearable() {
+    let inspector = Inspector::new(3);
+    let mut changes = inspector.subscribe();
+    for id in 1..=5 {
+        inspector.record("git", Direction::Outgoing, &request(id, "ping"), None, None);
+    }
+    assert!(changes.has_changed().unwrap());
+
+    let ids: Vec<Option<u64>> = inspector.entries().iter().map(|entry| entry.request_id).collect();
+    assert_eq!(ids, vec![Some(3), Some(4), Some(5)]);
+
+    inspector.clear();
+    assert!(inspector.entries().is_empty());
+
+    // Sequence numbers keep increasing after a clear
+    inspector.record("git", Direction::Outgoing, &request(6, "ping"), None, None);
+    assert_eq!(inspector.entries()[0].seq, 6);
+}
+
+#[test]
+fn test_jsonl_export_round_trip() {
+    let inspector = Inspector::default();
+    inspector.record("git", Direction::Outgoing, &request(1, "tools/list"), None, None);
+    inspector.record_failure("git", Some("tools/list".to_string()), Some(1), "connection closed", 12);
+    let entries = inspector.entries();
+
+    let jsonl = to_jsonl(&entries).unwrap();
+    assert_eq!(jsonl.lines().count(), 2);
+    let first: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+    assert_eq!(first["direction"], "outgoing");
+    assert_eq!(first["kind"], "request");
+    assert_eq!(first["message"]["method"], "tools/list");
+
+    assert_eq!(from_jsonl(&format!("{}\n\n", jsonl)).unwrap(), entries);
+
+    let dir = TempDir::new("mdesk-inspector");
+    let path = write_jsonl(&dir, &entries).unwrap();
+    assert_eq!(path.extension().and_then(|ext| ext.to_str()), Some("jsonl"));
+    assert_eq!(from_jsonl(&std::fs::read_to_string(&path).unwrap()).unwrap(), entries);
+}
+
+#[tokio::test]
+async fn test_tool_annotations_are_kept_from_tools_list() {
+    let inspector = Inspector::default();
+    let listed = message(json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "result": { "tools": [
+            { "name": "read_file", "inputSchema": {}, "annotations": { "readOnlyHint": true } },
+            { "name": "delete_file", "inputSchema": {}, "annotations": { "destructiveHint": true } },
+            { "name": "touch", "inputSchema": {} }
+        ] }
+    }));
+    let mut service = inspector.layer("filesystem").layer(FakeService { answer: Ok(listed) });
+    service.call(request(3, "tools/list")).await.unwrap();
+    inspector.clear();
+
+    let annotations = inspector.annotations();
+    assert_eq!(annotations.get("filesystem", "read_file").read_only, Some(true));
+    assert_eq!(annotations.get("filesystem", "delete_file").destructive, Some(true));
+    assert_eq!(annotations.get("filesystem", "touch").read_only, None);
+    assert_eq!(annotations.get("git", "read_file").read_only, None);
+}
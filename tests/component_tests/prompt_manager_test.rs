@@ -0,0 +1,83 @@
+use m_desk_new::components::message::MessageRole;
+use m_desk_new::components::prompt_manager::{PromptError, PromptManager};
+use mcp_core::prompt::{Prompt, PromptArgument, PromptMessage, PromptMessageContent, PromptMessageRole};
+use serde_json::json;
+use std::collections::HashMap;
+
+fn create_prompt() -> Prompt {
+    Prompt {
+        name: "review_code".to_string(),
+        description: Some("Review a file".to_string()),
+        arguments: Some(vec![
+            PromptArgument {
+                name: "path".to_string(),
+                description: Some("File to review".to_string()),
+                required: Some(true),
+            },
+            PromptArgument {
+                name: "focus".to_string(),
+                description: None,
+                required: None,
+            },
+        ]),
+    }
+}
+
+fn values(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn test_build_arguments_from_form_values() {
+    let prompt = create_prompt();
+
+    let arguments = PromptManager::build_arguments(&prompt, &values(&[("path", " src/main.rs "), ("focus", "")])).unwrap();
+    assert_eq!(arguments, json!({"path": "src/main.rs"}));
+
+    let arguments = PromptManager::build_arguments(&prompt, &values(&[("path", "a.rs"), ("focus", "errors")])).unwrap();
+    assert_eq!(arguments, json!({"path": "a.rs", "focus": "errors"}));
+}
+
+#[test]
+fn test_build_arguments_requires_required_values() {
+    let prompt = create_prompt();
+
+    let result = PromptManager::build_arguments(&prompt, &values(&[("focus", "errors")]));
+    assert!(matches!(result, Err(PromptError::MissingArgument(name)) if name == "path"));
+
+    let result = PromptManager::build_arguments(&prompt, &values(&[("path", "   ")]));
+    assert!(matches!(result, Err(PromptError::MissingArgument(_))));
+}
+
+#[test]
+fn test_build_arguments_without_argument_definitions() {
+    let prompt = Prompt {
+        name: "greeting".to_string(),
+        description: None,
+        arguments: None,
+    };
+
+    let arguments = PromptManager::build_arguments(&prompt, &values(&[("ignored", "value")])).unwrap();
+    assert_eq!(arguments, json!({}));
+}
+
+#[test]
+fn test_prompt_messages_become_chat_messages() {
+    let prompt_messages = vec![
+        PromptMessage {
+            role: PromptMessageRole::Assistant,
+            content: PromptMessageContent::Text { text: "I review code carefully.".to_string() },
+        },
+        PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::Text { text: "Review src/main.rs".to_string() },
+        },
+    ];
+
+    let messages = PromptManager::to_chat_messages(&prompt_messages);
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].role, MessageRole::Assistant);
+    assert_eq!(messages[0].content, "I review code carefully.");
+    assert_eq!(messages[1].role, MessageRole::User);
+    assert_eq!(messages[1].content, "Review src/main.rs");
+}
@@ -0,0 +1,256 @@
+use async_trait::async_trait;
+use m_desk_new::components::message::{Message, MessageRole};
+use m_desk_new::resources::mcp::{mcp_uri, parse_mcp_uri};
+use m_desk_new::resources::{
+    estimate_tokens, uri_scheme, AttachmentContent, LocalResourceProvider, Resource, ResourceAttachment,
+    ResourceContent, ResourceError, ResourceMetadata, ResourceProvider, ResourceProviderCapabilities,
+    ResourceProviderRegistry, ResourceQuery, ResourceService, SqliteResourceProvider,
+};
+use std::sync::Arc;
+
+use crate::support::TempDir;
+
+/// Provider serving a fixed set of resources under its own scheme
+struct StaticProvider {
+    scheme: &'static str,
+    resources: Vec<Resource>,
+}
+
+#[async_trait]
+impl ResourceProvider for StaticProvider {
+    async fn list_resources(&self, query: Option<ResourceQuery>) -> Result<Vec<Resource>, ResourceError> {
+        Ok(query.unwrap_or_default().apply(self.resources.clone()))
+    }
+
+    async fn read_resource(&self, uri: &str) -> Result<ResourceContent, ResourceError> {
+        self.resources
+            .iter()
+            .find(|r| r.uri == uri)
+            .map(|r| ResourceContent::Text(format!("content of {}", r.name)))
+            .ok_or_else(|| ResourceError::NotFound(uri.to_string()))
+    }
+
+    fn supports_scheme(&self, scheme: &str) -> bool {
+        scheme == self.scheme
+    }
+
+    fn capabilities(&self) -> ResourceProviderCapabilities {
+        ResourceProviderCapabilities { readable: true, ..Default::default() }
+    }
+}
+
+fn create_resource(uri: &str, name: &str) -> Resource {
+    Resource {
+        uri: uri.to_string(),
+        name: name.to_string(),
+        description: None,
+        mime_type: "text/plain".to_string(),
+        provider: String::new(),
+        metadata: ResourceMetadata::default(),
+    }
+}
+
+#[test]
+fn test_uri_scheme() {
+    assert_eq!(uri_scheme("file:///tmp/notes.txt"), Some("file"));
+    assert_eq!(uri_scheme("mcp://git/repo://log"), Some("mcp"));
+    assert_eq!(uri_scheme("sqlite://users"), Some("sqlite"));
+    assert_eq!(uri_scheme("no scheme here"), None);
+    assert_eq!(uri_scheme(":missing"), None);
+}
+
+#[test]
+fn test_mcp_uris_keep_the_server_uri() {
+    let uri = mcp_uri("filesystem", "file:///tmp/notes.txt");
+    assert_eq!(uri, "mcp://filesystem/file:///tmp/notes.txt");
+    assert_eq!(parse_mcp_uri(&uri).unwrap(), ("filesystem", "file:///tmp/notes.txt"));
+
+    assert!(parse_mcp_uri("mcp://filesystem").is_err());
+    assert!(parse_mcp_uri("file:///tmp/notes.txt").is_err());
+}
+
+#[tokio::test]
+async fn test_registry_routes_by_scheme() {
+    let mut registry = ResourceProviderRegistry::new();
+    registry.register("notes", Arc::new(StaticProvider {
+        scheme: "notes",
+        resources: vec![create_resource("notes://todo", "todo")],
+    }));
+    registry.register("docs", Arc::new(StaticProvider {
+        scheme: "docs",
+        resources: vec![create_resource("docs://readme", "readme")],
+    }));
+    registry.map_scheme("memo", "notes");
+
+    assert_eq!(registry.provider_name_for_uri("docs://readme").as_deref(), Some("docs"));
+    assert_eq!(registry.provider_name_for_uri("memo://x").as_deref(), Some("notes"));
+    assert!(registry.provider_for_uri("ftp://example.com/file").is_none());
+    assert_eq!(registry.list_providers().len(), 2);
+
+    let service = ResourceService::new(registry);
+    let listing = service.list_resources(None, None).await.unwrap();
+    assert_eq!(listing.resources.len(), 2);
+    assert!(listing.resources.iter().any(|r| r.provider == "docs" && r.name == "readme"));
+
+    let content = service.read_resource("notes://todo").await.unwrap();
+    assert_eq!(content, ResourceContent::Text("content of todo".to_string()));
+    assert!(matches!(service.read_resource("ftp://x").await, Err(ResourceError::NoProvider(_))));
+    assert!(matches!(service.list_resources(None, Some("missing")).await, Err(ResourceError::NoProvider(_))));
+
+    let found = service.search(ResourceQuery::text("READ")).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].uri, "docs://readme");
+}
+
+#[test]
+fn test_query_filters() {
+    let mut tagged = create_resource("notes://a", "alpha");
+    tagged.metadata.tags = vec!["work".to_string()];
+    tagged.metadata.priority = Some(0.9);
+    let plain = create_resource("notes://b", "beta");
+
+    let query = ResourceQuery { tags: Some(vec!["work".to_string()]), ..Default::default() };
+    assert!(query.matches(&tagged));
+    assert!(!query.matches(&plain));
+
+    let query = ResourceQuery { min_priority: Some(0.5), ..Default::default() };
+    assert!(query.matches(&tagged));
+    assert!(!query.matches(&plain));
+
+    let query = ResourceQuery { limit: Some(1), ..Default::default() };
+    assert_eq!(query.apply(vec![tagged, plain]).len(), 1);
+}
+
+#[tokio::test]
+async fn test_local_provider_lists_and_reads_files_below_root() {
+    let root = TempDir::new("mdesk-local");
+    std::fs::write(root.join("notes.txt"), "Remember the milk").unwrap();
+    std::fs::create_dir(root.join("images")).unwrap();
+    std::fs::write(root.join("images").join("logo.png"), [0x89u8, 0x50, 0x4e, 0x47, 0xff]).unwrap();
+    std::fs::write(root.join(".hidden"), "secret").unwrap();
+
+    let provider = LocalResourceProvider::new(&root);
+    let resources = provider.list_resources(None).await.unwrap();
+    let names: Vec<&str> = resources.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, vec!["logo.png", "notes.txt"]);
+    assert_eq!(resources[0].mime_type, "image/png");
+
+    let notes = resources.iter().find(|r| r.name == "notes.txt").unwrap();
+    let content = provider.read_resource(&notes.uri).await.unwrap();
+    assert_eq!(content, ResourceContent::Text("Remember the milk".to_string()));
+
+    let logo = resources.iter().find(|r| r.name == "logo.png").unwrap();
+    assert!(matches!(provider.read_resource(&logo.uri).await.unwrap(), ResourceContent::Binary(data) if data.len() == 5));
+
+    // Files outside the root can't be read through the provider
+    let outside = LocalResourceProvider::file_uri(&root.join("..").join("elsewhere.txt"));
+    assert!(matches!(provider.read_resource(&outside).await, Err(ResourceError::PermissionDenied(_)) | Err(ResourceError::NotFound(_))));
+    assert!(matches!(provider.read_resource("http://example.com").await, Err(ResourceError::InvalidUri(_))));
+
+    let written = provider
+        .write_resource(&LocalResourceProvider::file_uri(&root.join("new.txt")), ResourceContent::Text("hi".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(written.name, "new.txt");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_local_provider_skips_symlinks() {
+    let root = TempDir::new("mdesk-local");
+    let outside = TempDir::new("mdesk-outside");
+    std::fs::write(outside.join("secret.txt"), "not for the model").unwrap();
+    std::fs::write(root.join("notes.txt"), "Remember the milk").unwrap();
+    std::os::unix::fs::symlink(&outside, root.join("linked-dir")).unwrap();
+    std::os::unix::fs::symlink(outside.join("secret.txt"), root.join("linked.txt")).unwrap();
+
+    let resources = LocalResourceProvider::new(&root).list_resources(None).await.unwrap();
+    let names: Vec<&str> = resources.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, vec!["notes.txt"]);
+}
+
+#[tokio::test]
+async fn test_sqlite_provider_exposes_tables_as_json() {
+    let dir = TempDir::new("mdesk-sqlite");
+    let path = dir.join("app.db");
+    {
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, score REAL);
+                 INSERT INTO users (name, score) VALUES ('ada', 9.5), ('linus', NULL);",
+            )
+            .unwrap();
+    }
+
+    let provider = SqliteResourceProvider::new(&path);
+    let resources = provider.list_resources(None).await.unwrap();
+    assert_eq!(resources.len(), 1);
+    assert_eq!(resources[0].uri, "sqlite://users");
+    assert_eq!(resources[0].description.as_deref(), Some("table with 2 rows"));
+
+    let ResourceContent::Text(json) = provider.read_resource("sqlite://users").await.unwrap() else {
+        panic!("expected JSON text");
+    };
+    let rows: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(rows[0]["name"], "ada");
+    assert_eq!(rows[1]["score"], serde_json::Value::Null);
+
+    assert!(matches!(provider.read_resource("sqlite://missing").await, Err(ResourceError::NotFound(_))));
+    assert!(matches!(provider.read_resource("sqlite://users; DROP TABLE users").await, Err(ResourceError::NotFound(_))));
+}
+
+#[test]
+fn test_estimate_tokens_rounds_up() {
+    assert_eq!(estimate_tokens(""), 0);
+    assert_eq!(estimate_tokens("abc"), 1);
+    assert_eq!(estimate_tokens("abcdefgh"), 2);
+    assert_eq!(estimate_tokens("abcdefghi"), 3);
+}
+
+#[test]
+fn test_attachment_from_text_and_binary_content() {
+    let mut notes = create_resource("file:///notes.txt", "notes.txt");
+    notes.provider = "local".to_string();
+    let attachment = ResourceAttachment::new(&notes, &ResourceContent::Text("Remember the milk".to_string()));
+    assert_eq!(attachment.provider, "local");
+    assert_eq!(attachment.contents, vec![AttachmentContent::Text("Remember the milk".to_string())]);
+
+    let context = attachment.context_text();
+    assert!(context.starts_with("<resource uri=\"file:///notes.txt\" name=\"notes.txt\">"));
+    assert!(context.contains("Remember the milk"));
+    assert_eq!(attachment.estimated_tokens(), estimate_tokens(&context));
+
+    let mut logo = create_resource("file:///logo.png", "logo.png");
+    logo.mime_type = "image/png".to_string();
+    let attachment = ResourceAttachment::new(&logo, &ResourceContent::Binary(vec![0; 5]));
+    assert_eq!(
+        attachment.contents,
+        vec![AttachmentContent::Blob { mime_type: Some("image/png".to_string()), size_bytes: 5 }]
+    );
+    assert!(attachment.context_text().contains("[Binary content omitted: 5 bytes of image/png]"));
+}
+
+#[test]
+fn test_attachments_are_sent_with_the_user_message() {
+    let attachment = ResourceAttachment::new(
+        &create_resource("file:///notes.txt", "notes.txt"),
+        &ResourceContent::Text("Remember the milk".to_string()),
+    );
+    let message = Message::new(MessageRole::User, "Summarize my notes".to_string())
+        .with_attachments(vec![attachment]);
+
+    let history = Message::history_for_model(&[message.clone()]);
+    assert_eq!(history.len(), 1);
+    assert!(history[0].content.starts_with("Summarize my notes"));
+    assert!(history[0].content.contains("Remember the milk"));
+
+    // Attachments survive saving the conversation
+    let json = serde_json::to_string(&message).unwrap();
+    let restored: Message = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.attachments, message.attachments);
+
+    // Messages without attachments are sent unchanged
+    let plain = Message::new(MessageRole::User, "Hello".to_string());
+    assert_eq!(plain.to_openrouter_format().content, "Hello");
+}
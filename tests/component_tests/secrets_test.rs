@@ -0,0 +1,127 @@
+use m_desk_new::secrets::{self, SecretError, SecretStore, MASK};
+use m_desk_new::server_config::{ServerConfig, TransportConfig};
+use m_desk_new::server_logs::ServerLogs;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::support::TempDir;
+
+fn unlocked_store(path: &PathBuf) -> SecretStore {
+    let store = SecretStore::new(path);
+    store.unlock("correct horse").unwrap();
+    store
+}
+
+#[test]
+fn test_secrets_are_saved_encrypted() {
+    let dir = TempDir::new("mdesk-secrets");
+    let path = dir.join("secrets.json");
+    let store = SecretStore::new(&path);
+    assert!(!store.exists());
+    assert!(!store.is_unlocked());
+    assert!(matches!(store.set("TOKEN", "value"), Err(SecretError::Locked)));
+
+    // Unlocking a store without a file starts an empty one
+    store.unlock("correct horse").unwrap();
+    store.set("GITHUB_TOKEN", "ghp_supersecret").unwrap();
+    store.set("OPENROUTER_API_KEY", "sk-or-123456").unwrap();
+    assert!(store.exists());
+
+    let contents = fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains("ghp_supersecret"));
+    assert!(!contents.contains("GITHUB_TOKEN"));
+
+    let reopened = SecretStore::new(&path);
+    assert!(matches!(reopened.unlock("wrong"), Err(SecretError::WrongPassphrase)));
+    assert!(matches!(reopened.unlock(""), Err(SecretError::EmptyPassphrase)));
+    assert!(!reopened.is_unlocked());
+
+    reopened.unlock("correct horse").unwrap();
+    assert_eq!(reopened.names(), vec!["GITHUB_TOKEN", "OPENROUTER_API_KEY"]);
+    assert_eq!(reopened.get("GITHUB_TOKEN").as_deref(), Some("ghp_supersecret"));
+    assert_eq!(reopened.openrouter_api_key().as_deref(), Some("sk-or-123456"));
+
+    assert!(reopened.remove("GITHUB_TOKEN").unwrap());
+    assert!(!reopened.remove("GITHUB_TOKEN").unwrap());
+    reopened.change_passphrase("battery staple").unwrap();
+
+    reopened.lock();
+    assert!(reopened.get("OPENROUTER_API_KEY").is_none());
+    assert!(matches!(reopened.unlock("correct horse"), Err(SecretError::WrongPassphrase)));
+    reopened.unlock("battery staple").unwrap();
+    assert_eq!(reopened.names(), vec!["OPENROUTER_API_KEY"]);
+}
+
+#[test]
+fn test_invalid_names_and_files_are_rejected() {
+    let dir = TempDir::new("mdesk-secrets");
+    let path = dir.join("secrets.json");
+    let store = unlocked_store(&path);
+    assert!(matches!(store.set("has space", "x"), Err(SecretError::InvalidName(_))));
+    assert!(matches!(store.set("", "x"), Err(SecretError::InvalidName(_))));
+
+    fs::write(&path, "{ \"not\": \"secrets\" }").unwrap();
+    assert!(matches!(SecretStore::new(&path).unlock("correct horse"), Err(SecretError::Corrupt { .. })));
+}
+
+#[test]
+fn test_references_are_resolved() {
+    assert_eq!(secrets::secret_refs("Bearer ${secret:API_TOKEN}"), vec!["API_TOKEN"]);
+    assert_eq!(secrets::secret_ref("API_TOKEN"), "${secret:API_TOKEN}");
+    assert_eq!(secrets::suggested_name("git-hub", "api key"), "GIT_HUB_API_KEY");
+
+    let dir = TempDir::new("mdesk-secrets");
+    let path = dir.join("secrets.json");
+    let store = SecretStore::new(&path);
+    // Values without references don't need the store to be unlocked
+    assert_eq!(store.resolve("plain").unwrap(), "plain");
+    assert!(matches!(store.resolve("${secret:API_TOKEN}"), Err(SecretError::Locked)));
+
+    store.unlock("correct horse").unwrap();
+    store.set("API_TOKEN", "tok-42").unwrap();
+    assert_eq!(store.resolve("Bearer ${secret:API_TOKEN}").unwrap(), "Bearer tok-42");
+    match store.resolve("${secret:API_TOKEN}:${secret:OTHER}") {
+        Err(SecretError::Missing(name)) => assert_eq!(name, "OTHER"),
+        other => panic!("expected a missing secret, got {:?}", other),
+    }
+
+    let mut env = HashMap::new();
+    env.insert("TOKEN".to_string(), "${secret:API_TOKEN}".to_string());
+    env.insert("MODE".to_string(), "read-only".to_string());
+    let config = ServerConfig::new("GitHub".to_string(), "npx".to_string(), Vec::new(), env, None, false);
+    let resolved = store.resolve_config(&config).unwrap();
+    assert_eq!(resolved.env["TOKEN"], "tok-42");
+    assert_eq!(resolved.env["MODE"], "read-only");
+    // The configuration itself keeps the reference
+    assert_eq!(config.env["TOKEN"], "${secret:API_TOKEN}");
+
+    let mut headers = HashMap::new();
+    headers.insert("Authorization".to_string(), "Bearer ${secret:API_TOKEN}".to_string());
+    let remote = ServerConfig::remote(
+        "Remote".to_string(),
+        TransportConfig::Http {
+            url: "https://example.com/mcp".to_string(),
+            headers,
+        },
+        None,
+        false,
+    );
+    let resolved = store.resolve_config(&remote).unwrap();
+    assert_eq!(resolved.transport.headers().unwrap()["Authorization"], "Bearer tok-42");
+}
+
+#[test]
+fn test_secret_values_are_masked_in_server_logs() {
+    let dir = TempDir::new("mdesk-secrets");
+    let path = dir.join("secrets.json");
+    let store = unlocked_store(&path);
+    store.set("API_TOKEN", "tok-4242").unwrap();
+    store.set("SHORT", "ab").unwrap();
+
+    assert_eq!(store.mask("using tok-4242 for ab"), format!("using {} for ab", MASK));
+
+    let logs = ServerLogs::in_memory().with_secrets(store.clone());
+    logs.append("github", "Authorization: Bearer tok-4242");
+    assert_eq!(logs.lines("github")[0].text, format!("Authorization: Bearer {}", MASK));
+}
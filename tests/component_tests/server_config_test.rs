@@ -0,0 +1,415 @@
+use m_desk_new::server_config::{
+    config_path, parse_import, ConfigError, ConfigWatcher, ConflictResolution, ImportError, Profile, ServerConfig,
+    ServerConfigs, TransportConfig, CONFIG_PATH_ENV,
+};
+use m_desk_new::approvals::{ApprovalAction, ApprovalRule};
+use m_desk_new::argument_rules::{ArgumentConstraint, ArgumentRule};
+use m_desk_new::components::SanitizationProfile;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::support::TempDir;
+
+fn create_config(id: &str, command: &str) -> ServerConfig {
+    ServerConfig {
+        id: id.to_string(),
+        ..ServerConfig::new(format!("{} server", id), command.to_string(), Vec::new(), HashMap::new(), None, false)
+    }
+}
+
+fn configs(servers: Vec<ServerConfig>) -> ServerConfigs {
+    ServerConfigs {
+        servers,
+        ..ServerConfigs::default()
+    }
+}
+
+#[test]
+fn test_config_path_can_be_overridden() {
+    std::env::set_var(CONFIG_PATH_ENV, "/tmp/mdesk-test/servers.json");
+    assert_eq!(config_path(), PathBuf::from("/tmp/mdesk-test/servers.json"));
+
+    std::env::remove_var(CONFIG_PATH_ENV);
+    let default = config_path();
+    assert!(default.ends_with("servers.json"));
+    assert!(default.is_absolute() || default == Path::new("servers.json"));
+}
+
+#[test]
+fn test_parse_errors_report_their_location() {
+    let json = "{\n  \"servers\": [\n    { \"id\": \"git\", \"name\": }\n  ]\n}";
+    match ServerConfigs::parse(Path::new("servers.json"), json) {
+        Err(ConfigError::Parse { line, column, message, .. }) => {
+            assert_eq!(line, 3);
+            assert!(column > 0);
+            assert!(!message.contains("at line"));
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+
+    // Missing fields are reported by name
+    let error = ServerConfigs::parse(Path::new("servers.json"), r#"{ "servers": [ { "id": "git" } ] }"#).unwrap_err();
+    let text = error.to_string();
+    assert!(text.starts_with("servers.json:1:"), "{}", text);
+    assert!(text.contains("missing field `name`"), "{}", text);
+}
+
+#[test]
+fn test_validation_reports_every_problem_by_field() {
+    let mut remote = create_config("remote", "");
+    remote.transport = TransportConfig::Http {
+        url: "ftp://example.com".to_string(),
+        headers: HashMap::new(),
+    };
+    let mut unhealthy = create_config("fetch", "uvx");
+    unhealthy.health.timeout_ms = 0;
+    let invalid = configs(vec![
+        create_config("git", "uvx"),
+        create_config("git", "docker"),
+        create_config("empty", "  "),
+        remote,
+        unhealthy,
+    ]);
+
+    let problems: Vec<String> = invalid.validate().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        problems,
+        vec![
+            "servers[1].id: \"git\" is already used by servers[0]",
+            "servers[2].command: must not be empty for a stdio server",
+            "servers[3].transport.url: \"ftp://example.com\" is not an http or https URL",
+            "servers[4].health.timeout_ms: must be greater than 0",
+        ]
+    );
+
+    let json = serde_json::to_string(&invalid).unwrap();
+    match ServerConfigs::parse(Path::new("servers.json"), &json) {
+        Err(ConfigError::Invalid { problems, .. }) => assert_eq!(problems.len(), 4),
+        other => panic!("expected an invalid configuration, got {:?}", other),
+    }
+
+    assert!(configs(vec![create_config("git", "uvx")]).validate().is_empty());
+}
+
+#[test]
+fn test_save_and_load_round_trip() {
+    let dir = TempDir::new("mdesk-server-config");
+    let path = dir.join("nested").join("servers.json");
+    let saved = configs(vec![create_config("git", "uvx"), create_config("fetch", "docker")]);
+
+    saved.save_to_file(&path).unwrap();
+    assert_eq!(ServerConfigs::load_from_file(&path).unwrap(), saved);
+    // The temporary file is renamed into place
+    assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    assert_eq!(ServerConfigs::load_or_init(&path).unwrap(), saved);
+
+    // An invalid file is reported and left alone
+    fs::write(&path, "{ \"servers\": [").unwrap();
+    assert!(matches!(ServerConfigs::load_or_init(&path), Err(ConfigError::Parse { .. })));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{ \"servers\": [");
+
+    let missing = ServerConfigs::load_from_file(dir.join("missing.json")).unwrap_err();
+    assert!(missing.is_not_found());
+}
+
+#[test]
+fn test_diff_finds_added_removed_and_changed_servers() {
+    let old = configs(vec![
+        create_config("git", "uvx"),
+        create_config("fetch", "uvx"),
+        create_config("filesystem", "docker"),
+    ]);
+
+    let mut renamed = create_config("git", "uvx");
+    renamed.name = "Git".to_string();
+    let mut fetch = create_config("fetch", "uvx");
+    fetch.args = vec!["mcp-server-fetch".to_string()];
+    let new = configs(vec![renamed, fetch.clone(), create_config("time", "uvx")]);
+
+    let diff = old.diff(&new);
+    assert_eq!(diff.added.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["time"]);
+    assert_eq!(diff.removed, vec!["filesystem"]);
+    // A new name doesn't need a restart, new arguments do
+    assert_eq!(diff.changed, vec![fetch]);
+
+    assert!(new.diff(&new).is_empty());
+}
+
+#[test]
+fn test_watcher_reports_changed_contents() {
+    let dir = TempDir::new("mdesk-server-config");
+    let path = dir.join("servers.json");
+    configs(vec![create_config("git", "uvx")]).save_to_file(&path).unwrap();
+
+    let mut watcher = ConfigWatcher::new(&path);
+    assert!(watcher.check().is_none());
+
+    let updated = configs(vec![create_config("git", "uvx"), create_config("time", "uvx")]);
+    updated.save_to_file(&path).unwrap();
+    assert_eq!(watcher.check().unwrap().unwrap(), updated);
+    assert!(watcher.check().is_none());
+
+    fs::write(&path, "not json").unwrap();
+    assert!(matches!(watcher.check(), Some(Err(ConfigError::Parse { .. }))));
+    assert!(watcher.check().is_none());
+
+    // Editors briefly remove the file while saving
+    fs::remove_file(&path).unwrap();
+    assert!(watcher.check().is_none());
+
+    updated.save_to_file(&path).unwrap();
+    assert_eq!(watcher.check().unwrap().unwrap(), updated);
+}
+
+#[test]
+fn test_import_reads_mcp_servers_documents() {
+    let json = r#"{
+        "mcpServers": {
+            "git": { "command": "uvx", "args": ["mcp-server-git"], "env": { "GIT_DIR": "/repo" } },
+            "legacy": { "url": "http://localhost:8000/sse" },
+            "remote": { "url": "https://example.com/mcp", "headers": { "Authorization": "Bearer token" } },
+            "typed": { "type": "sse", "url": "https://example.com/events" }
+        }
+    }"#;
+    let servers = parse_import(json).unwrap();
+    let ids: Vec<&str> = servers.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["git", "legacy", "remote", "typed"]);
+
+    assert_eq!(servers[0].transport, TransportConfig::Stdio);
+    assert_eq!(servers[0].command, "uvx");
+    assert_eq!(servers[0].args, vec!["mcp-server-git"]);
+    assert_eq!(servers[0].env.get("GIT_DIR").map(String::as_str), Some("/repo"));
+    // Without a type, URLs ending in /sse are taken for the older SSE transport
+    assert_eq!(servers[1].transport.label(), "sse");
+    assert_eq!(servers[2].transport.label(), "http");
+    assert_eq!(
+        servers[2].transport.headers().unwrap().get("Authorization").map(String::as_str),
+        Some("Bearer token")
+    );
+    assert_eq!(servers[3].transport.label(), "sse");
+
+    // mDesk's own format is accepted too
+    let own = configs(vec![create_config("fetch", "uvx")]);
+    assert_eq!(parse_import(&serde_json::to_string(&own).unwrap()).unwrap(), own.servers);
+}
+
+#[test]
+fn test_import_reports_unusable_documents() {
+    assert!(matches!(parse_import("{ \"mcpServers\": "), Err(ImportError::Parse { line: 1, .. })));
+    assert_eq!(parse_import("{ \"tools\": [] }"), Err(ImportError::UnknownFormat));
+    assert_eq!(parse_import("{ \"mcpServers\": {} }"), Err(ImportError::Empty));
+
+    let error = parse_import(r#"{ "mcpServers": { "broken": { "args": ["x"] } } }"#).unwrap_err();
+    assert_eq!(error.to_string(), "Server \"broken\" has neither a command nor a url");
+
+    let error = parse_import(r#"{ "mcpServers": { "ftp": { "url": "ftp://example.com" } } }"#).unwrap_err();
+    assert!(matches!(error, ImportError::Invalid(ref problems) if problems.len() == 1), "{:?}", error);
+}
+
+#[test]
+fn test_import_resolves_id_conflicts() {
+    let mut existing = configs(vec![create_config("git", "uvx"), create_config("fetch", "uvx")]);
+    existing.servers[0].is_default = true;
+
+    let imported = vec![
+        create_config("git", "docker"),
+        create_config("fetch", "docker"),
+        create_config("time", "uvx"),
+        create_config("sqlite", "uvx"),
+    ];
+    let mut items = existing.preview_import(imported);
+    assert_eq!(items.iter().map(|item| item.conflicts).collect::<Vec<_>>(), vec![true, true, false, false]);
+    assert!(items.iter().all(|item| item.selected && item.resolution == ConflictResolution::Skip));
+
+    items[0].resolution = ConflictResolution::Replace;
+    items[1].resolution = ConflictResolution::KeepBoth;
+    items[3].selected = false;
+    let summary = existing.apply_import(&items);
+
+    assert_eq!(summary.added, vec!["fetch-2", "time"]);
+    assert_eq!(summary.replaced, vec!["git"]);
+    assert_eq!(summary.skipped, vec!["sqlite"]);
+    assert_eq!(summary.to_string(), "Imported 3 servers, replaced git, skipped sqlite");
+
+    let ids: Vec<&str> = existing.servers.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["git", "fetch", "fetch-2", "time"]);
+    // The replaced server keeps being the default
+    assert_eq!(existing.servers[0].command, "docker");
+    assert!(existing.servers[0].is_default);
+    assert!(existing.validate().is_empty());
+}
+
+#[test]
+fn test_export_round_trips_through_import() {
+    let mut remote = create_config("remote", "");
+    remote.transport = TransportConfig::Http {
+        url: "https://example.com/mcp".to_string(),
+        headers: HashMap::from([("Authorization".to_string(), "Bearer token".to_string())]),
+    };
+    let mut git = create_config("git", "uvx");
+    git.args = vec!["mcp-server-git".to_string()];
+    git.env.insert("GIT_DIR".to_string(), "/repo".to_string());
+    let exported = configs(vec![git, remote]);
+
+    let json = exported.to_mcp_servers_json();
+    let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(document["mcpServers"]["git"]["command"], "uvx");
+    assert_eq!(document["mcpServers"]["remote"]["type"], "http");
+
+    let imported = parse_import(&json).unwrap();
+    let git = imported.iter().find(|s| s.id == "git").unwrap();
+    assert_eq!(git.command, "uvx");
+    assert_eq!(git.args, exported.servers[0].args);
+    assert_eq!(git.env, exported.servers[0].env);
+    let remote = imported.iter().find(|s| s.id == "remote").unwrap();
+    assert_eq!(remote.transport, exported.servers[1].transport);
+
+    let dir = TempDir::new("mdesk-server-config");
+    let path = exported.write_mcp_servers(&dir).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), json);
+}
+
+fn profile(name: &str, servers: &[&str]) -> Profile {
+    Profile {
+        name: name.to_string(),
+        description: None,
+        servers: servers.iter().map(|id| id.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_validation_reports_unknown_variables_and_bad_profiles() {
+    let mut git = create_config("git", "uvx");
+    git.args = vec!["--repository".to_string(), "${workspace}".to_string(), "${HOEM}/src".to_string()];
+    git.env.insert("TOKEN".to_string(), "${secret:GITHUB_TOKEN}".to_string());
+    git.env.insert("PROXY".to_string(), "${proxy}".to_string());
+    let mut configs = configs(vec![git, create_config("fetch", "uvx")]);
+    configs.profiles = vec![profile("work", &["git", "time"]), profile("work", &[]), profile(" ", &[])];
+
+    let fields: Vec<String> = configs.validate().into_iter().map(|problem| problem.field).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "servers[0].args[2]",
+            "servers[0].env.PROXY",
+            "profiles[0].servers[1]",
+            "profiles[1].name",
+            "profiles[2].name",
+        ]
+    );
+}
+
+#[test]
+fn test_profiles_select_the_servers_to_start() {
+    let mut configs = configs(vec![
+        create_config("filesystem", "npx"),
+        create_config("git", "uvx"),
+        create_config("fetch", "uvx"),
+    ]);
+    configs.profiles = vec![profile("work", &["git"])];
+
+    let ids = |configs: &ServerConfigs| -> Vec<String> { configs.servers.iter().map(|s| s.id.clone()).collect() };
+    assert_eq!(ids(&configs.for_profile(None)), vec!["filesystem", "git", "fetch"]);
+    assert_eq!(ids(&configs.for_profile(Some("work"))), vec!["git"]);
+    // A profile that no longer exists falls back to every server
+    assert_eq!(ids(&configs.for_profile(Some("home"))), vec!["filesystem", "git", "fetch"]);
+
+    configs.set_enabled("work", "fetch", true);
+    configs.set_enabled("work", "git", false);
+    assert!(configs.is_enabled("fetch", Some("work")));
+    assert!(!configs.is_enabled("git", Some("work")));
+    assert!(configs.is_enabled("git", None));
+
+    // Switching profiles stops the servers the new one leaves out and starts the ones it adds
+    let diff = configs.for_profile(None).diff(&configs.for_profile(Some("work")));
+    assert_eq!(diff.removed, vec!["filesystem".to_string(), "git".to_string()]);
+    assert!(diff.added.is_empty() && diff.changed.is_empty());
+
+    assert!(configs.remove_server("fetch"));
+    assert!(configs.get_profile("work").unwrap().servers.is_empty());
+}
+
+#[test]
+fn test_approval_rules_are_validated_and_follow_removed_servers() {
+    let mut configs = configs(vec![create_config("filesystem", "npx"), create_config("git", "uvx")]);
+    configs.approval_rules = vec![
+        ApprovalRule { action: ApprovalAction::Allow, server: Some("git".to_string()), ..ApprovalRule::default() },
+        ApprovalRule { action: ApprovalAction::Deny, server: Some("sqlite".to_string()), ..ApprovalRule::default() },
+        ApprovalRule { action: ApprovalAction::Deny, tool: Some(" ".to_string()), ..ApprovalRule::default() },
+    ];
+
+    let fields: Vec<String> = configs.validate().into_iter().map(|problem| problem.field).collect();
+    assert_eq!(fields, vec!["approval_rules[1].server", "approval_rules[2].tool"]);
+
+    configs.approval_rules.truncate(1);
+    assert!(configs.remove_server("git"));
+    assert!(configs.approval_rules.is_empty());
+    assert!(configs.validate().is_empty());
+}
+
+#[test]
+fn test_argument_rules_are_validated_and_follow_removed_servers() {
+    let mut configs = configs(vec![create_config("filesystem", "npx"), create_config("sqlite", "uvx")]);
+    configs.argument_rules = vec![
+        ArgumentRule {
+            tool: "write_file".to_string(),
+            server: Some("filesystem".to_string()),
+            allowed_roots: vec!["${HOME}/Projects".to_string()],
+            ..ArgumentRule::default()
+        },
+        ArgumentRule {
+            tool: "execute_query".to_string(),
+            server: Some("postgres".to_string()),
+            constraints: vec![ArgumentConstraint {
+                pointer: "/query".to_string(),
+                deny_regex: Some("(?i)drop(".to_string()),
+                ..ArgumentConstraint::default()
+            }],
+            ..ArgumentRule::default()
+        },
+    ];
+
+    let fields: Vec<String> = configs.validate().into_iter().map(|problem| problem.field).collect();
+    assert_eq!(fields, vec!["argument_rules[1].server", "argument_rules[1].constraints[0].deny_regex"]);
+
+    configs.argument_rules.truncate(1);
+    assert!(configs.validate().is_empty());
+    assert_eq!(configs.for_profile(None).argument_rules, configs.argument_rules);
+    assert!(configs.remove_server("filesystem"));
+    assert!(configs.argument_rules.is_empty());
+}
+
+#[test]
+fn test_sanitization_is_read_validated_and_follows_removed_servers() {
+    let mut configs: ServerConfigs = serde_json::from_str(
+        r#"{
+            "servers": [],
+            "sanitization": {
+                "max_string_length": 0,
+                "default_profile": "text",
+                "fields": { "title": "line" },
+                "tools": [
+                    { "tool": "write_file", "server": "filesystem", "fields": { "content": "raw" } },
+                    { "tool": " ", "fields": {} }
+                ]
+            }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(configs.sanitization.default_profile, Some(SanitizationProfile::Text));
+    assert_eq!(configs.sanitization.fields.get("title"), Some(&SanitizationProfile::Line));
+
+    let fields: Vec<String> = configs.validate().into_iter().map(|problem| problem.field).collect();
+    assert_eq!(
+        fields,
+        vec!["sanitization.max_string_length", "sanitization.tools[0].server", "sanitization.tools[1].tool"]
+    );
+
+    // Configurations without the section are written without it
+    assert!(!serde_json::to_string(&ServerConfigs::default()).unwrap().contains("sanitization"));
+
+    configs.servers.push(create_config("filesystem", "npx"));
+    assert!(configs.remove_server("filesystem"));
+    assert_eq!(configs.sanitization.tools.len(), 1);
+}
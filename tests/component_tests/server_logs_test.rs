@@ -0,0 +1,112 @@
+use m_desk_new::server_logs::{ServerLogs, MAX_LINE_LEN};
+
+use crate::support::TempDir;
+
+fn texts(logs: &ServerLogs, server_id: &str) -> Vec<String> {
+    logs.lines(server_id).into_iter().map(|line| line.text).collect()
+}
+
+#[test]
+fn test_ring_buffer_keeps_the_newest_lines() {
+    let logs = ServerLogs::new(None, 3);
+    for i in 1..=5 {
+        logs.append("git", &format!("line {}", i));
+    }
+
+    assert_eq!(texts(&logs, "git"), vec!["line 3", "line 4", "line 5"]);
+    assert_eq!(logs.dropped("git"), 2);
+
+    // Servers have buffers of their own
+    logs.append("filesystem", "ready");
+    assert_eq!(texts(&logs, "filesystem"), vec!["ready"]);
+    assert_eq!(logs.server_ids(), vec!["filesystem", "git"]);
+    assert!(logs.lines("unknown").is_empty());
+}
+
+#[test]
+fn test_search_tail_and_clear() {
+    let logs = ServerLogs::in_memory();
+    logs.start_session("git", "uvx mcp-server-git");
+    logs.append("git", "Listening on stdio");
+    logs.append("git", "ERROR: repository not found");
+    logs.append("git", "Shutting down");
+
+    let found = logs.search("git", "error");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].text, "ERROR: repository not found");
+    assert_eq!(logs.search("git", "").len(), 4);
+
+    let lines = logs.lines("git");
+    assert!(lines[0].is_start);
+    assert_eq!(lines[0].text, "Starting uvx mcp-server-git");
+
+    let tail: Vec<String> = logs.tail("git", 2).into_iter().map(|line| line.text).collect();
+    assert_eq!(tail, vec!["ERROR: repository not found", "Shutting down"]);
+
+    let mut changes = logs.subscribe();
+    logs.clear("git");
+    assert!(changes.has_changed().unwrap());
+    assert!(logs.lines("git").is_empty());
+}
+
+#[test]
+fn test_long_lines_are_cut_off() {
+    let logs = ServerLogs::in_memory();
+    logs.append("git", &"é".repeat(MAX_LINE_LEN));
+
+    let text = &logs.lines("git")[0].text;
+    assert!(text.len() <= MAX_LINE_LEN + 3);
+    assert!(text.ends_with("..."));
+}
+
+#[test]
+fn test_lines_are_appended_to_the_server_log_file() {
+    let dir = TempDir::new("mdesk-server-logs");
+    let logs = ServerLogs::new(Some(dir.to_path_buf()), 10);
+
+    logs.start_session("git", "uvx mcp-server-git");
+    logs.append("git", "first");
+    logs.append("git", "second");
+
+    let path = logs.log_path("git").unwrap();
+    assert_eq!(path, dir.join("git.log"));
+    let contents = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].ends_with("--- Starting uvx mcp-server-git ---"));
+    assert!(lines[2].ends_with("] second"));
+
+    // The file outlives what is kept in memory
+    logs.clear("git");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+
+    // Ids can't point outside the log directory
+    assert_eq!(logs.log_path("../etc/passwd").unwrap(), dir.join("___etc_passwd.log"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_process_stderr_is_captured() {
+    use m_desk_new::transport::ProcessTransport;
+    use mcp_client::Transport;
+    use std::collections::HashMap;
+
+    let logs = ServerLogs::in_memory();
+    let mut changes = logs.subscribe();
+    let handle = ProcessTransport::new(
+        "sh",
+        vec!["-c".to_string(), "echo 'missing API key' >&2; cat".to_string()],
+        HashMap::new(),
+    )
+    .with_log(logs.clone(), "broken")
+    .start()
+    .await
+    .unwrap();
+
+    while !logs.lines("broken").iter().any(|line| line.text == "missing API key") {
+        changes.changed().await.unwrap();
+    }
+    assert!(logs.lines("broken")[0].is_start);
+
+    handle.shutdown().await;
+}
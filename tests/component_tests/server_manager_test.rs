@@ -3,7 +3,7 @@ mod tests {
     use std::collections::HashMap;
     
     // Import components and types
-    use m_desk_new::server_config::{ServerConfig, ServerConfigs};
+    use m_desk_new::server_config::{HealthCheckConfig, RestartPolicy, ServerConfig, ServerConfigs, TransportConfig};
     
     #[test]
     fn test_server_config_creation() {
@@ -11,11 +11,14 @@ mod tests {
         let config = ServerConfig {
             id: "test-server".to_string(),
             name: "Test Server".to_string(),
+            transport: TransportConfig::Stdio,
             command: "docker".to_string(),
             args: vec!["run".to_string(), "-i".to_string(), "--rm".to_string()],
             description: Some("Test server description".to_string()),
             is_default: false,
             env: HashMap::new(),
+            restart: RestartPolicy::default(),
+            health: HealthCheckConfig::default(),
         };
         
         assert_eq!(config.id, "test-server");
@@ -36,22 +39,28 @@ mod tests {
         let config = ServerConfig {
             id: "test-server".to_string(),
             name: "Test Server".to_string(),
+            transport: TransportConfig::Stdio,
             command: "docker".to_string(),
             args: vec!["run".to_string()],
             description: None,
             is_default: false,  // explicitly not default
             env: HashMap::new(),
+            restart: RestartPolicy::default(),
+            health: HealthCheckConfig::default(),
         };
         
         // Add another server to ensure we're not the only one
         let config2 = ServerConfig {
             id: "another-server".to_string(),
             name: "Another Server".to_string(),
+            transport: TransportConfig::Stdio,
             command: "docker".to_string(),
             args: vec!["run".to_string()],
             description: None,
             is_default: true,  // This one is the default
             env: HashMap::new(),
+            restart: RestartPolicy::default(),
+            health: HealthCheckConfig::default(),
         };
         
         // Test add_server
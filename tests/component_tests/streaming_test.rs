@@ -0,0 +1,62 @@
+use m_desk_new::event_stream::SseParser;
+use m_desk_new::openrouter::{parse_stream_payload, StreamAccumulator, StreamEvent};
+
+#[test]
+fn test_sse_parser_reads_openrouter_streams() {
+    let mut parser = SseParser::new();
+
+    assert!(parser.push(b": OPENROUTER PROCESSING\n\ndata: {\"a\"").is_empty());
+    let payloads: Vec<String> = parser
+        .push(b":1}\r\n\r\ndata: [DONE]\n\n")
+        .into_iter()
+        .map(|event| event.data)
+        .collect();
+    assert_eq!(payloads, vec!["{\"a\":1}", "[DONE]"]);
+}
+
+#[test]
+fn test_parse_stream_payload() {
+    let events = parse_stream_payload(
+        r#"{"id":"gen-1","choices":[{"index":0,"delta":{"role":"assistant","content":"Hel"},"finish_reason":null}]}"#,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(events, vec![StreamEvent::Content("Hel".to_string())]);
+
+    let events = parse_stream_payload(
+        r#"{"id":"gen-1","choices":[{"index":0,"delta":{"content":""},"finish_reason":"stop"}]}"#,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(events, vec![StreamEvent::Finished(Some("stop".to_string()))]);
+
+    assert!(parse_stream_payload("[DONE]").unwrap().is_none());
+    assert!(parse_stream_payload(r#"{"error":{"message":"Provider returned error"}}"#).is_err());
+}
+
+#[test]
+fn test_accumulator_assembles_tool_calls() {
+    let payloads = [
+        r#"{"choices":[{"delta":{"content":"Let me check. "}}]}"#,
+        r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"read_file","arguments":""}}]}}]}"#,
+        r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"path\":"}}]}}]}"#,
+        r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"main.rs\"}"}}]}}]}"#,
+        r#"{"choices":[{"delta":{},"finish_reason":"tool_calls"}]}"#,
+    ];
+
+    let mut reply = StreamAccumulator::new();
+    for payload in payloads {
+        for event in parse_stream_payload(payload).unwrap().unwrap() {
+            reply.apply(&event);
+        }
+    }
+
+    assert_eq!(reply.content, "Let me check. ");
+    assert_eq!(reply.finish_reason.as_deref(), Some("tool_calls"));
+
+    let tool_calls = reply.complete_tool_calls();
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].id, "call_1");
+    assert_eq!(tool_calls[0].function.name, "read_file");
+    assert_eq!(tool_calls[0].parsed_arguments(), serde_json::json!({"path": "main.rs"}));
+}
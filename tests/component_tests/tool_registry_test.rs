@@ -0,0 +1,110 @@
+use m_desk_new::components::tool_registry::{ToolRegistry, ToolResolveError};
+use mcp_core::Tool;
+use serde_json::json;
+use std::collections::HashMap;
+
+fn create_tool(name: &str) -> Tool {
+    Tool {
+        name: name.to_string(),
+        description: format!("The {} tool", name),
+        input_schema: json!({"type": "object"}),
+    }
+}
+
+fn create_test_registry() -> ToolRegistry {
+    let mut server_tools = HashMap::new();
+    server_tools.insert(
+        "filesystem".to_string(),
+        vec![create_tool("read_file"), create_tool("list_directory")],
+    );
+    server_tools.insert(
+        "git".to_string(),
+        vec![create_tool("git_status"), create_tool("read_file")],
+    );
+    server_tools.insert(
+        "sqlite".to_string(),
+        vec![create_tool("execute_query")],
+    );
+    ToolRegistry::from_server_tools(&server_tools)
+}
+
+#[test]
+fn test_resolve_unique_tool_to_owning_server() {
+    let registry = create_test_registry();
+
+    assert_eq!(
+        registry.resolve("execute_query"),
+        Ok(("sqlite".to_string(), "execute_query".to_string()))
+    );
+    assert_eq!(
+        registry.resolve("git_status"),
+        Ok(("git".to_string(), "git_status".to_string()))
+    );
+}
+
+#[test]
+fn test_resolve_colliding_tool_requires_qualified_name() {
+    let registry = create_test_registry();
+
+    match registry.resolve("read_file") {
+        Err(ToolResolveError::AmbiguousTool { name, servers }) => {
+            assert_eq!(name, "read_file");
+            assert_eq!(servers, vec!["filesystem".to_string(), "git".to_string()]);
+        }
+        other => panic!("Expected AmbiguousTool, got {:?}", other),
+    }
+
+    assert_eq!(
+        registry.resolve("git/read_file"),
+        Ok(("git".to_string(), "read_file".to_string()))
+    );
+    assert_eq!(
+        registry.resolve("filesystem/read_file"),
+        Ok(("filesystem".to_string(), "read_file".to_string()))
+    );
+}
+
+#[test]
+fn test_resolve_unknown_tool() {
+    let registry = create_test_registry();
+
+    assert_eq!(
+        registry.resolve("web_search"),
+        Err(ToolResolveError::UnknownTool("web_search".to_string()))
+    );
+    assert_eq!(
+        registry.resolve("sqlite/read_file"),
+        Err(ToolResolveError::UnknownTool("sqlite/read_file".to_string()))
+    );
+}
+
+#[test]
+fn test_tools_qualifies_only_colliding_names() {
+    let registry = create_test_registry();
+    let names: Vec<String> = registry.tools().into_iter().map(|t| t.name).collect();
+
+    assert_eq!(registry.len(), 5);
+    assert!(names.contains(&"filesystem/read_file".to_string()));
+    assert!(names.contains(&"git/read_file".to_string()));
+    assert!(names.contains(&"list_directory".to_string()));
+    assert!(names.contains(&"execute_query".to_string()));
+    assert!(!names.contains(&"read_file".to_string()));
+
+    // Every presented name must resolve back to exactly one server
+    for name in &names {
+        assert!(registry.resolve(name).is_ok(), "{} should resolve", name);
+    }
+}
+
+#[test]
+fn test_remove_server_clears_collision() {
+    let mut registry = create_test_registry();
+    registry.remove_server("git");
+
+    assert_eq!(
+        registry.resolve("read_file"),
+        Ok(("filesystem".to_string(), "read_file".to_string()))
+    );
+    assert!(registry.get("git_status").is_none());
+    assert_eq!(registry.server_ids(), vec!["filesystem".to_string(), "sqlite".to_string()]);
+}
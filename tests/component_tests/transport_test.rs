@@ -0,0 +1,385 @@
+use m_desk_new::event_stream::SseParser;
+use m_desk_new::server_config::{ServerConfig, ServerConfigs, TransportConfig};
+use m_desk_new::transport::{self, ProcessTransport, ShutdownOutcome, SseTransport, StreamableHttpTransport};
+use mcp_client::transport::TransportHandle;
+use mcp_client::{McpClientTrait, Transport};
+use mcp_core::protocol::JsonRpcMessage;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+/// A request received by the stand-in server
+struct StandInRequest {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<StandInRequest> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length: usize = headers.get("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+    let mut body = buffer[header_end + 4..].to_vec();
+    while body.len() < length {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+
+    Some(StandInRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+async fn write_response(stream: &mut TcpStream, status: &str, extra_headers: &str, content_type: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        extra_headers,
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Result the stand-in server returns for an MCP method
+fn result_for(method: &str) -> Value {
+    match method {
+        "initialize" => json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "stand-in", "version": "1.0.0" }
+        }),
+        "tools/list" => json!({
+            "tools": [{
+                "name": "echo",
+                "description": "Echo the input back",
+                "inputSchema": { "type": "object", "properties": { "text": { "type": "string" } } }
+            }]
+        }),
+        _ => json!({ "method": method }),
+    }
+}
+
+fn response_for(body: &str) -> Option<Value> {
+    let request: Value = serde_json::from_str(body).ok()?;
+    let id = request.get("id")?.clone();
+    let method = request["method"].as_str().unwrap_or_default();
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result_for(method) }))
+}
+
+/// Streamable HTTP stand-in: answers `ping` as an event stream and everything else as JSON,
+/// and records the headers of every POST
+async fn spawn_http_server() -> (String, Arc<Mutex<Vec<HashMap<String, String>>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+    let seen_headers = Arc::new(Mutex::new(Vec::new()));
+
+    let seen = seen_headers.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let Some(request) = read_request(&mut stream).await else { continue };
+            seen.lock().unwrap().push(request.headers.clone());
+
+            match response_for(&request.body) {
+                None => write_response(&mut stream, "202 Accepted", "", "text/plain", "").await,
+                Some(response) if response["result"]["method"] == "ping" => {
+                    let body = format!(": keep-alive\n\nevent: message\ndata: {}\n\n", response);
+                    write_response(&mut stream, "200 OK", "", "text/event-stream", &body).await
+                }
+                Some(response) => {
+                    write_response(
+                        &mut stream,
+                        "200 OK",
+                        "Mcp-Session-Id: session-1\r\n",
+                        "application/json",
+                        &response.to_string(),
+                    )
+                    .await
+                }
+            }
+        }
+    });
+
+    (url, seen_headers)
+}
+
+/// HTTP+SSE stand-in: announces `/messages` on `/sse` and answers posted requests on the stream
+async fn spawn_sse_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/sse", listener.local_addr().unwrap());
+    let (events_tx, events_rx) = mpsc::unbounded_channel::<String>();
+    let events_rx = Arc::new(tokio::sync::Mutex::new(events_rx));
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let Some(request) = read_request(&mut stream).await else { continue };
+
+            if request.method == "GET" && request.path == "/sse" {
+                let events_rx = events_rx.clone();
+                tokio::spawn(async move {
+                    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n";
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let _ = stream.write_all(b"event: endpoint\ndata: /messages?session=1\n\n").await;
+
+                    let mut events_rx = events_rx.lock().await;
+                    while let Some(data) = events_rx.recv().await {
+                        let event = format!("event: message\ndata: {}\n\n", data);
+                        if stream.write_all(event.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            } else if request.method == "POST" && request.path.starts_with("/messages") {
+                if let Some(response) = response_for(&request.body) {
+                    let _ = events_tx.send(response.to_string());
+                }
+                write_response(&mut stream, "202 Accepted", "", "text/plain", "").await;
+            } else {
+                write_response(&mut stream, "404 Not Found", "", "text/plain", "").await;
+            }
+        }
+    });
+
+    url
+}
+
+fn request(id: u64, method: &str) -> JsonRpcMessage {
+    serde_json::from_value(json!({ "jsonrpc": "2.0", "id": id, "method": method })).unwrap()
+}
+
+fn result_of(message: JsonRpcMessage) -> Value {
+    match message {
+        JsonRpcMessage::Response(response) => response.result.unwrap(),
+        _ => panic!("expected a response"),
+    }
+}
+
+#[test]
+fn test_configs_without_transport_are_stdio() {
+    let json = r#"{
+        "servers": [{
+            "id": "filesystem",
+            "name": "Filesystem MCP",
+            "command": "docker",
+            "args": ["run", "-i", "--rm", "mcp/filesystem"],
+            "env": {},
+            "description": null,
+            "is_default": true
+        }]
+    }"#;
+
+    let configs: ServerConfigs = serde_json::from_str(json).unwrap();
+    let server = &configs.servers[0];
+    assert_eq!(server.transport, TransportConfig::Stdio);
+    assert_eq!(server.endpoint(), "docker run -i --rm mcp/filesystem");
+}
+
+#[test]
+fn test_remote_transport_round_trip() {
+    let mut headers = HashMap::new();
+    headers.insert("Authorization".to_string(), "Bearer token".to_string());
+    let server = ServerConfig::remote(
+        "Remote".to_string(),
+        TransportConfig::Http {
+            url: "https://example.com/mcp".to_string(),
+            headers,
+        },
+        None,
+        false,
+    );
+
+    let json = serde_json::to_value(&server).unwrap();
+    assert_eq!(json["transport"]["type"], "http");
+    assert_eq!(json["transport"]["url"], "https://example.com/mcp");
+    assert_eq!(json["transport"]["headers"]["Authorization"], "Bearer token");
+
+    let parsed: ServerConfig = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed, server);
+    assert_eq!(parsed.transport.label(), "http");
+    assert_eq!(parsed.endpoint(), "https://example.com/mcp");
+
+    // Headers are optional
+    let sse: TransportConfig = serde_json::from_value(json!({ "type": "sse", "url": "http://localhost/sse" })).unwrap();
+    assert_eq!(sse.url(), Some("http://localhost/sse"));
+    assert!(sse.headers().unwrap().is_empty());
+}
+
+#[test]
+fn test_sse_parser_handles_split_chunks() {
+    let mut parser = SseParser::new();
+
+    assert!(parser.push(b": comment\r\nevent: endpoint\r\nda").is_empty());
+    let events = parser.push(b"ta: /messages\r\n\r\ndata: {\"a\":\ndata: 1}\n\n");
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].event, "endpoint");
+    assert_eq!(events[0].data, "/messages");
+    assert_eq!(events[1].event, "message");
+    assert_eq!(events[1].data, "{\"a\":\n1}");
+
+    // A multi-byte character split across chunks survives
+    let bytes = "data: héllo\n\n".as_bytes();
+    assert!(parser.push(&bytes[..8]).is_empty());
+    let events = parser.push(&bytes[8..]);
+    assert_eq!(events[0].data, "héllo");
+}
+
+#[tokio::test]
+async fn test_streamable_http_json_and_event_stream_responses() {
+    let (url, seen_headers) = spawn_http_server().await;
+    let mut headers = HashMap::new();
+    headers.insert("Authorization".to_string(), "Bearer secret".to_string());
+
+    let handle = StreamableHttpTransport::new(url, headers).start().await.unwrap();
+
+    let result = result_of(handle.send(request(1, "tools/list")).await.unwrap());
+    assert_eq!(result["tools"][0]["name"], "echo");
+
+    // The event stream response is matched to the request by id
+    let result = result_of(handle.send(request(2, "ping")).await.unwrap());
+    assert_eq!(result["method"], "ping");
+
+    let notification: JsonRpcMessage =
+        serde_json::from_value(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).unwrap();
+    assert!(matches!(handle.send(notification).await.unwrap(), JsonRpcMessage::Nil));
+
+    let seen = seen_headers.lock().unwrap();
+    assert_eq!(seen.len(), 3);
+    assert!(seen.iter().all(|h| h.get("authorization").map(String::as_str) == Some("Bearer secret")));
+    // The session the server assigned is sent back from then on
+    assert!(seen[0].get("mcp-session-id").is_none());
+    assert_eq!(seen[1].get("mcp-session-id").map(String::as_str), Some("session-1"));
+}
+
+#[tokio::test]
+async fn test_sse_transport_routes_responses_from_the_stream() {
+    let url = spawn_sse_server().await;
+    let handle = SseTransport::new(url, HashMap::new()).start().await.unwrap();
+
+    let result = result_of(handle.send(request(7, "ping")).await.unwrap());
+    assert_eq!(result["method"], "ping");
+
+    let result = result_of(handle.send(request(8, "tools/list")).await.unwrap());
+    assert_eq!(result["tools"][0]["name"], "echo");
+}
+
+#[tokio::test]
+async fn test_connect_initializes_over_any_transport() {
+    let (http_url, _) = spawn_http_server().await;
+    let sse_url = spawn_sse_server().await;
+
+    for transport_config in [
+        TransportConfig::Http { url: http_url, headers: HashMap::new() },
+        TransportConfig::Sse { url: sse_url, headers: HashMap::new() },
+    ] {
+        let server = ServerConfig::remote("Stand-in".to_string(), transport_config, None, false);
+        let client = transport::connect(&server).await.unwrap();
+
+        let tools = client.list_tools(None).await.unwrap();
+        assert_eq!(tools.tools.len(), 1);
+        assert_eq!(tools.tools[0].name, "echo");
+    }
+}
+
+#[tokio::test]
+async fn test_connect_reports_unreachable_servers() {
+    // Bind and drop a listener so the port is known to be closed
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/sse", listener.local_addr().unwrap());
+    drop(listener);
+
+    let server = ServerConfig::remote(
+        "Unreachable".to_string(),
+        TransportConfig::Sse { url, headers: HashMap::new() },
+        None,
+        false,
+    );
+
+    let error = transport::connect(&server).await.err().expect("connecting should fail");
+    assert!(matches!(error, transport::ConnectError::Start(_)));
+    assert!(error.to_string().starts_with("Failed to start"));
+}
+
+/// Start a shell script as a stdio server
+#[cfg(unix)]
+async fn start_script(script: &str) -> transport::ProcessTransportHandle {
+    ProcessTransport::new("sh", vec!["-c".to_string(), script.to_string()], HashMap::new())
+        .start()
+        .await
+        .unwrap()
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_process_exits_when_its_input_closes() {
+    // Answers every line with the response to request 1 and exits at end of input
+    let handle = start_script(r#"while read line; do echo '{"jsonrpc":"2.0","id":1,"result":{"ok":true}}'; done"#).await;
+
+    let result = result_of(handle.send(request(1, "ping")).await.unwrap());
+    assert_eq!(result["ok"], true);
+
+    assert_eq!(handle.shutdown().await, ShutdownOutcome::Exited(Some(0)));
+
+    // Nothing can be sent once the input is closed
+    assert!(handle.send(request(2, "ping")).await.is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_process_group_is_terminated_when_it_ignores_its_input() {
+    // Never reads its input and keeps a child of its own running
+    let handle = start_script("sleep 60 & wait").await;
+    let pid = handle.pid().unwrap() as libc::pid_t;
+
+    assert_eq!(handle.shutdown().await, ShutdownOutcome::Terminated);
+
+    // The child went down with the group
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert_eq!(unsafe { libc::killpg(pid, 0) }, -1);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_children_are_terminated_when_the_server_exits() {
+    // Exits at end of input, leaving its child running
+    let handle = start_script("sleep 60 & while read line; do :; done").await;
+    let pid = handle.pid().unwrap() as libc::pid_t;
+
+    assert_eq!(handle.shutdown().await, ShutdownOutcome::Exited(Some(0)));
+
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert_eq!(unsafe { libc::killpg(pid, 0) }, -1);
+}
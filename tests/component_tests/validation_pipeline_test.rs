@@ -1,6 +1,8 @@
-use m_desk_new::components::{ValidationPipeline, ValidationState};
+use m_desk_new::components::{SanitizationConfig, SanitizationProfile, ToolSanitization, ValidationPipeline, ValidationState};
+use m_desk_new::argument_rules::ArgumentRule;
 use mcp_core::Tool;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 
 fn create_test_tool() -> Tool {
     Tool {
@@ -53,7 +55,8 @@ fn test_validation_pipeline_valid_input() {
 
 #[test]
 fn test_validation_pipeline_sanitized_input() {
-    let pipeline = ValidationPipeline::new();
+    let pipeline = ValidationPipeline::new()
+        .with_field_profile("name", SanitizationProfile::Line);
     let tool = create_test_tool();
     
     let input = json!({
@@ -74,15 +77,70 @@ fn test_validation_pipeline_sanitized_input() {
                 sanitized.get("count").and_then(Value::as_i64).unwrap(),
                 42
             );
+            // Markup is passed on as given, it is escaped where it is displayed
             let tags = sanitized.get("tags").and_then(Value::as_array).unwrap();
-            let tag = tags[0].as_str().unwrap();
-            assert!(tag.contains("&lt;script&gt;") && tag.contains("alert") && tag.contains("&lt;/script&gt;"));
-            assert!(!changes.is_empty());
+            assert_eq!(tags[0].as_str().unwrap(), "<script>alert('xss')</script>");
+            assert_eq!(changes, vec!["Sanitized string value in field 'name'".to_string()]);
         }
         _ => panic!("Expected Sanitized state"),
     }
 }
 
+#[test]
+fn test_validation_pipeline_passes_code_through() {
+    let pipeline = ValidationPipeline::new();
+    let tool = Tool {
+        name: "write_file".to_string(),
+        description: "Write a file".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string"},
+                "content": {"type": "string"}
+            }
+        }),
+    };
+    
+    let content = "if a < b && c > \"d\" {\n\treturn '&amp;';\n}\n".repeat(100);
+    let input = json!({"path": "/tmp/a.rs", "content": content});
+    assert_eq!(pipeline.validate_input(&tool, input.clone()), ValidationState::Valid(input));
+}
+
+#[test]
+fn test_validation_pipeline_profiles_from_schema() {
+    let tool = Tool {
+        name: "send".to_string(),
+        description: "Send a note".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "to": {"type": "string", "format": "email"},
+                "body": {"type": "string", "contentMediaType": "text/plain"},
+                "script": {"type": "string", "contentMediaType": "text/x-python"},
+                "pattern": {"type": "string", "format": "regex"}
+            }
+        }),
+    };
+    let input = json!({
+        "to": " ada@example.com\n",
+        "body": "line\u{0007} one\nline two",
+        "script": "print('\u{0007}')  ",
+        "pattern": "^\\s*<\\w+>  "
+    });
+    
+    let result = ValidationPipeline::new().validate_input(&tool, input.clone());
+    let value = result.get_value().unwrap();
+    assert_eq!(value["to"], "ada@example.com");
+    assert_eq!(value["body"], "line one\nline two");
+    assert_eq!(value["script"], input["script"]);
+    assert_eq!(value["pattern"], input["pattern"]);
+    
+    // Control characters are only removed elsewhere when asked for
+    let strict = ValidationPipeline::new().with_default_profile(SanitizationProfile::Text);
+    let result = strict.validate_input(&create_test_tool(), json!({"name": "a\u{0000}b", "count": 1}));
+    assert_eq!(result.get_value().unwrap()["name"], "ab");
+}
+
 #[test]
 fn test_validation_pipeline_invalid_input() {
     let pipeline = ValidationPipeline::new()
@@ -394,4 +452,63 @@ fn test_validation_pipeline_complex_recovery() {
         },
         _ => panic!("Expected Recovered state with multiple strategies"),
     }
-} 
\ No newline at end of file
+}
+
+#[test]
+fn test_validation_pipeline_argument_rules() {
+    let tool = Tool {
+        name: "filesystem/write_file".to_string(),
+        description: "Write a file".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "required": ["path"],
+            "properties": {"path": {"type": "string"}}
+        }),
+    };
+    let rules = vec![ArgumentRule {
+        tool: "write_file".to_string(),
+        server: Some("filesystem".to_string()),
+        allowed_roots: vec!["/srv/data".to_string()],
+        ..ArgumentRule::default()
+    }];
+    let pipeline = ValidationPipeline::new()
+        .with_argument_rules(rules)
+        .with_server(Some("filesystem".to_string()));
+
+    assert!(pipeline.validate_input(&tool, json!({"path": "/srv/data/notes.txt"})).is_valid());
+
+    // Breaking a rule makes the input invalid, with the reason and without alternatives
+    let result = pipeline.validate_input(&tool, json!({"path": "/srv/data/../../etc/passwd"}));
+    assert!(!result.is_valid());
+    assert!(result.get_errors()[0].contains("Argument /path of write_file is not allowed"));
+    assert!(result.get_alternative_tools().is_empty());
+
+    // The rule is for another server
+    let other = ValidationPipeline::new()
+        .with_argument_rules(vec![ArgumentRule { server: Some("other".to_string()), ..ArgumentRule::default() }]);
+    assert!(other.validate_input(&tool, json!({"path": "/etc/passwd"})).is_valid());
+}
+
+#[test]
+fn test_validation_pipeline_configured_sanitization() {
+    let config = SanitizationConfig {
+        max_string_length: Some(8),
+        default_profile: Some(SanitizationProfile::Text),
+        fields: HashMap::from([("name".to_string(), SanitizationProfile::Line)]),
+        tools: vec![ToolSanitization {
+            tool: "test_*".to_string(),
+            server: Some("local".to_string()),
+            fields: HashMap::from([("name".to_string(), SanitizationProfile::Raw)]),
+        }],
+    };
+    let input = json!({ "name": " a\u{7}b ", "count": 1, "tags": ["x\u{7}y", "a long tag"] });
+
+    // The tool's own profiles apply on its server only
+    let pipeline = ValidationPipeline::new().with_sanitization(&config);
+    let sanitized = pipeline.validate_input(&create_test_tool(), input.clone());
+    assert_eq!(sanitized.get_value().unwrap(), &json!({ "name": "ab", "count": 1, "tags": ["xy", "a long t"] }));
+
+    let pipeline = ValidationPipeline::new().with_sanitization(&config).with_server(Some("local".to_string()));
+    let sanitized = pipeline.validate_input(&create_test_tool(), input);
+    assert_eq!(sanitized.get_value().unwrap()["name"], " a\u{7}b ");
+}
@@ -0,0 +1,70 @@
+use m_desk_new::server_config::ServerConfig;
+use m_desk_new::variables::{unknown_variables, VariableContext, VariableError};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+fn context() -> VariableContext {
+    VariableContext {
+        home: Some(PathBuf::from("/home/ada")),
+        workspace: PathBuf::from("/work/mdesk"),
+    }
+}
+
+#[test]
+fn test_home_and_workspace_are_expanded() {
+    let context = context();
+    assert_eq!(context.expand("${HOME}/Desktop").unwrap(), "/home/ada/Desktop");
+    assert_eq!(context.expand("${workspace}/src:${HOME}").unwrap(), "/work/mdesk/src:/home/ada");
+    assert_eq!(context.expand("no variables, $HOME stays").unwrap(), "no variables, $HOME stays");
+
+    let homeless = VariableContext { home: None, ..context };
+    assert_eq!(homeless.expand("${HOME}"), Err(VariableError::NoHome));
+}
+
+#[test]
+fn test_environment_variables_are_expanded() {
+    std::env::set_var("MDESK_VARIABLES_TEST", "from-env");
+    let context = context();
+    assert_eq!(context.expand("--token=${env:MDESK_VARIABLES_TEST}").unwrap(), "--token=from-env");
+    assert_eq!(
+        context.expand("${env:MDESK_VARIABLES_TEST_UNSET}"),
+        Err(VariableError::MissingEnv("MDESK_VARIABLES_TEST_UNSET".to_string()))
+    );
+    std::env::remove_var("MDESK_VARIABLES_TEST");
+}
+
+#[test]
+fn test_secrets_are_left_for_the_secret_store() {
+    let context = context();
+    assert_eq!(context.expand("Bearer ${secret:GITHUB_TOKEN}").unwrap(), "Bearer ${secret:GITHUB_TOKEN}");
+    assert_eq!(context.expand("${USER}"), Err(VariableError::Unknown("USER".to_string())));
+}
+
+#[test]
+fn test_unknown_variables_are_listed() {
+    assert!(unknown_variables("${HOME}/${workspace}/${env:PATH}/${secret:KEY}").is_empty());
+    assert_eq!(unknown_variables("${HOME:x} ${env} ${Workspace}"), vec!["${HOME:x}", "${env}", "${Workspace}"]);
+}
+
+#[test]
+fn test_server_configs_are_expanded() {
+    let mut env = HashMap::new();
+    env.insert("ROOT".to_string(), "${workspace}".to_string());
+    env.insert("TOKEN".to_string(), "${secret:TOKEN}".to_string());
+    let config = ServerConfig::new(
+        "Filesystem".to_string(),
+        "${HOME}/.local/bin/mcp-server-filesystem".to_string(),
+        vec!["${HOME}/Desktop".to_string(), "${workspace}".to_string()],
+        env,
+        None,
+        false,
+    );
+
+    let expanded = context().expand_config(&config).unwrap();
+    assert_eq!(expanded.command, "/home/ada/.local/bin/mcp-server-filesystem");
+    assert_eq!(expanded.args, vec!["/home/ada/Desktop", "/work/mdesk"]);
+    assert_eq!(expanded.env["ROOT"], "/work/mdesk");
+    assert_eq!(expanded.env["TOKEN"], "${secret:TOKEN}");
+    // The configuration itself keeps its variables
+    assert_eq!(config.args[0], "${HOME}/Desktop");
+}
@@ -0,0 +1,54 @@
+use m_desk_new::connection_manager::ConnectionError;
+use m_desk_new::inspector::MessageKind;
+use m_desk_new::transport::ConnectError;
+use m_desk_new::ServerStatus;
+use serde_json::json;
+
+use super::mock_server::{fixture, logged, mock_config, start_state};
+use crate::support::TempDir;
+
+#[tokio::test]
+async fn test_server_starts_and_lists_its_tools() {
+    let fixtures = TempDir::new("mdesk-mock-server");
+    let state = start_state(&fixtures, vec![mock_config(&fixtures, "mock", fixture())]).await;
+
+    assert_eq!(state.server_status.get("mock"), Some(&ServerStatus::Running));
+    let mut names: Vec<String> = state.tool_registry.tools().into_iter().map(|tool| tool.name).collect();
+    names.sort();
+    assert_eq!(names, vec!["crash", "echo", "fail", "reject", "slow"]);
+
+    // The handshake went through the inspector, along with the annotations of the tools
+    let entries = state.inspector.entries();
+    assert!(entries
+        .iter()
+        .any(|entry| entry.method.as_deref() == Some("initialize") && entry.kind == MessageKind::Response));
+    assert_eq!(state.inspector.annotations().get("mock", "echo").read_only, Some(true));
+    assert!(logged(&state, "mock", "mock server ready").await);
+
+    // Closing its input lets the server exit by itself
+    assert!(state.connections.stop("mock").await);
+    assert_eq!(state.connections.status("mock"), Some(ServerStatus::Stopped));
+}
+
+#[tokio::test]
+async fn test_servers_failing_to_initialize_are_reported() {
+    let fixtures = TempDir::new("mdesk-mock-server");
+    let mut config = mock_config(
+        &fixtures,
+        "refusing",
+        json!({ "tools": [], "methods": { "initialize": { "error": { "message": "not today" } } } }),
+    );
+    let state = start_state(&fixtures, vec![config.clone()]).await;
+    assert!(matches!(state.server_status.get("refusing"), Some(ServerStatus::Failed(_))));
+
+    // Crashing during the handshake, or not getting to it at all, fails the start too
+    config.args = vec!["/nonexistent/fixture.json".to_string()];
+    let crashing = mock_config(&fixtures, "crashing", json!({ "methods": { "initialize": { "crash": 1 } } }));
+    for config in [config, crashing] {
+        let error = state.connections.start(&config).await.err().expect("starting should fail");
+        assert!(matches!(error, ConnectionError::Connect(ConnectError::Initialize(_))));
+        assert!(matches!(state.connections.status(&config.id), Some(ServerStatus::Failed(_))));
+    }
+    assert!(logged(&state, "refusing", "can't read /nonexistent/fixture.json").await);
+    assert!(logged(&state, "crashing", "crashing with exit code 1").await);
+}
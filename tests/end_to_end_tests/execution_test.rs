@@ -0,0 +1,93 @@
+use m_desk_new::resources::{McpResourceProvider, ResourceContent, ResourceProvider};
+use m_desk_new::{PromptManager, ToolExecutionError, ToolManager, ToolResolveError, ToolRouter};
+use serde_json::json;
+
+use super::mock_server::{fixture, mock_config, start_state};
+use crate::support::TempDir;
+
+#[tokio::test]
+async fn test_tool_calls_reach_the_server() {
+    let fixtures = TempDir::new("mdesk-mock-server");
+    let state = start_state(&fixtures, vec![mock_config(&fixtures, "mock", fixture())]).await;
+    let router = ToolRouter::from(&state);
+
+    let result = ToolManager::execute_tool("echo".to_string(), json!({"text": "hi"}), &router).await.unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert_eq!(ToolManager::format_tool_result(&result).trim(), r#"{"text":"hi"}"#);
+
+    // A failure the tool reports is a result, a JSON-RPC error is not
+    let result = ToolManager::execute_tool("fail".to_string(), json!({}), &router).await.unwrap();
+    assert_eq!(result.is_error, Some(true));
+    assert_eq!(ToolManager::format_tool_result(&result).trim(), "no such file");
+
+    let error = ToolManager::execute_tool("reject".to_string(), json!({}), &router).await.unwrap_err();
+    assert!(matches!(error, ToolExecutionError::Mcp(_)));
+    assert!(error.to_string().contains("invalid path"));
+
+    let error = ToolManager::execute_tool("missing".to_string(), json!({}), &router).await.unwrap_err();
+    assert!(matches!(error, ToolExecutionError::Resolve(ToolResolveError::UnknownTool(_))));
+
+    state.connections.stop_all().await;
+}
+
+#[tokio::test]
+async fn test_tools_with_the_same_name_are_routed_to_their_server() {
+    let fixtures = TempDir::new("mdesk-mock-server");
+    let echo = |text: &str| {
+        json!({ "tools": [{
+            "name": "echo",
+            "description": "Echo",
+            "inputSchema": { "type": "object" },
+            "result": { "content": [{ "type": "text", "text": text }] }
+        }] })
+    };
+    let configs = vec![mock_config(&fixtures, "a", echo("from a")), mock_config(&fixtures, "b", echo("from b"))];
+    let state = start_state(&fixtures, configs).await;
+    let router = ToolRouter::from(&state);
+
+    for server in ["a", "b"] {
+        let result = ToolManager::execute_tool(format!("{}/echo", server), json!({}), &router).await.unwrap();
+        assert_eq!(ToolManager::format_tool_result(&result).trim(), format!("from {}", server));
+    }
+    let error = ToolManager::execute_tool("echo".to_string(), json!({}), &router).await.unwrap_err();
+    assert!(matches!(error, ToolExecutionError::Resolve(ToolResolveError::AmbiguousTool { .. })));
+
+    state.connections.stop_all().await;
+}
+
+#[tokio::test]
+async fn test_resources_and_prompts_are_read_from_the_server() {
+    let fixtures = TempDir::new("mdesk-mock-server");
+    let broken = json!({
+        "prompts": [{ "name": "greet", "messages": [] }],
+        "methods": { "prompts/get": { "error": { "code": -32603, "message": "prompt store offline" } } }
+    });
+    let configs = vec![mock_config(&fixtures, "mock", fixture()), mock_config(&fixtures, "broken", broken)];
+    let state = start_state(&fixtures, configs).await;
+
+    let provider = McpResourceProvider::new(state.active_clients.clone());
+    let uris: Vec<String> = provider.list_resources(None).await.unwrap().into_iter().map(|r| r.uri).collect();
+    assert!(uris.contains(&"mcp://mock/file:///notes.txt".to_string()));
+    assert!(matches!(
+        provider.read_resource("mcp://mock/file:///notes.txt").await.unwrap(),
+        ResourceContent::Text(text) if text.contains("hello from the mock")
+    ));
+    assert!(matches!(
+        provider.read_resource("mcp://mock/file:///logo.png").await.unwrap(),
+        ResourceContent::Binary(data) if data == b"hi"
+    ));
+    assert!(provider.read_resource("mcp://mock/file:///missing.txt").await.is_err());
+
+    let listing = PromptManager::list_all(&state.active_clients).await;
+    assert_eq!(listing.prompts.len(), 2);
+    assert!(listing.errors.is_empty());
+
+    let messages = PromptManager::get_prompt(&state.active_clients, "mock", "greet", json!({"name": "Ada"}))
+        .await
+        .unwrap();
+    assert_eq!(messages.len(), 1);
+    let error = PromptManager::get_prompt(&state.active_clients, "broken", "greet", json!({})).await.unwrap_err();
+    assert!(error.to_string().contains("prompt store offline"));
+
+    state.connections.stop_all().await;
+}
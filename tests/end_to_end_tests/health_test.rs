@@ -0,0 +1,103 @@
+use m_desk_new::connection_manager::sync_state;
+use m_desk_new::server_config::{HealthCheckConfig, RestartMode, RestartPolicy, ServerConfig};
+use m_desk_new::{ServerStatus, ToolManager, ToolRouter};
+use serde_json::json;
+use std::time::Duration;
+
+use super::mock_server::{fixture, logged, mock_config, start_state};
+use crate::support::TempDir;
+
+#[tokio::test]
+async fn test_pings_are_answered_during_slow_tool_calls() {
+    let fixtures = TempDir::new("mdesk-mock-server");
+    let state = start_state(&fixtures, vec![mock_config(&fixtures, "mock", fixture())]).await;
+
+    state.connections.supervise_once().await;
+    let health = state.connections.health("mock").unwrap();
+    assert_eq!(health.history.len(), 1);
+    assert!(health.last_latency().is_some());
+    assert_eq!(state.connections.status("mock"), Some(ServerStatus::Running));
+    // Pings don't fill the inspector
+    assert!(!state.inspector.entries().iter().any(|entry| entry.method.as_deref() == Some("ping")));
+
+    // The slow call holds the client, the ping goes through the transport handle
+    let router = ToolRouter::from(&state);
+    let call = tokio::spawn(async move { ToolManager::execute_tool("slow".to_string(), json!({}), &router).await });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let latency = state.connections.ping("mock").await.unwrap();
+    assert!(latency < Duration::from_millis(500), "ping took {:?}", latency);
+    assert!(!call.is_finished());
+    assert!(call.await.unwrap().is_ok());
+
+    state.connections.stop_all().await;
+}
+
+#[tokio::test]
+async fn test_unanswered_pings_degrade_then_fail_the_server() {
+    let fixtures = TempDir::new("mdesk-mock-server");
+    let config = ServerConfig {
+        restart: RestartPolicy {
+            mode: RestartMode::Never,
+            ..RestartPolicy::default()
+        },
+        health: HealthCheckConfig {
+            interval_ms: 0,
+            timeout_ms: 100,
+            max_missed: 2,
+            ..HealthCheckConfig::default()
+        },
+        ..mock_config(&fixtures, "stuck", json!({ "tools": [], "methods": { "ping": { "delay_ms": 1000 } } }))
+    };
+    let state = start_state(&fixtures, vec![config]).await;
+
+    state.connections.supervise_once().await;
+    assert!(matches!(state.connections.status("stuck"), Some(ServerStatus::Degraded(_))));
+    state.connections.supervise_once().await;
+    assert_eq!(
+        state.connections.status("stuck"),
+        Some(ServerStatus::Failed("Connection lost: no answer to 2 pings in a row".to_string()))
+    );
+    assert_eq!(state.connections.health("stuck").unwrap().missed_total(), 2);
+
+    state.connections.stop_all().await;
+}
+
+#[tokio::test]
+async fn test_crashed_server_is_restarted() {
+    let fixtures = TempDir::new("mdesk-mock-server");
+    // Noticing the crash doesn't take a ping
+    let config = ServerConfig {
+        health: HealthCheckConfig {
+            enabled: false,
+            ..HealthCheckConfig::default()
+        },
+        ..mock_config(&fixtures, "mock", fixture())
+    };
+    let mut state = start_state(&fixtures, vec![config]).await;
+
+    assert!(ToolManager::execute_tool("crash".to_string(), json!({}), &ToolRouter::from(&state)).await.is_err());
+    assert!(logged(&state, "mock", "crashing with exit code 3").await);
+    let mut failed = false;
+    for _ in 0..100 {
+        if matches!(state.connections.status("mock"), Some(ServerStatus::Failed(_))) {
+            failed = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(failed, "the crash went unnoticed: {:?}", state.connections.status("mock"));
+
+    // Without a backoff the next round starts it again
+    state.connections.supervise_once().await;
+    assert_eq!(state.connections.status("mock"), Some(ServerStatus::Running));
+    let retry = state.connections.retry_state("mock").unwrap();
+    assert_eq!(retry.retries, 1);
+    assert!(retry.last_error.unwrap().starts_with("Connection lost"));
+
+    sync_state(&mut state);
+    let router = ToolRouter::from(&state);
+    let result = ToolManager::execute_tool("echo".to_string(), json!({"text": "again"}), &router).await.unwrap();
+    assert_eq!(ToolManager::format_tool_result(&result).trim(), r#"{"text":"again"}"#);
+
+    state.connections.stop_all().await;
+}
@@ -0,0 +1,133 @@
+//! Runs the `mock-mcp-server` binary as a stdio server, serving the fixture a test gives it
+
+use m_desk_new::connection_manager::{sync_state, ConnectionManager, McpConnector};
+use m_desk_new::inspector::Inspector;
+use m_desk_new::secrets::SecretStore;
+use m_desk_new::server_config::{HealthCheckConfig, RestartPolicy, ServerConfig, ServerConfigs, TransportConfig};
+use m_desk_new::server_logs::ServerLogs;
+use m_desk_new::McpState;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::support::TempDir;
+
+/// The mock server, built by cargo along with the tests
+pub const MOCK_SERVER: &str = env!("CARGO_BIN_EXE_mock-mcp-server");
+
+/// Stdio server running the mock server with `fixture`, pinged in every round of
+/// supervision and restarted as soon as it fails
+///
+/// The fixture is written to `dir`, which has to outlive the server.
+pub fn mock_config(dir: &TempDir, id: &str, fixture: Value) -> ServerConfig {
+    let path = dir.join(format!("{}.json", id));
+    std::fs::write(&path, serde_json::to_string_pretty(&fixture).unwrap()).unwrap();
+
+    ServerConfig {
+        id: id.to_string(),
+        name: format!("Mock {}", id),
+        transport: TransportConfig::Stdio,
+        command: MOCK_SERVER.to_string(),
+        args: vec![path.to_string_lossy().to_string()],
+        env: HashMap::new(),
+        description: None,
+        is_default: false,
+        restart: RestartPolicy {
+            initial_backoff_ms: 0,
+            max_backoff_ms: 0,
+            ..RestartPolicy::default()
+        },
+        health: HealthCheckConfig {
+            interval_ms: 0,
+            timeout_ms: 2_000,
+            ..HealthCheckConfig::default()
+        },
+    }
+}
+
+/// A server with a tool for every way of answering, a text and a binary resource and a prompt
+pub fn fixture() -> Value {
+    json!({
+        "stderr": ["mock server ready"],
+        "tools": [
+            {
+                "name": "echo",
+                "description": "Echo the arguments back",
+                "inputSchema": { "type": "object", "properties": { "text": { "type": "string" } } },
+                "annotations": { "readOnlyHint": true }
+            },
+            {
+                "name": "fail",
+                "description": "Report a failure in its result",
+                "inputSchema": { "type": "object" },
+                "result": { "content": [{ "type": "text", "text": "no such file" }], "isError": true }
+            },
+            {
+                "name": "reject",
+                "description": "Answer with a JSON-RPC error",
+                "inputSchema": { "type": "object" },
+                "error": { "code": -32602, "message": "invalid path" }
+            },
+            {
+                "name": "slow",
+                "description": "Answer after a second",
+                "inputSchema": { "type": "object" },
+                "delay_ms": 1000
+            },
+            {
+                "name": "crash",
+                "description": "Exit instead of answering",
+                "inputSchema": { "type": "object" },
+                "crash": 3
+            }
+        ],
+        "resources": [
+            { "uri": "file:///notes.txt", "name": "Notes", "mimeType": "text/plain", "text": "hello from the mock" },
+            { "uri": "file:///logo.png", "name": "Logo", "mimeType": "image/png", "blob": "aGk=" }
+        ],
+        "prompts": [
+            {
+                "name": "greet",
+                "description": "Greet someone",
+                "arguments": [{ "name": "name", "required": true }],
+                "messages": [{ "role": "user", "content": { "type": "text", "text": "Say hello" } }]
+            }
+        ]
+    })
+}
+
+/// App state whose servers log and record their traffic in memory, with `configs` started
+///
+/// Servers that fail to start are left failed for the test to check.
+pub async fn start_state(dir: &TempDir, configs: Vec<ServerConfig>) -> McpState {
+    let secrets = SecretStore::new(dir.join("secrets.json"));
+    let server_logs = ServerLogs::in_memory().with_secrets(secrets.clone());
+    let inspector = Inspector::default();
+    let mut state = McpState {
+        server_configs: ServerConfigs {
+            servers: configs.clone(),
+            ..ServerConfigs::default()
+        },
+        connections: ConnectionManager::new(McpConnector::new(server_logs.clone(), inspector.clone(), secrets.clone())),
+        server_logs,
+        inspector,
+        secrets,
+        ..McpState::default()
+    };
+
+    state.connections.start_all(&configs).await;
+    sync_state(&mut state);
+    state
+}
+
+/// Whether a server wrote a line containing `text` to stderr, waiting a little for it
+/// since stderr is read apart from the responses
+pub async fn logged(state: &McpState, server_id: &str, text: &str) -> bool {
+    for _ in 0..50 {
+        if state.server_logs.lines(server_id).iter().any(|line| line.text.contains(text)) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    false
+}
@@ -1,5 +1,8 @@
 // This file acts as the test harness for integration tests
 
+#[cfg(test)]
+mod support;
+
 #[cfg(test)]
 mod component_tests {
     // Include component test modules
@@ -9,6 +12,32 @@ mod component_tests {
     mod parameter_validation_test;
     mod validation_pipeline_test;
     mod tool_selection_cache_test;
+    mod tool_registry_test;
+    mod function_calling_test;
+    mod streaming_test;
+    mod agent_test;
+    mod conversation_store_test;
+    mod conversation_export_test;
+    mod prompt_manager_test;
+    mod resources_test;
+    mod transport_test;
+    mod connection_manager_test;
+    mod server_logs_test;
+    mod inspector_test;
+    mod server_config_test;
+    mod secrets_test;
+    mod variables_test;
+    mod approvals_test;
+    mod argument_rules_test;
+}
+
+#[cfg(test)]
+mod end_to_end_tests {
+    // Drive the transports and the connection code against the mock MCP server binary
+    mod mock_server;
+    mod connection_test;
+    mod execution_test;
+    mod health_test;
 }
 
 #[cfg(test)]